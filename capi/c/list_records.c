@@ -0,0 +1,59 @@
+/*
+ * Example client of the C API: lists the annotated functions of a binary.
+ *
+ *     cc -std=c99 -I ../include list_records.c -L <dir> -lsecurity_scanner_capi
+ *
+ * Prints one line per function, then the integrity counts.
+ */
+
+#include <stdio.h>
+
+#include "security_scanner.h"
+
+int main(int argc, char **argv)
+{
+    ss_binary *binary;
+    ss_record record;
+    ss_integrity integrity;
+    size_t i;
+    int status;
+
+    if (argc != 2) {
+        fprintf(stderr, "usage: %s BINARY\n", argv[0]);
+        return 2;
+    }
+    if (ss_abi_version() != SS_ABI_VERSION) {
+        fprintf(stderr, "unsupported ABI version %u\n", ss_abi_version());
+        return 1;
+    }
+
+    status = ss_open_binary(argv[1], &binary);
+    if (status != SS_OK) {
+        fprintf(stderr, "%s\n", ss_status_message(status));
+        return 1;
+    }
+
+    record.struct_size = sizeof record;
+    while ((status = ss_next_record(binary, &record)) == SS_OK) {
+        printf("%s tests=0x%02x threat_level=%u sla_days=%u reads_env=",
+               record.name, (unsigned)record.tests, record.threat_level,
+               record.sla_days);
+        for (i = 0; i < record.reads_env_len; i++) {
+            printf("%s%s", i ? "," : "", record.reads_env[i]);
+        }
+        if (record.line != 0) {
+            printf(" location=%s:%u", record.file, record.line);
+        }
+        printf("\n");
+        record.struct_size = sizeof record;
+    }
+
+    integrity.struct_size = sizeof integrity;
+    if (status == SS_END && ss_get_integrity(binary, &integrity) == SS_OK) {
+        printf("verified=%zu corrupted=%zu\n", integrity.verified,
+               integrity.corrupted);
+    }
+
+    ss_free(binary);
+    return status == SS_END ? 0 : 1;
+}
@@ -0,0 +1,97 @@
+/*
+ * C API for reading security-scanner metadata out of compiled binaries.
+ *
+ * Link against the security_scanner_capi shared or static library. Structs
+ * filled in by the library start with a struct_size field; set it to the
+ * sizeof of the struct before each call. See the crate documentation for
+ * the versioning rules.
+ */
+
+#ifndef SECURITY_SCANNER_H
+#define SECURITY_SCANNER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SS_ABI_VERSION 1
+
+/* Status codes */
+#define SS_OK 0
+#define SS_END 1
+#define SS_ERR_NULL (-1)
+#define SS_ERR_PATH (-2)
+#define SS_ERR_IO (-3)
+#define SS_ERR_FORMAT (-4)
+#define SS_ERR_NO_METADATA (-5)
+#define SS_ERR_STRUCT_SIZE (-6)
+#define SS_ERR_INTERNAL (-7)
+
+/* Bits of ss_record.tests */
+#define SS_TEST_SQL_INJECTION (1u << 0)
+#define SS_TEST_RACE_CONDITION (1u << 1)
+#define SS_TEST_TIMING_ATTACK (1u << 2)
+#define SS_TEST_BUFFER_OVERFLOW (1u << 3)
+#define SS_TEST_ENV_INJECTION (1u << 4)
+#define SS_TEST_XSS (1u << 5)
+#define SS_TEST_COMMAND_INJECTION (1u << 6)
+#define SS_TEST_PATH_TRAVERSAL (1u << 7)
+#define SS_TEST_SSRF (1u << 8)
+#define SS_TEST_XXE (1u << 9)
+#define SS_TEST_DESERIALIZATION (1u << 10)
+#define SS_TEST_OPEN_REDIRECT (1u << 11)
+
+typedef struct ss_binary ss_binary;
+
+/* One annotated function. Strings are owned by the ss_binary. */
+typedef struct ss_record {
+    size_t struct_size;
+    const char *name;
+    uint32_t tests;
+    uint8_t threat_level; /* 0 low, 1 medium, 2 high, 3 critical */
+    uint8_t capabilities;
+    uint8_t stride;
+    uint8_t test_context;
+    uint16_t sla_days; /* 0 if unset */
+    const char *const *reads_env;
+    size_t reads_env_len;
+    const char *const *fixtures;
+    size_t fixtures_len;
+    const char *const *attack_techniques;
+    size_t attack_techniques_len;
+    /* Added after the first version */
+    const char *file; /* "" if unknown */
+    uint32_t line;    /* 0 if unknown */
+    uint32_t column;  /* 0 if unknown */
+    const char *const *input_params; /* "name: Type" */
+    size_t input_params_len;
+} ss_record;
+
+/* Integrity counts of a binary's records section. */
+typedef struct ss_integrity {
+    size_t struct_size;
+    size_t verified;
+    size_t unchecked;
+    size_t corrupted;
+    size_t foreign_endian;
+    size_t truncated;
+    size_t unrecognized;
+    size_t unsupported; /* records of an unknown format version */
+} ss_integrity;
+
+uint32_t ss_abi_version(void);
+int ss_open_binary(const char *path, ss_binary **out);
+int ss_next_record(ss_binary *binary, ss_record *record);
+int ss_rewind(ss_binary *binary);
+int ss_get_integrity(const ss_binary *binary, ss_integrity *integrity);
+void ss_free(ss_binary *binary);
+const char *ss_status_message(int status);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SECURITY_SCANNER_H */
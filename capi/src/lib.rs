@@ -0,0 +1,420 @@
+//! # Security Scanner C API
+//!
+//! A C ABI over [`security_scanner::extract`], for scanners that read
+//! metadata out of compiled binaries without a Rust toolchain. The matching
+//! declarations are in `include/security_scanner.h`; `c/list_records.c` is a
+//! small example client.
+//!
+//! ```c
+//! ss_binary *binary;
+//! if (ss_open_binary("target/debug/my_app", &binary) == SS_OK) {
+//!     ss_record record;
+//!     record.struct_size = sizeof record;
+//!     while (ss_next_record(binary, &record) == SS_OK) {
+//!         printf("%s %u\n", record.name, record.threat_level);
+//!     }
+//!     ss_free(binary);
+//! }
+//! ```
+//!
+//! ## ABI Versioning
+//!
+//! [`ss_abi_version`] returns [`SS_ABI_VERSION`], which changes only when
+//! existing declarations change incompatibly. Structs filled in by the
+//! library start with a `struct_size` field that the caller sets to the size
+//! of the struct it was compiled against. New fields are only ever appended,
+//! and the library writes no more than `struct_size` bytes, so clients built
+//! against an older header keep working with a newer library.
+//!
+//! ## Memory
+//!
+//! Strings in a record are NUL-terminated UTF-8 and owned by the binary
+//! handle; they stay valid until [`ss_free`]. Handles are not thread-safe.
+//!
+//! ## Other Languages
+//!
+//! Python has its own bindings, the `security_scanner_reader` module of the
+//! `security-scanner-python` crate. Node.js and other runtimes with a
+//! foreign function interface, such as `koffi` for Node.js, load the
+//! `cdylib` of this crate and declare the functions and structs of the
+//! header; `struct_size` keeps them working when they lag the library.
+
+use std::ffi::{c_char, CStr, CString};
+use std::os::raw::c_int;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use security_scanner::extract;
+use security_scanner::format::IntegrityStats;
+use security_scanner::{FormatError, ScannerError, SecurityTestMetadata, TestType};
+
+/// Version of the C ABI.
+pub const SS_ABI_VERSION: u32 = 1;
+
+/// The call succeeded.
+pub const SS_OK: c_int = 0;
+/// [`ss_next_record`] has returned every record.
+pub const SS_END: c_int = 1;
+/// A required pointer argument was null.
+pub const SS_ERR_NULL: c_int = -1;
+/// The path isn't valid UTF-8.
+pub const SS_ERR_PATH: c_int = -2;
+/// The file couldn't be read.
+pub const SS_ERR_IO: c_int = -3;
+/// The file isn't a supported binary or is malformed.
+pub const SS_ERR_FORMAT: c_int = -4;
+/// The binary contains no metadata.
+pub const SS_ERR_NO_METADATA: c_int = -5;
+/// `struct_size` is smaller than the first version of the struct.
+pub const SS_ERR_STRUCT_SIZE: c_int = -6;
+/// The library failed unexpectedly; the handle should not be used again.
+pub const SS_ERR_INTERNAL: c_int = -7;
+
+/// Test type bits of [`SsRecord::tests`].
+pub const SS_TEST_SQL_INJECTION: u32 = 1 << 0;
+pub const SS_TEST_RACE_CONDITION: u32 = 1 << 1;
+pub const SS_TEST_TIMING_ATTACK: u32 = 1 << 2;
+pub const SS_TEST_BUFFER_OVERFLOW: u32 = 1 << 3;
+pub const SS_TEST_ENV_INJECTION: u32 = 1 << 4;
+pub const SS_TEST_XSS: u32 = 1 << 5;
+pub const SS_TEST_COMMAND_INJECTION: u32 = 1 << 6;
+pub const SS_TEST_PATH_TRAVERSAL: u32 = 1 << 7;
+pub const SS_TEST_SSRF: u32 = 1 << 8;
+pub const SS_TEST_XXE: u32 = 1 << 9;
+pub const SS_TEST_DESERIALIZATION: u32 = 1 << 10;
+pub const SS_TEST_OPEN_REDIRECT: u32 = 1 << 11;
+
+/// A binary opened with [`ss_open_binary`].
+pub struct SsBinary {
+    records: Vec<Record>,
+    next: usize,
+    integrity: IntegrityStats,
+}
+
+/// Owned C strings behind one [`SsRecord`].
+struct Record {
+    metadata: SecurityTestMetadata,
+    name: CString,
+    file: CString,
+    reads_env: Strings,
+    fixtures: Strings,
+    attack_techniques: Strings,
+    input_params: Strings,
+}
+
+struct Strings {
+    _owned: Vec<CString>,
+    pointers: Vec<*const c_char>,
+}
+
+impl Strings {
+    fn new(values: &[String]) -> Self {
+        let owned: Vec<CString> = values.iter().map(|value| c_string(value)).collect();
+        let pointers = owned.iter().map(|value| value.as_ptr()).collect();
+        Strings {
+            _owned: owned,
+            pointers,
+        }
+    }
+}
+
+/// One annotated function, filled in by [`ss_next_record`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SsRecord {
+    /// Set by the caller to `sizeof(ss_record)`
+    pub struct_size: usize,
+    /// Fully-qualified function path
+    pub name: *const c_char,
+    /// `SS_TEST_*` bits of the requested tests
+    pub tests: u32,
+    /// 0 low, 1 medium, 2 high, 3 critical
+    pub threat_level: u8,
+    /// Raw capability byte of the record format
+    pub capabilities: u8,
+    /// Raw STRIDE byte of the record format
+    pub stride: u8,
+    /// 1 if compiled for test code
+    pub test_context: u8,
+    /// Remediation deadline in days, 0 if unset
+    pub sla_days: u16,
+    pub reads_env: *const *const c_char,
+    pub reads_env_len: usize,
+    pub fixtures: *const *const c_char,
+    pub fixtures_len: usize,
+    pub attack_techniques: *const *const c_char,
+    pub attack_techniques_len: usize,
+    /// Source file, empty if unknown (added after the first version)
+    pub file: *const c_char,
+    /// 1-based source line of the function name, 0 if unknown
+    pub line: u32,
+    /// 1-based source column of the function name, 0 if unknown
+    pub column: u32,
+    /// Parameters as `name: Type`
+    pub input_params: *const *const c_char,
+    pub input_params_len: usize,
+}
+
+/// Size of the first version of [`SsRecord`], which ended with
+/// `attack_techniques_len`.
+const RECORD_V1_SIZE: usize = std::mem::offset_of!(SsRecord, file);
+
+/// Integrity counts of a binary's records, filled in by [`ss_get_integrity`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SsIntegrity {
+    /// Set by the caller to `sizeof(ss_integrity)`
+    pub struct_size: usize,
+    pub verified: usize,
+    pub unchecked: usize,
+    pub corrupted: usize,
+    pub foreign_endian: usize,
+    pub truncated: usize,
+    pub unrecognized: usize,
+    /// Intact records of a format version the library doesn't know
+    pub unsupported: usize,
+}
+
+/// Size of the first version of [`SsIntegrity`], which ended with
+/// `unrecognized`.
+const INTEGRITY_V1_SIZE: usize = std::mem::offset_of!(SsIntegrity, unsupported);
+
+/// Returns the ABI version of the library, [`SS_ABI_VERSION`].
+#[no_mangle]
+pub extern "C" fn ss_abi_version() -> u32 {
+    SS_ABI_VERSION
+}
+
+/// Opens the binary at `path` and reads its metadata.
+///
+/// On success `*out` receives a handle to release with [`ss_free`].
+///
+/// # Safety
+///
+/// `path` must be a NUL-terminated string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ss_open_binary(path: *const c_char, out: *mut *mut SsBinary) -> c_int {
+    if path.is_null() || out.is_null() {
+        return SS_ERR_NULL;
+    }
+    *out = ptr::null_mut();
+    let Ok(path) = CStr::from_ptr(path).to_str() else {
+        return SS_ERR_PATH;
+    };
+
+    guard(|| match extract::extract_file(path) {
+        Ok(extraction) => {
+            let binary = SsBinary {
+                records: extraction.functions.into_iter().map(record).collect(),
+                next: 0,
+                integrity: extraction.integrity,
+            };
+            *out = Box::into_raw(Box::new(binary));
+            SS_OK
+        }
+        Err(ScannerError::Format(FormatError::NoMetadata)) => SS_ERR_NO_METADATA,
+        Err(ScannerError::Format(_)) => SS_ERR_FORMAT,
+        Err(ScannerError::Io { .. }) => SS_ERR_IO,
+        Err(_) => SS_ERR_INTERNAL,
+    })
+}
+
+/// Fills in `record` with the next function, returning [`SS_END`] after the
+/// last one.
+///
+/// # Safety
+///
+/// `binary` must come from [`ss_open_binary`] and not be freed. `record` must
+/// point to at least `record->struct_size` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn ss_next_record(binary: *mut SsBinary, record: *mut SsRecord) -> c_int {
+    if binary.is_null() || record.is_null() {
+        return SS_ERR_NULL;
+    }
+    let binary = &mut *binary;
+
+    guard(|| {
+        let Some(next) = binary.records.get(binary.next) else {
+            return SS_END;
+        };
+        let config = &next.metadata.config;
+        let location = next.metadata.location.as_ref();
+        let tests = [
+            (TestType::SqlInjection, SS_TEST_SQL_INJECTION),
+            (TestType::RaceCondition, SS_TEST_RACE_CONDITION),
+            (TestType::TimingAttack, SS_TEST_TIMING_ATTACK),
+            (TestType::BufferOverflow, SS_TEST_BUFFER_OVERFLOW),
+            (TestType::EnvInjection, SS_TEST_ENV_INJECTION),
+            (TestType::Xss, SS_TEST_XSS),
+            (TestType::CommandInjection, SS_TEST_COMMAND_INJECTION),
+            (TestType::PathTraversal, SS_TEST_PATH_TRAVERSAL),
+            (TestType::Ssrf, SS_TEST_SSRF),
+            (TestType::Xxe, SS_TEST_XXE),
+            (TestType::Deserialization, SS_TEST_DESERIALIZATION),
+            (TestType::OpenRedirect, SS_TEST_OPEN_REDIRECT),
+        ]
+        .into_iter()
+        .filter(|(test_type, _)| config.has(*test_type))
+        .fold(0, |bits, (_, bit)| bits | bit);
+
+        let filled = SsRecord {
+            struct_size: std::mem::size_of::<SsRecord>(),
+            name: next.name.as_ptr(),
+            tests,
+            threat_level: config.threat_level as u8,
+            capabilities: config
+                .capabilities
+                .map_or(0, |capabilities| capabilities.to_byte()),
+            stride: config
+                .stride
+                .iter()
+                .fold(0, |bits, category| bits | category.bit()),
+            test_context: config.test_context as u8,
+            sla_days: config.sla_days.unwrap_or(0),
+            reads_env: next.reads_env.pointers.as_ptr(),
+            reads_env_len: next.reads_env.pointers.len(),
+            fixtures: next.fixtures.pointers.as_ptr(),
+            fixtures_len: next.fixtures.pointers.len(),
+            attack_techniques: next.attack_techniques.pointers.as_ptr(),
+            attack_techniques_len: next.attack_techniques.pointers.len(),
+            file: next.file.as_ptr(),
+            line: location.map_or(0, |location| location.line),
+            column: location.map_or(0, |location| location.column),
+            input_params: next.input_params.pointers.as_ptr(),
+            input_params_len: next.input_params.pointers.len(),
+        };
+        let status = write_versioned(record, filled, RECORD_V1_SIZE);
+        if status == SS_OK {
+            binary.next += 1;
+        }
+        status
+    })
+}
+
+/// Restarts [`ss_next_record`] from the first function.
+///
+/// # Safety
+///
+/// `binary` must come from [`ss_open_binary`] and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn ss_rewind(binary: *mut SsBinary) -> c_int {
+    if binary.is_null() {
+        return SS_ERR_NULL;
+    }
+    (*binary).next = 0;
+    SS_OK
+}
+
+/// Fills in the integrity counts of the binary's records section.
+///
+/// # Safety
+///
+/// `binary` must come from [`ss_open_binary`] and not be freed. `integrity`
+/// must point to at least `integrity->struct_size` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn ss_get_integrity(
+    binary: *const SsBinary,
+    integrity: *mut SsIntegrity,
+) -> c_int {
+    if binary.is_null() || integrity.is_null() {
+        return SS_ERR_NULL;
+    }
+    let stats = &(*binary).integrity;
+    let filled = SsIntegrity {
+        struct_size: std::mem::size_of::<SsIntegrity>(),
+        verified: stats.verified,
+        unchecked: stats.unchecked,
+        corrupted: stats.corrupted,
+        foreign_endian: stats.foreign_endian,
+        truncated: stats.truncated,
+        unrecognized: stats.unrecognized,
+        unsupported: stats.unsupported,
+    };
+    write_versioned(integrity, filled, INTEGRITY_V1_SIZE)
+}
+
+/// Releases a handle and every string it owns. Null is ignored.
+///
+/// # Safety
+///
+/// `binary` must come from [`ss_open_binary`] and not be freed already.
+#[no_mangle]
+pub unsafe extern "C" fn ss_free(binary: *mut SsBinary) {
+    if !binary.is_null() {
+        drop(Box::from_raw(binary));
+    }
+}
+
+/// Returns a static, NUL-terminated description of a status code.
+#[no_mangle]
+pub extern "C" fn ss_status_message(status: c_int) -> *const c_char {
+    let message: &'static CStr = match status {
+        SS_OK => c"ok",
+        SS_END => c"no more records",
+        SS_ERR_NULL => c"null pointer argument",
+        SS_ERR_PATH => c"path is not valid UTF-8",
+        SS_ERR_IO => c"failed to read binary",
+        SS_ERR_FORMAT => c"unsupported or malformed binary",
+        SS_ERR_NO_METADATA => c"binary contains no security test metadata",
+        SS_ERR_STRUCT_SIZE => c"struct_size is too small",
+        SS_ERR_INTERNAL => c"internal error",
+        _ => c"unknown status",
+    };
+    message.as_ptr()
+}
+
+fn record(metadata: SecurityTestMetadata) -> Record {
+    let config = &metadata.config;
+    Record {
+        name: c_string(&metadata.function_name),
+        file: c_string(
+            metadata
+                .location
+                .as_ref()
+                .map_or("", |location| location.file.as_str()),
+        ),
+        reads_env: Strings::new(&config.reads_env),
+        fixtures: Strings::new(&config.fixtures),
+        attack_techniques: Strings::new(&config.attack_techniques),
+        input_params: Strings::new(
+            &config
+                .input_params
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        ),
+        metadata,
+    }
+}
+
+/// Converts to a C string, dropping interior NULs, which valid metadata
+/// never contains.
+fn c_string(value: &str) -> CString {
+    CString::new(value.replace('\0', "")).unwrap_or_default()
+}
+
+/// Copies as much of `filled` as the caller's `struct_size` allows.
+///
+/// A `struct_size` below `first_version`, the size of the struct's first
+/// version, can't come from any header and is rejected.
+unsafe fn write_versioned<T: Copy>(out: *mut T, mut filled: T, first_version: usize) -> c_int {
+    let requested = ptr::read_unaligned(out.cast::<usize>());
+    if requested < first_version {
+        return SS_ERR_STRUCT_SIZE;
+    }
+    // Report the size actually written, which newer callers use to tell
+    // which fields an older library filled in
+    let written = requested.min(std::mem::size_of::<T>());
+    ptr::write_unaligned((&mut filled as *mut T).cast::<usize>(), written);
+    ptr::copy_nonoverlapping(
+        (&filled as *const T).cast::<u8>(),
+        out.cast::<u8>(),
+        written,
+    );
+    SS_OK
+}
+
+/// Keeps panics from unwinding into C.
+fn guard(body: impl FnOnce() -> c_int) -> c_int {
+    catch_unwind(AssertUnwindSafe(body)).unwrap_or(SS_ERR_INTERNAL)
+}
@@ -0,0 +1,221 @@
+//! Calls the C API on this test binary, from Rust and from the C example.
+
+// `strip` leaves no records to read
+#![cfg(all(
+    any(target_os = "linux", target_os = "macos", target_os = "windows"),
+    not(feature = "strip")
+))]
+
+use std::ffi::{c_char, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::ptr;
+
+use security_scanner::security_test;
+use security_scanner_capi::*;
+
+#[security_test(
+    sql_injection,
+    env_injection,
+    reads_env("DATABASE_URL", "DB_USER"),
+    sla_days = 30,
+    high
+)]
+fn connect() -> bool {
+    std::env::var("DATABASE_URL").is_ok()
+}
+
+fn own_path() -> CString {
+    CString::new(std::env::current_exe().unwrap().to_str().unwrap()).unwrap()
+}
+
+fn empty_record() -> SsRecord {
+    // SAFETY: every field is an integer or a raw pointer
+    let mut record: SsRecord = unsafe { std::mem::zeroed() };
+    record.struct_size = std::mem::size_of::<SsRecord>();
+    record
+}
+
+unsafe fn string(value: *const c_char) -> String {
+    CStr::from_ptr(value).to_str().unwrap().to_string()
+}
+
+#[test]
+fn reads_records_of_own_binary() {
+    connect();
+    unsafe {
+        let mut binary = ptr::null_mut();
+        assert_eq!(ss_open_binary(own_path().as_ptr(), &mut binary), SS_OK);
+
+        let mut record = empty_record();
+        assert_eq!(ss_next_record(binary, &mut record), SS_OK);
+        assert_eq!(string(record.name), "capi::connect");
+        assert_eq!(record.tests, SS_TEST_SQL_INJECTION | SS_TEST_ENV_INJECTION);
+        assert_eq!(record.threat_level, 2);
+        assert_eq!(record.sla_days, 30);
+        assert_eq!(record.test_context, 1);
+        let reads_env: Vec<String> = (0..record.reads_env_len)
+            .map(|index| string(*record.reads_env.add(index)))
+            .collect();
+        assert_eq!(reads_env, ["DATABASE_URL", "DB_USER"]);
+        assert_eq!(record.fixtures_len, 0);
+        assert_eq!(string(record.file), "capi/tests/capi.rs");
+        assert_eq!((record.line, record.column), (24, 4));
+        assert_eq!(record.input_params_len, 0);
+
+        assert_eq!(ss_next_record(binary, &mut record), SS_END);
+        assert_eq!(ss_rewind(binary), SS_OK);
+        assert_eq!(ss_next_record(binary, &mut record), SS_OK);
+
+        let mut integrity: SsIntegrity = std::mem::zeroed();
+        integrity.struct_size = std::mem::size_of::<SsIntegrity>();
+        assert_eq!(ss_get_integrity(binary, &mut integrity), SS_OK);
+        assert_eq!((integrity.verified, integrity.corrupted), (1, 0));
+
+        ss_free(binary);
+    }
+}
+
+#[test]
+fn reports_errors_as_status_codes() {
+    unsafe {
+        let mut binary = ptr::null_mut();
+        let missing = CString::new("/nonexistent/binary").unwrap();
+        assert_eq!(ss_open_binary(missing.as_ptr(), &mut binary), SS_ERR_IO);
+        assert!(binary.is_null());
+        assert_eq!(ss_open_binary(ptr::null(), &mut binary), SS_ERR_NULL);
+
+        assert_eq!(ss_open_binary(own_path().as_ptr(), &mut binary), SS_OK);
+        let mut record = empty_record();
+        record.struct_size = 8;
+        assert_eq!(ss_next_record(binary, &mut record), SS_ERR_STRUCT_SIZE);
+        ss_free(binary);
+
+        let message = CStr::from_ptr(ss_status_message(SS_ERR_NO_METADATA));
+        assert_eq!(
+            message.to_str().unwrap(),
+            "binary contains no security test metadata"
+        );
+    }
+}
+
+#[test]
+fn larger_struct_sizes_keep_the_trailing_bytes() {
+    #[repr(C)]
+    struct Newer {
+        record: SsRecord,
+        future_field: u64,
+    }
+
+    unsafe {
+        let mut binary = ptr::null_mut();
+        assert_eq!(ss_open_binary(own_path().as_ptr(), &mut binary), SS_OK);
+        let mut newer = Newer {
+            record: empty_record(),
+            future_field: 0xAAAA,
+        };
+        newer.record.struct_size = std::mem::size_of::<Newer>();
+        assert_eq!(ss_next_record(binary, &mut newer.record), SS_OK);
+        assert_eq!(newer.record.struct_size, std::mem::size_of::<SsRecord>());
+        assert_eq!(newer.future_field, 0xAAAA);
+        ss_free(binary);
+    }
+}
+
+#[test]
+fn first_version_struct_size_gets_the_first_version_fields() {
+    unsafe {
+        let mut binary = ptr::null_mut();
+        assert_eq!(ss_open_binary(own_path().as_ptr(), &mut binary), SS_OK);
+        let mut record = empty_record();
+        let first_version = std::mem::offset_of!(SsRecord, file);
+        record.struct_size = first_version;
+        assert_eq!(ss_next_record(binary, &mut record), SS_OK);
+        assert_eq!(record.struct_size, first_version);
+        assert_eq!(string(record.name), "capi::connect");
+        assert!(record.file.is_null());
+        assert_eq!(record.line, 0);
+        ss_free(binary);
+    }
+}
+
+/// Compiles the C example and a layout check against the header and the
+/// shared library, on hosts with a C compiler.
+#[test]
+#[cfg(unix)]
+fn c_example_agrees() {
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+    if Command::new(&cc).arg("--version").output().is_err() {
+        eprintln!("skipping: no C compiler ({})", cc);
+        return;
+    }
+    connect();
+
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // Cargo builds the shared library next to this test, in target/<profile>/deps
+    let lib_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("capi");
+    std::fs::create_dir_all(&out_dir).unwrap();
+
+    let build = |source: &Path, binary: &PathBuf| {
+        let status = Command::new(&cc)
+            .args(["-std=c99", "-Wall", "-Werror", "-I"])
+            .arg(crate_dir.join("include"))
+            .arg(source)
+            .arg("-L")
+            .arg(&lib_dir)
+            .arg("-lsecurity_scanner_capi")
+            .arg("-o")
+            .arg(binary)
+            .status()
+            .unwrap();
+        assert!(status.success(), "{} failed to compile", source.display());
+    };
+    let run = |binary: &PathBuf, args: &[&std::ffi::OsStr]| {
+        let output = Command::new(binary)
+            .args(args)
+            .env("LD_LIBRARY_PATH", &lib_dir)
+            .env("DYLD_LIBRARY_PATH", &lib_dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let layout_source = out_dir.join("layout.c");
+    std::fs::write(
+        &layout_source,
+        "#include <stddef.h>\n#include <stdio.h>\n#include \"security_scanner.h\"\n\
+         int main(void) {\n\
+         printf(\"%zu %zu %zu %zu\\n\", sizeof(ss_record), offsetof(ss_record, sla_days),\n\
+         offsetof(ss_record, line), sizeof(ss_integrity));\n\
+         return ss_abi_version() == SS_ABI_VERSION ? 0 : 1;\n}\n",
+    )
+    .unwrap();
+    let layout = out_dir.join("layout");
+    build(&layout_source, &layout);
+    assert_eq!(
+        run(&layout, &[]),
+        format!(
+            "{} {} {} {}\n",
+            std::mem::size_of::<SsRecord>(),
+            std::mem::offset_of!(SsRecord, sla_days),
+            std::mem::offset_of!(SsRecord, line),
+            std::mem::size_of::<SsIntegrity>()
+        )
+    );
+
+    let example = out_dir.join("list_records");
+    build(&crate_dir.join("c/list_records.c"), &example);
+    let exe = std::env::current_exe().unwrap();
+    assert_eq!(
+        run(&example, &[exe.as_os_str()]),
+        "capi::connect tests=0x11 threat_level=2 sla_days=30 reads_env=DATABASE_URL,DB_USER \
+         location=capi/tests/capi.rs:24\n\
+         verified=1 corrupted=0\n"
+    );
+}
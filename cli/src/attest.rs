@@ -0,0 +1,174 @@
+//! `attest`: bind the annotation inventory of built binaries to their
+//! provenance in an in-toto statement.
+//!
+//! The statement's subjects are the binaries, by SHA-256. Its predicate, of
+//! type [`PREDICATE_TYPE`], holds the inventory (every annotated function
+//! found in the binaries, sorted by path) and the SHA-256 of that inventory
+//! serialized as compact JSON with sorted keys, so a verifier can recompute
+//! the digest from the statement alone.
+//!
+//! With `--provenance`, the SLSA provenance (or any in-toto statement) of the
+//! build is read, either bare or wrapped in a DSSE envelope. Every binary
+//! must be one of its subjects, and the predicate records the provenance's
+//! digest and predicate type, tying the inventory to that build. Signing the
+//! statement is left to the attestation tooling of the pipeline.
+//!
+//! Binaries with damaged metadata are refused with `format.corrupted`, and
+//! those with encrypted records that `--metadata-key` doesn't decrypt with
+//! `format.encrypted`: an inventory with records missing must not be
+//! attested.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use security_scanner::extract;
+use security_scanner::{FormatError, ScannerError, SecurityTestMetadata};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+/// Predicate type of the statements written by `attest`.
+pub const PREDICATE_TYPE: &str =
+    "https://github.com/RPDevJesco/security-scanner/attestation/inventory/v1";
+
+/// Statement type of in-toto v1.
+const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+
+/// Runs `attest`, writing the statement to `output` or stdout.
+pub fn run(
+    binaries: &[PathBuf],
+    provenance: Option<&Path>,
+    output: Option<&Path>,
+    metadata_key: Option<&str>,
+) -> Result<(), ScannerError> {
+    let metadata_key = crate::metadata_key(metadata_key)?;
+    let mut subjects = Vec::new();
+    let mut functions: BTreeMap<String, SecurityTestMetadata> = BTreeMap::new();
+    for binary in binaries {
+        let data = std::fs::read(binary).map_err(|err| ScannerError::io("read", binary, err))?;
+        let extraction = match extract::extract_with_key(&data, metadata_key.as_ref()) {
+            Ok(extraction) => extraction,
+            Err(FormatError::NoMetadata) => Default::default(),
+            Err(err) => return Err(err.into()),
+        };
+        extraction.verify()?;
+
+        subjects.push(json!({
+            "name": crate::suggest::display_path(binary),
+            "digest": { "sha256": sha256(&data) },
+        }));
+        for metadata in extraction.functions {
+            functions
+                .entry(metadata.function_name.clone())
+                .or_insert(metadata);
+        }
+    }
+
+    let inventory: Vec<Value> = functions.values().map(inventory_entry).collect();
+    let inventory_digest = sha256(Value::from(inventory.clone()).to_string().as_bytes());
+
+    let mut predicate = json!({
+        "scanner": {
+            "name": "cargo-security-scan",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "inventory": {
+            "digest": { "sha256": inventory_digest },
+            "functions": inventory,
+        },
+    });
+    if let Some(path) = provenance {
+        predicate["provenance"] = bind_provenance(path, &subjects)?;
+    }
+
+    let statement = json!({
+        "_type": STATEMENT_TYPE,
+        "subject": subjects,
+        "predicateType": PREDICATE_TYPE,
+        "predicate": predicate,
+    });
+    let rendered = serde_json::to_string_pretty(&statement).expect("JSON values always serialize");
+    match output {
+        Some(path) => std::fs::write(path, rendered + "\n")
+            .map_err(|err| ScannerError::io("write", path, err)),
+        None => {
+            println!("{}", rendered);
+            Ok(())
+        }
+    }
+}
+
+/// The fields of one function that the inventory attests to.
+fn inventory_entry(metadata: &SecurityTestMetadata) -> Value {
+    let config = &metadata.config;
+    json!({
+        "function": metadata.function_name,
+        "location": metadata.location.as_ref().map(ToString::to_string),
+        "threat_level": config.threat_level.as_str(),
+        "test_types": config.test_types(),
+        "custom_tests": config.custom_tests,
+        "compliance_tags": config.compliance_tags,
+        "reads_env": config.reads_env,
+        "fixtures": config.fixtures,
+        "attack_techniques": config.attack_techniques,
+        "input_params": config
+            .input_params
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        "sla_days": config.sla_days,
+        "test_context": config.test_context,
+    })
+}
+
+/// Checks that the provenance at `path` covers every subject and returns
+/// the predicate's reference to it.
+fn bind_provenance(path: &Path, subjects: &[Value]) -> Result<Value, ScannerError> {
+    let data = std::fs::read(path).map_err(|err| ScannerError::io("read", path, err))?;
+    let parse_error = |message: String| ScannerError::Parse {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let document: Value =
+        serde_json::from_slice(&data).map_err(|err| parse_error(err.to_string()))?;
+    // DSSE envelopes carry the statement base64-encoded in `payload`
+    let statement = match document["payload"].as_str() {
+        Some(payload) => {
+            let decoded = base64::engine::general_purpose::STANDARD
+                .decode(payload)
+                .map_err(|err| parse_error(format!("invalid DSSE payload: {}", err)))?;
+            serde_json::from_slice(&decoded).map_err(|err| parse_error(err.to_string()))?
+        }
+        None => document,
+    };
+
+    let covered: BTreeSet<&str> = statement["subject"]
+        .as_array()
+        .ok_or_else(|| parse_error("not an in-toto statement: no subject".to_string()))?
+        .iter()
+        .filter_map(|subject| subject["digest"]["sha256"].as_str())
+        .collect();
+    for subject in subjects {
+        if !covered.contains(subject["digest"]["sha256"].as_str().unwrap_or_default()) {
+            return Err(ScannerError::Policy(format!(
+                "{} is not a subject of the provenance {}",
+                subject["name"].as_str().unwrap_or_default(),
+                path.display()
+            )));
+        }
+    }
+
+    Ok(json!({
+        "uri": crate::suggest::display_path(path),
+        "digest": { "sha256": sha256(&data) },
+        "predicateType": statement["predicateType"],
+    }))
+}
+
+fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,152 @@
+//! `coverage`: how many security-relevant public functions are annotated.
+//!
+//! A public free function is security-relevant when one of the `suggest`
+//! heuristics matches it (see [`crate::suggest`]) and no
+//! `// security-scanner: ignore(...)` comment covers the match. The command
+//! reports the ratio of those functions lacking `#[security_test]` and, with
+//! `--max-unannotated`, fails when the ratio exceeds the given maximum so
+//! coverage can't erode unnoticed as code grows. With `--json` the report is
+//! printed as a JSON object instead.
+
+use std::fs;
+use std::path::Path;
+
+use security_scanner::ScannerError;
+use syn::{Item, Visibility};
+
+use crate::suggest;
+
+/// A security-relevant public function without an annotation.
+struct Unannotated {
+    location: String,
+    function: String,
+    reasons: Vec<String>,
+}
+
+/// Annotation counts over the scanned files.
+#[derive(Default)]
+struct Coverage {
+    relevant: usize,
+    unannotated: Vec<Unannotated>,
+}
+
+impl Coverage {
+    /// Fraction of relevant functions lacking an annotation, 0 when there
+    /// are none.
+    fn unannotated_ratio(&self) -> f64 {
+        if self.relevant == 0 {
+            0.0
+        } else {
+            self.unannotated.len() as f64 / self.relevant as f64
+        }
+    }
+}
+
+/// Runs `coverage` over every Rust file under `path`.
+pub fn run(path: &Path, max_unannotated: Option<f64>, json: bool) -> Result<(), ScannerError> {
+    if let Some(max) = max_unannotated {
+        if !(0.0..=1.0).contains(&max) {
+            return Err(ScannerError::Usage(format!(
+                "--max-unannotated must be between 0 and 1, got {}",
+                max
+            )));
+        }
+    }
+
+    let mut files = Vec::new();
+    suggest::collect_rust_files(path, &mut files)?;
+
+    let mut coverage = Coverage::default();
+    for file in files {
+        let source =
+            fs::read_to_string(&file).map_err(|err| ScannerError::io("read", &file, err))?;
+        let parsed = match syn::parse_file(&source) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                eprintln!("warning: skipping {}: {}", file.display(), err);
+                continue;
+            }
+        };
+
+        let lines: Vec<&str> = source.lines().collect();
+        let display = suggest::display_path(&file);
+        collect_items(&parsed.items, &lines, &display, &mut coverage);
+    }
+
+    let ratio = coverage.unannotated_ratio();
+    if json {
+        let unannotated: Vec<_> = coverage
+            .unannotated
+            .iter()
+            .map(|function| {
+                serde_json::json!({
+                    "location": function.location,
+                    "function": function.function,
+                    "reasons": function.reasons,
+                })
+            })
+            .collect();
+        let report = serde_json::json!({
+            "relevant": coverage.relevant,
+            "annotated": coverage.relevant - coverage.unannotated.len(),
+            "unannotated_ratio": ratio,
+            "unannotated": unannotated,
+        });
+        println!("{}", report);
+    } else {
+        for function in &coverage.unannotated {
+            eprintln!(
+                "{}: {}: not annotated ({})",
+                function.location,
+                function.function,
+                function.reasons.join("; ")
+            );
+        }
+
+        println!(
+            "{} of {} security-relevant public function(s) annotated, {:.1}% unannotated",
+            coverage.relevant - coverage.unannotated.len(),
+            coverage.relevant,
+            ratio * 100.0
+        );
+    }
+
+    match max_unannotated {
+        Some(max) if ratio > max => Err(ScannerError::Policy(format!(
+            "unannotated ratio {:.1}% exceeds the maximum of {:.1}%",
+            ratio * 100.0,
+            max * 100.0
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn collect_items(items: &[Item], lines: &[&str], display: &str, coverage: &mut Coverage) {
+    for item in items {
+        match item {
+            Item::Fn(item_fn) if matches!(item_fn.vis, Visibility::Public(_)) => {
+                let Some(analysis) = suggest::analyze_fn(item_fn, lines) else {
+                    continue;
+                };
+                if !analysis.has_attribute() {
+                    continue;
+                }
+
+                coverage.relevant += 1;
+                if !suggest::has_attribute(&item_fn.attrs, "security_test") {
+                    coverage.unannotated.push(Unannotated {
+                        location: format!("{}:{}", display, analysis.line + 1),
+                        function: analysis.function,
+                        reasons: analysis.reasons,
+                    });
+                }
+            }
+            Item::Mod(item_mod) if !suggest::is_cfg_test(&item_mod.attrs) => {
+                if let Some((_, items)) = &item_mod.content {
+                    collect_items(items, lines, display, coverage);
+                }
+            }
+            _ => {}
+        }
+    }
+}
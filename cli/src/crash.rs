@@ -0,0 +1,241 @@
+//! `crash`: tell whether a crash happened in annotated code, from a core
+//! dump and the binary that crashed.
+//!
+//! Reads the core dump with [`security_scanner::coredump`] and finds where
+//! the binary was loaded in the crashed process, going by its file name.
+//! The program counter of the thread that received the signal gives the
+//! crashing frame: when it lies in the code of an annotated function, the
+//! crash happened `inside` it. Return addresses on that thread's stack give
+//! the functions the crash happened `beneath`, innermost first, such as an
+//! annotated parser whose call into `memcpy` faulted.
+//!
+//! The stack is scanned rather than unwound, since release builds rarely
+//! keep frame pointers and unwind tables aren't read: every word of the
+//! first 32 KiB above the stack pointer that points into an annotated
+//! function's code counts as a return address. A stale word left by an
+//! earlier call can make a function show up that wasn't on the stack, so
+//! `beneath` functions are triage leads rather than proof.
+//!
+//! The report lists each annotated function with its threat level and test
+//! types, and the most severe threat level among them as the crash's
+//! triage priority. Functions are found through the binary's symbols, so a
+//! stripped binary is refused; the unstripped binary of the same build
+//! will do. Only ELF core dumps of x86-64 and AArch64 Linux processes are
+//! read.
+
+use std::path::Path;
+
+use security_scanner::coredump::CoreDump;
+use security_scanner::debuginfo::DebugInfo;
+use security_scanner::extract;
+use security_scanner::names::DisplayNames;
+use security_scanner::{CodeRange, ScannerError, SecurityTestMetadata};
+use serde_json::json;
+
+use crate::scan::ScanOptions;
+
+/// Words of the stack scanned for return addresses, 32 KiB.
+const STACK_WORDS: usize = 4096;
+
+/// How an annotated function relates to the crash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Relation {
+    /// The crashing frame is in the function
+    Inside,
+    /// The function has a return address on the crashing stack
+    Beneath,
+}
+
+impl Relation {
+    fn as_str(self) -> &'static str {
+        match self {
+            Relation::Inside => "inside",
+            Relation::Beneath => "beneath",
+        }
+    }
+}
+
+/// Runs `crash` on the core dump at `core` of the program `binary`.
+pub fn run(
+    core: &Path,
+    binary: &Path,
+    metadata_key: Option<&str>,
+    offline: bool,
+    json: bool,
+) -> Result<(), ScannerError> {
+    // Named from the workspace of the current directory, as `scan --binary`
+    // names them
+    let names = if json {
+        DisplayNames::default()
+    } else {
+        crate::names::load(&ScanOptions {
+            manifest_path: None,
+            release: false,
+            tests: false,
+            offline,
+            binaries: vec![binary.to_path_buf()],
+            images: Vec::new(),
+            changed_since: None,
+            metadata_key: None,
+        })?
+    };
+    let metadata_key = crate::metadata_key(metadata_key)?;
+    let data = std::fs::read(binary).map_err(|err| ScannerError::io("read", binary, err))?;
+    let mut extraction = extract::extract_with_key(&data, metadata_key.as_ref())?;
+    extraction.verify()?;
+    if let Some(debug_info) = DebugInfo::read(&data)? {
+        debug_info.apply(&mut extraction.functions);
+    }
+    let symbols = extract::function_symbols(&data)?.ok_or_else(|| {
+        ScannerError::Usage(format!(
+            "{} has no symbols; give the unstripped binary of the same build",
+            binary.display()
+        ))
+    })?;
+
+    let core_data = std::fs::read(core).map_err(|err| ScannerError::io("read", core, err))?;
+    let dump = CoreDump::parse(&core_data)?;
+    let file_name = binary
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let load_map = dump.load_map(&file_name, &data)?.ok_or_else(|| {
+        ScannerError::Usage(format!(
+            "the core dump has no file named {} mapped",
+            file_name
+        ))
+    })?;
+
+    // Functions of the binary, to name the crashing frame
+    let functions: Vec<(&str, CodeRange)> = symbols
+        .iter()
+        .flat_map(|(path, symbols)| {
+            symbols
+                .iter()
+                .map(move |symbol| (path.as_str(), symbol.range))
+        })
+        .collect();
+    let function_at = |address: u64| {
+        functions
+            .iter()
+            .find(|(_, range)| range.contains(address))
+            .map(|&(path, _)| path)
+    };
+    let annotated = |path: &str| {
+        extraction
+            .functions
+            .iter()
+            .find(|metadata| metadata.function_name == path)
+    };
+
+    let pc = load_map.link_address(dump.pc);
+    let frame = pc.and_then(function_at);
+    let mut matches: Vec<(Relation, &SecurityTestMetadata)> = Vec::new();
+    if let Some(metadata) = frame.and_then(annotated) {
+        matches.push((Relation::Inside, metadata));
+    }
+    for word in dump.stack_words(STACK_WORDS) {
+        let Some(metadata) = load_map
+            .link_address(word)
+            .and_then(function_at)
+            .and_then(annotated)
+        else {
+            continue;
+        };
+        if !matches
+            .iter()
+            .any(|(_, found)| found.function_name == metadata.function_name)
+        {
+            matches.push((Relation::Beneath, metadata));
+        }
+    }
+    let priority = matches
+        .iter()
+        .map(|(_, metadata)| metadata.config.threat_level)
+        .max();
+
+    if json {
+        let functions: Vec<_> = matches
+            .iter()
+            .map(|(relation, metadata)| {
+                let config = &metadata.config;
+                json!({
+                    "function": metadata.function_name,
+                    "relation": relation.as_str(),
+                    "location": metadata.location.as_ref().map(ToString::to_string),
+                    "threat_level": config.threat_level.to_string(),
+                    "test_types": config.test_types(),
+                    "custom_tests": config.custom_tests,
+                })
+            })
+            .collect();
+        let report = json!({
+            "signal": dump.signal,
+            "signal_name": signal_name(dump.signal),
+            "pc": dump.pc,
+            "frame": {
+                "address": pc,
+                "function": frame,
+            },
+            "annotated": functions,
+            "priority": priority.map(|level| level.to_string()),
+        });
+        println!("{}", report);
+        return Ok(());
+    }
+
+    println!(
+        "signal {}{} at {:#x}, in {}",
+        dump.signal,
+        signal_name(dump.signal).map_or(String::new(), |name| format!(" ({})", name)),
+        dump.pc,
+        match (frame, pc) {
+            (Some(function), _) => function.to_string(),
+            (None, Some(_)) => format!("{} outside any function", file_name),
+            (None, None) => format!("code outside {}", file_name),
+        }
+    );
+    for (relation, metadata) in &matches {
+        let config = &metadata.config;
+        let tests: Vec<String> = config
+            .test_types()
+            .into_iter()
+            .map(|test_type| names.test_type(test_type).to_string())
+            .chain(config.custom_tests.iter().cloned())
+            .collect();
+        println!(
+            "{} {}: {}, {}{}",
+            relation.as_str(),
+            metadata.function_name,
+            names.threat_level(config.threat_level),
+            if tests.is_empty() {
+                "-".to_string()
+            } else {
+                tests.join(", ")
+            },
+            metadata
+                .location
+                .as_ref()
+                .map_or(String::new(), |location| format!(" ({})", location))
+        );
+    }
+    match priority {
+        Some(level) => println!("triage priority: {}", names.threat_level(level)),
+        None => println!("no annotated function on the crashing stack"),
+    }
+    Ok(())
+}
+
+/// Returns the name of a Linux signal that crashes a process.
+fn signal_name(signal: u32) -> Option<&'static str> {
+    Some(match signal {
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        7 => "SIGBUS",
+        8 => "SIGFPE",
+        11 => "SIGSEGV",
+        31 => "SIGSYS",
+        _ => return None,
+    })
+}
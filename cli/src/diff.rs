@@ -0,0 +1,103 @@
+//! Insert-only source edits: rendering them as a unified diff, or applying
+//! them in place.
+
+/// Number of unchanged lines shown around each insertion.
+const CONTEXT: usize = 3;
+
+/// A line to insert before the given 0-based line of the original file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Insertion {
+    pub line: usize,
+    pub text: String,
+}
+
+/// Renders insertions into `original` as a unified diff that `git apply` and
+/// `patch -p1` accept.
+pub fn unified_diff(path: &str, original: &str, insertions: &[Insertion]) -> String {
+    let lines: Vec<&str> = original.lines().collect();
+    let missing_final_newline = !original.is_empty() && !original.ends_with('\n');
+
+    let mut insertions = insertions.to_vec();
+    insertions.sort_by_key(|insertion| insertion.line);
+
+    // Group insertions whose context windows touch into shared hunks
+    let mut hunks: Vec<Vec<&Insertion>> = Vec::new();
+    for insertion in &insertions {
+        match hunks.last_mut() {
+            Some(hunk) if insertion.line <= hunk[hunk.len() - 1].line + 2 * CONTEXT => {
+                hunk.push(insertion)
+            }
+            _ => hunks.push(vec![insertion]),
+        }
+    }
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", path, path);
+    let mut inserted_so_far = 0;
+
+    for hunk in hunks {
+        let start = hunk[0].line.saturating_sub(CONTEXT);
+        let end = (hunk[hunk.len() - 1].line + CONTEXT).min(lines.len());
+        let old_count = end - start;
+        let new_count = old_count + hunk.len();
+
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(start, old_count),
+            range(start + inserted_so_far, new_count)
+        ));
+
+        let mut pending = hunk.iter().peekable();
+        for (index, line) in lines.iter().enumerate().take(end).skip(start) {
+            while let Some(insertion) = pending.next_if(|insertion| insertion.line == index) {
+                out.push_str(&format!("+{}\n", insertion.text));
+            }
+            out.push_str(&format!(" {}\n", line));
+            if missing_final_newline && index + 1 == lines.len() {
+                out.push_str("\\ No newline at end of file\n");
+            }
+        }
+        for insertion in pending {
+            out.push_str(&format!("+{}\n", insertion.text));
+        }
+
+        inserted_so_far += hunk.len();
+    }
+
+    out
+}
+
+/// Applies insertions to `original`, leaving every existing line byte-for-byte
+/// intact. Inserted lines use the line ending of the line they precede.
+pub fn apply(original: &str, insertions: &[Insertion]) -> String {
+    let mut insertions = insertions.to_vec();
+    insertions.sort_by_key(|insertion| insertion.line);
+    let mut pending = insertions.iter().peekable();
+
+    let mut out = String::with_capacity(original.len());
+    for (index, line) in original.split_inclusive('\n').enumerate() {
+        let ending = if line.ends_with("\r\n") { "\r\n" } else { "\n" };
+        while let Some(insertion) = pending.next_if(|insertion| insertion.line == index) {
+            out.push_str(&insertion.text);
+            out.push_str(ending);
+        }
+        out.push_str(line);
+    }
+    for insertion in pending {
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&insertion.text);
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Formats a hunk range; an empty range refers to the line before it.
+fn range(start: usize, count: usize) -> String {
+    if count == 0 {
+        format!("{},0", start)
+    } else {
+        format!("{},{}", start + 1, count)
+    }
+}
@@ -0,0 +1,506 @@
+//! `doctor`: check that annotations survive the user's toolchain.
+//!
+//! Writes a small fixture program with an annotated function and a
+//! `#[security_program]` entry point, builds it with the user's cargo,
+//! rustc, linker and target (`--target` and `--release` as for a real
+//! build), and reads the executable back the way `scan` does. Each step is
+//! reported as a check:
+//!
+//! - `toolchain`: `rustc -vV` runs, giving the compiler version and host
+//! - `build`: the fixture compiles and links
+//! - `sections`: the metadata section is in the executable, which fails
+//!   when a linker script or `--gc-sections` discarded it, or the build ran
+//!   with `SECURITY_SCANNER_STRIP`
+//! - `integrity`: every record passes its checksum
+//! - `round trip`: the records read back hold what the fixture annotated
+//! - `addresses`: the address of the `anchor` function was recorded, so
+//!   `scan` can tell it has code; a warning where it can't be
+//! - `debug info`: the executable's DWARF debug info maps the function to
+//!   its source; a warning without it, as in split or stripped debug info
+//!
+//! The fixture depends on the `security-scanner` the workspace at
+//! `--manifest-path` (or in the current directory) uses, so the check covers
+//! the version the user's annotations are compiled with, and on the
+//! version of this command outside a workspace. It is built under the
+//! workspace's target directory, or the system's temporary directory, in
+//! `security-scan-doctor`, which keeps later runs fast.
+//!
+//! The command fails with an `integration` error when a check fails; with
+//! `--json` the checks are printed as one JSON object.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use security_scanner::debuginfo::DebugInfo;
+use security_scanner::{extract, FormatError, ScannerError, TestType, ThreatLevel};
+
+/// What to build the fixture with.
+pub struct DoctorOptions {
+    pub manifest_path: Option<PathBuf>,
+    /// Target triple; the host when `None`
+    pub target: Option<String>,
+    pub release: bool,
+    pub offline: bool,
+    /// Key that records are encrypted with, as hex digits
+    pub metadata_key: Option<String>,
+}
+
+/// Name of the fixture package, and its crate as named in records.
+const FIXTURE_PACKAGE: &str = "security-scan-doctor";
+const FIXTURE_FUNCTION: &str = "security_scan_doctor::find_user";
+const FIXTURE_PROGRAM: &str = "security_scan_doctor::main";
+
+/// Source of the fixture; `find_user` is on line 4, column 4.
+const FIXTURE_MAIN: &str = r#"use security_scanner::{security_program, security_test};
+
+#[security_test(sql_injection, reads_env("DOCTOR_DATABASE_URL"), critical, anchor)]
+fn find_user(name: &str) -> bool {
+    name.bytes().all(|byte| byte.is_ascii_alphanumeric())
+}
+
+#[security_program(exposure = internal)]
+fn main() {
+    let name = std::env::args().nth(1).unwrap_or_default();
+    std::process::exit(i32::from(!find_user(&name)));
+}
+"#;
+const FIXTURE_LINE: u32 = 4;
+
+/// Outcome of one check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+    /// Not run, because an earlier check failed
+    Skipped,
+}
+
+impl Status {
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+            Status::Skipped => "skipped",
+        }
+    }
+}
+
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+}
+
+/// The checks run so far.
+#[derive(Default)]
+struct Report {
+    checks: Vec<Check>,
+}
+
+impl Report {
+    fn push(&mut self, name: &'static str, status: Status, detail: impl Into<String>) {
+        self.checks.push(Check {
+            name,
+            status,
+            detail: detail.into(),
+        });
+    }
+
+    /// Records `names` as skipped after a failed check.
+    fn skip(&mut self, names: &[&'static str]) {
+        for name in names {
+            self.push(name, Status::Skipped, "an earlier check failed");
+        }
+    }
+}
+
+/// Checks after `build`, in order.
+const READ_CHECKS: &[&str] = &[
+    "sections",
+    "integrity",
+    "round trip",
+    "addresses",
+    "debug info",
+];
+
+/// Runs `doctor`, printing each check.
+pub fn run(options: &DoctorOptions, json: bool) -> Result<(), ScannerError> {
+    let metadata_key = crate::metadata_key(options.metadata_key.as_deref())?;
+    let mut report = Report::default();
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    match Command::new(&rustc).arg("-vV").output() {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout);
+            let field = |key: &str| {
+                version
+                    .lines()
+                    .find_map(|line| line.strip_prefix(key))
+                    .unwrap_or("unknown")
+                    .trim()
+                    .to_string()
+            };
+            report.push(
+                "toolchain",
+                Status::Ok,
+                format!(
+                    "rustc {} on {}, building for {}",
+                    field("release:"),
+                    field("host:"),
+                    options.target.clone().unwrap_or_else(|| field("host:"))
+                ),
+            );
+        }
+        Ok(output) => report.push(
+            "toolchain",
+            Status::Fail,
+            format!("{} -vV failed ({})", rustc, output.status),
+        ),
+        Err(err) => report.push(
+            "toolchain",
+            Status::Fail,
+            format!("failed to run {}: {}", rustc, err),
+        ),
+    }
+
+    let executable = match build_fixture(options) {
+        Ok(executable) => {
+            report.push(
+                "build",
+                Status::Ok,
+                crate::suggest::display_path(&executable),
+            );
+            Some(executable)
+        }
+        Err(detail) => {
+            report.push("build", Status::Fail, detail);
+            None
+        }
+    };
+    match executable {
+        Some(executable) => read_checks(&executable, metadata_key.as_ref(), &mut report)?,
+        None => report.skip(READ_CHECKS),
+    }
+
+    print_report(&report, json);
+    let failed = report
+        .checks
+        .iter()
+        .filter(|check| check.status == Status::Fail)
+        .count();
+    match failed {
+        0 => Ok(()),
+        failed => Err(ScannerError::Integration(format!(
+            "{} of {} doctor check(s) failed",
+            failed,
+            report.checks.len()
+        ))),
+    }
+}
+
+/// Reads the fixture's executable back, running every check after `build`.
+fn read_checks(
+    executable: &Path,
+    metadata_key: Option<&[u8; security_scanner::format::tlv::KEY_LEN]>,
+    report: &mut Report,
+) -> Result<(), ScannerError> {
+    let data =
+        std::fs::read(executable).map_err(|err| ScannerError::io("read", executable, err))?;
+
+    let extraction = match extract::extract_with_key(&data, metadata_key) {
+        Ok(extraction) if extraction.encrypted > 0 => {
+            report.push(
+                "sections",
+                Status::Fail,
+                format!(
+                    "{} record(s) are encrypted with a key other than --metadata-key",
+                    extraction.encrypted
+                ),
+            );
+            report.skip(&READ_CHECKS[1..]);
+            return Ok(());
+        }
+        Ok(extraction) => extraction,
+        Err(FormatError::NoMetadata) => {
+            let cause = if std::env::var_os("SECURITY_SCANNER_STRIP").is_some() {
+                "SECURITY_SCANNER_STRIP is set, which leaves the metadata out"
+            } else {
+                "the linker discarded it; a linker script needs to KEEP the \
+                 `.security_tests` section"
+            };
+            report.push(
+                "sections",
+                Status::Fail,
+                format!("no metadata section in the executable: {}", cause),
+            );
+            report.skip(&READ_CHECKS[1..]);
+            return Ok(());
+        }
+        Err(err) => {
+            report.push("sections", Status::Fail, err.to_string());
+            report.skip(&READ_CHECKS[1..]);
+            return Ok(());
+        }
+    };
+    report.push(
+        "sections",
+        Status::Ok,
+        format!(
+            "{} function record(s){}",
+            extraction.functions.len(),
+            if extraction.program.is_some() {
+                " and a program record"
+            } else {
+                ""
+            }
+        ),
+    );
+
+    match extraction.verify() {
+        Ok(()) => report.push("integrity", Status::Ok, "every record passes its checksum"),
+        Err(err) => report.push("integrity", Status::Fail, err.to_string()),
+    }
+
+    let function = extraction
+        .functions
+        .iter()
+        .find(|function| function.function_name == FIXTURE_FUNCTION);
+    let mut mismatches = Vec::new();
+    match function {
+        Some(function) => {
+            let config = &function.config;
+            if config.test_types() != [TestType::SqlInjection] {
+                mismatches.push(format!("test types {:?}", config.test_types()));
+            }
+            if config.threat_level != ThreatLevel::Critical {
+                mismatches.push(format!("threat level {}", config.threat_level));
+            }
+            if config.reads_env != ["DOCTOR_DATABASE_URL"] {
+                mismatches.push(format!("environment variables {:?}", config.reads_env));
+            }
+            let params: Vec<String> = config
+                .input_params
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            if params != ["name: &str"] {
+                mismatches.push(format!("parameters {:?}", params));
+            }
+            match &function.location {
+                Some(location) if location.line == FIXTURE_LINE => {}
+                Some(location) => mismatches.push(format!("location {}", location)),
+                None => mismatches.push("no location".to_string()),
+            }
+        }
+        None => mismatches.push(format!("no record of {}", FIXTURE_FUNCTION)),
+    }
+    match &extraction.program {
+        Some(program) if program.entry_point == FIXTURE_PROGRAM => {}
+        Some(program) => mismatches.push(format!("program record of {}", program.entry_point)),
+        None => mismatches.push("no program record".to_string()),
+    }
+    if mismatches.is_empty() {
+        report.push(
+            "round trip",
+            Status::Ok,
+            "the records hold what the fixture annotated",
+        );
+    } else {
+        report.push(
+            "round trip",
+            Status::Fail,
+            format!(
+                "the records differ from the fixture: {}",
+                mismatches.join(", ")
+            ),
+        );
+    }
+
+    match function.and_then(|function| function.function_address) {
+        Some(address) => report.push(
+            "addresses",
+            Status::Ok,
+            format!("{} is at {:#x}", FIXTURE_FUNCTION, address),
+        ),
+        None => report.push(
+            "addresses",
+            Status::Warn,
+            "no address recorded for the `anchor` function, so `scan` can't tell whether \
+             annotated functions have code",
+        ),
+    }
+
+    let debug_info = DebugInfo::read(&data)?;
+    let source = debug_info
+        .as_ref()
+        .and_then(|debug_info| debug_info.function(FIXTURE_FUNCTION));
+    match (source, debug_info.is_some()) {
+        (Some(source), _) => report.push(
+            "debug info",
+            Status::Ok,
+            format!(
+                "{} spans {}:{}-{}",
+                FIXTURE_FUNCTION, source.file, source.first_line, source.last_line
+            ),
+        ),
+        (None, true) => report.push(
+            "debug info",
+            Status::Warn,
+            format!("the debug info doesn't describe {}", FIXTURE_FUNCTION),
+        ),
+        (None, false) => report.push(
+            "debug info",
+            Status::Warn,
+            "no DWARF debug info in the executable, so reports lack the end line of functions",
+        ),
+    }
+
+    Ok(())
+}
+
+/// Writes and builds the fixture, returning its executable or why it
+/// couldn't be built.
+fn build_fixture(options: &DoctorOptions) -> Result<PathBuf, String> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let (dependency, target_dir) = workspace(&cargo, options);
+    let root = target_dir.join(FIXTURE_PACKAGE);
+    let fixture = root.join("fixture");
+
+    // Its own `[workspace]` keeps the fixture out of the user's workspace
+    let manifest = format!(
+        "[package]\nname = \"{}\"\nversion = \"0.0.0\"\nedition = \"2021\"\npublish = false\n\n\
+         [dependencies]\nsecurity-scanner = {}\n\n[workspace]\n",
+        FIXTURE_PACKAGE, dependency
+    );
+    std::fs::create_dir_all(fixture.join("src"))
+        .and_then(|()| std::fs::write(fixture.join("Cargo.toml"), manifest))
+        .and_then(|()| std::fs::write(fixture.join("src/main.rs"), FIXTURE_MAIN))
+        .map_err(|err| {
+            format!(
+                "failed to write the fixture to {}: {}",
+                fixture.display(),
+                err
+            )
+        })?;
+
+    let mut command = Command::new(&cargo);
+    command
+        .args([
+            "build",
+            "--message-format=json-render-diagnostics",
+            "--manifest-path",
+        ])
+        .arg(fixture.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(root.join("target"));
+    if let Some(target) = &options.target {
+        command.args(["--target", target]);
+    }
+    if options.release {
+        command.arg("--release");
+    }
+    if options.offline {
+        command.arg("--offline");
+    }
+
+    let output = command
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| format!("failed to run {}: {}", cargo, err))?;
+    if !output.status.success() {
+        let hint = match &options.target {
+            Some(target) => format!(
+                "; check that `rustup target add {}` was run and a linker is configured for it",
+                target
+            ),
+            None => String::new(),
+        };
+        return Err(format!(
+            "cargo build of the fixture failed ({}){}",
+            output.status, hint
+        ));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .flat_map(crate::scan::artifact_paths)
+        .find(|path| {
+            path.file_stem()
+                .is_some_and(|stem| stem.to_string_lossy() == FIXTURE_PACKAGE)
+        })
+        .ok_or_else(|| "cargo reported no executable for the fixture".to_string())
+}
+
+/// Returns the `security-scanner` dependency of the fixture, as a TOML
+/// value, and the directory to build it in.
+fn workspace(cargo: &str, options: &DoctorOptions) -> (String, PathBuf) {
+    let published = format!("\"={}\"", env!("CARGO_PKG_VERSION"));
+
+    let mut command = Command::new(cargo);
+    command.args(["metadata", "--format-version", "1"]);
+    if let Some(manifest_path) = &options.manifest_path {
+        command.arg("--manifest-path").arg(manifest_path);
+    }
+    if options.offline {
+        command.arg("--offline");
+    }
+    let metadata = command
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| serde_json::from_slice::<serde_json::Value>(&output.stdout).ok());
+    let Some(metadata) = metadata else {
+        return (published, std::env::temp_dir());
+    };
+
+    let target_dir = metadata["target_directory"]
+        .as_str()
+        .map_or_else(std::env::temp_dir, PathBuf::from);
+    let source = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|package| package["name"] == "security-scanner")
+        .find_map(|package| package["manifest_path"].as_str())
+        .and_then(|manifest| Path::new(manifest).parent().map(Path::to_path_buf));
+    let dependency = match source {
+        // Debug formatting escapes the path the way TOML strings need
+        Some(dir) => format!("{{ path = {:?} }}", dir.display().to_string()),
+        None => published,
+    };
+    (dependency, target_dir)
+}
+
+fn print_report(report: &Report, json: bool) {
+    if json {
+        let checks: Vec<_> = report
+            .checks
+            .iter()
+            .map(|check| {
+                serde_json::json!({
+                    "check": check.name,
+                    "status": check.status.as_str(),
+                    "detail": check.detail,
+                })
+            })
+            .collect();
+        let report = serde_json::json!({
+            "ok": report.checks.iter().all(|check| check.status != Status::Fail),
+            "checks": checks,
+        });
+        println!("{}", report);
+        return;
+    }
+
+    for check in &report.checks {
+        println!(
+            "{:7} {:10}  {}",
+            check.status.as_str(),
+            check.name,
+            check.detail
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! `ebpf-manifest`: list where to attach uprobes to the annotated functions
+//! of built binaries.
+//!
+//! Builds and reads the binaries the way `scan` does and, for every function
+//! at or above `--threat-level` (`critical` unless given), looks its code up
+//! in the binary's symbol table. The manifest is a JSON document with, per
+//! binary, its absolute path and a probe per copy of each function's code:
+//!
+//! - `symbol`: the mangled symbol, as `uprobe:<path>:<symbol>` wants it
+//! - `address`: the virtual address of the code in the file, as `bpftrace`
+//!   takes probe addresses
+//! - `offset`: the file offset of the code, as libbpf's
+//!   `bpf_program__attach_uprobe` takes it
+//! - `size`, the function's path, threat level, test types and custom tests
+//!
+//! With `--format bpftrace` the same probes are printed as a `bpftrace`
+//! script that prints the process, thread and function on every call, so
+//! `bpftrace manifest.bt` attaches to them all in one step.
+//!
+//! Only ELF binaries can carry uprobes; functions are looked up like `scan`
+//! checks for code, so those of stripped binaries, of trait impls and of
+//! generic types are named in a warning instead of probed.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use clap::ValueEnum;
+use security_scanner::extract::{self, FunctionSymbol};
+use security_scanner::{FormatError, ScannerError, SecurityTestMetadata, ThreatLevel};
+use serde_json::{json, Value};
+
+use crate::scan::ScanOptions;
+
+/// How `ebpf-manifest` prints the probes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// A JSON manifest
+    Json,
+    /// A `bpftrace` script attaching to every probe
+    Bpftrace,
+}
+
+/// One place to attach a uprobe.
+struct Probe<'a> {
+    metadata: &'a SecurityTestMetadata,
+    symbol: FunctionSymbol,
+}
+
+/// Runs `ebpf-manifest` for the functions at `threat_level` or above.
+pub fn run(
+    options: &ScanOptions,
+    threat_level: ThreatLevel,
+    format: Format,
+) -> Result<(), ScannerError> {
+    let metadata_key = crate::metadata_key(options.metadata_key.as_deref())?;
+    let mut extractions = Vec::new();
+    for artifact in crate::scan::artifacts(options)? {
+        let data =
+            std::fs::read(&artifact).map_err(|err| ScannerError::io("read", &artifact, err))?;
+        let extraction = match extract::extract_with_key(&data, metadata_key.as_ref()) {
+            Ok(extraction) => extraction,
+            Err(FormatError::NoMetadata) => continue,
+            Err(err) => return Err(err.into()),
+        };
+        extraction.verify()?;
+        let symbols = extract::function_symbols(&data)?.unwrap_or_default();
+        extractions.push((absolute(&artifact), extraction, symbols));
+    }
+
+    let mut binaries: BTreeMap<String, Vec<Probe<'_>>> = BTreeMap::new();
+    let mut unprobed = Vec::new();
+    for (path, extraction, symbols) in &extractions {
+        let probes = binaries.entry(path.clone()).or_default();
+        for metadata in &extraction.functions {
+            if metadata.config.threat_level < threat_level {
+                continue;
+            }
+            match symbols.get(&metadata.function_name) {
+                Some(copies) if !metadata.function_name.contains('<') => {
+                    probes.extend(copies.iter().map(|symbol| Probe {
+                        metadata,
+                        symbol: symbol.clone(),
+                    }));
+                }
+                _ => unprobed.push(metadata.function_name.as_str()),
+            }
+        }
+    }
+    binaries.retain(|_, probes| !probes.is_empty());
+
+    match format {
+        Format::Json => print_json(&binaries, threat_level),
+        Format::Bpftrace => print_bpftrace(&binaries),
+    }
+
+    if !unprobed.is_empty() {
+        unprobed.sort_unstable();
+        unprobed.dedup();
+        eprintln!(
+            "warning: functions without a symbol to attach to: {}",
+            unprobed.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Returns the absolute path of a binary, which probes must be given.
+fn absolute(path: &Path) -> String {
+    std::fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+fn print_json(binaries: &BTreeMap<String, Vec<Probe<'_>>>, threat_level: ThreatLevel) {
+    let binaries: Vec<Value> = binaries
+        .iter()
+        .map(|(path, probes)| {
+            let probes: Vec<Value> = probes
+                .iter()
+                .map(|probe| {
+                    let config = &probe.metadata.config;
+                    json!({
+                        "function": probe.metadata.function_name,
+                        "symbol": probe.symbol.symbol,
+                        "address": probe.symbol.range.address,
+                        "offset": probe.symbol.file_offset,
+                        "size": probe.symbol.range.size,
+                        "threat_level": config.threat_level.to_string(),
+                        "test_types": config.test_types(),
+                        "custom_tests": config.custom_tests,
+                    })
+                })
+                .collect();
+            json!({ "path": path, "probes": probes })
+        })
+        .collect();
+    let manifest = json!({
+        "threat_level": threat_level.to_string(),
+        "binaries": binaries,
+    });
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&manifest).expect("manifests always serialize")
+    );
+}
+
+fn print_bpftrace(binaries: &BTreeMap<String, Vec<Probe<'_>>>) {
+    println!("#!/usr/bin/env bpftrace");
+    for (path, probes) in binaries {
+        for probe in probes {
+            let config = &probe.metadata.config;
+            let tests: Vec<String> = config
+                .test_types()
+                .into_iter()
+                .map(|test_type| test_type.to_string())
+                .chain(config.custom_tests.iter().cloned())
+                .collect();
+            println!();
+            println!(
+                "// {}: {}, {}",
+                probe.metadata.function_name,
+                config.threat_level,
+                if tests.is_empty() {
+                    "-".to_string()
+                } else {
+                    tests.join(", ")
+                }
+            );
+            println!(
+                "uprobe:{}:{:#x} {{ printf(\"%d %d %s\\n\", pid, tid, \"{}\"); }}",
+                path, probe.symbol.range.address, probe.metadata.function_name
+            );
+        }
+    }
+}
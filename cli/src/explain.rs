@@ -0,0 +1,401 @@
+//! `explain`: print everything known about one annotated function.
+//!
+//! Builds and reads the binaries the way `scan` does, with the same options,
+//! and reports on a single function, which makes it the place to start when
+//! a gate fails on one. The function is named by its path, or by the end of
+//! its path (`auth::login` for `my_crate::auth::login`) when that matches a
+//! single function.
+//!
+//! The report lists:
+//!
+//! - the annotation: test types, threat level and CVSS score, the
+//!   per-profile levels, capabilities, STRIDE categories, ATT&CK techniques,
+//!   compliance tags, environment variables and fixtures
+//! - where the function is: its source location, with the last line of its
+//!   code from debug info, its parameters, its binaries and the address of
+//!   its code in them
+//! - the payload engines for its test types: `sql_injection` has the
+//!   `security_scanner::payloads::SQL_INJECTION` corpus, `timing_attack`
+//!   the `security_scanner::timing` harness and `race_condition` the
+//!   `security_scanner::concurrency` stress test, which `generate_tests`
+//!   runs; the other types and custom tests need an external engine
+//! - what `scan` warns about it: banned API calls, missing code, critical
+//!   code built without overflow checks and `buffer_overflow` code in
+//!   binaries without overflow mitigations
+//! - its policy status: the threat level weighed by program exposure and
+//!   the remediation deadline under the default SLA policy
+//!
+//! The text report names threat levels and test types with the workspace's
+//! display names (see [`crate::names`]). With `--json` the report is one
+//! JSON object, with identifiers for both and each finding under a stable
+//! `kind`.
+
+use std::collections::BTreeMap;
+
+use security_scanner::hardening::Hardening;
+use security_scanner::names::DisplayNames;
+use security_scanner::payloads;
+use security_scanner::severity::effective_threat_level;
+use security_scanner::sla::SlaPolicy;
+use security_scanner::{Capabilities, FormatError, ScannerError, TestType};
+
+use crate::scan::{self, Found, ScanOptions};
+
+/// One thing `scan` warns about in a function.
+struct Finding {
+    /// Stable identifier, for `--json`
+    kind: &'static str,
+    message: String,
+}
+
+/// Runs `explain` for the function named `function`.
+pub fn run(function: &str, options: &ScanOptions, json: bool) -> Result<(), ScannerError> {
+    let names = if json {
+        DisplayNames::default()
+    } else {
+        crate::names::load(options)?
+    };
+    let inventory = scan::collect(options)?;
+    let found = find(&inventory.functions, function, inventory.scanned)?;
+    let findings = findings(found, &inventory.hardening);
+    if json {
+        print_json(found, &findings);
+    } else {
+        print_text(found, &findings, &names);
+    }
+
+    match inventory.damaged {
+        0 => Ok(()),
+        count => Err(FormatError::Corrupted { count }.into()),
+    }
+}
+
+/// Looks up a function by its path or the end of it.
+fn find<'a>(
+    functions: &'a BTreeMap<String, Found>,
+    function: &str,
+    scanned: usize,
+) -> Result<&'a Found, ScannerError> {
+    if let Some(found) = functions.get(function) {
+        return Ok(found);
+    }
+
+    let suffix = format!("::{}", function);
+    let matches: Vec<&Found> = functions
+        .values()
+        .filter(|found| found.metadata.function_name.ends_with(&suffix))
+        .collect();
+    match matches.as_slice() {
+        [found] => Ok(found),
+        [] => Err(ScannerError::Usage(format!(
+            "no annotated function `{}` in the {} binary(ies) with metadata",
+            function, scanned
+        ))),
+        _ => Err(ScannerError::Usage(format!(
+            "`{}` matches several functions, give more of the path: {}",
+            function,
+            matches
+                .iter()
+                .map(|found| found.metadata.function_name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+/// Returns the built-in engine for a test type, by path, with the number
+/// of payloads when it is a corpus.
+fn payload_engine(test_type: TestType) -> Option<(&'static str, Option<usize>)> {
+    match test_type {
+        TestType::SqlInjection => Some((
+            "security_scanner::payloads::SQL_INJECTION",
+            Some(payloads::SQL_INJECTION.len()),
+        )),
+        TestType::RaceCondition => Some(("security_scanner::concurrency::stress", None)),
+        TestType::TimingAttack => Some(("security_scanner::timing::assert_constant_time", None)),
+        _ => None,
+    }
+}
+
+/// Returns what `scan` warns about in `found`.
+fn findings(found: &Found, hardening: &BTreeMap<String, Hardening>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let banned_calls = &found.metadata.config.banned_calls;
+    if !banned_calls.is_empty() {
+        findings.push(Finding {
+            kind: "banned_calls",
+            message: format!("calls banned APIs: {}", banned_calls.join(", ")),
+        });
+    }
+    if found.has_code == Some(false) {
+        findings.push(Finding {
+            kind: "no_code",
+            message: "has no code in its binaries, removed as dead code or inlined into every \
+                      caller; `inline_never` and `anchor` keep it"
+                .to_string(),
+        });
+    }
+    if found.unchecked_overflow() {
+        findings.push(Finding {
+            kind: "overflow_checks",
+            message: "is critical but was built without overflow checks".to_string(),
+        });
+    }
+    for (binary, missing) in found.unmitigated_overflow(hardening) {
+        findings.push(Finding {
+            kind: "overflow_mitigations",
+            message: format!(
+                "is tested for buffer overflows but {} has no {}",
+                binary,
+                missing.join(", no ")
+            ),
+        });
+    }
+    findings
+}
+
+fn capability_names(capabilities: Capabilities) -> Vec<&'static str> {
+    [
+        ("net", capabilities.net),
+        ("fs_read", capabilities.fs_read),
+        ("fs_write", capabilities.fs_write),
+        ("spawn", capabilities.spawn),
+    ]
+    .into_iter()
+    .filter_map(|(name, declared)| declared.then_some(name))
+    .collect()
+}
+
+/// Joins `items`, or `-` when there are none.
+fn list<T: ToString>(items: &[T]) -> String {
+    if items.is_empty() {
+        "-".to_string()
+    } else {
+        items
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn print_text(found: &Found, findings: &[Finding], names: &DisplayNames) {
+    let metadata = &found.metadata;
+    let config = &metadata.config;
+    // Test type names can outgrow the label column
+    let field = |label: &str, value: String| println!("  {:15} {}", label, value);
+
+    println!("{}", metadata.function_name);
+    field(
+        "location",
+        match &metadata.location {
+            Some(location) if location.end_line > location.line => {
+                format!("{} to line {}", location, location.end_line)
+            }
+            Some(location) => location.to_string(),
+            None => "unknown".to_string(),
+        },
+    );
+    field("parameters", list(&config.input_params));
+    field("binaries", list(&found.artifacts));
+    field(
+        "code",
+        match (found.code.as_slice(), found.has_code) {
+            ([], Some(true)) => "present, address unknown".to_string(),
+            ([], Some(false)) => "none".to_string(),
+            ([], None) => "unknown".to_string(),
+            (code, _) => code
+                .iter()
+                .map(|(binary, range)| format!("{} in {}", range, binary))
+                .collect::<Vec<_>>()
+                .join(", "),
+        },
+    );
+    if !metadata.instances.is_empty() {
+        field("instances", list(&metadata.instances));
+    }
+
+    println!("annotation");
+    let tests: Vec<&str> = config
+        .test_types()
+        .into_iter()
+        .map(|test_type| names.test_type(test_type))
+        .collect();
+    field("tests", list(&tests));
+    if !config.custom_tests.is_empty() {
+        field("custom tests", list(&config.custom_tests));
+    }
+    field(
+        "threat level",
+        names.threat_level(config.threat_level).to_string(),
+    );
+    if let Some(cvss) = &config.cvss {
+        field("cvss", cvss.to_string());
+    }
+    if let Some(levels) = config.profile_threat_levels {
+        field(
+            "by profile",
+            format!(
+                "debug {}, release {}",
+                names.threat_level(levels.debug),
+                names.threat_level(levels.release)
+            ),
+        );
+    }
+    field(
+        "capabilities",
+        match config.capabilities {
+            Some(capabilities) => match capability_names(capabilities).as_slice() {
+                [] => "none".to_string(),
+                names => names.join(", "),
+            },
+            None => "undeclared".to_string(),
+        },
+    );
+    field("stride", list(&config.stride));
+    field("attack", list(&config.attack_techniques));
+    field("compliance", list(&config.compliance_tags));
+    field("reads env", list(&config.reads_env));
+    field("fixtures", list(&config.fixtures));
+    field("test context", config.test_context.to_string());
+    if let Some(build) = &metadata.build {
+        field("build", build.to_string());
+    }
+
+    println!("payload engines");
+    for test_type in config.test_types() {
+        field(
+            names.test_type(test_type),
+            match payload_engine(test_type) {
+                Some((engine, Some(payloads))) => format!(
+                    "{} ({} payloads), run by `generate_tests`",
+                    engine, payloads
+                ),
+                Some((engine, None)) => format!("{}, run by `generate_tests`", engine),
+                None => "none built in".to_string(),
+            },
+        );
+    }
+    for custom in &config.custom_tests {
+        field(custom, "scanner plugin".to_string());
+    }
+
+    println!("findings");
+    if findings.is_empty() {
+        println!("  none");
+    }
+    for finding in findings {
+        println!("  - {}", finding.message);
+    }
+
+    println!("policy");
+    let effective = names.threat_level(effective_threat_level(
+        config.threat_level,
+        metadata.exposure,
+    ));
+    field(
+        "effective level",
+        match metadata.exposure {
+            Some(exposure) => format!("{}, in a program with {} exposure", effective, exposure),
+            None => effective.to_string(),
+        },
+    );
+    field(
+        "deadline",
+        match SlaPolicy::default().deadline_days_exposed(config, metadata.exposure) {
+            Some(days) if config.sla_days.is_some() => format!("{} days, from `sla_days`", days),
+            Some(days) => format!("{} days, the default for {} functions", days, effective),
+            None => "none".to_string(),
+        },
+    );
+}
+
+fn print_json(found: &Found, findings: &[Finding]) {
+    let metadata = &found.metadata;
+    let config = &metadata.config;
+    let effective = effective_threat_level(config.threat_level, metadata.exposure);
+
+    let payload_engines: Vec<_> = config
+        .test_types()
+        .into_iter()
+        .map(|test_type| {
+            let engine = payload_engine(test_type);
+            serde_json::json!({
+                "test_type": test_type,
+                "engine": engine.map(|(engine, _)| engine),
+                "payloads": engine.and_then(|(_, payloads)| payloads),
+            })
+        })
+        .collect();
+    let findings: Vec<_> = findings
+        .iter()
+        .map(|finding| {
+            serde_json::json!({
+                "kind": finding.kind,
+                "message": finding.message,
+            })
+        })
+        .collect();
+
+    let report = serde_json::json!({
+        "function": metadata.function_name,
+        "location": metadata.location.as_ref().map(ToString::to_string),
+        "end_line": metadata
+            .location
+            .as_ref()
+            .map(|location| location.end_line)
+            .filter(|end_line| *end_line > 0),
+        "input_params": config
+            .input_params
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        "binaries": found.artifacts,
+        "has_code": found.has_code,
+        "code": found
+            .code
+            .iter()
+            .map(|(binary, range)| serde_json::json!({
+                "binary": binary,
+                "address": range.address,
+                "size": range.size,
+            }))
+            .collect::<Vec<_>>(),
+        "instances": metadata.instances,
+        "test_types": config.test_types(),
+        "custom_tests": config.custom_tests,
+        "threat_level": config.threat_level.to_string(),
+        "cvss": config.cvss.as_ref().map(|cvss| serde_json::json!({
+            "score": cvss.score(),
+            "vector": cvss.vector,
+        })),
+        "threat_level_by_profile": config.profile_threat_levels.map(|levels| {
+            serde_json::json!({
+                "debug": levels.debug.to_string(),
+                "release": levels.release.to_string(),
+            })
+        }),
+        "capabilities": config.capabilities.map(capability_names),
+        "stride": config.stride,
+        "attack_techniques": config.attack_techniques,
+        "compliance_tags": config.compliance_tags,
+        "reads_env": config.reads_env,
+        "fixtures": config.fixtures,
+        "test_context": config.test_context,
+        "build": metadata.build.as_ref().map(|build| serde_json::json!({
+            "rustc_version": build.rustc_version,
+            "panic_unwind": build.panic_unwind,
+            "overflow_checks": build.overflow_checks,
+            "debug_assertions": build.debug_assertions,
+        })),
+        "payload_engines": payload_engines,
+        "findings": findings,
+        "policy": {
+            "exposure": metadata.exposure,
+            "effective_threat_level": effective.to_string(),
+            "sla_days": SlaPolicy::default().deadline_days_exposed(config, metadata.exposure),
+            "sla_from_annotation": config.sla_days.is_some(),
+        },
+    });
+    println!("{}", report);
+}
@@ -0,0 +1,115 @@
+//! `// security-scanner: ignore(...)` comments.
+//!
+//! Code that shouldn't carry metadata at all can opt out of individual
+//! suggestions with a comment directly above the function (or between its
+//! attributes):
+//!
+//! ```text
+//! // security-scanner: ignore(sql_injection) reason="uses bound params"
+//! pub fn find_user(pool: &Pool, name: &str) -> User { ... }
+//! ```
+//!
+//! `ignore(...)` takes test type names (`sql_injection`, `buffer_overflow`,
+//! ...) or capability names (`spawn`); a bare `ignore` covers every
+//! suggestion for the function. The `reason` is optional but is recorded
+//! alongside each ignored suggestion.
+
+/// Prefix identifying a directive comment.
+const DIRECTIVE_PREFIX: &str = "security-scanner:";
+
+/// One parsed `ignore` directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreDirective {
+    /// Ignored suggestion names, or `None` for everything
+    pub targets: Option<Vec<String>>,
+    pub reason: Option<String>,
+}
+
+impl IgnoreDirective {
+    /// Returns whether this directive covers the named suggestion.
+    pub fn covers(&self, name: &str) -> bool {
+        match &self.targets {
+            Some(targets) => targets.iter().any(|target| target == name),
+            None => true,
+        }
+    }
+}
+
+/// Collects the directives that apply to an item.
+///
+/// `item_line` is the 0-based first line of the item including attributes and
+/// `sig_line` the line of its signature. Directives are read from the comment
+/// block immediately above the item and from comment lines between its
+/// attributes.
+pub fn directives_for(
+    lines: &[&str],
+    item_line: usize,
+    sig_line: usize,
+) -> Vec<Result<IgnoreDirective, String>> {
+    let mut directives = Vec::new();
+
+    let mut above = item_line;
+    while above > 0 && lines[above - 1].trim_start().starts_with("//") {
+        above -= 1;
+    }
+
+    for line in lines.iter().take(sig_line).skip(above) {
+        if let Some(parsed) = parse_comment(line) {
+            directives.push(parsed);
+        }
+    }
+
+    directives
+}
+
+/// Parses a single source line, returning `None` if it isn't a directive.
+pub fn parse_comment(line: &str) -> Option<Result<IgnoreDirective, String>> {
+    let comment = line.trim_start().strip_prefix("//")?;
+    // Doc comments are never directives
+    if comment.starts_with('/') || comment.starts_with('!') {
+        return None;
+    }
+
+    let directive = comment.trim_start().strip_prefix(DIRECTIVE_PREFIX)?.trim();
+    Some(parse_directive(directive))
+}
+
+fn parse_directive(directive: &str) -> Result<IgnoreDirective, String> {
+    let rest = directive
+        .strip_prefix("ignore")
+        .ok_or_else(|| format!("unknown security-scanner directive \"{}\"", directive))?;
+
+    let (targets, rest) = match rest.trim_start().strip_prefix('(') {
+        Some(list) => {
+            let (list, rest) = list
+                .split_once(')')
+                .ok_or_else(|| "unterminated ignore(...) list".to_string())?;
+            let targets: Vec<String> = list
+                .split(',')
+                .map(|target| target.trim().to_string())
+                .filter(|target| !target.is_empty())
+                .collect();
+            (Some(targets), rest)
+        }
+        None => (None, rest),
+    };
+
+    let rest = rest.trim();
+    let reason = if rest.is_empty() {
+        None
+    } else {
+        let value = rest
+            .strip_prefix("reason")
+            .map(str::trim_start)
+            .and_then(|value| value.strip_prefix('='))
+            .map(str::trim)
+            .ok_or_else(|| format!("unexpected \"{}\" after ignore", rest))?;
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .ok_or_else(|| "reason must be a double-quoted string".to_string())?;
+        Some(value.to_string())
+    };
+
+    Ok(IgnoreDirective { targets, reason })
+}
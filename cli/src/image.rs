@@ -0,0 +1,359 @@
+//! Binaries of container images, for `scan --image`.
+//!
+//! An image is given as a tarball or as a reference, which is saved to one
+//! with `docker save`; `podman` works through its `docker` alias. Both the
+//! tarballs `docker save` writes, with a `manifest.json` listing the layers,
+//! and OCI image layouts, with an `index.json`, are read. Of an index with
+//! several manifests, such as a multi-platform image, the first is taken.
+//!
+//! The layers are applied in order, honoring whiteout files, so what is
+//! scanned is the filesystem the image runs with: a binary deleted or
+//! replaced by a later layer isn't scanned. Every ELF file of it is read by
+//! `scan` like a binary given with `--binary`, and those without metadata
+//! are skipped. Uncompressed layers are read directly; gzip and zstd layers
+//! are decompressed with the `gzip` and `zstd` commands.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use security_scanner::ScannerError;
+use serde_json::Value;
+
+/// Size of a tar block.
+const BLOCK: usize = 512;
+
+/// Prefix of a whiteout file, deleting the file it names from lower layers.
+const WHITEOUT: &str = ".wh.";
+
+/// Whiteout file deleting the contents of its directory in lower layers.
+const OPAQUE: &str = ".wh..wh..opq";
+
+/// Returns the ELF files of `image` by their path from the image's root,
+/// such as `usr/local/bin/app`.
+pub(crate) fn binaries(image: &str) -> Result<BTreeMap<String, Vec<u8>>, ScannerError> {
+    let archive = if Path::new(image).exists() {
+        std::fs::read(image).map_err(|err| ScannerError::io("read", Path::new(image), err))?
+    } else {
+        save(image)?
+    };
+    let invalid = |message: &str| ScannerError::Parse {
+        path: image.into(),
+        message: message.to_string(),
+    };
+    let files = read_tar(&archive).ok_or_else(|| invalid("not a tar archive"))?;
+    let file = |name: &str| {
+        files
+            .get(name)
+            .ok_or_else(|| invalid(&format!("image has no {}", name)))
+    };
+    let json = |name: &str| {
+        serde_json::from_slice::<Value>(file(name)?)
+            .map_err(|err| invalid(&format!("{}: {}", name, err)))
+    };
+
+    let layers: Vec<String> = if files.contains_key("manifest.json") {
+        strings(&json("manifest.json")?[0]["Layers"])
+    } else {
+        let mut manifest = json("index.json")?;
+        // An index lists manifests, or other indexes for multi-platform
+        // images
+        while let Some(digest) = manifest["manifests"][0]["digest"].as_str() {
+            manifest = json(&blob(digest))?;
+        }
+        manifest["layers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|layer| layer["digest"].as_str().map(blob))
+            .collect()
+    };
+    if layers.is_empty() {
+        return Err(invalid("image has no layers"));
+    }
+
+    let mut binaries = BTreeMap::new();
+    for layer in &layers {
+        let data = decompress(file(layer)?)?;
+        let entries =
+            tar_entries(&data).ok_or_else(|| invalid(&format!("{} isn't a tar archive", layer)))?;
+        apply_layer(&mut binaries, entries);
+    }
+    Ok(binaries)
+}
+
+/// Saves the image `reference` with `docker save`.
+fn save(reference: &str) -> Result<Vec<u8>, ScannerError> {
+    let output = Command::new("docker")
+        .args(["save", reference])
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| ScannerError::Integration(format!("failed to run docker: {}", err)))?;
+    if !output.status.success() {
+        return Err(ScannerError::Integration(format!(
+            "docker save {} failed ({})",
+            reference, output.status
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Returns the path of a blob of an OCI image layout.
+fn blob(digest: &str) -> String {
+    format!("blobs/{}", digest.replacen(':', "/", 1))
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|value| value.as_str().map(str::to_string))
+        .collect()
+}
+
+/// Decompresses a layer going by its magic bytes, through the `gzip` or
+/// `zstd` command.
+fn decompress(layer: &[u8]) -> Result<Vec<u8>, ScannerError> {
+    let tool = if layer.starts_with(&[0x1f, 0x8b]) {
+        "gzip"
+    } else if layer.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        "zstd"
+    } else {
+        return Ok(layer.to_vec());
+    };
+    let mut child = Command::new(tool)
+        .args(["-dc"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|err| ScannerError::Integration(format!("failed to run {}: {}", tool, err)))?;
+    // Written from another thread, so a full stdout pipe can't block it
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let output = std::thread::scope(|scope| {
+        scope.spawn(move || stdin.write_all(layer));
+        child.wait_with_output()
+    })
+    .map_err(|err| ScannerError::Integration(format!("failed to run {}: {}", tool, err)))?;
+    if !output.status.success() {
+        return Err(ScannerError::Integration(format!(
+            "{} -dc failed ({})",
+            tool, output.status
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// An entry of a tar archive.
+struct Entry<'a> {
+    path: String,
+    kind: EntryKind<'a>,
+}
+
+enum EntryKind<'a> {
+    File(&'a [u8]),
+    /// A hard link to the file at the path given
+    Link(String),
+    /// A symbolic link to the path given, relative to the root
+    Symlink(String),
+    /// Directories and devices
+    Other,
+}
+
+/// Reads the files of a tar archive, `None` when it is malformed, with
+/// links resolved to the files they point to. Paths are relative to the
+/// root, without a leading `./` or `/`.
+fn read_tar(data: &[u8]) -> Option<BTreeMap<String, &[u8]>> {
+    let mut files = BTreeMap::new();
+    for entry in tar_entries(data)? {
+        let data = match entry.kind {
+            EntryKind::File(data) => data,
+            // `docker save` links layers shared between images
+            EntryKind::Link(target) | EntryKind::Symlink(target) => match files.get(&target) {
+                Some(&data) => data,
+                None => continue,
+            },
+            EntryKind::Other => continue,
+        };
+        files.insert(entry.path, data);
+    }
+    Some(files)
+}
+
+/// Reads the entries of a tar archive in order, `None` when it is
+/// malformed, with paths as [`read_tar`] gives them.
+fn tar_entries(data: &[u8]) -> Option<Vec<Entry<'_>>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    // Names given by a GNU long name or PAX header for the next entry
+    let mut long_path = None;
+    let mut long_link = None;
+    while offset + BLOCK <= data.len() {
+        let header = &data[offset..offset + BLOCK];
+        if header.iter().all(|&byte| byte == 0) {
+            break;
+        }
+        let size = usize::try_from(octal(&header[124..136])?).ok()?;
+        let body = data.get(offset + BLOCK..offset + BLOCK + size)?;
+        offset += BLOCK + size.div_ceil(BLOCK) * BLOCK;
+
+        let kind = header[156];
+        match kind {
+            b'L' => long_path = Some(text(body)),
+            b'K' => long_link = Some(text(body)),
+            b'x' => {
+                for (key, value) in pax_records(body) {
+                    match key {
+                        "path" => long_path = Some(value.to_string()),
+                        "linkpath" => long_link = Some(value.to_string()),
+                        _ => {}
+                    }
+                }
+            }
+            b'g' => {}
+            _ => {
+                let path = long_path.take().unwrap_or_else(|| {
+                    let name = text(&header[..100]);
+                    let prefix = if &header[257..262] == b"ustar" {
+                        text(&header[345..500])
+                    } else {
+                        String::new()
+                    };
+                    if prefix.is_empty() {
+                        name
+                    } else {
+                        format!("{}/{}", prefix, name)
+                    }
+                });
+                let link = long_link.take().unwrap_or_else(|| text(&header[157..257]));
+                entries.push(Entry {
+                    path: normalize(&path),
+                    kind: match kind {
+                        b'0' | 0 | b'7' => EntryKind::File(body),
+                        b'1' => EntryKind::Link(normalize(&link)),
+                        b'2' => EntryKind::Symlink(resolve(&normalize(&path), &link)),
+                        _ => EntryKind::Other,
+                    },
+                });
+            }
+        }
+    }
+    Some(entries)
+}
+
+/// Applies the entries of a layer to the ELF files of the layers below.
+fn apply_layer(binaries: &mut BTreeMap<String, Vec<u8>>, entries: Vec<Entry<'_>>) {
+    // Whiteouts apply to the layers below, whatever their place in the layer
+    for entry in &entries {
+        let (dir, name) = match entry.path.rsplit_once('/') {
+            Some((dir, name)) => (format!("{}/", dir), name),
+            None => (String::new(), entry.path.as_str()),
+        };
+        if name == OPAQUE {
+            binaries.retain(|existing, _| !existing.starts_with(&dir));
+        } else if let Some(deleted) = name.strip_prefix(WHITEOUT) {
+            let deleted = format!("{}{}", dir, deleted);
+            let under = format!("{}/", deleted);
+            binaries.retain(|existing, _| existing != &deleted && !existing.starts_with(&under));
+        }
+    }
+    for entry in entries {
+        let name = entry.path.rsplit('/').next().unwrap_or_default();
+        if name.starts_with(WHITEOUT) {
+            continue;
+        }
+        let data = match entry.kind {
+            EntryKind::File(data) => Some(data.to_vec()),
+            EntryKind::Link(target) => binaries.get(&target).cloned(),
+            // The file it points to is scanned by its own path
+            EntryKind::Symlink(_) | EntryKind::Other => None,
+        };
+        match data {
+            Some(data) if data.starts_with(b"\x7fELF") => {
+                binaries.insert(entry.path, data);
+            }
+            // Anything else replaces a binary of a layer below
+            _ => {
+                binaries.remove(&entry.path);
+            }
+        }
+    }
+}
+
+/// Parses a tar number: octal digits, or base 256 when the high bit of the
+/// first byte is set.
+fn octal(field: &[u8]) -> Option<u64> {
+    if field[0] & 0x80 != 0 {
+        return field[1..]
+            .iter()
+            .try_fold(u64::from(field[0] & 0x7f), |value, &byte| {
+                value.checked_mul(256)?.checked_add(u64::from(byte))
+            });
+    }
+    let digits = text(field);
+    let digits = digits.trim();
+    if digits.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(digits, 8).ok()
+}
+
+/// Returns a NUL-terminated string field.
+fn text(field: &[u8]) -> String {
+    let end = field
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Returns the records of a PAX extended header, `<len> <key>=<value>\n`.
+fn pax_records(body: &[u8]) -> Vec<(&str, &str)> {
+    let mut records = Vec::new();
+    let mut rest = body;
+    while let Some(space) = rest.iter().position(|&byte| byte == b' ') {
+        let Some(len) = std::str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|len| len.parse::<usize>().ok())
+            .filter(|&len| len > space && len <= rest.len())
+        else {
+            break;
+        };
+        if let Some((key, value)) = std::str::from_utf8(&rest[space + 1..len - 1])
+            .ok()
+            .and_then(|record| record.split_once('='))
+        {
+            records.push((key, value));
+        }
+        rest = &rest[len..];
+    }
+    records
+}
+
+fn normalize(path: &str) -> String {
+    let path = path.trim_start_matches("./").trim_start_matches('/');
+    path.trim_end_matches('/').to_string()
+}
+
+/// Returns the path a symbolic link at `path` to `target` points to.
+fn resolve(path: &str, target: &str) -> String {
+    let mut parts: Vec<&str> = if target.starts_with('/') {
+        Vec::new()
+    } else {
+        path.split('/').collect()
+    };
+    // The link's own name
+    parts.pop();
+    for part in target.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    normalize(&parts.join("/"))
+}
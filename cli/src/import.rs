@@ -0,0 +1,451 @@
+//! Findings of other scanners, for `scan --import`.
+//!
+//! [`load`] reads the JSON output of another scanner, telling the format
+//! from its shape:
+//!
+//! - SARIF 2.1.0 logs, such as CodeQL's or clippy's through
+//!   `clippy-sarif`; runs of `security-scanner` itself are skipped
+//! - Semgrep's `--json` output
+//! - cargo-audit's `--json` output, whose vulnerabilities are against a
+//!   crate rather than a line
+//!
+//! Each finding is given the test type it is about from the CWE IDs in its
+//! rule's tags or metadata, falling back to words of its rule ID such as
+//! `sqli` or `path-traversal`. [`correlate`] then places it against the
+//! inventory: a finding in a source line of an annotated function is in
+//! that function, whose annotation may lack the finding's test type; one
+//! outside every annotated function is in unannotated code. A function's
+//! lines run from its name through the last line of its code, which takes
+//! debug info; functions whose end isn't known only contain the line of
+//! their name. Files are matched on their path relative to the workspace
+//! root, as records give it, so absolute paths and `file://` URIs match by
+//! their end.
+//!
+//! Vulnerabilities of a crate are correlated with the annotated functions
+//! of that crate, which are in the inventory when the crate is a member of
+//! the workspace or a dependency whose annotations are linked in.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use security_scanner::names::DisplayNames;
+use security_scanner::{ScannerError, TestType};
+use serde_json::{json, Value};
+
+use crate::scan::Found;
+
+/// CWE IDs of the weaknesses each test type looks for.
+const CWES: &[(u32, TestType)] = &[
+    (89, TestType::SqlInjection),
+    (564, TestType::SqlInjection),
+    (362, TestType::RaceCondition),
+    (366, TestType::RaceCondition),
+    (367, TestType::RaceCondition),
+    (208, TestType::TimingAttack),
+    (385, TestType::TimingAttack),
+    (119, TestType::BufferOverflow),
+    (120, TestType::BufferOverflow),
+    (121, TestType::BufferOverflow),
+    (122, TestType::BufferOverflow),
+    (125, TestType::BufferOverflow),
+    (787, TestType::BufferOverflow),
+    (426, TestType::EnvInjection),
+    (427, TestType::EnvInjection),
+    (454, TestType::EnvInjection),
+    (79, TestType::Xss),
+    (77, TestType::CommandInjection),
+    (78, TestType::CommandInjection),
+    (22, TestType::PathTraversal),
+    (23, TestType::PathTraversal),
+    (36, TestType::PathTraversal),
+    (918, TestType::Ssrf),
+    (611, TestType::Xxe),
+    (776, TestType::Xxe),
+    (502, TestType::Deserialization),
+    (601, TestType::OpenRedirect),
+];
+
+/// Words of rule IDs naming each test type, looked for when a finding has
+/// no CWE ID of a test type.
+const RULE_WORDS: &[(&str, TestType)] = &[
+    ("sql", TestType::SqlInjection),
+    ("race", TestType::RaceCondition),
+    ("toctou", TestType::RaceCondition),
+    ("timing", TestType::TimingAttack),
+    ("overflow", TestType::BufferOverflow),
+    ("out-of-bounds", TestType::BufferOverflow),
+    ("environment", TestType::EnvInjection),
+    ("xss", TestType::Xss),
+    ("command", TestType::CommandInjection),
+    ("exec", TestType::CommandInjection),
+    ("traversal", TestType::PathTraversal),
+    ("ssrf", TestType::Ssrf),
+    ("xxe", TestType::Xxe),
+    ("deserializ", TestType::Deserialization),
+    ("redirect", TestType::OpenRedirect),
+];
+
+/// A finding of another scanner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Imported {
+    /// Tool that reported it, such as `semgrep`
+    pub tool: String,
+    /// Its rule or advisory ID
+    pub rule: String,
+    pub message: String,
+    /// Source file and line it is in, `None` for crate vulnerabilities
+    pub location: Option<(String, u32)>,
+    /// Crate a vulnerability is in
+    pub package: Option<String>,
+    /// The test type it is about, if known
+    pub test_type: Option<TestType>,
+}
+
+/// Where an imported finding is, going by the inventory.
+pub(crate) enum Correlation<'a> {
+    /// In an annotated function that has the finding's test type, or of a
+    /// finding whose test type isn't known
+    Covered(&'a Found),
+    /// In an annotated function without the finding's test type
+    Missing(&'a Found, TestType),
+    /// In code of no annotated function
+    Unannotated,
+    /// In a crate, with the crate's annotated functions
+    Crate(Vec<&'a Found>),
+}
+
+impl Correlation<'_> {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Correlation::Covered(_) => "covered",
+            Correlation::Missing(..) => "missing_test_type",
+            Correlation::Unannotated => "unannotated",
+            Correlation::Crate(_) => "crate",
+        }
+    }
+}
+
+/// Reads the findings in the scanner output at `path`.
+pub(crate) fn load(path: &Path) -> Result<Vec<Imported>, ScannerError> {
+    let text = std::fs::read_to_string(path).map_err(|err| ScannerError::io("read", path, err))?;
+    let parse_error = |message: String| ScannerError::Parse {
+        path: path.to_path_buf(),
+        message,
+    };
+    let document: Value =
+        serde_json::from_str(&text).map_err(|err| parse_error(err.to_string()))?;
+
+    if document["runs"].is_array() {
+        Ok(read_sarif(&document))
+    } else if document["vulnerabilities"].is_object() {
+        Ok(read_cargo_audit(&document))
+    } else if document["results"].is_array() {
+        Ok(read_semgrep(&document))
+    } else {
+        Err(parse_error(
+            "not a SARIF log or Semgrep or cargo-audit JSON output".into(),
+        ))
+    }
+}
+
+fn read_sarif(log: &Value) -> Vec<Imported> {
+    let mut findings = Vec::new();
+    for run in log["runs"].as_array().into_iter().flatten() {
+        let driver = &run["tool"]["driver"];
+        let tool = driver["name"].as_str().unwrap_or("sarif");
+        if tool == "security-scanner" {
+            continue;
+        }
+        let rules = driver["rules"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+        for result in run["results"].as_array().into_iter().flatten() {
+            let rule_id = result["ruleId"]
+                .as_str()
+                .or_else(|| result["rule"]["id"].as_str())
+                .unwrap_or_default();
+            let rule = result["ruleIndex"]
+                .as_u64()
+                .and_then(|index| rules.get(index as usize))
+                .or_else(|| rules.iter().find(|rule| rule["id"] == rule_id));
+            let rule_tags = rule.map_or(&Value::Null, |rule| &rule["properties"]["tags"]);
+            let tags = strings(&result["properties"]["tags"])
+                .into_iter()
+                .chain(strings(rule_tags))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let physical = &result["locations"][0]["physicalLocation"];
+            let location = physical["artifactLocation"]["uri"]
+                .as_str()
+                .zip(physical["region"]["startLine"].as_u64())
+                .map(|(uri, line)| (uri.to_string(), line as u32));
+            findings.push(Imported {
+                tool: tool.to_string(),
+                rule: rule_id.to_string(),
+                message: result["message"]["text"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                location,
+                package: None,
+                test_type: test_type(&tags, rule_id),
+            });
+        }
+    }
+    findings
+}
+
+fn read_semgrep(output: &Value) -> Vec<Imported> {
+    output["results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|result| {
+            let rule = result["check_id"].as_str().unwrap_or_default();
+            let cwe = strings(&result["extra"]["metadata"]["cwe"]).join(" ");
+            Imported {
+                tool: "semgrep".to_string(),
+                rule: rule.to_string(),
+                message: result["extra"]["message"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                location: result["path"]
+                    .as_str()
+                    .zip(result["start"]["line"].as_u64())
+                    .map(|(path, line)| (path.to_string(), line as u32)),
+                package: None,
+                test_type: test_type(&cwe, rule),
+            }
+        })
+        .collect()
+}
+
+fn read_cargo_audit(report: &Value) -> Vec<Imported> {
+    report["vulnerabilities"]["list"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|vulnerability| {
+            let advisory = &vulnerability["advisory"];
+            let title = advisory["title"].as_str().unwrap_or_default();
+            let package = vulnerability["package"]["name"]
+                .as_str()
+                .or_else(|| advisory["package"].as_str())
+                .unwrap_or_default();
+            Imported {
+                tool: "cargo-audit".to_string(),
+                rule: advisory["id"].as_str().unwrap_or_default().to_string(),
+                message: match vulnerability["package"]["version"].as_str() {
+                    Some(version) => format!("{} (version {})", title, version),
+                    None => title.to_string(),
+                },
+                location: None,
+                package: Some(package.to_string()),
+                // Advisories have keywords rather than CWE IDs
+                test_type: test_type("", &strings(&advisory["keywords"]).join(" ")),
+            }
+        })
+        .collect()
+}
+
+/// Returns the strings of a string or an array of them.
+fn strings(value: &Value) -> Vec<&str> {
+    match value {
+        Value::String(string) => vec![string.as_str()],
+        Value::Array(values) => values.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns the test type of the first CWE ID in `cwes` that has one, such
+/// as `CWE-89: SQL Injection` or `external/cwe/cwe-089`, or else of the
+/// first word of `rule` naming one.
+fn test_type(cwes: &str, rule: &str) -> Option<TestType> {
+    let lower = cwes.to_ascii_lowercase();
+    let by_cwe = lower.match_indices("cwe").find_map(|(index, _)| {
+        let digits: String = lower[index + 3..]
+            .trim_start_matches(['-', '/', ' ', '_'])
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        let id: u32 = digits.parse().ok()?;
+        CWES.iter()
+            .find(|&&(cwe, _)| cwe == id)
+            .map(|&(_, test_type)| test_type)
+    });
+    let rule = rule.to_ascii_lowercase();
+    by_cwe.or_else(|| {
+        RULE_WORDS
+            .iter()
+            .find(|(word, _)| rule.contains(word))
+            .map(|&(_, test_type)| test_type)
+    })
+}
+
+/// Places `finding` against the annotated `functions`.
+pub(crate) fn correlate<'a>(
+    finding: &Imported,
+    functions: &'a BTreeMap<String, Found>,
+) -> Correlation<'a> {
+    if let Some(package) = &finding.package {
+        let krate = package.replace('-', "_");
+        return Correlation::Crate(
+            functions
+                .values()
+                .filter(|found| found.metadata.function_name.split("::").next() == Some(&krate))
+                .collect(),
+        );
+    }
+    let Some((file, line)) = &finding.location else {
+        return Correlation::Unannotated;
+    };
+    let file = normalize(file);
+    // The innermost function, for closures and nested functions
+    let function = functions
+        .values()
+        .filter(|found| {
+            found.metadata.location.as_ref().is_some_and(|location| {
+                same_file(&normalize(&location.file), &file)
+                    && (location.line..=location.end_line.max(location.line)).contains(line)
+            })
+        })
+        .max_by_key(|found| {
+            found
+                .metadata
+                .location
+                .as_ref()
+                .map(|location| location.line)
+        });
+    match (function, finding.test_type) {
+        (None, _) => Correlation::Unannotated,
+        (Some(found), Some(test_type)) if !found.metadata.config.has(test_type) => {
+            Correlation::Missing(found, test_type)
+        }
+        (Some(found), _) => Correlation::Covered(found),
+    }
+}
+
+fn normalize(path: &str) -> String {
+    let path = path
+        .strip_prefix("file://")
+        .unwrap_or(path)
+        .replace('\\', "/");
+    path.strip_prefix("./").unwrap_or(&path).to_string()
+}
+
+/// Returns whether two paths name the same file, one relative to a
+/// directory the other is under.
+pub(crate) fn same_file(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    !short.is_empty()
+        && (short == long
+            || long.ends_with(short) && long[..long.len() - short.len()].ends_with('/'))
+}
+
+/// Returns where `finding` is, as `file:line` or the crate it is in.
+fn place(finding: &Imported) -> String {
+    match (&finding.location, &finding.package) {
+        (Some((file, line)), _) => format!("{}:{}", file, line),
+        (None, Some(package)) => format!("crate {}", package),
+        (None, None) => "-".to_string(),
+    }
+}
+
+/// Prints the imported findings and where they are under the functions of
+/// `scan`'s table.
+pub(crate) fn print_table(findings: &[(Imported, Correlation<'_>)], names: &DisplayNames) {
+    println!("imported ({})", findings.len());
+    for (finding, correlation) in findings {
+        println!(
+            "  {} {} at {}: {}",
+            finding.tool,
+            finding.rule,
+            place(finding),
+            finding.message
+        );
+        match correlation {
+            Correlation::Covered(found) => {
+                println!("    in {}", found.metadata.function_name)
+            }
+            Correlation::Missing(found, test_type) => println!(
+                "    in {}, not annotated for {}",
+                found.metadata.function_name,
+                names.test_type(*test_type)
+            ),
+            Correlation::Unannotated => println!("    in no annotated function"),
+            Correlation::Crate(functions) => {
+                println!("    {} annotated function(s) in the crate", functions.len())
+            }
+        }
+    }
+}
+
+/// Returns the imported findings as the `imported` array of `scan`'s JSON
+/// report.
+pub(crate) fn json(findings: &[(Imported, Correlation<'_>)]) -> Value {
+    findings
+        .iter()
+        .map(|(finding, correlation)| {
+            let functions: Vec<&str> = match correlation {
+                Correlation::Covered(found) | Correlation::Missing(found, _) => {
+                    vec![found.metadata.function_name.as_str()]
+                }
+                Correlation::Crate(functions) => functions
+                    .iter()
+                    .map(|found| found.metadata.function_name.as_str())
+                    .collect(),
+                Correlation::Unannotated => Vec::new(),
+            };
+            json!({
+                "tool": finding.tool,
+                "rule": finding.rule,
+                "message": finding.message,
+                "location": finding.location.as_ref().map(|(file, line)| format!("{}:{}", file, line)),
+                "package": finding.package,
+                "test_type": finding.test_type,
+                "correlation": correlation.as_str(),
+                "functions": functions,
+            })
+        })
+        .collect()
+}
+
+/// Returns a SARIF run per tool of the imported findings, to follow
+/// `scan`'s own run, with where each is under `securityScanner/*`
+/// properties of its results.
+pub(crate) fn sarif_runs(findings: &[(Imported, Correlation<'_>)]) -> Vec<Value> {
+    let mut runs: BTreeMap<&str, Vec<Value>> = BTreeMap::new();
+    for (finding, correlation) in findings {
+        let mut result = json!({
+            "ruleId": finding.rule,
+            "level": "warning",
+            "message": { "text": finding.message },
+            "properties": {
+                "securityScanner/correlation": correlation.as_str(),
+            },
+        });
+        if let Some((file, line)) = &finding.location {
+            result["locations"] = json!([{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": file },
+                    "region": { "startLine": line },
+                },
+            }]);
+        }
+        let properties = &mut result["properties"];
+        if let Some(test_type) = finding.test_type {
+            properties["securityScanner/testType"] = json!(test_type);
+        }
+        if let Correlation::Covered(found) | Correlation::Missing(found, _) = correlation {
+            properties["securityScanner/function"] = json!(found.metadata.function_name);
+        }
+        runs.entry(&finding.tool).or_default().push(result);
+    }
+    runs.into_iter()
+        .map(|(tool, results)| {
+            json!({
+                "tool": { "driver": { "name": tool } },
+                "results": results,
+            })
+        })
+        .collect()
+}
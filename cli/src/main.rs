@@ -0,0 +1,517 @@
+//! # cargo security-scan
+//!
+//! Cargo subcommand for working with `#[security_test]` annotations.
+//!
+//! ## Commands
+//!
+//! - `suggest` - Parses workspace sources and suggests `#[security_test(...)]`
+//!   attributes for functions matching security-relevant heuristics, printed
+//!   as a unified diff for review or written in place with `--apply`
+//! - `coverage` - Reports the share of security-relevant public functions
+//!   lacking `#[security_test]`, failing above `--max-unannotated`
+//! - `scan` - Builds the workspace, or takes the binaries of a container
+//!   image with `--image`, extracts the embedded metadata from the binaries
+//!   and prints the functions grouped by threat level, or as a SARIF log
+//!   with `--format sarif`; `--changed-since origin/main`
+//!   reports only the functions in files changed since that revision, and
+//!   annotations left without code by dead-code elimination or calling
+//!   banned APIs are warned about; `--metadata-key` reads records encrypted
+//!   at build time; each binary's hardening (PIE, RELRO, NX, stack canaries,
+//!   fortify, CFG) is listed, and `buffer_overflow` code in binaries without
+//!   overflow mitigations is warned about; `--import` merges the findings
+//!   of SARIF logs and Semgrep and cargo-audit output into the report, with
+//!   the annotated function each is in
+//! - `explain` - Prints everything known about one annotated function, from
+//!   the same build as `scan`: its metadata, source location and
+//!   parameters, the payload engines for its test types, what `scan` warns
+//!   about it and its remediation deadline
+//! - `doctor` - Builds a small annotated program with the user's toolchain
+//!   and target and checks that its metadata reads back intact, to diagnose
+//!   linker scripts and targets that lose annotations
+//! - `attest` - Writes an in-toto statement binding the annotation inventory
+//!   of built binaries to them and, with `--provenance`, to their SLSA
+//!   provenance
+//! - `ebpf-manifest` - Lists the symbol, address and file offset of every
+//!   critical function's code in the built binaries, as JSON or a
+//!   `bpftrace` script, to attach uprobes to them in one step
+//! - `crash` - Reads a core dump of a binary and reports whether it crashed
+//!   inside or beneath an annotated function, with the function's test
+//!   types and threat level as the crash's triage priority
+//! - `stamp` - Scans a binary and writes the verdict, with its counts, the
+//!   time and a signature, into a `.security_verdict` section of it, for
+//!   deploy pipelines to check
+//! - `verify-artifact` - Checks a stamped binary before it's deployed: that
+//!   its verdict's signature verifies, the binary wasn't changed since, the
+//!   scan is recent enough and its counts meet `--max-critical` and
+//!   `--deny-warnings`, failing otherwise
+//!
+//! ## JSON Output
+//!
+//! With `--json`, commands print their results as one JSON document on
+//! stdout, and a failure is printed on stderr as
+//! `{"error": {"code": "...", "message": "..."}}` with a stable code from
+//! [`security_scanner::ScannerError::code`], so automation can react to the
+//! kind of failure rather than its wording.
+//!
+//! ## Display Names
+//!
+//! Text output names threat levels and test types in English, such as
+//! "SQL injection" for `sql_injection`. A workspace can rename them in
+//! `[workspace.metadata.security-scanner.display_names]` of its manifest to
+//! present findings in its own language or terms; JSON output and SARIF
+//! rule IDs keep the identifiers.
+//!
+//! ## Network Access
+//!
+//! The scanner itself never opens a network connection. The only subsystem
+//! that can reach the network is the `cargo build` that `scan` runs, which
+//! may fetch dependencies; with `--offline` it is run with `--offline` too,
+//! so a scan in an air-gapped environment either works from local sources or
+//! fails.
+//!
+//! The scanner has no setting of its own for this. To make offline the
+//! default, set cargo's `net.offline = true` in `.cargo/config.toml`, or
+//! `CARGO_NET_OFFLINE=true` in the environment: the cargo commands that
+//! `scan` runs read them like any other cargo command does.
+
+mod attest;
+mod coverage;
+mod crash;
+mod diff;
+mod doctor;
+mod ebpf;
+mod explain;
+mod ignore;
+mod image;
+mod import;
+mod names;
+mod scan;
+mod stamp;
+mod suggest;
+mod verify;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand};
+use security_scanner::format::tlv;
+use security_scanner::ScannerError;
+
+#[derive(Parser)]
+#[command(
+    name = "cargo-security-scan",
+    bin_name = "cargo security-scan",
+    version,
+    about
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Print results and errors as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Never access the network, failing instead. Cargo's `net.offline`
+    /// setting makes this the default
+    #[arg(long, global = true)]
+    offline: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Suggest `#[security_test]` attributes from static heuristics
+    Suggest(SuggestArgs),
+    /// Report how many security-relevant public functions are annotated
+    Coverage(CoverageArgs),
+    /// Build the workspace and summarize the metadata in its binaries
+    Scan(ScanArgs),
+    /// Print everything known about one annotated function
+    Explain(ExplainArgs),
+    /// Check that annotations survive the toolchain, end to end
+    Doctor(DoctorArgs),
+    /// Write an in-toto statement attesting to the binaries' annotations
+    Attest(AttestArgs),
+    /// List where to attach uprobes to the annotated functions of binaries
+    EbpfManifest(EbpfManifestArgs),
+    /// Tell whether a core dump crashed in an annotated function
+    Crash(CrashArgs),
+    /// Write the verdict of a scan into the binary scanned
+    Stamp(StampArgs),
+    /// Check a stamped binary's verdict before deploying it
+    VerifyArtifact(VerifyArtifactArgs),
+}
+
+#[derive(Args)]
+struct SuggestArgs {
+    /// Directory or file to scan
+    #[arg(long, default_value = ".")]
+    path: PathBuf,
+
+    /// Insert the suggested attributes into the source files instead of
+    /// printing a diff
+    #[arg(long)]
+    apply: bool,
+
+    /// With --apply, print what would be written without changing any files
+    #[arg(long, requires = "apply")]
+    dry_run: bool,
+}
+
+#[derive(Args)]
+struct CoverageArgs {
+    /// Directory or file to scan
+    #[arg(long, default_value = ".")]
+    path: PathBuf,
+
+    /// Fail if more than this fraction (0 to 1) of security-relevant public
+    /// functions lack an annotation
+    #[arg(long, value_name = "RATIO")]
+    max_unannotated: Option<f64>,
+}
+
+#[derive(Args)]
+struct ScanArgs {
+    #[command(flatten)]
+    binaries: BinaryArgs,
+
+    /// Scan the binaries of this container image, a tarball written by
+    /// `docker save` or an OCI image layout, or a reference to save with
+    /// `docker save`, instead of building; may be repeated
+    #[arg(
+        long = "image",
+        value_name = "IMAGE",
+        conflicts_with_all = ["manifest_path", "release", "tests"]
+    )]
+    images: Vec<String>,
+
+    /// Only report functions in files changed since this git revision.
+    /// There are no harnesses for a `run` command to limit yet, so this
+    /// narrows the inventory `scan` reports instead
+    #[arg(long, value_name = "REV")]
+    changed_since: Option<String>,
+
+    /// Output format; defaults to `json` with --json, `table` otherwise
+    #[arg(long, value_enum)]
+    format: Option<scan::Format>,
+
+    /// Merge the findings of a SARIF log, or of Semgrep's or cargo-audit's
+    /// JSON output, into the report; may be repeated
+    #[arg(long = "import", value_name = "PATH")]
+    imports: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+struct ExplainArgs {
+    /// Path of the function, such as `my_crate::auth::login`; a path
+    /// matching the end of a single function's, like `auth::login`, will do
+    function: String,
+
+    #[command(flatten)]
+    binaries: BinaryArgs,
+}
+
+#[derive(Args)]
+struct DoctorArgs {
+    /// Path to the Cargo.toml of the workspace whose `security-scanner`
+    /// version to check
+    #[arg(long, value_name = "PATH")]
+    manifest_path: Option<PathBuf>,
+
+    /// Build the fixture for this target triple instead of the host
+    #[arg(long, value_name = "TRIPLE")]
+    target: Option<String>,
+
+    /// Build the fixture in release mode
+    #[arg(long)]
+    release: bool,
+
+    /// Key to encrypt and read records with, as 64 hex digits
+    #[arg(
+        long,
+        value_name = "HEX",
+        env = "SECURITY_SCANNER_METADATA_KEY",
+        hide_env_values = true
+    )]
+    metadata_key: Option<String>,
+}
+
+/// Which binaries `scan`, `explain` and `ebpf-manifest` read.
+#[derive(Args)]
+struct BinaryArgs {
+    /// Path to the Cargo.toml of the workspace to build
+    #[arg(long, value_name = "PATH")]
+    manifest_path: Option<PathBuf>,
+
+    /// Build and scan release binaries
+    #[arg(long)]
+    release: bool,
+
+    /// Also build and scan test binaries, which link in library crates
+    #[arg(long)]
+    tests: bool,
+
+    /// Scan this binary instead of building; may be repeated
+    #[arg(
+        long = "binary",
+        value_name = "PATH",
+        conflicts_with_all = ["manifest_path", "release", "tests"]
+    )]
+    binaries: Vec<PathBuf>,
+
+    /// Key that records were encrypted with at build time, as 64 hex digits
+    #[arg(
+        long,
+        value_name = "HEX",
+        env = "SECURITY_SCANNER_METADATA_KEY",
+        hide_env_values = true
+    )]
+    metadata_key: Option<String>,
+}
+
+#[derive(Args)]
+struct AttestArgs {
+    /// Binary to attest to; may be repeated
+    #[arg(long = "binary", value_name = "PATH", required = true)]
+    binaries: Vec<PathBuf>,
+
+    /// SLSA provenance or other in-toto statement of the build, bare or in
+    /// a DSSE envelope; every binary must be one of its subjects
+    #[arg(long, value_name = "PATH")]
+    provenance: Option<PathBuf>,
+
+    /// Write the statement here instead of stdout
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Key that records were encrypted with at build time, as 64 hex digits
+    #[arg(
+        long,
+        value_name = "HEX",
+        env = "SECURITY_SCANNER_METADATA_KEY",
+        hide_env_values = true
+    )]
+    metadata_key: Option<String>,
+}
+
+#[derive(Args)]
+struct EbpfManifestArgs {
+    #[command(flatten)]
+    binaries: BinaryArgs,
+
+    /// Probe the functions at this threat level or above
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        default_value = "critical",
+        value_parser = ["critical", "high", "medium", "low"]
+    )]
+    threat_level: String,
+
+    /// Output format; defaults to `json`
+    #[arg(long, value_enum)]
+    format: Option<ebpf::Format>,
+}
+
+#[derive(Args)]
+struct CrashArgs {
+    /// Core dump of the crashed process
+    #[arg(long, value_name = "PATH")]
+    core: PathBuf,
+
+    /// The binary that crashed, with its symbols
+    #[arg(long, value_name = "PATH")]
+    binary: PathBuf,
+
+    /// Key that records were encrypted with at build time, as 64 hex digits
+    #[arg(
+        long,
+        value_name = "HEX",
+        env = "SECURITY_SCANNER_METADATA_KEY",
+        hide_env_values = true
+    )]
+    metadata_key: Option<String>,
+}
+
+#[derive(Args)]
+struct StampArgs {
+    /// Binary to scan and stamp
+    #[arg(long, value_name = "PATH")]
+    binary: PathBuf,
+
+    /// Write the stamped binary here instead of over the binary
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Key to sign the verdict with, as 64 hex digits
+    #[arg(
+        long,
+        value_name = "HEX",
+        env = "SECURITY_SCANNER_SIGNING_KEY",
+        hide_env_values = true
+    )]
+    signing_key: Option<String>,
+
+    /// Key that records were encrypted with at build time, as 64 hex digits
+    #[arg(
+        long,
+        value_name = "HEX",
+        env = "SECURITY_SCANNER_METADATA_KEY",
+        hide_env_values = true
+    )]
+    metadata_key: Option<String>,
+}
+
+#[derive(Args)]
+struct VerifyArtifactArgs {
+    /// Stamped binary to verify
+    #[arg(value_name = "BINARY")]
+    binary: PathBuf,
+
+    /// Key the verdict was signed with, as 64 hex digits
+    #[arg(
+        long,
+        value_name = "HEX",
+        env = "SECURITY_SCANNER_SIGNING_KEY",
+        hide_env_values = true
+    )]
+    signing_key: Option<String>,
+
+    /// Oldest scan accepted, such as 12h or 7d
+    #[arg(long, value_name = "DURATION", default_value = "30d", value_parser = verify::parse_duration)]
+    max_age: u64,
+
+    /// Fail if the verdict counts more critical functions than this
+    #[arg(long, value_name = "N")]
+    max_critical: Option<u64>,
+
+    /// Fail if the verdict counts any warnings
+    #[arg(long)]
+    deny_warnings: bool,
+}
+
+fn main() -> ExitCode {
+    // Invoked as `cargo security-scan ...`, cargo passes the subcommand name
+    // through as the first argument
+    let mut args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("security-scan") {
+        args.remove(1);
+    }
+
+    let cli = Cli::parse_from(args);
+
+    let json = cli.json;
+    let result = match cli.command {
+        Command::Suggest(args) => suggest::run(&args.path, args.apply, args.dry_run, json),
+        Command::Coverage(args) => coverage::run(&args.path, args.max_unannotated, json),
+        Command::Scan(args) => scan::run(
+            &scan::ScanOptions {
+                images: args.images,
+                ..args.binaries.options(cli.offline, args.changed_since)
+            },
+            args.format.unwrap_or(if json {
+                scan::Format::Json
+            } else {
+                scan::Format::Table
+            }),
+            &args.imports,
+        ),
+        Command::Explain(args) => explain::run(
+            &args.function,
+            &args.binaries.options(cli.offline, None),
+            json,
+        ),
+        Command::Doctor(args) => doctor::run(
+            &doctor::DoctorOptions {
+                manifest_path: args.manifest_path,
+                target: args.target,
+                release: args.release,
+                offline: cli.offline,
+                metadata_key: args.metadata_key,
+            },
+            json,
+        ),
+        Command::Attest(args) => attest::run(
+            &args.binaries,
+            args.provenance.as_deref(),
+            args.output.as_deref(),
+            args.metadata_key.as_deref(),
+        ),
+        Command::EbpfManifest(args) => ebpf::run(
+            &args.binaries.options(cli.offline, None),
+            scan::LEVELS
+                .into_iter()
+                .find(|level| level.to_string() == args.threat_level)
+                .expect("clap checks the level"),
+            args.format.unwrap_or(ebpf::Format::Json),
+        ),
+        Command::Crash(args) => crash::run(
+            &args.core,
+            &args.binary,
+            args.metadata_key.as_deref(),
+            cli.offline,
+            json,
+        ),
+        Command::Stamp(args) => stamp::run(
+            &args.binary,
+            args.output.as_deref(),
+            args.signing_key.as_deref(),
+            args.metadata_key,
+            json,
+        ),
+        Command::VerifyArtifact(args) => verify::run(
+            &args.binary,
+            args.signing_key.as_deref(),
+            &verify::Policy {
+                max_age: args.max_age,
+                max_critical: args.max_critical,
+                deny_warnings: args.deny_warnings,
+            },
+            json,
+        ),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            report_error(&err, json);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+impl BinaryArgs {
+    fn options(self, offline: bool, changed_since: Option<String>) -> scan::ScanOptions {
+        scan::ScanOptions {
+            manifest_path: self.manifest_path,
+            release: self.release,
+            tests: self.tests,
+            offline,
+            binaries: self.binaries,
+            images: Vec::new(),
+            changed_since,
+            metadata_key: self.metadata_key,
+        }
+    }
+}
+
+/// Parses `--metadata-key`, treating an empty value as none.
+pub(crate) fn metadata_key(hex: Option<&str>) -> Result<Option<[u8; tlv::KEY_LEN]>, ScannerError> {
+    match hex.filter(|hex| !hex.trim().is_empty()) {
+        Some(hex) => tlv::parse_key(hex).map(Some).ok_or_else(|| {
+            ScannerError::Usage("--metadata-key must be 64 hex digits, a 256-bit key".into())
+        }),
+        None => Ok(None),
+    }
+}
+
+fn report_error(err: &ScannerError, json: bool) {
+    if json {
+        let error = serde_json::json!({
+            "error": { "code": err.code(), "message": err.to_string() }
+        });
+        eprintln!("{}", error);
+    } else {
+        eprintln!("error: {}", err);
+    }
+}
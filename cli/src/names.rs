@@ -0,0 +1,116 @@
+//! Display names from the workspace manifest.
+//!
+//! The text reports of `scan` and `explain` and the messages of SARIF logs
+//! name threat levels and test types in English. A workspace can rename
+//! them, for its language or its own terms, in its root manifest:
+//!
+//! ```toml
+//! [workspace.metadata.security-scanner.display_names]
+//! threat_levels = { critical = "kritisch", high = "hoch" }
+//! test_types = { sql_injection = "SQL-Injektion" }
+//! ```
+//!
+//! A single-crate project can put the table under
+//! `[package.metadata.security-scanner.display_names]` instead; the workspace
+//! table wins when both exist. Names left out stay English, and JSON reports
+//! keep the identifiers whatever the manifest says.
+
+use std::path::Path;
+
+use security_scanner::names::DisplayNames;
+use security_scanner::{ScannerError, TestType};
+use serde_json::Value;
+
+use crate::scan::{ScanOptions, LEVELS};
+
+/// Reads the display names of the workspace being scanned, the English
+/// ones when it has none or there is no workspace to read.
+pub fn load(options: &ScanOptions) -> Result<DisplayNames, ScannerError> {
+    // Scanning prebuilt binaries doesn't need a workspace
+    let Some(metadata) = crate::scan::cargo_metadata(options) else {
+        return Ok(DisplayNames::default());
+    };
+
+    let root_manifest = metadata["workspace_root"]
+        .as_str()
+        .map(|root| Path::new(root).join("Cargo.toml"));
+    let root_package = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|package| {
+            package["manifest_path"].as_str().map(Path::new) == root_manifest.as_deref()
+        });
+    let tables = [
+        ("workspace", &metadata["metadata"]),
+        (
+            "package",
+            root_package.map_or(&Value::Null, |package| &package["metadata"]),
+        ),
+    ];
+    let configured = tables
+        .into_iter()
+        .map(|(table, metadata)| (table, &metadata["security-scanner"]["display_names"]))
+        .find(|(_, names)| !names.is_null());
+    match configured {
+        Some((table, names)) => parse(
+            names,
+            &format!("[{}.metadata.security-scanner.display_names]", table),
+        ),
+        None => Ok(DisplayNames::default()),
+    }
+}
+
+/// Reads a `display_names` table, named `table` in errors.
+fn parse(value: &Value, table: &str) -> Result<DisplayNames, ScannerError> {
+    let invalid = |message: String| ScannerError::Usage(format!("{} {}", table, message));
+    let Some(entries) = value.as_object() else {
+        return Err(invalid("must be a table".to_string()));
+    };
+
+    let mut names = DisplayNames::default();
+    for (key, renames) in entries {
+        let Some(renames) = renames.as_object() else {
+            return Err(invalid(format!("`{}` must be a table", key)));
+        };
+        for (identifier, name) in renames {
+            let name = match name.as_str().map(str::trim) {
+                Some(name) if !name.is_empty() => name,
+                _ => {
+                    return Err(invalid(format!(
+                        "`{}.{}` must be a non-empty string",
+                        key, identifier
+                    )))
+                }
+            };
+            match key.as_str() {
+                "threat_levels" => {
+                    let level = LEVELS
+                        .into_iter()
+                        .find(|level| level.as_str() == identifier)
+                        .ok_or_else(|| {
+                            invalid(format!(
+                                "has unknown threat level `{}`, expected one of: critical, \
+                                 high, medium, low",
+                                identifier
+                            ))
+                        })?;
+                    names.rename_threat_level(level, name);
+                }
+                "test_types" => {
+                    let test_type = identifier
+                        .parse::<TestType>()
+                        .map_err(|err| invalid(format!("has an {}", err)))?;
+                    names.rename_test_type(test_type, name);
+                }
+                _ => {
+                    return Err(invalid(format!(
+                        "has unknown key `{}`, expected `threat_levels` or `test_types`",
+                        key
+                    )))
+                }
+            }
+        }
+    }
+    Ok(names)
+}
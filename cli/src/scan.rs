@@ -0,0 +1,784 @@
+//! `scan`: build the workspace and summarize the metadata in its binaries.
+//!
+//! Runs `cargo build --message-format=json`, collects the executables and
+//! shared libraries cargo reports, and reads every one with
+//! [`security_scanner::extract`]. Library crates only end up in binaries
+//! through their dependents or their test harnesses, so `--tests` adds the
+//! test binaries. Binaries given with `--binary` are scanned as they are,
+//! without building, and so are the binaries of container images given
+//! with `--image` (see [`crate::image`]), named by the image and their path
+//! in it, such as `app.tar:/usr/local/bin/app`.
+//!
+//! The annotated functions are printed grouped by threat level, most severe
+//! first, or with `--format sarif` as a SARIF 2.1.0 log for code scanning.
+//! Both name threat levels and test types with the workspace's display
+//! names (see [`crate::names`]). A
+//! function linked into several binaries is listed once. Crates whose critical
+//! functions were built without overflow checks are named in a warning. Binaries
+//! without metadata are skipped; damaged records are reported and make the
+//! command fail with `format.corrupted` after the summary. Binaries whose
+//! entry point has `#[security_program]` are listed with their exposure and
+//! data classes. A function in several binaries is weighed by the most
+//! exposed of those that declare an exposure; SARIF levels and the JSON
+//! report's `effective_threat_level` apply it to the annotated level.
+//!
+//! Annotations whose function has no code in any of its binaries, going by
+//! their symbol tables, are named in a warning: the linker removed the
+//! function as dead code or the compiler inlined it into every caller, so
+//! only the record is left and there is nothing for a scanner to test.
+//! `inline_never` keeps a function from being inlined away and `anchor`
+//! from being removed. Stripped binaries, PE images and
+//! WebAssembly modules can't be checked, nor can methods of trait impls and
+//! generic types, apart from functions with `anchor`, whose address is
+//! recorded.
+//!
+//! Functions whose body calls an API listed in `banned_calls` of their
+//! crate's manifest are named in a warning with the APIs they call.
+//!
+//! Each binary's exploit mitigations (PIE, RELRO, NX, stack canaries,
+//! fortified libc calls and, on Windows, Control Flow Guard) are listed
+//! before the functions, and under `hardening` in the JSON report. See
+//! [`security_scanner::hardening`] for how they are detected. Functions
+//! annotated with `buffer_overflow` in a binary without stack canaries,
+//! NX or PIE are named in a warning with the mitigations missing.
+//!
+//! `--import` merges the findings of other scanners into the report, read
+//! and placed against the inventory by [`crate::import`]: the table lists
+//! them after the functions, the JSON report under `imported` and the
+//! SARIF log as a run per tool after its own. Findings in an annotated
+//! function that isn't annotated for their test type are named in a
+//! warning. With `--changed-since`, findings in unchanged files are left
+//! out with the functions there.
+//!
+//! A generic function with a record per instance is listed once, with its
+//! instances under `instances` in the JSON report.
+//!
+//! Binaries with DWARF debug info supply the last line of each function's
+//! code, which SARIF results give as the end of their region, and a
+//! location for functions whose records lack one.
+//!
+//! Records encrypted at build time are read with the key given with
+//! `--metadata-key` or in `SECURITY_SCANNER_METADATA_KEY`, the variable the
+//! build encrypts with, so a scan that builds with the variable set reads
+//! its own binaries. Encrypted records that can't be read are counted in a
+//! warning per binary.
+//!
+//! For the functions that could be checked, the JSON report lists the
+//! address and size of each copy of their code in each binary under `code`,
+//! so disassembly-based engines can inspect just those instructions, for
+//! example to check for a stack protector or banned instructions.
+//!
+//! `--changed-since <REV>` limits the report to functions whose source file
+//! differs from `REV` according to `git diff`, including uncommitted changes,
+//! for quick checks of a pull request. Files are matched on the path recorded
+//! with each function, relative to the workspace root even when the manifest
+//! given is a member's; functions without a recorded file are always
+//! reported, since they can't be ruled out. The flag was asked for on a
+//! `run` command that runs only the harnesses of changed functions; until
+//! there are harnesses to run, `scan` is where it narrows the output.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use clap::ValueEnum;
+use security_scanner::debuginfo::DebugInfo;
+use security_scanner::extract;
+use security_scanner::hardening::Hardening;
+use security_scanner::names::DisplayNames;
+use security_scanner::report::sarif;
+use security_scanner::severity::effective_threat_level;
+use security_scanner::{
+    CodeRange, FormatError, ProgramContext, ScannerError, SecurityTestMetadata, TestType,
+    ThreatLevel,
+};
+
+use crate::import::{self, Correlation, Imported};
+
+/// Threat levels in report order.
+pub(crate) const LEVELS: [ThreatLevel; 4] = [
+    ThreatLevel::Critical,
+    ThreatLevel::High,
+    ThreatLevel::Medium,
+    ThreatLevel::Low,
+];
+
+/// How `scan` prints the functions it found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Functions grouped by threat level
+    Table,
+    /// A JSON report, as printed by `--json`
+    Json,
+    /// A SARIF 2.1.0 log
+    Sarif,
+}
+
+/// What to build and scan.
+pub struct ScanOptions {
+    pub manifest_path: Option<PathBuf>,
+    pub release: bool,
+    pub tests: bool,
+    /// Build without network access
+    pub offline: bool,
+    /// Prebuilt binaries; when non-empty, nothing is built
+    pub binaries: Vec<PathBuf>,
+    /// Container images, as tarballs or references; when non-empty,
+    /// nothing is built
+    pub images: Vec<String>,
+    /// Git revision to report changed functions against
+    pub changed_since: Option<String>,
+    /// Key that records were encrypted with, as hex digits
+    pub metadata_key: Option<String>,
+}
+
+/// One annotated function and the binaries it was found in.
+pub(crate) struct Found {
+    pub metadata: SecurityTestMetadata,
+    pub artifacts: Vec<String>,
+    /// Whether the function has code in any of the binaries, `None` when
+    /// their symbols can't tell
+    pub has_code: Option<bool>,
+    /// Each copy of the function's code, with the binary it is in
+    pub code: Vec<(String, CodeRange)>,
+}
+
+impl Found {
+    /// Returns whether the function is critical and was built without
+    /// overflow checks.
+    pub fn unchecked_overflow(&self) -> bool {
+        self.metadata.config.threat_level == ThreatLevel::Critical
+            && self
+                .metadata
+                .build
+                .as_ref()
+                .is_some_and(|build| !build.overflow_checks)
+    }
+
+    /// Returns the binaries without the mitigations `buffer_overflow` code
+    /// wants, with the mitigations missing, if the function has the type.
+    pub fn unmitigated_overflow<'a>(
+        &'a self,
+        hardening: &BTreeMap<String, Hardening>,
+    ) -> Vec<(&'a str, Vec<&'static str>)> {
+        if !self.metadata.config.has(TestType::BufferOverflow) {
+            return Vec::new();
+        }
+        self.artifacts
+            .iter()
+            .filter_map(|binary| {
+                let missing = hardening.get(binary)?.missing_overflow_mitigations();
+                (!missing.is_empty()).then_some((binary.as_str(), missing))
+            })
+            .collect()
+    }
+}
+
+/// The annotated functions of every binary scanned.
+pub(crate) struct Inventory {
+    /// Functions by name
+    pub functions: BTreeMap<String, Found>,
+    /// Program context by binary
+    pub programs: BTreeMap<String, ProgramContext>,
+    /// Exploit mitigations by binary
+    pub hardening: BTreeMap<String, Hardening>,
+    /// Number of binaries with metadata
+    pub scanned: usize,
+    /// Number of damaged records, already reported
+    pub damaged: usize,
+}
+
+/// Runs `scan`, printing the functions found in `format` with the findings
+/// of the scanner outputs at `imports`.
+pub fn run(options: &ScanOptions, format: Format, imports: &[PathBuf]) -> Result<(), ScannerError> {
+    // Read before building, so a bad table or import fails fast; JSON
+    // doesn't use the table
+    let names = match format {
+        Format::Json => DisplayNames::default(),
+        Format::Table | Format::Sarif => crate::names::load(options)?,
+    };
+    let mut imported = Vec::new();
+    for path in imports {
+        imported.extend(import::load(path)?);
+    }
+    let Inventory {
+        mut functions,
+        programs,
+        hardening,
+        scanned,
+        damaged,
+    } = collect(options)?;
+
+    if let Some(rev) = &options.changed_since {
+        let changed = changed_files(options, rev)?;
+        let total = functions.len();
+        functions.retain(|_, found| match &found.metadata.location {
+            Some(location) if !location.file.is_empty() => {
+                changed.contains(&location.file.replace('\\', "/"))
+            }
+            _ => true,
+        });
+        eprintln!(
+            "note: {} of {} function(s) are in files changed since {}",
+            functions.len(),
+            total,
+            rev
+        );
+        imported.retain(|finding| match &finding.location {
+            Some((file, _)) => changed
+                .iter()
+                .any(|changed| import::same_file(file, changed)),
+            None => true,
+        });
+    }
+    let imported: Vec<_> = imported
+        .into_iter()
+        .map(|finding| {
+            let correlation = import::correlate(&finding, &functions);
+            (finding, correlation)
+        })
+        .collect();
+
+    match format {
+        Format::Table => {
+            print_table(&functions, &programs, &hardening, scanned, &names);
+            if !imports.is_empty() {
+                import::print_table(&imported, &names);
+            }
+        }
+        Format::Json => print_json(&functions, &programs, &hardening, scanned, &imported),
+        Format::Sarif => print_sarif(&functions, &names, &imported),
+    }
+
+    let unchecked: BTreeSet<&str> = functions
+        .values()
+        .filter(|found| found.unchecked_overflow())
+        .filter_map(|found| found.metadata.function_name.split("::").next())
+        .collect();
+    if !unchecked.is_empty() {
+        eprintln!(
+            "warning: critical functions built without overflow checks in: {}",
+            unchecked.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    let banned: Vec<String> = functions
+        .values()
+        .filter(|found| !found.metadata.config.banned_calls.is_empty())
+        .map(|found| {
+            format!(
+                "{} ({})",
+                found.metadata.function_name,
+                found.metadata.config.banned_calls.join(", ")
+            )
+        })
+        .collect();
+    if !banned.is_empty() {
+        eprintln!(
+            "warning: annotated functions calling banned APIs: {}",
+            banned.join(", ")
+        );
+    }
+
+    let unmitigated: Vec<String> = functions
+        .values()
+        .flat_map(|found| {
+            found
+                .unmitigated_overflow(&hardening)
+                .into_iter()
+                .map(|(binary, missing)| {
+                    format!(
+                        "{} in {} (no {})",
+                        found.metadata.function_name,
+                        binary,
+                        missing.join(", no ")
+                    )
+                })
+        })
+        .collect();
+    if !unmitigated.is_empty() {
+        eprintln!(
+            "warning: buffer_overflow functions in binaries without overflow mitigations: {}",
+            unmitigated.join(", ")
+        );
+    }
+
+    let missing: Vec<String> = imported
+        .iter()
+        .filter_map(|(finding, correlation)| match correlation {
+            import::Correlation::Missing(found, test_type) => Some(format!(
+                "{} ({} from {})",
+                found.metadata.function_name, test_type, finding.tool
+            )),
+            _ => None,
+        })
+        .collect();
+    if !missing.is_empty() {
+        eprintln!(
+            "warning: imported findings in functions not annotated for their test type: {}",
+            missing.join(", ")
+        );
+    }
+
+    let dead: Vec<&str> = functions
+        .values()
+        .filter(|found| found.has_code == Some(false))
+        .map(|found| found.metadata.function_name.as_str())
+        .collect();
+    if !dead.is_empty() {
+        eprintln!(
+            "warning: annotated functions without code in their binaries, removed as dead code \
+             or inlined into every caller: {}",
+            dead.join(", ")
+        );
+    }
+
+    match damaged {
+        0 => Ok(()),
+        count => Err(FormatError::Corrupted { count }.into()),
+    }
+}
+
+/// Builds the workspace unless binaries or images were given, and reads the annotated
+/// functions of every binary, reporting damaged records as warnings.
+pub(crate) fn collect(options: &ScanOptions) -> Result<Inventory, ScannerError> {
+    let metadata_key = crate::metadata_key(options.metadata_key.as_deref())?;
+    let artifacts = artifacts(options)?;
+
+    let mut functions: BTreeMap<String, Found> = BTreeMap::new();
+    let mut programs: BTreeMap<String, ProgramContext> = BTreeMap::new();
+    let mut hardening: BTreeMap<String, Hardening> = BTreeMap::new();
+    let mut scanned = 0;
+    let mut damaged = 0;
+    let mut locked = false;
+    // The binaries of an image are read with the image, files one by one
+    let mut images = Vec::new();
+    for image in &options.images {
+        for (path, data) in crate::image::binaries(image)? {
+            images.push((format!("{}:/{}", image, path), data));
+        }
+    }
+    let files = artifacts.iter().map(|artifact| -> Result<_, ScannerError> {
+        let data =
+            std::fs::read(artifact).map_err(|err| ScannerError::io("read", artifact, err))?;
+        Ok((crate::suggest::display_path(artifact), data))
+    });
+    for binary in files.chain(images.into_iter().map(Ok)) {
+        let (display, data) = binary?;
+        let mut extraction = match extract::extract_with_key(&data, metadata_key.as_ref()) {
+            Ok(extraction) => extraction,
+            Err(FormatError::NoMetadata) => continue,
+            Err(err) => return Err(err.into()),
+        };
+        let defined = extract::defined_functions(&data)?;
+        if let Some(debug_info) = DebugInfo::read(&data)? {
+            debug_info.apply(&mut extraction.functions);
+        }
+        locked |= extraction.encrypted > 0;
+        scanned += 1;
+
+        if let Err(err) = extraction.verify() {
+            eprintln!("warning: {}: {}", display, err);
+            if let FormatError::Corrupted { count } = err {
+                damaged += count;
+            }
+        }
+        if let Some(program) = extraction.program {
+            programs.insert(display.clone(), program);
+        }
+        if let Some(mitigations) = Hardening::read(&data)? {
+            hardening.insert(display.clone(), mitigations);
+        }
+        for mut metadata in extraction.functions {
+            let exposure = metadata.exposure;
+            let instances = std::mem::take(&mut metadata.instances);
+            // Symbols spell generic and trait impl paths differently
+            let checkable = defined
+                .as_ref()
+                .filter(|_| !metadata.function_name.contains('<'));
+            // An address means code, even without symbols
+            let has_code = checkable
+                .map(|defined| defined.contains_key(&metadata.function_name))
+                .max(metadata.function_address.map(|_| true));
+            let ranges = checkable
+                .and_then(|defined| defined.get(&metadata.function_name))
+                .cloned()
+                .unwrap_or_default();
+            let found = functions
+                .entry(metadata.function_name.clone())
+                .or_insert_with(|| Found {
+                    metadata,
+                    artifacts: Vec::new(),
+                    has_code: None,
+                    code: Vec::new(),
+                });
+            found.metadata.exposure = found.metadata.exposure.max(exposure);
+            for instance in instances {
+                if !found.metadata.instances.contains(&instance) {
+                    found.metadata.instances.push(instance);
+                }
+            }
+            found.has_code = found.has_code.max(has_code);
+            found
+                .code
+                .extend(ranges.into_iter().map(|range| (display.clone(), range)));
+            found.artifacts.push(display.clone());
+        }
+    }
+
+    if locked {
+        eprintln!(
+            "note: encrypted records are read with --metadata-key or \
+             SECURITY_SCANNER_METADATA_KEY, holding the key they were built with"
+        );
+    }
+
+    Ok(Inventory {
+        functions,
+        programs,
+        hardening,
+        scanned,
+        damaged,
+    })
+}
+
+/// Returns the binaries given, or builds the workspace and returns those
+/// cargo produced when no binaries or images were given.
+pub(crate) fn artifacts(options: &ScanOptions) -> Result<Vec<PathBuf>, ScannerError> {
+    if options.binaries.is_empty() && options.images.is_empty() {
+        build(options)
+    } else {
+        Ok(options.binaries.clone())
+    }
+}
+
+/// Builds the workspace and returns the binaries cargo produced.
+fn build(options: &ScanOptions) -> Result<Vec<PathBuf>, ScannerError> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let mut command = Command::new(&cargo);
+    command.args(["build", "--message-format=json-render-diagnostics"]);
+    if let Some(manifest_path) = &options.manifest_path {
+        command.arg("--manifest-path").arg(manifest_path);
+    }
+    if options.release {
+        command.arg("--release");
+    }
+    if options.tests {
+        // `--tests` alone would skip the regular binaries
+        command.args(["--bins", "--tests"]);
+    }
+    if options.offline {
+        command.arg("--offline");
+    }
+
+    // Diagnostics are rendered on stderr; stdout carries the JSON messages
+    let output = command
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| ScannerError::Integration(format!("failed to run {}: {}", cargo, err)))?;
+    if !output.status.success() {
+        return Err(ScannerError::Integration(format!(
+            "cargo build failed ({})",
+            output.status
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().flat_map(artifact_paths).collect())
+}
+
+/// Returns the files that differ from `rev`, relative to the workspace root.
+fn changed_files(options: &ScanOptions, rev: &str) -> Result<BTreeSet<String>, ScannerError> {
+    // Records spell paths from the workspace root, which the manifest given
+    // may be below; without a workspace, the current directory is the root
+    let root = cargo_metadata(options)
+        .and_then(|metadata| metadata["workspace_root"].as_str().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("."));
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&root)
+            .stderr(Stdio::inherit())
+            .output()
+            .map_err(|err| ScannerError::Integration(format!("failed to run git: {}", err)))?;
+        if !output.status.success() {
+            return Err(ScannerError::Integration(format!(
+                "git {} failed ({})",
+                args[0], output.status
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    // `git diff` prints paths from the top level of the repository
+    let top_level = git(&["rev-parse", "--show-toplevel"])?;
+    let top_level = canonical(Path::new(top_level.trim()));
+    let prefix = canonical(&root)
+        .strip_prefix(&top_level)
+        .map(|prefix| prefix.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_default();
+    let diff = git(&["diff", "--name-only", rev, "--"])?;
+
+    Ok(diff
+        .lines()
+        .filter_map(|path| match prefix.as_str() {
+            "" => Some(path),
+            prefix => path
+                .strip_prefix(prefix)
+                .and_then(|path| path.strip_prefix('/')),
+        })
+        .map(str::to_string)
+        .collect())
+}
+
+/// Resolves symbolic links in `path`, which `git` and `cargo` may spell
+/// differently, keeping `path` if it can't be resolved.
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Runs `cargo metadata` without dependencies for the workspace being
+/// scanned, `None` when there is no workspace to read.
+pub(crate) fn cargo_metadata(options: &ScanOptions) -> Option<serde_json::Value> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let mut command = Command::new(&cargo);
+    command.args(["metadata", "--no-deps", "--format-version", "1"]);
+    if let Some(manifest_path) = &options.manifest_path {
+        command.arg("--manifest-path").arg(manifest_path);
+    }
+    if options.offline {
+        command.arg("--offline");
+    }
+    command
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| serde_json::from_slice(&output.stdout).ok())
+}
+
+/// Returns the executables and shared libraries of one cargo JSON message.
+pub(crate) fn artifact_paths(line: &str) -> Vec<PathBuf> {
+    let Ok(message) = serde_json::from_str::<serde_json::Value>(line) else {
+        return Vec::new();
+    };
+    if message["reason"] != "compiler-artifact" {
+        return Vec::new();
+    }
+    if let Some(executable) = message["executable"].as_str() {
+        return vec![PathBuf::from(executable)];
+    }
+
+    let shared = message["target"]["kind"]
+        .as_array()
+        .is_some_and(|kinds| kinds.iter().any(|kind| kind == "cdylib" || kind == "dylib"));
+    if !shared {
+        return Vec::new();
+    }
+    message["filenames"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|filename| filename.as_str())
+        .map(PathBuf::from)
+        .filter(|path| is_shared_library(path))
+        .collect()
+}
+
+fn is_shared_library(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "so" || ext == "dylib" || ext == "dll")
+}
+
+fn print_table(
+    functions: &BTreeMap<String, Found>,
+    programs: &BTreeMap<String, ProgramContext>,
+    hardening: &BTreeMap<String, Hardening>,
+    scanned: usize,
+    names: &DisplayNames,
+) {
+    for (binary, program) in programs {
+        let data_classes: Vec<String> = program
+            .data_classes
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        println!(
+            "program {}: exposure {}, data {}",
+            binary,
+            program
+                .exposure
+                .map_or("undeclared".to_string(), |exposure| exposure.to_string()),
+            if data_classes.is_empty() {
+                "-".to_string()
+            } else {
+                data_classes.join(", ")
+            }
+        );
+    }
+    for (binary, mitigations) in hardening {
+        println!("hardening {}: {}", binary, mitigations);
+    }
+    let width = functions.keys().map(String::len).max().unwrap_or(0);
+    for level in LEVELS {
+        let group: Vec<&Found> = functions
+            .values()
+            .filter(|found| found.metadata.config.threat_level == level)
+            .collect();
+        println!("{} ({})", names.threat_level(level), group.len());
+        for found in group {
+            let config = &found.metadata.config;
+            let tests: Vec<String> = config
+                .test_types()
+                .into_iter()
+                .map(|test_type| names.test_type(test_type).to_string())
+                .chain(config.custom_tests.iter().cloned())
+                .collect();
+            println!(
+                "  {:width$}  {}",
+                found.metadata.function_name,
+                if tests.is_empty() {
+                    "-".to_string()
+                } else {
+                    tests.join(", ")
+                },
+                width = width
+            );
+        }
+    }
+    println!(
+        "{} function(s) in {} binary(ies) with metadata",
+        functions.len(),
+        scanned
+    );
+}
+
+fn print_json(
+    functions: &BTreeMap<String, Found>,
+    programs: &BTreeMap<String, ProgramContext>,
+    hardening: &BTreeMap<String, Hardening>,
+    scanned: usize,
+    imported: &[(Imported, Correlation<'_>)],
+) {
+    let mut levels = serde_json::Map::new();
+    for level in LEVELS {
+        let group: Vec<_> = functions
+            .values()
+            .filter(|found| found.metadata.config.threat_level == level)
+            .map(|found| {
+                let config = &found.metadata.config;
+                serde_json::json!({
+                    "function": found.metadata.function_name,
+                    "location": found.metadata.location.as_ref().map(ToString::to_string),
+                    "exposure": found.metadata.exposure,
+                    "effective_threat_level": effective_threat_level(
+                        config.threat_level,
+                        found.metadata.exposure
+                    )
+                    .to_string(),
+                    "build": found.metadata.build.as_ref().map(|build| serde_json::json!({
+                        "rustc_version": build.rustc_version,
+                        "panic_unwind": build.panic_unwind,
+                        "overflow_checks": build.overflow_checks,
+                        "debug_assertions": build.debug_assertions,
+                    })),
+                    "input_params": config
+                        .input_params
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>(),
+                    "cvss": config.cvss.as_ref().map(|cvss| serde_json::json!({
+                        "score": cvss.score(),
+                        "vector": cvss.vector,
+                    })),
+                    "threat_level_by_profile": config.profile_threat_levels.map(|levels| {
+                        serde_json::json!({
+                            "debug": levels.debug.to_string(),
+                            "release": levels.release.to_string(),
+                        })
+                    }),
+                    "test_types": config.test_types(),
+                    "custom_tests": config.custom_tests,
+                    "compliance_tags": config.compliance_tags,
+                    "reads_env": config.reads_env,
+                    "fixtures": config.fixtures,
+                    "attack_techniques": config.attack_techniques,
+                    "sla_days": config.sla_days,
+                    "test_context": config.test_context,
+                    "binaries": found.artifacts,
+                    "banned_calls": config.banned_calls,
+                    "instances": found.metadata.instances,
+                    "has_code": found.has_code,
+                    "code": found
+                        .code
+                        .iter()
+                        .map(|(binary, range)| serde_json::json!({
+                            "binary": binary,
+                            "address": range.address,
+                            "size": range.size,
+                        }))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        levels.insert(level.to_string(), group.into());
+    }
+
+    let programs: Vec<_> = programs
+        .iter()
+        .map(|(binary, program)| {
+            serde_json::json!({
+                "binary": binary,
+                "entry_point": program.entry_point,
+                "location": program.location.as_ref().map(ToString::to_string),
+                "exposure": program.exposure,
+                "data_classes": program.data_classes,
+            })
+        })
+        .collect();
+
+    let hardening: Vec<_> = hardening
+        .iter()
+        .map(|(binary, mitigations)| {
+            serde_json::json!({
+                "binary": binary,
+                "pie": mitigations.pie,
+                "relro": mitigations.relro.map(|relro| relro.to_string()),
+                "nx": mitigations.nx,
+                "stack_protector": mitigations.stack_protector,
+                "fortify": mitigations.fortify,
+                "cfg": mitigations.cfg,
+            })
+        })
+        .collect();
+
+    let report = serde_json::json!({
+        "binaries_scanned": scanned,
+        "functions": functions.len(),
+        "programs": programs,
+        "hardening": hardening,
+        "threat_levels": levels,
+        "imported": import::json(imported),
+    });
+    println!("{}", report);
+}
+
+fn print_sarif(
+    functions: &BTreeMap<String, Found>,
+    names: &DisplayNames,
+    imported: &[(Imported, Correlation<'_>)],
+) {
+    let metadata: Vec<SecurityTestMetadata> = functions
+        .values()
+        .map(|found| found.metadata.clone())
+        .collect();
+    let log = sarif::Log::from_metadata_with_names(&metadata, names);
+    let mut log = serde_json::to_value(&log).expect("SARIF logs always serialize");
+    if let Some(runs) = log["runs"].as_array_mut() {
+        runs.extend(import::sarif_runs(imported));
+    }
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&log).expect("SARIF logs always serialize")
+    );
+}
@@ -0,0 +1,406 @@
+//! `stamp`: write the verdict of a scan into the binary scanned.
+//!
+//! Scans one binary the way `scan --binary` does and writes a summary of
+//! the result into a [`SECTION`] section appended to it, so whoever
+//! deploys the binary can tell it was scanned, when and by which version,
+//! and with what result, without running the scan again. `verify-artifact`
+//! checks it. The section holds one JSON object:
+//!
+//! - `version`: 1, the version of this layout
+//! - `tool`: the `name` and `version` of the scanner
+//! - `scanned_at`: when the scan ran, in seconds since the Unix epoch
+//! - `binary`: the `length` and `sha256` of the binary as scanned, see
+//!   [`binary_digest`]
+//! - `functions` and `threat_levels`: how many annotated functions the
+//!   binary has, in all and by threat level
+//! - `warnings`: how many functions `scan` warns about, by warning:
+//!   `no_code`, `banned_calls`, `unmitigated_overflow` and
+//!   `overflow_checks`
+//! - `signature`: the HMAC-SHA-256 of the object without `signature`,
+//!   serialized as compact JSON with sorted keys, under the key given with
+//!   `--signing-key`, in hex
+//!
+//! The signature is a MAC rather than a public-key signature: verifying it
+//! takes the same key, which the deploy pipeline keeps like any other
+//! secret. Only ELF binaries can be stamped, and only once; stamp the
+//! binary as it was built. Binaries with damaged records are refused with
+//! `format.corrupted`, as a verdict over missing records would mislead.
+//! There are no harnesses whose test runs a verdict could include yet, so
+//! it summarizes the scan.
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use security_scanner::format::tlv;
+use security_scanner::{FormatError, ScannerError};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::scan::{self, Found, Inventory, ScanOptions};
+
+/// Name of the section holding the verdict.
+pub const SECTION: &str = ".security_verdict";
+
+/// Version of the verdict's layout.
+const VERSION: u64 = 1;
+
+/// First reserved section index; section counts from it on are kept in the
+/// first section header instead.
+const SHN_LORESERVE: u16 = 0xff00;
+
+/// `sh_type` of sections with contents of the program's own format.
+const SHT_PROGBITS: u64 = 1;
+
+/// Runs `stamp` on `binary`, writing the stamped binary to `output`, or
+/// over `binary` without one.
+pub fn run(
+    binary: &Path,
+    output: Option<&Path>,
+    signing_key: Option<&str>,
+    metadata_key: Option<String>,
+    json: bool,
+) -> Result<(), ScannerError> {
+    let key = signing_key_of(signing_key)?;
+    let data = std::fs::read(binary).map_err(|err| ScannerError::io("read", binary, err))?;
+    if read(&data)?.is_some() {
+        return Err(ScannerError::Usage(format!(
+            "{} already has a verdict; stamp the binary as it was built",
+            binary.display()
+        )));
+    }
+    let inventory = scan::collect(&ScanOptions {
+        manifest_path: None,
+        release: false,
+        tests: false,
+        offline: true,
+        binaries: vec![binary.to_path_buf()],
+        images: Vec::new(),
+        changed_since: None,
+        metadata_key,
+    })?;
+    if inventory.damaged > 0 {
+        return Err(FormatError::Corrupted {
+            count: inventory.damaged,
+        }
+        .into());
+    }
+
+    let scanned_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let mut verdict = summary(&inventory);
+    verdict["version"] = json!(VERSION);
+    verdict["tool"] = json!({
+        "name": "cargo-security-scan",
+        "version": env!("CARGO_PKG_VERSION"),
+    });
+    verdict["scanned_at"] = json!(scanned_at);
+    verdict["binary"] = json!({
+        "length": data.len(),
+        "sha256": binary_digest(&data, data.len()).expect("the whole binary is in range"),
+    });
+    verdict["signature"] = json!(signature(&verdict, &key));
+
+    let stamped = add_section(&data, SECTION, verdict.to_string().as_bytes())
+        .map_err(|err| ScannerError::Usage(format!("can't stamp {}: {}", binary.display(), err)))?;
+    let output = output.unwrap_or(binary);
+    std::fs::write(output, stamped).map_err(|err| ScannerError::io("write", output, err))?;
+    // A new file keeps the binary executable
+    if output != binary {
+        let permissions = std::fs::metadata(binary)
+            .map_err(|err| ScannerError::io("read", binary, err))?
+            .permissions();
+        std::fs::set_permissions(output, permissions)
+            .map_err(|err| ScannerError::io("write", output, err))?;
+    }
+
+    if json {
+        println!("{}", verdict);
+    } else {
+        println!(
+            "stamped {}: {} function(s), {} critical, {} warning(s)",
+            output.display(),
+            verdict["functions"],
+            verdict["threat_levels"]["critical"],
+            verdict["warnings"]
+                .as_object()
+                .map_or(0, |warnings| warnings
+                    .values()
+                    .filter_map(Value::as_u64)
+                    .sum())
+        );
+    }
+    Ok(())
+}
+
+/// Returns the counts of a verdict for the functions of `inventory`.
+fn summary(inventory: &Inventory) -> Value {
+    let count = |matches: &dyn Fn(&Found) -> bool| {
+        inventory
+            .functions
+            .values()
+            .filter(|found| matches(found))
+            .count()
+    };
+    let mut levels = serde_json::Map::new();
+    for level in scan::LEVELS {
+        levels.insert(
+            level.to_string(),
+            json!(count(&|found| found.metadata.config.threat_level == level)),
+        );
+    }
+    json!({
+        "functions": inventory.functions.len(),
+        "threat_levels": levels,
+        "warnings": {
+            "no_code": count(&|found| found.has_code == Some(false)),
+            "banned_calls": count(&|found| !found.metadata.config.banned_calls.is_empty()),
+            "unmitigated_overflow": count(&|found| {
+                !found.unmitigated_overflow(&inventory.hardening).is_empty()
+            }),
+            "overflow_checks": count(&|found| found.unchecked_overflow()),
+        },
+    })
+}
+
+/// Parses `--signing-key`.
+pub(crate) fn signing_key_of(hex: Option<&str>) -> Result<[u8; tlv::KEY_LEN], ScannerError> {
+    hex.filter(|hex| !hex.trim().is_empty())
+        .and_then(tlv::parse_key)
+        .ok_or_else(|| {
+            ScannerError::Usage("--signing-key must be 64 hex digits, a 256-bit key".into())
+        })
+}
+
+/// Returns the verdict in the [`SECTION`] section of `data`, `None` when it
+/// has none.
+pub(crate) fn read(data: &[u8]) -> Result<Option<Value>, ScannerError> {
+    let Some(contents) = Layout::of(data).and_then(|layout| layout.section(data, SECTION)) else {
+        return Ok(None);
+    };
+    serde_json::from_slice(contents)
+        .map(Some)
+        .map_err(|err| FormatError::Unsupported(format!("damaged verdict: {}", err)).into())
+}
+
+/// Returns the signature of `verdict`, the HMAC-SHA-256 of its compact JSON
+/// without `signature`, in hex.
+pub(crate) fn signature(verdict: &Value, key: &[u8; tlv::KEY_LEN]) -> String {
+    let mut signed = verdict.clone();
+    if let Some(fields) = signed.as_object_mut() {
+        fields.remove("signature");
+    }
+    hex(&hmac(key, signed.to_string().as_bytes()))
+}
+
+/// Returns the SHA-256 of the first `length` bytes of an ELF binary with
+/// the location and count of its section headers zeroed, in hex: stamping appends the verdict and a new section header
+/// table past the end and changes only those fields, so the stamped binary
+/// has the digest of the binary scanned. `None` if the binary is shorter.
+pub(crate) fn binary_digest(data: &[u8], length: usize) -> Option<String> {
+    let mut binary = data.get(..length)?.to_vec();
+    if let Some(layout) = Layout::of(&binary) {
+        binary[layout.shoff_at..layout.shoff_at + layout.word].fill(0);
+        binary[layout.shnum_at..layout.shnum_at + 2].fill(0);
+    }
+    Some(hex(&Sha256::digest(&binary)))
+}
+
+/// HMAC-SHA-256 of `message` under a 32-byte key (RFC 2104).
+fn hmac(key: &[u8; tlv::KEY_LEN], message: &[u8]) -> [u8; 32] {
+    let mut pad = [0x36u8; 64];
+    for (pad, key) in pad.iter_mut().zip(key) {
+        *pad ^= key;
+    }
+    let inner = Sha256::new()
+        .chain_update(pad)
+        .chain_update(message)
+        .finalize();
+    for byte in &mut pad {
+        // Turns the inner pad (0x36) into the outer one (0x5c)
+        *byte ^= 0x36 ^ 0x5c;
+    }
+    Sha256::new()
+        .chain_update(pad)
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Where an ELF header keeps the fields stamping changes.
+struct Layout {
+    big_endian: bool,
+    /// Size of addresses and offsets, 4 or 8
+    word: usize,
+    /// Offset of `e_shoff`
+    shoff_at: usize,
+    /// Offset of `e_shnum`, followed by `e_shstrndx`
+    shnum_at: usize,
+    /// Size of a section header
+    header_len: usize,
+}
+
+impl Layout {
+    fn of(data: &[u8]) -> Option<Self> {
+        if !data.starts_with(b"\x7fELF") || data.len() < 64 {
+            return None;
+        }
+        let big_endian = match data[5] {
+            1 => false,
+            2 => true,
+            _ => return None,
+        };
+        match data[4] {
+            1 => Some(Layout {
+                big_endian,
+                word: 4,
+                shoff_at: 0x20,
+                shnum_at: 0x30,
+                header_len: 40,
+            }),
+            2 => Some(Layout {
+                big_endian,
+                word: 8,
+                shoff_at: 0x28,
+                shnum_at: 0x3c,
+                header_len: 64,
+            }),
+            _ => None,
+        }
+    }
+
+    fn get(&self, data: &[u8], offset: usize, size: usize) -> Option<u64> {
+        let bytes = data.get(offset..offset.checked_add(size)?)?;
+        let fold = |value: u64, &byte: &u8| value << 8 | u64::from(byte);
+        Some(if self.big_endian {
+            bytes.iter().fold(0, fold)
+        } else {
+            bytes.iter().rev().fold(0, fold)
+        })
+    }
+
+    fn set(&self, data: &mut [u8], offset: usize, size: usize, value: u64) {
+        let bytes = &mut data[offset..offset + size];
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            let shift = if self.big_endian {
+                8 * (size - 1 - index)
+            } else {
+                8 * index
+            };
+            *byte = (value >> shift) as u8;
+        }
+    }
+
+    /// Returns the section headers, with the section name table.
+    fn section_headers<'a>(&self, data: &'a [u8]) -> Option<(&'a [u8], &'a [u8])> {
+        let field = |offset: usize, size: usize| {
+            self.get(data, offset, size)
+                .and_then(|value| usize::try_from(value).ok())
+        };
+        let shoff = field(self.shoff_at, self.word)?;
+        let shnum = field(self.shnum_at, 2)?;
+        let shstrndx = field(self.shnum_at + 2, 2)?;
+        if shoff == 0 || shnum == 0 || shstrndx >= shnum {
+            return None;
+        }
+        let headers = data.get(shoff..shoff.checked_add(shnum * self.header_len)?)?;
+        let names = self.section_data(data, &headers[shstrndx * self.header_len..])?;
+        Some((headers, names))
+    }
+
+    /// Returns the contents of the section whose header starts `header`.
+    fn section_data<'a>(&self, data: &'a [u8], header: &[u8]) -> Option<&'a [u8]> {
+        let (offset_at, size_at) = self.offset_and_size();
+        let offset = usize::try_from(self.get(header, offset_at, self.word)?).ok()?;
+        let size = usize::try_from(self.get(header, size_at, self.word)?).ok()?;
+        data.get(offset..offset.checked_add(size)?)
+    }
+
+    /// Returns the contents of the section called `name`.
+    fn section<'a>(&self, data: &'a [u8], name: &str) -> Option<&'a [u8]> {
+        let (headers, names) = self.section_headers(data)?;
+        headers.chunks_exact(self.header_len).find_map(|header| {
+            let start = usize::try_from(self.get(header, 0, 4)?).ok()?;
+            let rest = names.get(start..)?;
+            let end = rest.iter().position(|&byte| byte == 0)?;
+            (&rest[..end] == name.as_bytes())
+                .then(|| self.section_data(data, header))
+                .flatten()
+        })
+    }
+
+    /// Offsets of `sh_offset` and `sh_size` in a section header.
+    fn offset_and_size(&self) -> (usize, usize) {
+        if self.word == 8 {
+            (24, 32)
+        } else {
+            (16, 20)
+        }
+    }
+}
+
+/// Returns an ELF binary with a section `name` of `contents` added, which
+/// isn't loaded. The contents, a copy of the section name table with the
+/// new name and a new section header table are appended, and the header
+/// points at the new table, so every byte of the binary stays where it
+/// was.
+fn add_section(data: &[u8], name: &str, contents: &[u8]) -> Result<Vec<u8>, String> {
+    let layout = Layout::of(data).ok_or("only ELF binaries can be stamped")?;
+    let (headers, names) = layout
+        .section_headers(data)
+        .ok_or("the binary has no section name table")?;
+    let word = layout.word;
+    let header_len = layout.header_len;
+    let shnum = headers.len() / header_len;
+    if shnum + 1 >= usize::from(SHN_LORESERVE) {
+        return Err("the binary has too many sections".into());
+    }
+    let shstrndx = layout
+        .get(data, layout.shnum_at + 2, 2)
+        .expect("read with the headers") as usize;
+    let (offset_at, size_at) = layout.offset_and_size();
+    let names_header = shstrndx * header_len;
+
+    let mut out = data.to_vec();
+    let contents_offset = out.len();
+    out.extend_from_slice(contents);
+    let new_names_offset = out.len();
+    out.extend_from_slice(names);
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    out.resize(out.len().next_multiple_of(word), 0);
+
+    let new_shoff = out.len();
+    out.extend_from_slice(headers);
+    let names_at = new_shoff + names_header;
+    layout.set(
+        &mut out,
+        names_at + offset_at,
+        word,
+        new_names_offset as u64,
+    );
+    layout.set(
+        &mut out,
+        names_at + size_at,
+        word,
+        (names.len() + name.len() + 1) as u64,
+    );
+
+    let mut header = vec![0u8; header_len];
+    // `sh_name`; `sh_type` is `SHT_PROGBITS`, the section isn't allocated
+    layout.set(&mut header, 0, 4, names.len() as u64);
+    layout.set(&mut header, 4, 4, SHT_PROGBITS);
+    layout.set(&mut header, offset_at, word, contents_offset as u64);
+    layout.set(&mut header, size_at, word, contents.len() as u64);
+    // `sh_addralign`
+    layout.set(&mut header, header_len - 2 * word, word, 1);
+    out.extend_from_slice(&header);
+
+    layout.set(&mut out, layout.shoff_at, word, new_shoff as u64);
+    layout.set(&mut out, layout.shnum_at, 2, (shnum + 1) as u64);
+    Ok(out)
+}
@@ -0,0 +1,558 @@
+//! `suggest`: propose `#[security_test]` attributes from static heuristics.
+//!
+//! Every `.rs` file under the given path is parsed with `syn`, and each free
+//! function without an existing `#[security_test]` is checked against these
+//! heuristics:
+//!
+//! | Pattern                                           | Suggests                     |
+//! |---------------------------------------------------|------------------------------|
+//! | `format!` with a SQL statement and placeholders   | `sql_injection`, `high`      |
+//! | `Command::new` with a non-literal program         | `capabilities(spawn)`, `high`|
+//! | Pointer arithmetic or raw slices inside `unsafe`  | `buffer_overflow`, `high`    |
+//! | Credential-named parameters (`password`, ...)     | `timing_attack`, `critical`  |
+//! | `env::var` reads                                  | `env_injection`, `reads_env` |
+//!
+//! Functions inside `#[cfg(test)]` modules and `#[test]` functions are skipped,
+//! as are individual suggestions covered by a `// security-scanner: ignore(...)`
+//! comment (see [`crate::ignore`]); ignored suggestions are still reported.
+//! The suggestions are printed as a unified diff on stdout, with the reasons
+//! for each one on stderr. With `--apply` the attributes are written into the
+//! files instead; only the inserted lines change, and functions that already
+//! carry `#[security_test]` are skipped, so re-running is a no-op. Adding
+//! `--dry-run` to `--apply` prints the diff and summary `--apply` would
+//! produce without writing anything. With `--json` the suggestions and the
+//! summary are printed as one JSON object instead of the diff and notes.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use security_scanner::{ScannerError, TestType, ThreatLevel};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Attribute, Expr, FnArg, Item, ItemFn, Lit, Pat, Token, Visibility};
+
+use crate::diff::{self, Insertion};
+use crate::ignore;
+
+/// Names accepted by `// security-scanner: ignore(...)`.
+const IGNORABLE: &[&str] = &[
+    "sql_injection",
+    "race_condition",
+    "timing_attack",
+    "buffer_overflow",
+    "env_injection",
+    "spawn",
+];
+
+/// Parameter name fragments that suggest a credential.
+const CREDENTIAL_NAMES: &[&str] = &["password", "passwd", "secret", "api_key"];
+
+/// SQL statement prefixes looked for in `format!` strings.
+const SQL_KEYWORDS: &[&str] = &["select ", "insert into", "update ", "delete from"];
+
+/// Raw-pointer methods that perform unchecked arithmetic or copies.
+const POINTER_METHODS: &[&str] = &[
+    "add",
+    "sub",
+    "offset",
+    "byte_add",
+    "byte_sub",
+    "byte_offset",
+    "copy_to",
+    "copy_to_nonoverlapping",
+    "copy_from",
+    "copy_from_nonoverlapping",
+];
+
+/// Free functions that build slices or copy memory from raw pointers.
+const RAW_MEMORY_FUNCTIONS: &[&str] = &[
+    "from_raw_parts",
+    "from_raw_parts_mut",
+    "copy",
+    "copy_nonoverlapping",
+];
+
+/// A suggested annotation for one function.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    /// Name of the function
+    pub function: String,
+    /// 0-based line the attribute is inserted before
+    pub line: usize,
+    /// Indentation of the function signature
+    pub indent: String,
+    pub test_types: BTreeSet<TestType>,
+    pub reads_env: BTreeSet<String>,
+    pub capabilities: BTreeSet<&'static str>,
+    pub threat_level: ThreatLevel,
+    /// Why each part of the attribute was suggested
+    pub reasons: Vec<String>,
+    /// Suggestions suppressed by ignore comments, with their reasons
+    pub ignored: Vec<String>,
+    /// Problems with the function's ignore comments
+    pub warnings: Vec<String>,
+}
+
+impl Suggestion {
+    /// Returns whether anything is left to insert after ignore comments.
+    pub fn has_attribute(&self) -> bool {
+        !self.reasons.is_empty()
+    }
+
+    /// Renders the suggested attribute line, including indentation.
+    pub fn attribute(&self) -> String {
+        let mut args: Vec<String> = self.test_types.iter().map(|t| t.to_string()).collect();
+
+        if !self.reads_env.is_empty() {
+            let names: Vec<String> = self.reads_env.iter().map(|n| format!("{:?}", n)).collect();
+            args.push(format!("reads_env({})", names.join(", ")));
+        }
+        if !self.capabilities.is_empty() {
+            let names: Vec<&str> = self.capabilities.iter().copied().collect();
+            args.push(format!("capabilities({})", names.join(", ")));
+        }
+        args.push(self.threat_level.to_string());
+
+        format!(
+            "{}#[security_scanner::security_test({})]",
+            self.indent,
+            args.join(", ")
+        )
+    }
+}
+
+/// Runs `suggest` over every Rust file under `path`, printing a diff or, with
+/// `apply`, editing the files in place. With `dry_run`, `apply` only reports
+/// what it would write; with `json`, the output is a JSON report.
+pub fn run(path: &Path, apply: bool, dry_run: bool, json: bool) -> Result<(), ScannerError> {
+    let mut files = Vec::new();
+    collect_rust_files(path, &mut files)?;
+
+    let mut total = 0;
+    let mut files_changed = 0;
+    let mut report = Vec::new();
+    for file in files {
+        let source =
+            fs::read_to_string(&file).map_err(|err| ScannerError::io("read", &file, err))?;
+
+        let suggestions = match suggest_file(&source) {
+            Ok(suggestions) => suggestions,
+            Err(err) => {
+                eprintln!("warning: skipping {}: {}", file.display(), err);
+                continue;
+            }
+        };
+        if suggestions.is_empty() {
+            continue;
+        }
+
+        let display = display_path(&file);
+        for suggestion in &suggestions {
+            let location = format!("{}:{}", display, suggestion.line + 1);
+            if json {
+                report.push(serde_json::json!({
+                    "location": location,
+                    "function": suggestion.function,
+                    "attribute": suggestion
+                        .has_attribute()
+                        .then(|| suggestion.attribute().trim_start().to_string()),
+                    "reasons": suggestion.reasons,
+                    "ignored": suggestion.ignored,
+                    "warnings": suggestion.warnings,
+                }));
+                continue;
+            }
+
+            for warning in &suggestion.warnings {
+                eprintln!(
+                    "warning: {}: {}: {}",
+                    location, suggestion.function, warning
+                );
+            }
+            let notes: Vec<&str> = suggestion
+                .reasons
+                .iter()
+                .chain(&suggestion.ignored)
+                .map(String::as_str)
+                .collect();
+            if !notes.is_empty() {
+                eprintln!(
+                    "{}: {}: {}",
+                    location,
+                    suggestion.function,
+                    notes.join("; ")
+                );
+            }
+        }
+
+        let insertions: Vec<Insertion> = suggestions
+            .iter()
+            .filter(|suggestion| suggestion.has_attribute())
+            .map(|suggestion| Insertion {
+                line: suggestion.line,
+                text: suggestion.attribute(),
+            })
+            .collect();
+        if insertions.is_empty() {
+            continue;
+        }
+
+        if apply && !dry_run {
+            fs::write(&file, diff::apply(&source, &insertions))
+                .map_err(|err| ScannerError::io("write", &file, err))?;
+        } else if !json {
+            print!("{}", diff::unified_diff(&display, &source, &insertions));
+        }
+        files_changed += 1;
+        total += insertions.len();
+    }
+
+    if json {
+        let summary = serde_json::json!({
+            "suggestions": report,
+            "total": total,
+            "files_changed": files_changed,
+            "applied": apply && !dry_run,
+        });
+        println!("{}", summary);
+    } else if apply && dry_run {
+        eprintln!(
+            "dry run: would annotate {} function(s) in {} file(s)",
+            total, files_changed
+        );
+    } else if apply {
+        eprintln!(
+            "annotated {} function(s) in {} file(s)",
+            total, files_changed
+        );
+    } else {
+        eprintln!("{} suggestion(s)", total);
+    }
+    Ok(())
+}
+
+/// Returns the suggestions for one source file.
+pub fn suggest_file(source: &str) -> syn::Result<Vec<Suggestion>> {
+    let file = syn::parse_file(source)?;
+    let lines: Vec<&str> = source.lines().collect();
+
+    let mut suggestions = Vec::new();
+    collect_items(&file.items, &lines, &mut suggestions);
+    Ok(suggestions)
+}
+
+fn collect_items(items: &[Item], lines: &[&str], suggestions: &mut Vec<Suggestion>) {
+    for item in items {
+        match item {
+            Item::Fn(item_fn) => {
+                if let Some(suggestion) = suggest_fn(item_fn, lines) {
+                    suggestions.push(suggestion);
+                }
+            }
+            Item::Mod(item_mod) if !is_cfg_test(&item_mod.attrs) => {
+                if let Some((_, items)) = &item_mod.content {
+                    collect_items(items, lines, suggestions);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn suggest_fn(item_fn: &ItemFn, lines: &[&str]) -> Option<Suggestion> {
+    if has_attribute(&item_fn.attrs, "security_test") {
+        return None;
+    }
+    analyze_fn(item_fn, lines)
+}
+
+/// Runs the heuristics on one function, whether or not it is already
+/// annotated. Returns `None` for `#[test]` functions and functions nothing
+/// matched.
+pub fn analyze_fn(item_fn: &ItemFn, lines: &[&str]) -> Option<Suggestion> {
+    if has_attribute(&item_fn.attrs, "test") {
+        return None;
+    }
+
+    let mut scan = BodyScan {
+        unsafe_depth: usize::from(item_fn.sig.unsafety.is_some()),
+        ..BodyScan::default()
+    };
+    scan.visit_block(&item_fn.block);
+
+    // Each heuristic is keyed by the name an ignore comment uses for it
+    let mut matches: Vec<(&'static str, ThreatLevel, String)> = Vec::new();
+    if scan.sql_format {
+        matches.push((
+            TestType::SqlInjection.as_str(),
+            ThreatLevel::High,
+            "formats a SQL statement with `format!`".to_string(),
+        ));
+    }
+    if scan.spawns_variable_command {
+        matches.push((
+            "spawn",
+            ThreatLevel::High,
+            "passes a non-literal program to `Command::new`".to_string(),
+        ));
+    }
+    if scan.raw_memory {
+        matches.push((
+            TestType::BufferOverflow.as_str(),
+            ThreatLevel::High,
+            "performs pointer arithmetic inside `unsafe`".to_string(),
+        ));
+    }
+    if scan.reads_env {
+        matches.push((
+            TestType::EnvInjection.as_str(),
+            ThreatLevel::Medium,
+            "reads configuration with `env::var`".to_string(),
+        ));
+    }
+    for input in &item_fn.sig.inputs {
+        if let Some(name) = credential_param(input) {
+            matches.push((
+                TestType::TimingAttack.as_str(),
+                ThreatLevel::Critical,
+                format!("parameter `{}` looks like a credential", name),
+            ));
+        }
+    }
+
+    let start = match &item_fn.vis {
+        Visibility::Inherited => item_fn.sig.span().start(),
+        vis => vis.span().start(),
+    };
+    let line = start.line.saturating_sub(1);
+    let item_line = item_fn.span().start().line.saturating_sub(1);
+
+    let mut directives = Vec::new();
+    let mut warnings = Vec::new();
+    for directive in ignore::directives_for(lines, item_line, line) {
+        match directive {
+            Ok(directive) => {
+                for target in directive.targets.iter().flatten() {
+                    if !IGNORABLE.contains(&target.as_str()) {
+                        warnings.push(format!("ignore names unknown suggestion `{}`", target));
+                    }
+                }
+                directives.push(directive);
+            }
+            Err(err) => warnings.push(err),
+        }
+    }
+
+    let mut test_types = BTreeSet::new();
+    let mut capabilities = BTreeSet::new();
+    let mut threat_level = ThreatLevel::Low;
+    let mut reasons = Vec::new();
+    let mut ignored = Vec::new();
+
+    for (key, level, reason) in matches {
+        if let Some(directive) = directives.iter().find(|directive| directive.covers(key)) {
+            ignored.push(match &directive.reason {
+                Some(why) => format!("ignored {} ({})", key, why),
+                None => format!("ignored {}", key),
+            });
+            continue;
+        }
+
+        match key.parse::<TestType>() {
+            Ok(test_type) => {
+                test_types.insert(test_type);
+            }
+            Err(_) => {
+                capabilities.insert(key);
+            }
+        }
+        threat_level = threat_level.max(level);
+        reasons.push(reason);
+    }
+
+    if reasons.is_empty() && ignored.is_empty() && warnings.is_empty() {
+        return None;
+    }
+
+    let reads_env = if test_types.contains(&TestType::EnvInjection) {
+        scan.env_vars
+    } else {
+        BTreeSet::new()
+    };
+    let indent = lines
+        .get(line)
+        .map(|text| text.chars().take_while(|c| c.is_whitespace()).collect())
+        .unwrap_or_default();
+
+    Some(Suggestion {
+        function: item_fn.sig.ident.to_string(),
+        line,
+        indent,
+        test_types,
+        reads_env,
+        capabilities,
+        threat_level,
+        reasons,
+        ignored,
+        warnings,
+    })
+}
+
+/// What the heuristics found in one function body.
+#[derive(Default)]
+struct BodyScan {
+    unsafe_depth: usize,
+    sql_format: bool,
+    spawns_variable_command: bool,
+    raw_memory: bool,
+    reads_env: bool,
+    env_vars: BTreeSet<String>,
+}
+
+impl<'ast> Visit<'ast> for BodyScan {
+    fn visit_expr_unsafe(&mut self, node: &'ast syn::ExprUnsafe) {
+        self.unsafe_depth += 1;
+        visit::visit_expr_unsafe(self, node);
+        self.unsafe_depth -= 1;
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        if self.unsafe_depth > 0 && POINTER_METHODS.contains(&node.method.to_string().as_str()) {
+            self.raw_memory = true;
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        if let Expr::Path(func) = &*node.func {
+            let segments: Vec<String> = func
+                .path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect();
+            let tail: Vec<&str> = segments.iter().rev().take(2).map(String::as_str).collect();
+
+            match tail.as_slice() {
+                ["new", "Command"] if !node.args.first().is_some_and(is_string_literal) => {
+                    self.spawns_variable_command = true;
+                }
+                ["var", "env"] | ["var_os", "env"] => {
+                    self.reads_env = true;
+                    if let Some(Expr::Lit(lit)) = node.args.first() {
+                        if let Lit::Str(name) = &lit.lit {
+                            self.env_vars.insert(name.value());
+                        }
+                    }
+                }
+                [name, ..] if self.unsafe_depth > 0 && RAW_MEMORY_FUNCTIONS.contains(name) => {
+                    self.raw_memory = true;
+                }
+                _ => {}
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        if node.path.is_ident("format") {
+            let args = node.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated);
+            if let Ok(args) = args {
+                if let Some(Expr::Lit(lit)) = args.first() {
+                    if let Lit::Str(template) = &lit.lit {
+                        self.sql_format |= is_sql_template(&template.value());
+                    }
+                }
+                for arg in &args {
+                    self.visit_expr(arg);
+                }
+            }
+        }
+        visit::visit_macro(self, node);
+    }
+}
+
+fn is_sql_template(template: &str) -> bool {
+    let lower = template.to_lowercase();
+    lower.contains('{') && SQL_KEYWORDS.iter().any(|keyword| lower.contains(keyword))
+}
+
+fn is_string_literal(expr: &Expr) -> bool {
+    matches!(expr, Expr::Lit(lit) if matches!(lit.lit, Lit::Str(_)))
+}
+
+fn credential_param(input: &FnArg) -> Option<String> {
+    let FnArg::Typed(typed) = input else {
+        return None;
+    };
+    let Pat::Ident(pat) = &*typed.pat else {
+        return None;
+    };
+
+    let name = pat.ident.to_string();
+    let lower = name.to_lowercase();
+    let is_credential = CREDENTIAL_NAMES
+        .iter()
+        .any(|fragment| lower.contains(fragment))
+        || lower.ends_with("_token");
+    is_credential.then_some(name)
+}
+
+pub fn has_attribute(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name)
+    })
+}
+
+pub fn is_cfg_test(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("cfg")
+            && attr
+                .parse_args::<syn::Ident>()
+                .is_ok_and(|ident| ident == "test")
+    })
+}
+
+/// Collects `.rs` files under `path`, skipping `target` and hidden directories.
+pub fn collect_rust_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), ScannerError> {
+    if path.is_file() {
+        if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path.to_path_buf());
+        }
+        return Ok(());
+    }
+
+    let entries =
+        fs::read_dir(path).map_err(|err| ScannerError::io("read directory", path, err))?;
+    let mut entries: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    entries.sort();
+
+    for entry in entries {
+        let name = entry
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        if entry.is_dir() && (name == "target" || name.starts_with('.')) {
+            continue;
+        }
+        collect_rust_files(&entry, files)?;
+    }
+
+    Ok(())
+}
+
+/// Renders a path for diff headers, without a leading `./`.
+pub fn display_path(path: &Path) -> String {
+    let display = path.display().to_string();
+    display
+        .strip_prefix("./")
+        .map(str::to_string)
+        .unwrap_or(display)
+}
@@ -0,0 +1,177 @@
+//! `verify-artifact`: check the verdict `stamp` wrote into a binary before
+//! deploying it.
+//!
+//! Fails with `policy.failed` unless every check passes, naming each one
+//! that didn't:
+//!
+//! - the binary has a verdict, in the section [`crate::stamp::SECTION`]
+//! - its signature verifies under `--signing-key`, the key it was stamped
+//!   with
+//! - the binary is the one scanned: the digest of its first `length` bytes
+//!   matches the verdict's, see [`crate::stamp::binary_digest`]; stripping
+//!   or otherwise rewriting a binary after stamping fails this
+//! - the scan is no older than `--max-age`, `30d` unless given
+//! - the counts meet the policy: no more critical functions than
+//!   `--max-critical`, and with `--deny-warnings` none that `scan` warns
+//!   about
+//!
+//! The counts are those of the verdict, so the policy can be tightened
+//! without scanning again, but a scanner fixed since the stamp isn't
+//! applied to it; stamp again with a newer build to have it be.
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use security_scanner::ScannerError;
+use serde_json::json;
+
+use crate::stamp;
+
+/// What a verdict must meet.
+pub struct Policy {
+    /// Oldest scan accepted, in seconds
+    pub max_age: u64,
+    /// Most critical functions accepted
+    pub max_critical: Option<u64>,
+    /// Whether warnings fail the check
+    pub deny_warnings: bool,
+}
+
+/// Runs `verify-artifact` on `binary`.
+pub fn run(
+    binary: &Path,
+    signing_key: Option<&str>,
+    policy: &Policy,
+    json: bool,
+) -> Result<(), ScannerError> {
+    let key = stamp::signing_key_of(signing_key)?;
+    let data = std::fs::read(binary).map_err(|err| ScannerError::io("read", binary, err))?;
+    let Some(verdict) = stamp::read(&data)? else {
+        return Err(ScannerError::Policy(format!(
+            "{} has no verdict; stamp it after scanning",
+            binary.display()
+        )));
+    };
+
+    let mut failures = Vec::new();
+    let signed = verdict["signature"]
+        .as_str()
+        .is_some_and(|signature| same(signature, &stamp::signature(&verdict, &key)));
+    if !signed {
+        failures.push("the signature doesn't verify with the signing key".to_string());
+    }
+    let digest = verdict["binary"]["length"]
+        .as_u64()
+        .and_then(|length| usize::try_from(length).ok())
+        .and_then(|length| stamp::binary_digest(&data, length));
+    if digest.is_none() || digest.as_deref() != verdict["binary"]["sha256"].as_str() {
+        failures.push("the binary changed since it was scanned".to_string());
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let age = verdict["scanned_at"]
+        .as_u64()
+        .map(|scanned_at| now.saturating_sub(scanned_at));
+    match age {
+        Some(age) if age <= policy.max_age => {}
+        Some(age) => failures.push(format!(
+            "the scan is {} old, more than {}",
+            duration(age),
+            duration(policy.max_age)
+        )),
+        None => failures.push("the verdict has no scan time".to_string()),
+    }
+
+    let critical = verdict["threat_levels"]["critical"].as_u64().unwrap_or(0);
+    if let Some(max) = policy.max_critical.filter(|&max| critical > max) {
+        failures.push(format!(
+            "{} critical function(s), more than {}",
+            critical, max
+        ));
+    }
+    let warnings: Vec<String> = verdict["warnings"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(warning, count)| {
+            let count = count.as_u64().filter(|&count| count > 0)?;
+            Some(format!("{} {}", count, warning))
+        })
+        .collect();
+    if policy.deny_warnings && !warnings.is_empty() {
+        failures.push(format!("warnings: {}", warnings.join(", ")));
+    }
+
+    if json {
+        let report = json!({
+            "binary": binary.display().to_string(),
+            "verified": failures.is_empty(),
+            "failures": failures,
+            "age": age,
+            "verdict": verdict,
+        });
+        println!("{}", report);
+    } else if failures.is_empty() {
+        println!(
+            "verified {}: scanned {} ago by {} {}, {} function(s), {} critical",
+            binary.display(),
+            duration(age.unwrap_or(0)),
+            verdict["tool"]["name"].as_str().unwrap_or("-"),
+            verdict["tool"]["version"].as_str().unwrap_or("-"),
+            verdict["functions"],
+            critical
+        );
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(ScannerError::Policy(format!(
+            "{} failed verification: {}",
+            binary.display(),
+            failures.join("; ")
+        )))
+    }
+}
+
+/// Parses a duration such as `90s`, `30m`, `12h` or `7d` into seconds; a
+/// bare number is seconds.
+pub fn parse_duration(text: &str) -> Result<u64, String> {
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => text.split_at(index),
+        None => (text, "s"),
+    };
+    let scale = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("unknown unit {:?}; use s, m, h or d", unit)),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(scale))
+        .ok_or_else(|| format!("{:?} isn't a duration", text))
+}
+
+/// Formats seconds in the largest unit they fill.
+fn duration(seconds: u64) -> String {
+    match seconds {
+        0..=119 => format!("{}s", seconds),
+        120..=7199 => format!("{}m", seconds / 60),
+        7200..=172_799 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86_400),
+    }
+}
+
+/// Compares two signatures in time independent of where they differ.
+fn same(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
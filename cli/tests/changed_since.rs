@@ -0,0 +1,99 @@
+//! `scan --changed-since` run on a member crate of a workspace, whose
+//! records spell paths from the workspace root rather than the member.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const MANIFEST: &str = "[workspace]\nmembers = [\"member\"]\nresolver = \"2\"\n";
+
+const MAIN: &str = r#"mod auth;
+mod feed;
+
+fn main() {
+    auth::login("alice");
+    feed::render("home");
+}
+"#;
+
+const AUTH: &str = r#"use security_scanner::security_test;
+
+#[security_test(sql_injection, critical, anchor)]
+pub fn login(name: &str) -> bool {
+    name == "alice"
+}
+"#;
+
+const FEED: &str = r#"use security_scanner::security_test;
+
+#[security_test(xss, low, anchor)]
+pub fn render(page: &str) -> usize {
+    page.len()
+}
+"#;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .expect("git runs");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+#[test]
+fn member_manifests_match_paths_from_the_workspace_root() {
+    let workspace = Path::new(env!("CARGO_TARGET_TMPDIR")).join("changed-since");
+    let _ = fs::remove_dir_all(&workspace);
+    let member = workspace.join("member");
+    fs::create_dir_all(member.join("src")).unwrap();
+    let scanner = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    fs::write(workspace.join("Cargo.toml"), MANIFEST).unwrap();
+    fs::write(
+        member.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"member\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+             [dependencies]\nsecurity-scanner = {{ path = {:?} }}\n",
+            scanner.display().to_string()
+        ),
+    )
+    .unwrap();
+    fs::write(member.join("src/main.rs"), MAIN).unwrap();
+    fs::write(member.join("src/auth.rs"), AUTH).unwrap();
+    fs::write(member.join("src/feed.rs"), FEED).unwrap();
+
+    git(&workspace, &["init", "--quiet"]);
+    git(&workspace, &["add", "."]);
+    git(&workspace, &["commit", "--quiet", "-m", "fixture"]);
+    fs::write(member.join("src/auth.rs"), AUTH.replace("alice", "bob")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-security-scan"))
+        .args([
+            "--json",
+            "scan",
+            "--changed-since",
+            "HEAD",
+            "--manifest-path",
+        ])
+        .arg(member.join("Cargo.toml"))
+        // Shares the dependencies between runs
+        .env(
+            "CARGO_TARGET_DIR",
+            workspace.with_file_name("changed-since-target"),
+        )
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["functions"], 1, "{}", report);
+    assert_eq!(
+        report["threat_levels"]["critical"][0]["function"],
+        "member::auth::login"
+    );
+}
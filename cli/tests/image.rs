@@ -0,0 +1,133 @@
+//! `scan --image` on a `docker save` tarball of a small crate's binary,
+//! with a later, gzip-compressed layer deleting one copy of it. Linux only,
+//! where the binary is an ELF file as in an image.
+
+#![cfg(target_os = "linux")]
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const MAIN: &str = r#"use security_scanner::security_test;
+
+#[security_test(command_injection, high, anchor)]
+pub fn run(command: &str) -> usize {
+    command.len()
+}
+
+fn main() {
+    run("ls");
+}
+"#;
+
+/// Appends a ustar entry to `archive`.
+fn entry(archive: &mut Vec<u8>, path: &str, kind: u8, link: &str, data: &[u8]) {
+    let mut header = [0u8; 512];
+    header[..path.len()].copy_from_slice(path.as_bytes());
+    header[100..108].copy_from_slice(b"0000755\0");
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", data.len()).as_bytes());
+    header[136..148].copy_from_slice(b"00000000000\0");
+    header[148..156].copy_from_slice(b"        ");
+    header[156] = kind;
+    header[157..157 + link.len()].copy_from_slice(link.as_bytes());
+    header[257..265].copy_from_slice(b"ustar\x0000");
+    let checksum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    archive.extend_from_slice(&header);
+    archive.extend_from_slice(data);
+    archive.resize(archive.len().next_multiple_of(512), 0);
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut child = Command::new("gzip")
+        .arg("-c")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("gzip runs");
+    child.stdin.take().unwrap().write_all(data).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    output.stdout
+}
+
+#[test]
+fn scans_the_binaries_an_image_runs_with() {
+    let fixture = Path::new(env!("CARGO_TARGET_TMPDIR")).join("image");
+    let _ = fs::remove_dir_all(&fixture);
+    fs::create_dir_all(fixture.join("src")).unwrap();
+    let scanner = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    fs::write(
+        fixture.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"image-fixture\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+             [dependencies]\nsecurity-scanner = {{ path = {:?} }}\n\n[workspace]\n",
+            scanner.display().to_string()
+        ),
+    )
+    .unwrap();
+    fs::write(fixture.join("src/main.rs"), MAIN).unwrap();
+    let target = fixture.with_file_name("image-target");
+    let build = Command::new(env!("CARGO"))
+        .arg("build")
+        .arg("--manifest-path")
+        .arg(fixture.join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", &target)
+        .output()
+        .unwrap();
+    assert!(
+        build.status.success(),
+        "{}",
+        String::from_utf8_lossy(&build.stderr)
+    );
+    let binary = fs::read(target.join("debug/image-fixture")).unwrap();
+
+    let mut base = Vec::new();
+    entry(
+        &mut base,
+        "etc/passwd",
+        b'0',
+        "",
+        b"root:x:0:0::/root:/bin/sh\n",
+    );
+    entry(&mut base, "usr/bin/app", b'0', "", &binary);
+    entry(&mut base, "usr/bin/old", b'0', "", &binary);
+    entry(&mut base, "usr/bin/alias", b'2', "app", b"");
+    base.extend_from_slice(&[0; 1024]);
+    let mut update = Vec::new();
+    entry(&mut update, "usr/bin/.wh.old", b'0', "", b"");
+    update.extend_from_slice(&[0; 1024]);
+
+    let manifest = r#"[{"Config":"config.json","RepoTags":["app:latest"],"Layers":["base/layer.tar","update/layer.tar"]}]"#;
+    let mut image = Vec::new();
+    entry(&mut image, "manifest.json", b'0', "", manifest.as_bytes());
+    entry(&mut image, "config.json", b'0', "", b"{}");
+    entry(&mut image, "base/layer.tar", b'0', "", &base);
+    entry(&mut image, "update/layer.tar", b'0', "", &gzip(&update));
+    image.extend_from_slice(&[0; 1024]);
+    let tarball = fixture.join("app.tar");
+    fs::write(&tarball, image).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-security-scan"))
+        .args(["--json", "scan", "--image"])
+        .arg(&tarball)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["binaries_scanned"], 1, "{}", report);
+    let function = &report["threat_levels"]["high"][0];
+    assert_eq!(function["function"], "image_fixture::run");
+    assert_eq!(
+        function["binaries"],
+        serde_json::json!([format!("{}:/usr/bin/app", tarball.display())])
+    );
+}
@@ -0,0 +1,166 @@
+//! `scan --import` merging Semgrep, SARIF and cargo-audit findings into the
+//! report of a small crate. The findings are on the lines of function
+//! names, which records give on every platform; the lines after them take
+//! DWARF debug info in the binary.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const MAIN: &str = r#"mod db;
+
+fn main() {
+    db::lookup("alice");
+    db::render("home");
+}
+"#;
+
+const DB: &str = r#"use security_scanner::security_test;
+
+#[security_test(sql_injection, critical, anchor)]
+pub fn lookup(name: &str) -> usize {
+    name.len()
+}
+
+#[security_test(xss, low, anchor)]
+pub fn render(page: &str) -> usize {
+    page.len() + 1
+}
+"#;
+
+const SEMGREP: &str = r#"{
+  "results": [
+    {
+      "check_id": "rust.lang.security.sqlx-format",
+      "path": "src/db.rs",
+      "start": { "line": 9, "col": 8 },
+      "extra": {
+        "message": "SQL built with format!",
+        "metadata": { "cwe": ["CWE-89: Improper Neutralization of Special Elements used in an SQL Command"] }
+      }
+    },
+    {
+      "check_id": "rust.lang.security.command-injection",
+      "path": "src/main.rs",
+      "start": { "line": 4, "col": 5 },
+      "extra": { "message": "Command built from input", "metadata": {} }
+    }
+  ],
+  "errors": []
+}"#;
+
+const SARIF: &str = r#"{
+  "version": "2.1.0",
+  "runs": [
+    {
+      "tool": { "driver": { "name": "CodeQL", "rules": [
+        { "id": "rust/sql-injection", "properties": { "tags": ["security", "external/cwe/cwe-089"] } }
+      ] } },
+      "results": [
+        {
+          "ruleId": "rust/sql-injection",
+          "ruleIndex": 0,
+          "message": { "text": "Query built from user input" },
+          "locations": [{ "physicalLocation": {
+            "artifactLocation": { "uri": "file:///work/import/src/db.rs" },
+            "region": { "startLine": 4 }
+          } }]
+        }
+      ]
+    }
+  ]
+}"#;
+
+const AUDIT: &str = r#"{
+  "vulnerabilities": {
+    "found": true,
+    "count": 1,
+    "list": [
+      {
+        "advisory": { "id": "RUSTSEC-2099-0001", "package": "import-fixture", "title": "Overflow in lookup", "keywords": [] },
+        "package": { "name": "import-fixture", "version": "0.0.0" }
+      }
+    ]
+  }
+}"#;
+
+#[test]
+fn imported_findings_are_placed_in_annotated_functions() {
+    let fixture = Path::new(env!("CARGO_TARGET_TMPDIR")).join("import");
+    let _ = fs::remove_dir_all(&fixture);
+    fs::create_dir_all(fixture.join("src")).unwrap();
+    let scanner = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    fs::write(
+        fixture.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"import-fixture\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+             [dependencies]\nsecurity-scanner = {{ path = {:?} }}\n\n[workspace]\n",
+            scanner.display().to_string()
+        ),
+    )
+    .unwrap();
+    fs::write(fixture.join("src/main.rs"), MAIN).unwrap();
+    fs::write(fixture.join("src/db.rs"), DB).unwrap();
+    fs::write(fixture.join("semgrep.json"), SEMGREP).unwrap();
+    fs::write(fixture.join("codeql.sarif"), SARIF).unwrap();
+    fs::write(fixture.join("audit.json"), AUDIT).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-security-scan"))
+        .args(["--json", "scan", "--manifest-path"])
+        .arg(fixture.join("Cargo.toml"))
+        .arg("--import")
+        .arg(fixture.join("semgrep.json"))
+        .arg("--import")
+        .arg(fixture.join("codeql.sarif"))
+        .arg("--import")
+        .arg(fixture.join("audit.json"))
+        // Shares the dependencies between runs
+        .env("CARGO_TARGET_DIR", fixture.with_file_name("import-target"))
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(
+        stderr.contains("import_fixture::db::render (sql_injection from semgrep)"),
+        "{}",
+        stderr
+    );
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let imported = report["imported"].as_array().unwrap();
+    let correlations: Vec<(&str, &str, &serde_json::Value)> = imported
+        .iter()
+        .map(|finding| {
+            (
+                finding["tool"].as_str().unwrap(),
+                finding["correlation"].as_str().unwrap(),
+                &finding["functions"],
+            )
+        })
+        .collect();
+    assert_eq!(
+        correlations,
+        [
+            (
+                "semgrep",
+                "missing_test_type",
+                &serde_json::json!(["import_fixture::db::render"])
+            ),
+            ("semgrep", "unannotated", &serde_json::json!([])),
+            (
+                "CodeQL",
+                "covered",
+                &serde_json::json!(["import_fixture::db::lookup"])
+            ),
+            (
+                "cargo-audit",
+                "crate",
+                &serde_json::json!(["import_fixture::db::lookup", "import_fixture::db::render"])
+            ),
+        ],
+        "{}",
+        report
+    );
+    assert_eq!(imported[0]["test_type"], "sql_injection");
+    assert_eq!(imported[1]["test_type"], "command_injection");
+}
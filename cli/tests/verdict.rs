@@ -0,0 +1,142 @@
+//! `stamp` writing the verdict of a scan into a small crate's binary, and
+//! `verify-artifact` checking it, on Linux, whose binaries are ELF.
+
+#![cfg(target_os = "linux")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const MAIN: &str = r#"use security_scanner::security_test;
+
+#[security_test(sql_injection, critical, anchor)]
+pub fn lookup(name: &str) -> usize {
+    name.len()
+}
+
+fn main() {
+    std::process::exit(lookup("alice") as i32);
+}
+"#;
+
+const KEY: &str = "0707070707070707070707070707070707070707070707070707070707070707";
+
+/// Builds the fixture into its own directory and returns its binary.
+fn fixture(name: &str) -> PathBuf {
+    let fixture = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&fixture);
+    fs::create_dir_all(fixture.join("src")).unwrap();
+    let scanner = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    fs::write(
+        fixture.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"verdict-fixture\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+             [dependencies]\nsecurity-scanner = {{ path = {:?} }}\n\n[workspace]\n",
+            scanner.display().to_string()
+        ),
+    )
+    .unwrap();
+    fs::write(fixture.join("src/main.rs"), MAIN).unwrap();
+    let target = fixture.with_file_name("verdict-target");
+    let build = Command::new(env!("CARGO"))
+        .arg("build")
+        .arg("--manifest-path")
+        .arg(fixture.join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", &target)
+        .output()
+        .unwrap();
+    assert!(
+        build.status.success(),
+        "{}",
+        String::from_utf8_lossy(&build.stderr)
+    );
+    let binary = fixture.join("verdict-fixture");
+    fs::copy(target.join("debug/verdict-fixture"), &binary).unwrap();
+    binary
+}
+
+fn scanner(args: &[&str], binary: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-security-scan"))
+        .args(args)
+        .arg(binary)
+        .env("SECURITY_SCANNER_SIGNING_KEY", KEY)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn stamps_the_verdict_into_the_binary() {
+    let binary = fixture("stamp");
+    let output = scanner(&["--json", "stamp", "--binary"], &binary);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let verdict: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(verdict["version"], 1);
+    assert_eq!(verdict["functions"], 1);
+    assert_eq!(verdict["threat_levels"]["critical"], 1);
+    assert_eq!(verdict["signature"].as_str().unwrap().len(), 64);
+
+    // Still runs, and still scans the same
+    let status = Command::new(&binary).status().unwrap();
+    assert_eq!(status.code(), Some(5));
+    let output = scanner(&["--json", "scan", "--binary"], &binary);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report["threat_levels"]["critical"][0]["function"],
+        "verdict_fixture::lookup"
+    );
+
+    let output = scanner(&["stamp", "--binary"], &binary);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already has a verdict"));
+}
+
+#[test]
+fn verifies_the_verdict_before_deploying() {
+    let binary = fixture("verify");
+    let output = scanner(&["stamp", "--binary"], &binary);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = scanner(&["--json", "verify-artifact"], &binary);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["verified"], true);
+
+    let output = scanner(&["verify-artifact", "--max-critical", "0"], &binary);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 critical function(s), more than 0"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-security-scan"))
+        .arg("verify-artifact")
+        .arg(&binary)
+        .env("SECURITY_SCANNER_SIGNING_KEY", KEY.replace('7', "8"))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("signature doesn't verify"));
+
+    // A byte of code changed after stamping
+    let mut data = fs::read(&binary).unwrap();
+    data[0x1000] ^= 0xff;
+    fs::write(&binary, data).unwrap();
+    let output = scanner(&["verify-artifact"], &binary);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("binary changed since it was scanned"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("signature"), "{}", stderr);
+}
@@ -0,0 +1,193 @@
+/*
+ * Reference parser for security-scanner metadata records.
+ *
+ * Reads a raw dump of the records section, for example
+ *
+ *     objcopy -O binary --only-section=.security_tests app records.bin
+ *
+ * and prints one line per record found, of either format version. Records
+ * are matched on their magic values, so padding between them is skipped.
+ * Only the header and the C standard library are used; build with
+ *
+ *     cc -std=c99 -I ../include -o reference_parser reference_parser.c
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "security_scanner_format.h"
+
+static uint64_t read_le(const unsigned char *bytes, size_t size)
+{
+    uint64_t value = 0;
+    size_t i;
+    for (i = 0; i < size; i++) {
+        value |= (uint64_t)bytes[i] << (8 * i);
+    }
+    return value;
+}
+
+static uint32_t crc32_ieee(const unsigned char *bytes, size_t len)
+{
+    uint32_t crc = 0xFFFFFFFFu;
+    size_t i;
+    int bit;
+    for (i = 0; i < len; i++) {
+        crc ^= bytes[i];
+        for (bit = 0; bit < 8; bit++) {
+            crc = (crc & 1) ? (crc >> 1) ^ 0xEDB88320u : crc >> 1;
+        }
+    }
+    return ~crc;
+}
+
+#define FIELD(record, name) \
+    read_le((record) + SECSCAN_OFFSET_##name, SECSCAN_SIZE_##name)
+
+static void print_record(size_t offset, const unsigned char *record)
+{
+    uint32_t stored = (uint32_t)FIELD(record, CHECKSUM);
+    const char *status;
+
+    if (stored == 0) {
+        status = "unchecked";
+    } else if (stored == crc32_ieee(record, SECSCAN_CHECKSUM_OFFSET)) {
+        status = "verified";
+    } else {
+        status = "corrupted";
+    }
+
+    printf("offset=%lu status=%s name_len=%u threat_level=%u"
+           " sql_injection=%u race_condition=%u timing_attack=%u"
+           " buffer_overflow=%u\n",
+           (unsigned long)offset, status,
+           (unsigned)FIELD(record, NAME_LEN),
+           (unsigned)FIELD(record, THREAT_LEVEL),
+           (unsigned)FIELD(record, SQL_INJECTION),
+           (unsigned)FIELD(record, RACE_CONDITION),
+           (unsigned)FIELD(record, TIMING_ATTACK),
+           (unsigned)FIELD(record, BUFFER_OVERFLOW));
+}
+
+#define TLV_HEADER(record, name) \
+    read_le((record) + SECSCAN_TLV_OFFSET_##name, SECSCAN_TLV_SIZE_##name)
+
+/* Prints a version 2 record and returns its length, or 0 if the header is
+ * unusable. */
+static size_t print_tlv_record(size_t offset, const unsigned char *record, size_t available)
+{
+    size_t header_len = (size_t)TLV_HEADER(record, HEADER_LEN);
+    size_t record_len = (size_t)TLV_HEADER(record, RECORD_LEN);
+    const unsigned char *entry, *end;
+    const char *status;
+    const unsigned char *path = NULL;
+    size_t path_len = 0;
+    uint64_t tests = 0, threat_level = 0, line = 0, column = 0;
+    uint32_t stored;
+
+    if (header_len < SECSCAN_TLV_HEADER_LEN
+        || record_len < header_len + SECSCAN_TLV_CHECKSUM_LEN || record_len > available) {
+        return 0;
+    }
+
+    end = record + record_len - SECSCAN_TLV_CHECKSUM_LEN;
+    stored = (uint32_t)read_le(end, SECSCAN_TLV_CHECKSUM_LEN);
+    if (stored != 0 && stored != crc32_ieee(record, (size_t)(end - record))) {
+        status = "corrupted";
+    } else if (TLV_HEADER(record, VERSION) != SECSCAN_TLV_VERSION) {
+        status = "unsupported";
+    } else {
+        /* Records written with security_test.h carry no checksum */
+        status = stored == 0 ? "unchecked" : "verified";
+    }
+
+    for (entry = record + header_len; end - entry >= SECSCAN_TLV_ENTRY_HEADER_LEN;) {
+        unsigned tag = (unsigned)read_le(entry, 2);
+        size_t len = (size_t)read_le(entry + 2, 2);
+        const unsigned char *value = entry + SECSCAN_TLV_ENTRY_HEADER_LEN;
+        if ((size_t)(end - value) < len) {
+            break;
+        }
+        switch (tag) {
+        case SECSCAN_TLV_TAG_PATH:
+            path = value;
+            /* Paths written from C end in a NUL */
+            path_len = len > 0 && value[len - 1] == 0 ? len - 1 : len;
+            break;
+        case SECSCAN_TLV_TAG_TESTS:
+            tests = read_le(value, len);
+            break;
+        case SECSCAN_TLV_TAG_THREAT_LEVEL:
+            threat_level = read_le(value, len);
+            break;
+        case SECSCAN_TLV_TAG_LINE:
+            line = read_le(value, len);
+            break;
+        case SECSCAN_TLV_TAG_COLUMN:
+            column = read_le(value, len);
+            break;
+        default:
+            /* Unknown tags are skipped */
+            break;
+        }
+        entry = value + len;
+    }
+
+    printf("offset=%lu status=%s version=%u path=%.*s tests=0x%02lx threat_level=%lu"
+           " line=%lu column=%lu\n",
+           (unsigned long)offset, status,
+           (unsigned)TLV_HEADER(record, VERSION),
+           (int)path_len, path ? (const char *)path : "",
+           (unsigned long)tests, (unsigned long)threat_level,
+           (unsigned long)line, (unsigned long)column);
+    return record_len;
+}
+
+int main(int argc, char **argv)
+{
+    FILE *file;
+    unsigned char *data = NULL;
+    size_t len = 0, capacity = 0, offset = 0, n;
+
+    if (argc != 2) {
+        fprintf(stderr, "usage: %s <records.bin>\n", argv[0]);
+        return 2;
+    }
+
+    file = fopen(argv[1], "rb");
+    if (file == NULL) {
+        perror(argv[1]);
+        return 1;
+    }
+    do {
+        if (len == capacity) {
+            capacity = capacity ? capacity * 2 : 4096;
+            data = realloc(data, capacity);
+            if (data == NULL) {
+                perror("realloc");
+                return 1;
+            }
+        }
+        n = fread(data + len, 1, capacity - len, file);
+        len += n;
+    } while (n > 0);
+    fclose(file);
+
+    while (offset + SECSCAN_TLV_HEADER_LEN <= len) {
+        size_t record_len;
+        if (TLV_HEADER(data + offset, MAGIC) == SECSCAN_TLV_MAGIC
+            && (record_len = print_tlv_record(offset, data + offset, len - offset)) > 0) {
+            offset += record_len;
+        } else if (offset + SECSCAN_RECORD_SIZE <= len
+                   && FIELD(data + offset, MAGIC) == SECSCAN_RECORD_MAGIC) {
+            print_record(offset, data + offset);
+            offset += SECSCAN_RECORD_SIZE;
+        } else {
+            offset += 1;
+        }
+    }
+
+    free(data);
+    return 0;
+}
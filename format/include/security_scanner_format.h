@@ -0,0 +1,168 @@
+/* Generated by security-scanner-format. Do not edit. */
+#ifndef SECURITY_SCANNER_FORMAT_H
+#define SECURITY_SCANNER_FORMAT_H
+
+#include <stdint.h>
+
+/* All multi-byte fields are little-endian on every target. */
+#define SECSCAN_SCHEMA_VERSION 19
+#define SECSCAN_RECORD_SIZE 64
+#define SECSCAN_RECORD_MAGIC UINT64_C(0xDEADBEEFCAFEBABE)
+#define SECSCAN_CHECKSUM_OFFSET 60
+
+/* Magic value 0xDEADBEEFCAFEBABE */
+#define SECSCAN_OFFSET_MAGIC 0
+#define SECSCAN_SIZE_MAGIC 8
+
+/* Length of the function name in bytes */
+#define SECSCAN_OFFSET_NAME_LEN 8
+#define SECSCAN_SIZE_NAME_LEN 1
+
+/* 1 if SQL injection testing was requested */
+#define SECSCAN_OFFSET_SQL_INJECTION 9
+#define SECSCAN_SIZE_SQL_INJECTION 1
+
+/* 1 if race condition testing was requested */
+#define SECSCAN_OFFSET_RACE_CONDITION 10
+#define SECSCAN_SIZE_RACE_CONDITION 1
+
+/* 1 if timing attack testing was requested */
+#define SECSCAN_OFFSET_TIMING_ATTACK 11
+#define SECSCAN_SIZE_TIMING_ATTACK 1
+
+/* 1 if buffer overflow testing was requested */
+#define SECSCAN_OFFSET_BUFFER_OVERFLOW 12
+#define SECSCAN_SIZE_BUFFER_OVERFLOW 1
+
+/* 0 low, 1 medium, 2 high, 3 critical */
+#define SECSCAN_OFFSET_THREAT_LEVEL 13
+#define SECSCAN_SIZE_THREAT_LEVEL 1
+
+/* IEEE CRC-32 of bytes 0..60, 0 if unchecked */
+#define SECSCAN_OFFSET_CHECKSUM 60
+#define SECSCAN_SIZE_CHECKSUM 4
+
+/* Version 2 records */
+#define SECSCAN_TLV_MAGIC UINT64_C(0xDEADBEEFCAFEF00D)
+#define SECSCAN_TLV_VERSION 2
+#define SECSCAN_TLV_HEADER_LEN 16
+#define SECSCAN_TLV_ENTRY_HEADER_LEN 4
+#define SECSCAN_TLV_CHECKSUM_LEN 4
+
+/* Magic value 0xDEADBEEFCAFEF00D */
+#define SECSCAN_TLV_OFFSET_MAGIC 0
+#define SECSCAN_TLV_SIZE_MAGIC 8
+
+/* Format version */
+#define SECSCAN_TLV_OFFSET_VERSION 8
+#define SECSCAN_TLV_SIZE_VERSION 2
+
+/* Header length; entries start here */
+#define SECSCAN_TLV_OFFSET_HEADER_LEN 10
+#define SECSCAN_TLV_SIZE_HEADER_LEN 2
+
+/* Record length, including header and checksum */
+#define SECSCAN_TLV_OFFSET_RECORD_LEN 12
+#define SECSCAN_TLV_SIZE_RECORD_LEN 4
+
+/* Fully-qualified function path (str) */
+#define SECSCAN_TLV_TAG_PATH 1
+
+/* Requested tests: bit 0 sql_injection, 1 race_condition, 2 timing_attack, 3 buffer_overflow, 4 env_injection, 5 xss, 6 command_injection, 7 path_traversal, 8 ssrf, 9 xxe, 10 deserialization, 11 open_redirect (u32) */
+#define SECSCAN_TLV_TAG_TESTS 2
+
+/* Threat level of the compiled profile: 0 low, 1 medium, 2 high, 3 critical (u8) */
+#define SECSCAN_TLV_TAG_THREAT_LEVEL 3
+
+/* 0x80 declared, 0x01 net, 0x02 fs_read, 0x04 fs_write, 0x08 spawn (u8) */
+#define SECSCAN_TLV_TAG_CAPABILITIES 4
+
+/* STRIDE categories, bit 0 spoofing to bit 5 elevation_of_privilege (u8) */
+#define SECSCAN_TLV_TAG_STRIDE 5
+
+/* Remediation deadline in days, 0 if unset (u16) */
+#define SECSCAN_TLV_TAG_SLA_DAYS 6
+
+/* 1 if compiled for test code (u8) */
+#define SECSCAN_TLV_TAG_TEST_CONTEXT 7
+
+/* 1-based source line of the function name, 0 if unknown (u32) */
+#define SECSCAN_TLV_TAG_LINE 8
+
+/* 1-based source column of the function name, 0 if unknown (u32) */
+#define SECSCAN_TLV_TAG_COLUMN 9
+
+/* Source file of the function, as given by file!() (str) */
+#define SECSCAN_TLV_TAG_FILE 10
+
+/* Environment variable declared with reads_env (str, repeated) */
+#define SECSCAN_TLV_TAG_READS_ENV 11
+
+/* Fixture declared with fixtures (str, repeated) */
+#define SECSCAN_TLV_TAG_FIXTURE 12
+
+/* MITRE ATT&CK technique ID declared with attack (str, repeated) */
+#define SECSCAN_TLV_TAG_ATTACK_TECHNIQUE 13
+
+/* Function parameter as name: Type, without lifetimes (str, repeated) */
+#define SECSCAN_TLV_TAG_INPUT_PARAM 14
+
+/* Compiler settings as key=value pairs separated by semicolons (str) */
+#define SECSCAN_TLV_TAG_BUILD 15
+
+/* Threat level declared for debug builds, when it differs by profile (u8) */
+#define SECSCAN_TLV_TAG_DEBUG_THREAT_LEVEL 16
+
+/* Threat level declared for release builds, when it differs by profile (u8) */
+#define SECSCAN_TLV_TAG_RELEASE_THREAT_LEVEL 17
+
+/* Name of a test type defined by another tool, declared with custom (str, repeated) */
+#define SECSCAN_TLV_TAG_CUSTOM_TEST 18
+
+/* CWE weakness ID declared with cwe (u32, repeated) */
+#define SECSCAN_TLV_TAG_CWE 19
+
+/* OWASP Top 10 category such as A03:2021, declared with owasp (str, repeated) */
+#define SECSCAN_TLV_TAG_OWASP 20
+
+/* CVSS base score in tenths, 0 to 100, declared with cvss (u8) */
+#define SECSCAN_TLV_TAG_CVSS_SCORE 21
+
+/* CVSS v3 base vector with its CVSS:3.x/ prefix, declared with cvss (str) */
+#define SECSCAN_TLV_TAG_CVSS_VECTOR 22
+
+/* Always 1, marks the record of #[security_program] rather than a function (u8) */
+#define SECSCAN_TLV_TAG_PROGRAM 23
+
+/* Exposure of a program: 0 airgapped, 1 internal, 2 partner, 3 internet (u8) */
+#define SECSCAN_TLV_TAG_EXPOSURE 24
+
+/* Data a program handles: 0x01 pii, 0x02 phi, 0x04 pci, 0x08 credentials (u8) */
+#define SECSCAN_TLV_TAG_DATA_CLASSES 25
+
+/* Banned API the function calls, as listed in banned_calls of its manifest (str, repeated) */
+#define SECSCAN_TLV_TAG_BANNED_CALL 26
+
+/* Sole entry of an encrypted record: a 16-byte synthetic IV and a whole record encrypted with HMAC-SHA-256 SIV (bytes) */
+#define SECSCAN_TLV_TAG_ENCRYPTED 27
+
+/* Generic arguments of the instance the record describes, such as T = u32 (str) */
+#define SECSCAN_TLV_TAG_INSTANCE 28
+
+/* Metadata records, fixed-size version 1 or tagged version 2 (records) */
+#define SECSCAN_SECTION_RECORDS_ELF ".security_tests"
+#define SECSCAN_SECTION_RECORDS_MACHO "__DATA,__sectests"
+#define SECSCAN_SECTION_RECORDS_COFF ".sectests"
+#define SECSCAN_SECTION_RECORDS_WASM "security_tests"
+
+/* Fully-qualified function paths, for version 1 records (str) */
+#define SECSCAN_SECTION_NAMES_ELF ".security_names"
+#define SECSCAN_SECTION_NAMES_MACHO "__DATA,__secnames"
+#define SECSCAN_SECTION_NAMES_COFF ".secnames"
+
+/* Address of the record and of the code of each function with anchor (addresses) */
+#define SECSCAN_SECTION_ADDRESSES_ELF ".security_addrs"
+#define SECSCAN_SECTION_ADDRESSES_MACHO "__DATA,__secaddrs"
+#define SECSCAN_SECTION_ADDRESSES_COFF ".secaddr"
+
+#endif /* SECURITY_SCANNER_FORMAT_H */
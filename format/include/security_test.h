@@ -0,0 +1,128 @@
+/*
+ * Annotations for C and C++ functions, the counterpart of #[security_test].
+ *
+ *     #include "security_test.h"
+ *
+ *     int parse_packet(const unsigned char *data, size_t len) { ... }
+ *     SECURITY_TEST(parse_packet, SECSCAN_TEST_BUFFER_OVERFLOW, SECSCAN_CRITICAL);
+ *
+ * Each annotation embeds a version 2 record in the same section as the
+ * records of Rust functions, so the functions of a mixed-language binary
+ * are read back, scanned and reported together. The record holds the
+ * function's name as written (`ns::parse` for a C++ function in a
+ * namespace), the requested tests, the threat level and the source file and
+ * line of the annotation. Its checksum is zero, which readers accept as
+ * unchecked, since C can't compute a CRC in a constant initializer; string
+ * values end in the NUL of their literal, which readers drop.
+ *
+ * `flags` is a bitwise OR of SECSCAN_TEST_* values, or 0 for a general
+ * review, and `level` one of SECSCAN_LOW, SECSCAN_MEDIUM, SECSCAN_HIGH and
+ * SECSCAN_CRITICAL. Annotations go at file scope. GCC and Clang are
+ * supported on ELF and Mach-O targets and with MinGW, and MSVC on Windows,
+ * where `fn` must be a plain identifier.
+ *
+ * Defining SECURITY_SCANNER_STRIP before including the header, for example
+ * with -DSECURITY_SCANNER_STRIP, turns every annotation into nothing, like
+ * the variable of the same name does for Rust crates.
+ */
+#ifndef SECURITY_TEST_H
+#define SECURITY_TEST_H
+
+#include "security_scanner_format.h"
+
+/* Bits of `flags`, in the order of the Rust TestType enum. */
+#define SECSCAN_TEST_SQL_INJECTION (1u << 0)
+#define SECSCAN_TEST_RACE_CONDITION (1u << 1)
+#define SECSCAN_TEST_TIMING_ATTACK (1u << 2)
+#define SECSCAN_TEST_BUFFER_OVERFLOW (1u << 3)
+#define SECSCAN_TEST_ENV_INJECTION (1u << 4)
+#define SECSCAN_TEST_XSS (1u << 5)
+#define SECSCAN_TEST_COMMAND_INJECTION (1u << 6)
+#define SECSCAN_TEST_PATH_TRAVERSAL (1u << 7)
+#define SECSCAN_TEST_SSRF (1u << 8)
+#define SECSCAN_TEST_XXE (1u << 9)
+#define SECSCAN_TEST_DESERIALIZATION (1u << 10)
+#define SECSCAN_TEST_OPEN_REDIRECT (1u << 11)
+
+/* Threat levels, as stored in records. */
+#define SECSCAN_LOW 0
+#define SECSCAN_MEDIUM 1
+#define SECSCAN_HIGH 2
+#define SECSCAN_CRITICAL 3
+
+#if defined(SECURITY_SCANNER_STRIP)
+
+#define SECURITY_TEST(fn, flags, level) extern int secscan_stripped_
+
+#else
+
+/* Places a definition in the records section and keeps it when unused. */
+#if defined(_MSC_VER) && !defined(__clang__)
+#pragma section(".sectests", read)
+#define SECSCAN_PLACE __declspec(allocate(".sectests"))
+#define SECSCAN_LINKAGE extern const
+#elif defined(__APPLE__)
+#define SECSCAN_PLACE __attribute__((section(SECSCAN_SECTION_RECORDS_MACHO), used, aligned(1)))
+#define SECSCAN_LINKAGE static const
+#elif defined(_WIN32)
+#define SECSCAN_PLACE __attribute__((section(SECSCAN_SECTION_RECORDS_COFF), used, aligned(1)))
+#define SECSCAN_LINKAGE static const
+#else
+#define SECSCAN_PLACE __attribute__((section(SECSCAN_SECTION_RECORDS_ELF), used, aligned(1)))
+#define SECSCAN_LINKAGE static const
+#endif
+
+/* Unique names for the records of one translation unit; MSVC records have
+ * external linkage, so their names also carry the function's. */
+#define SECSCAN_CONCAT_(a, b) a##b
+#define SECSCAN_CONCAT(a, b) SECSCAN_CONCAT_(a, b)
+#if defined(_MSC_VER) && !defined(__clang__)
+#define SECSCAN_NAME(fn) SECSCAN_CONCAT(secscan_record_##fn##_, __COUNTER__)
+#elif defined(__COUNTER__)
+#define SECSCAN_NAME(fn) SECSCAN_CONCAT(secscan_record_, __COUNTER__)
+#else
+#define SECSCAN_NAME(fn) SECSCAN_CONCAT(secscan_record_, __LINE__)
+#endif
+
+/* Little-endian bytes of an integer constant. */
+#define SECSCAN_U8(v) (unsigned char)((v) & 0xFFu)
+#define SECSCAN_U16(v) SECSCAN_U8(v), SECSCAN_U8((v) >> 8)
+#define SECSCAN_U32(v) SECSCAN_U16(v), SECSCAN_U16((v) >> 16)
+#define SECSCAN_U64(v) SECSCAN_U32(v), SECSCAN_U32((v) >> 32)
+
+/* Tag and value length of an entry. */
+#define SECSCAN_ENTRY(tag, len) SECSCAN_U16(tag), SECSCAN_U16(len)
+
+#define SECSCAN_RECORD_LEN(path, file)                                                   \
+    (SECSCAN_TLV_HEADER_LEN + SECSCAN_TLV_ENTRY_HEADER_LEN + sizeof(path)                \
+     + SECSCAN_TLV_ENTRY_HEADER_LEN + 4 + SECSCAN_TLV_ENTRY_HEADER_LEN + 1               \
+     + SECSCAN_TLV_ENTRY_HEADER_LEN + 4 + SECSCAN_TLV_ENTRY_HEADER_LEN + sizeof(file)    \
+     + SECSCAN_TLV_CHECKSUM_LEN)
+
+#define SECURITY_TEST(fn, flags, level)                                                  \
+    SECSCAN_PLACE SECSCAN_LINKAGE struct {                                               \
+        unsigned char header[SECSCAN_TLV_HEADER_LEN];                                    \
+        unsigned char path_entry[SECSCAN_TLV_ENTRY_HEADER_LEN];                          \
+        char path[sizeof(#fn)];                                                          \
+        unsigned char tests[SECSCAN_TLV_ENTRY_HEADER_LEN + 4];                           \
+        unsigned char threat_level[SECSCAN_TLV_ENTRY_HEADER_LEN + 1];                    \
+        unsigned char line[SECSCAN_TLV_ENTRY_HEADER_LEN + 4];                            \
+        unsigned char file_entry[SECSCAN_TLV_ENTRY_HEADER_LEN];                          \
+        char file[sizeof(__FILE__)];                                                     \
+        unsigned char checksum[SECSCAN_TLV_CHECKSUM_LEN];                                \
+    } SECSCAN_NAME(fn) = {                                                               \
+        {SECSCAN_U64(SECSCAN_TLV_MAGIC), SECSCAN_U16(SECSCAN_TLV_VERSION),               \
+         SECSCAN_U16(SECSCAN_TLV_HEADER_LEN),                                            \
+         SECSCAN_U32(SECSCAN_RECORD_LEN(#fn, __FILE__))},                                \
+        {SECSCAN_ENTRY(SECSCAN_TLV_TAG_PATH, sizeof(#fn))},                              \
+        #fn,                                                                             \
+        {SECSCAN_ENTRY(SECSCAN_TLV_TAG_TESTS, 4), SECSCAN_U32(flags)},                   \
+        {SECSCAN_ENTRY(SECSCAN_TLV_TAG_THREAT_LEVEL, 1), SECSCAN_U8(level)},             \
+        {SECSCAN_ENTRY(SECSCAN_TLV_TAG_LINE, 4), SECSCAN_U32(__LINE__)},                 \
+        {SECSCAN_ENTRY(SECSCAN_TLV_TAG_FILE, sizeof(__FILE__))},                         \
+        __FILE__,                                                                        \
+        {0, 0, 0, 0}}
+
+#endif
+
+#endif
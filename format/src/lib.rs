@@ -0,0 +1,405 @@
+//! # Security Scanner Format
+//!
+//! The binary record format shared by the `#[security_test]` macro, which
+//! writes records, and the readers that decode them.
+//!
+//! The macro writes one record per annotated function into the
+//! `.security_tests` section (`__DATA,__sectests` on macOS, `.sectests` on
+//! Windows). Current releases write variable-length version 2 records,
+//! described in [`tlv`]. Earlier releases wrote fixed 64-byte version 1
+//! records, which readers still accept, with the layout:
+//!
+//! | Offset | Size | Field                                   |
+//! |--------|------|-----------------------------------------|
+//! | 0      | 8    | Magic bytes, `0xDEADBEEFCAFEBABE` (LE)  |
+//! | 8      | 1    | Function name length                    |
+//! | 9      | 1    | `sql_injection` flag                    |
+//! | 10     | 1    | `race_condition` flag                   |
+//! | 11     | 1    | `timing_attack` flag                    |
+//! | 12     | 1    | `buffer_overflow` flag                  |
+//! | 13     | 1    | Threat level                            |
+//! | 14     | 46   | Reserved, zero                          |
+//! | 60     | 4    | CRC-32 of bytes 0..60 (LE)              |
+//!
+//! Every other attribute was added after version 2 records replaced version
+//! 1, so version 1 records never carry it. Their function names are
+//! stored in the names section, one entry per record in the same order;
+//! see [`schema::SECTIONS`].
+//!
+//! ## Byte Order
+//!
+//! Every multi-byte field is little-endian on every target. Records are built
+//! byte by byte, so a binary cross-compiled for a
+//! big-endian target such as s390x carries exactly the same bytes as one built
+//! for x86_64. Use the `read_*` and `write_*` helpers, such as [`read_u32`]
+//! and [`write_u32`], rather than native-endian conversions when adding
+//! fields. A record whose magic appears byte-swapped was written by a tool
+//! that used the target's native order and is rejected with
+//! [`RecordError::ForeignEndian`].
+//!
+//! ## Integrity
+//!
+//! The checksum is the IEEE CRC-32 (as used by zlib and PNG) over the rest
+//! of the record. Post-link tools such as strippers, packers and signers can
+//! truncate or rewrite sections; [`check_record`] and [`scan_section`] tell
+//! those records apart from valid ones. Records written before checksums were
+//! introduced store zero and are reported as [`RecordStatus::Unchecked`], as
+//! are version 2 records written from C.
+//!
+//! ## Other Languages
+//!
+//! [`schema`] describes the layout as a C header and a JSON descriptor for
+//! tooling written in other languages. `include/security_test.h` builds on
+//! the C header to annotate C and C++ functions with `SECURITY_TEST(...)`,
+//! embedding version 2 records next to those of Rust functions.
+//!
+//! ## `no_std`
+//!
+//! Without the default `std` feature the crate is `#![no_std]`: records can
+//! still be encoded in const context and checked one at a time, which is all
+//! the code generated by `#[security_test]` needs on bare-metal targets.
+//! [`scan_section`] and the [`schema`] generators need `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::fmt;
+
+mod cipher;
+pub mod schema;
+pub mod tlv;
+
+/// Magic value at the start of every metadata record.
+pub const RECORD_MAGIC: u64 = 0xDEAD_BEEF_CAFE_BABE;
+
+/// Size in bytes of one version 1 metadata record.
+pub const RECORD_SIZE: usize = 64;
+
+/// Offset of the CRC-32 at the end of a record.
+pub const CHECKSUM_OFFSET: usize = 60;
+
+/// Names of the test types, as written in `#[security_test(...)]`, indexed
+/// by their bit in the tests entry of version 2 records ([`tlv::tags::TESTS`]).
+/// New types are appended, so existing bits keep their meaning.
+pub const TEST_TYPES: [&str; 12] = [
+    "sql_injection",
+    "race_condition",
+    "timing_attack",
+    "buffer_overflow",
+    "env_injection",
+    "xss",
+    "command_injection",
+    "path_traversal",
+    "ssrf",
+    "xxe",
+    "deserialization",
+    "open_redirect",
+];
+
+/// Byte offsets of the version 1 record fields.
+pub mod offsets {
+    pub const MAGIC: usize = 0;
+    pub const NAME_LEN: usize = 8;
+    pub const SQL_INJECTION: usize = 9;
+    pub const RACE_CONDITION: usize = 10;
+    pub const TIMING_ATTACK: usize = 11;
+    pub const BUFFER_OVERFLOW: usize = 12;
+    pub const THREAT_LEVEL: usize = 13;
+}
+
+/// Computes the IEEE CRC-32 of `bytes`.
+pub const fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    let mut i = 0;
+    while i < bytes.len() {
+        crc ^= bytes[i] as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        i += 1;
+    }
+    !crc
+}
+
+/// Reads a little-endian `u16` at `offset`.
+///
+/// Panics if fewer than two bytes are available at `offset`.
+pub fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+/// Reads a little-endian `u32` at `offset`.
+///
+/// Panics if fewer than four bytes are available at `offset`.
+pub fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(buf)
+}
+
+/// Reads a little-endian `u64` at `offset`.
+///
+/// Panics if fewer than eight bytes are available at `offset`.
+pub fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(buf)
+}
+
+/// Writes `value` little-endian at `offset`.
+pub fn write_u16(bytes: &mut [u8], offset: usize, value: u16) {
+    bytes[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Writes `value` little-endian at `offset`.
+pub fn write_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Writes `value` little-endian at `offset`.
+pub fn write_u64(bytes: &mut [u8], offset: usize, value: u64) {
+    bytes[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Returns a new record with the magic bytes in place and everything else zero.
+pub fn empty_record() -> [u8; RECORD_SIZE] {
+    let mut record = [0u8; RECORD_SIZE];
+    write_u64(&mut record, offsets::MAGIC, RECORD_MAGIC);
+    record
+}
+
+/// Writes the checksum of a fully populated record into its last four bytes.
+pub fn seal(record: &mut [u8; RECORD_SIZE]) {
+    let crc = crc32(&record[..CHECKSUM_OFFSET]);
+    write_u32(record, CHECKSUM_OFFSET, crc);
+}
+
+/// How a record passed integrity checking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordStatus {
+    /// The stored checksum matches the record contents
+    Verified,
+    /// The record predates checksums or was written from C (stored
+    /// checksum is zero)
+    Unchecked,
+}
+
+/// Why a record failed integrity checking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordError {
+    /// Fewer bytes were available than the record needs
+    Truncated { len: usize, expected: usize },
+    /// The record doesn't start with [`RECORD_MAGIC`] (or [`tlv::MAGIC`])
+    BadMagic,
+    /// The magic bytes are byte-swapped: the record was written big-endian
+    ForeignEndian,
+    /// The stored checksum doesn't match the record contents
+    ChecksumMismatch { stored: u32, computed: u32 },
+    /// The header's lengths can't hold a header and checksum
+    BadLength { len: u32 },
+    /// The record is intact but of a format version this reader doesn't know
+    UnsupportedVersion { version: u16 },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::Truncated { len, expected } => {
+                write!(f, "record truncated to {} of {} bytes", len, expected)
+            }
+            RecordError::BadMagic => f.write_str("record does not start with the magic bytes"),
+            RecordError::ForeignEndian => {
+                f.write_str("record was written big-endian; records are always little-endian")
+            }
+            RecordError::ChecksumMismatch { stored, computed } => write!(
+                f,
+                "record checksum mismatch (stored {:08x}, computed {:08x})",
+                stored, computed
+            ),
+            RecordError::BadLength { len } => write!(f, "record length {} is too short", len),
+            RecordError::UnsupportedVersion { version } => {
+                write!(f, "record format version {} is not supported", version)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RecordError {}
+
+/// Checks the magic bytes and checksum of the version 1 record at the start
+/// of `bytes`. Version 2 records are checked by [`tlv::check_record`].
+pub fn check_record(bytes: &[u8]) -> Result<RecordStatus, RecordError> {
+    if bytes.len() < RECORD_SIZE {
+        return Err(RecordError::Truncated {
+            len: bytes.len(),
+            expected: RECORD_SIZE,
+        });
+    }
+
+    let magic = read_u64(bytes, offsets::MAGIC);
+    if magic == RECORD_MAGIC.swap_bytes() {
+        return Err(RecordError::ForeignEndian);
+    }
+    if magic != RECORD_MAGIC {
+        return Err(RecordError::BadMagic);
+    }
+
+    let stored = read_u32(bytes, CHECKSUM_OFFSET);
+    if stored == 0 {
+        return Ok(RecordStatus::Unchecked);
+    }
+
+    let computed = crc32(&bytes[..CHECKSUM_OFFSET]);
+    if stored != computed {
+        return Err(RecordError::ChecksumMismatch { stored, computed });
+    }
+
+    Ok(RecordStatus::Verified)
+}
+
+/// Integrity counts for one scanned section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IntegrityStats {
+    /// Records whose checksum matched
+    pub verified: usize,
+    /// Records without a checksum
+    pub unchecked: usize,
+    /// Records whose checksum didn't match
+    pub corrupted: usize,
+    /// Records written big-endian
+    pub foreign_endian: usize,
+    /// Intact records of a format version this reader doesn't know
+    pub unsupported: usize,
+    /// A trailing partial record
+    pub truncated: usize,
+    /// Runs of bytes between records that didn't start with the magic bytes
+    pub unrecognized: usize,
+}
+
+impl IntegrityStats {
+    /// Number of records that can be trusted.
+    pub fn valid(&self) -> usize {
+        self.verified + self.unchecked
+    }
+}
+
+/// The result of scanning a whole section.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct SectionScan<'a> {
+    /// Records that passed integrity checking, of either version, in
+    /// section order
+    pub records: Vec<&'a [u8]>,
+    pub stats: IntegrityStats,
+}
+
+/// Walks a section of back-to-back records, keeping the valid ones.
+///
+/// Version 1 and version 2 records can be mixed. When bytes at a record
+/// boundary don't start with either magic value (padding added by a linker
+/// or a post-link tool, or a partially overwritten record), the scanner
+/// skips ahead to the next occurrence of the magic bytes. So does a record
+/// running past the end of the section, which is counted as truncated.
+#[cfg(feature = "std")]
+pub fn scan_section(section: &[u8]) -> SectionScan<'_> {
+    let magics = [RECORD_MAGIC.to_le_bytes(), tlv::MAGIC.to_le_bytes()];
+    let mut scan = SectionScan::default();
+    let mut offset = 0;
+
+    while offset < section.len() {
+        let rest = &section[offset..];
+        let (result, len) = check_next(rest);
+        match result {
+            Ok(status) => {
+                match status {
+                    RecordStatus::Verified => scan.stats.verified += 1,
+                    RecordStatus::Unchecked => scan.stats.unchecked += 1,
+                }
+                scan.records.push(&rest[..len]);
+                offset += len;
+            }
+            Err(RecordError::ChecksumMismatch { .. }) => {
+                scan.stats.corrupted += 1;
+                offset += len;
+            }
+            Err(RecordError::ForeignEndian) if len > 0 => {
+                scan.stats.foreign_endian += 1;
+                offset += len;
+            }
+            Err(RecordError::UnsupportedVersion { .. }) => {
+                scan.stats.unsupported += 1;
+                offset += len;
+            }
+            Err(RecordError::Truncated { .. })
+                if magics.iter().any(|magic| rest.starts_with(magic)) =>
+            {
+                // The length may be what is damaged, with records after it
+                scan.stats.truncated += 1;
+                match next_magic(rest, &magics) {
+                    Some(next) => offset += next + 1,
+                    None => break,
+                }
+            }
+            Err(_) => {
+                // Zero padding between records is expected and not reported
+                if rest.iter().all(|byte| *byte == 0) {
+                    break;
+                }
+                match next_magic(rest, &magics) {
+                    Some(next) => {
+                        if rest[..next + 1].iter().any(|byte| *byte != 0) {
+                            scan.stats.unrecognized += 1;
+                        }
+                        offset += next + 1;
+                    }
+                    None => {
+                        scan.stats.unrecognized += 1;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    scan
+}
+
+/// Returns where the first of `magics` after the start of `bytes` begins,
+/// counted from `bytes[1]`.
+#[cfg(feature = "std")]
+fn next_magic(bytes: &[u8], magics: &[[u8; 8]]) -> Option<usize> {
+    magics
+        .iter()
+        .filter_map(|magic| {
+            bytes[1..]
+                .windows(magic.len())
+                .position(|window| window == magic)
+        })
+        .min()
+}
+
+/// Checks the record at the start of `bytes`, whatever its version, and
+/// returns how many bytes it spans: zero when a damaged header leaves the
+/// length unknown.
+#[cfg(feature = "std")]
+fn check_next(bytes: &[u8]) -> (Result<RecordStatus, RecordError>, usize) {
+    if tlv::has_magic(bytes) {
+        let len = tlv::record_len(bytes)
+            .filter(|len| *len <= bytes.len())
+            .unwrap_or(0);
+        let result = tlv::check_record(bytes).map(|record| {
+            match read_u32(record, record.len() - tlv::CHECKSUM_LEN) {
+                0 => RecordStatus::Unchecked,
+                _ => RecordStatus::Verified,
+            }
+        });
+        return (result, len);
+    }
+    (check_record(bytes), RECORD_SIZE)
+}
@@ -0,0 +1,627 @@
+//! Machine-readable descriptions of the format for non-Rust tooling.
+//!
+//! [`c_header`] renders the record layouts as C preprocessor constants and
+//! [`json`] as a language-neutral JSON descriptor. Both are generated from
+//! [`FIELDS`], [`TLV_HEADER`], [`TAGS`] and [`SECTIONS`]; the checked-in
+//! copies under `include/` and `schema/` are kept in sync by this crate's
+//! tests, and `c/reference_parser.c` is a small conformance parser built on
+//! the header.
+
+#[cfg(feature = "std")]
+use std::fmt::Write as _;
+
+use crate::{offsets, tlv, CHECKSUM_OFFSET};
+#[cfg(feature = "std")]
+use crate::{RECORD_MAGIC, RECORD_SIZE};
+
+/// Version of the descriptors, bumped whenever a field, tag or section
+/// changes.
+pub const SCHEMA_VERSION: u32 = 19;
+
+/// One fixed field of a version 1 record or of a version 2 header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    /// snake_case name, upper-cased for C
+    pub name: &'static str,
+    pub offset: usize,
+    /// Size in bytes; multi-byte fields are little-endian
+    pub size: usize,
+    pub description: &'static str,
+}
+
+/// Every version 1 record field, in offset order.
+pub const FIELDS: &[Field] = &[
+    Field {
+        name: "magic",
+        offset: offsets::MAGIC,
+        size: 8,
+        description: "Magic value 0xDEADBEEFCAFEBABE",
+    },
+    Field {
+        name: "name_len",
+        offset: offsets::NAME_LEN,
+        size: 1,
+        description: "Length of the function name in bytes",
+    },
+    Field {
+        name: "sql_injection",
+        offset: offsets::SQL_INJECTION,
+        size: 1,
+        description: "1 if SQL injection testing was requested",
+    },
+    Field {
+        name: "race_condition",
+        offset: offsets::RACE_CONDITION,
+        size: 1,
+        description: "1 if race condition testing was requested",
+    },
+    Field {
+        name: "timing_attack",
+        offset: offsets::TIMING_ATTACK,
+        size: 1,
+        description: "1 if timing attack testing was requested",
+    },
+    Field {
+        name: "buffer_overflow",
+        offset: offsets::BUFFER_OVERFLOW,
+        size: 1,
+        description: "1 if buffer overflow testing was requested",
+    },
+    Field {
+        name: "threat_level",
+        offset: offsets::THREAT_LEVEL,
+        size: 1,
+        description: "0 low, 1 medium, 2 high, 3 critical",
+    },
+    Field {
+        name: "checksum",
+        offset: CHECKSUM_OFFSET,
+        size: 4,
+        description: "IEEE CRC-32 of bytes 0..60, 0 if unchecked",
+    },
+];
+
+/// Every version 2 header field, in offset order.
+pub const TLV_HEADER: &[Field] = &[
+    Field {
+        name: "magic",
+        offset: tlv::header::MAGIC,
+        size: 8,
+        description: "Magic value 0xDEADBEEFCAFEF00D",
+    },
+    Field {
+        name: "version",
+        offset: tlv::header::VERSION,
+        size: 2,
+        description: "Format version",
+    },
+    Field {
+        name: "header_len",
+        offset: tlv::header::HEADER_LEN,
+        size: 2,
+        description: "Header length; entries start here",
+    },
+    Field {
+        name: "record_len",
+        offset: tlv::header::RECORD_LEN,
+        size: 4,
+        description: "Record length, including header and checksum",
+    },
+];
+
+/// How the value of a version 2 entry is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagKind {
+    /// UTF-8 without a terminator
+    Str,
+    U8,
+    U16,
+    U32,
+    /// Raw bytes
+    Bytes,
+}
+
+impl TagKind {
+    /// Returns the name used in the JSON descriptor.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TagKind::Str => "str",
+            TagKind::U8 => "u8",
+            TagKind::U16 => "u16",
+            TagKind::U32 => "u32",
+            TagKind::Bytes => "bytes",
+        }
+    }
+}
+
+/// One entry tag of version 2 records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tag {
+    /// snake_case name, upper-cased for C
+    pub name: &'static str,
+    pub tag: u16,
+    pub kind: TagKind,
+    /// Whether the entry appears once per list element
+    pub repeated: bool,
+    pub description: &'static str,
+}
+
+/// Every version 2 entry tag, in tag order.
+pub const TAGS: &[Tag] = &[
+    Tag {
+        name: "path",
+        tag: tlv::tags::PATH,
+        kind: TagKind::Str,
+        repeated: false,
+        description: "Fully-qualified function path",
+    },
+    Tag {
+        name: "tests",
+        tag: tlv::tags::TESTS,
+        kind: TagKind::U32,
+        repeated: false,
+        description: "Requested tests: bit 0 sql_injection, 1 race_condition, 2 timing_attack, \
+                      3 buffer_overflow, 4 env_injection, 5 xss, 6 command_injection, \
+                      7 path_traversal, 8 ssrf, 9 xxe, 10 deserialization, 11 open_redirect",
+    },
+    Tag {
+        name: "threat_level",
+        tag: tlv::tags::THREAT_LEVEL,
+        kind: TagKind::U8,
+        repeated: false,
+        description: "Threat level of the compiled profile: 0 low, 1 medium, 2 high, 3 critical",
+    },
+    Tag {
+        name: "capabilities",
+        tag: tlv::tags::CAPABILITIES,
+        kind: TagKind::U8,
+        repeated: false,
+        description: "0x80 declared, 0x01 net, 0x02 fs_read, 0x04 fs_write, 0x08 spawn",
+    },
+    Tag {
+        name: "stride",
+        tag: tlv::tags::STRIDE,
+        kind: TagKind::U8,
+        repeated: false,
+        description: "STRIDE categories, bit 0 spoofing to bit 5 elevation_of_privilege",
+    },
+    Tag {
+        name: "sla_days",
+        tag: tlv::tags::SLA_DAYS,
+        kind: TagKind::U16,
+        repeated: false,
+        description: "Remediation deadline in days, 0 if unset",
+    },
+    Tag {
+        name: "test_context",
+        tag: tlv::tags::TEST_CONTEXT,
+        kind: TagKind::U8,
+        repeated: false,
+        description: "1 if compiled for test code",
+    },
+    Tag {
+        name: "line",
+        tag: tlv::tags::LINE,
+        kind: TagKind::U32,
+        repeated: false,
+        description: "1-based source line of the function name, 0 if unknown",
+    },
+    Tag {
+        name: "column",
+        tag: tlv::tags::COLUMN,
+        kind: TagKind::U32,
+        repeated: false,
+        description: "1-based source column of the function name, 0 if unknown",
+    },
+    Tag {
+        name: "file",
+        tag: tlv::tags::FILE,
+        kind: TagKind::Str,
+        repeated: false,
+        description: "Source file of the function, as given by file!()",
+    },
+    Tag {
+        name: "reads_env",
+        tag: tlv::tags::READS_ENV,
+        kind: TagKind::Str,
+        repeated: true,
+        description: "Environment variable declared with reads_env",
+    },
+    Tag {
+        name: "fixture",
+        tag: tlv::tags::FIXTURE,
+        kind: TagKind::Str,
+        repeated: true,
+        description: "Fixture declared with fixtures",
+    },
+    Tag {
+        name: "attack_technique",
+        tag: tlv::tags::ATTACK_TECHNIQUE,
+        kind: TagKind::Str,
+        repeated: true,
+        description: "MITRE ATT&CK technique ID declared with attack",
+    },
+    Tag {
+        name: "input_param",
+        tag: tlv::tags::INPUT_PARAM,
+        kind: TagKind::Str,
+        repeated: true,
+        description: "Function parameter as name: Type, without lifetimes",
+    },
+    Tag {
+        name: "build",
+        tag: tlv::tags::BUILD,
+        kind: TagKind::Str,
+        repeated: false,
+        description: "Compiler settings as key=value pairs separated by semicolons",
+    },
+    Tag {
+        name: "debug_threat_level",
+        tag: tlv::tags::DEBUG_THREAT_LEVEL,
+        kind: TagKind::U8,
+        repeated: false,
+        description: "Threat level declared for debug builds, when it differs by profile",
+    },
+    Tag {
+        name: "release_threat_level",
+        tag: tlv::tags::RELEASE_THREAT_LEVEL,
+        kind: TagKind::U8,
+        repeated: false,
+        description: "Threat level declared for release builds, when it differs by profile",
+    },
+    Tag {
+        name: "custom_test",
+        tag: tlv::tags::CUSTOM_TEST,
+        kind: TagKind::Str,
+        repeated: true,
+        description: "Name of a test type defined by another tool, declared with custom",
+    },
+    Tag {
+        name: "cwe",
+        tag: tlv::tags::CWE,
+        kind: TagKind::U32,
+        repeated: true,
+        description: "CWE weakness ID declared with cwe",
+    },
+    Tag {
+        name: "owasp",
+        tag: tlv::tags::OWASP,
+        kind: TagKind::Str,
+        repeated: true,
+        description: "OWASP Top 10 category such as A03:2021, declared with owasp",
+    },
+    Tag {
+        name: "cvss_score",
+        tag: tlv::tags::CVSS_SCORE,
+        kind: TagKind::U8,
+        repeated: false,
+        description: "CVSS base score in tenths, 0 to 100, declared with cvss",
+    },
+    Tag {
+        name: "cvss_vector",
+        tag: tlv::tags::CVSS_VECTOR,
+        kind: TagKind::Str,
+        repeated: false,
+        description: "CVSS v3 base vector with its CVSS:3.x/ prefix, declared with cvss",
+    },
+    Tag {
+        name: "program",
+        tag: tlv::tags::PROGRAM,
+        kind: TagKind::U8,
+        repeated: false,
+        description: "Always 1, marks the record of #[security_program] rather than a function",
+    },
+    Tag {
+        name: "exposure",
+        tag: tlv::tags::EXPOSURE,
+        kind: TagKind::U8,
+        repeated: false,
+        description: "Exposure of a program: 0 airgapped, 1 internal, 2 partner, 3 internet",
+    },
+    Tag {
+        name: "data_classes",
+        tag: tlv::tags::DATA_CLASSES,
+        kind: TagKind::U8,
+        repeated: false,
+        description: "Data a program handles: 0x01 pii, 0x02 phi, 0x04 pci, 0x08 credentials",
+    },
+    Tag {
+        name: "banned_call",
+        tag: tlv::tags::BANNED_CALL,
+        kind: TagKind::Str,
+        repeated: true,
+        description: "Banned API the function calls, as listed in banned_calls of its manifest",
+    },
+    Tag {
+        name: "encrypted",
+        tag: tlv::tags::ENCRYPTED,
+        kind: TagKind::Bytes,
+        repeated: false,
+        description: "Sole entry of an encrypted record: a 16-byte synthetic IV and a whole \
+                      record encrypted with HMAC-SHA-256 SIV",
+    },
+    Tag {
+        name: "instance",
+        tag: tlv::tags::INSTANCE,
+        kind: TagKind::Str,
+        repeated: false,
+        description: "Generic arguments of the instance the record describes, such as T = u32",
+    },
+];
+
+/// How the entries of a section are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionLayout {
+    /// Back-to-back records of either version
+    Records,
+    /// One `&'static str` per function: a pointer and a length, each of the
+    /// target's pointer width, resolved through relocations
+    Str,
+    /// One [`FunctionAddress`](tlv::FunctionAddress) per function: pointers
+    /// to its record and to its code, each of the target's pointer width
+    Addresses,
+}
+
+impl SectionLayout {
+    /// Returns the name used in the JSON descriptor.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SectionLayout::Records => "records",
+            SectionLayout::Str => "str",
+            SectionLayout::Addresses => "addresses",
+        }
+    }
+}
+
+/// One metadata section and its name per object format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section {
+    /// snake_case name, upper-cased for C
+    pub name: &'static str,
+    pub layout: SectionLayout,
+    /// ELF section name
+    pub elf: &'static str,
+    /// Mach-O `segment,section` name
+    pub macho: &'static str,
+    /// COFF section name
+    pub coff: &'static str,
+    /// WebAssembly custom section name, `None` for sections never written
+    /// there
+    pub wasm: Option<&'static str>,
+    pub description: &'static str,
+}
+
+/// Every section written by the macro. Current releases write the records
+/// section, which is the only one on WebAssembly, and the addresses of
+/// functions with `anchor`; the names section holds the function names of
+/// version 1 records.
+pub const SECTIONS: &[Section] = &[
+    Section {
+        name: "records",
+        layout: SectionLayout::Records,
+        elf: ".security_tests",
+        macho: "__DATA,__sectests",
+        coff: ".sectests",
+        wasm: Some("security_tests"),
+        description: "Metadata records, fixed-size version 1 or tagged version 2",
+    },
+    Section {
+        name: "names",
+        layout: SectionLayout::Str,
+        elf: ".security_names",
+        macho: "__DATA,__secnames",
+        coff: ".secnames",
+        wasm: None,
+        description: "Fully-qualified function paths, for version 1 records",
+    },
+    Section {
+        name: "addresses",
+        layout: SectionLayout::Addresses,
+        elf: ".security_addrs",
+        macho: "__DATA,__secaddrs",
+        coff: ".secaddr",
+        wasm: None,
+        description: "Address of the record and of the code of each function with anchor",
+    },
+];
+
+/// Renders the format as a C header of preprocessor constants.
+#[cfg(feature = "std")]
+pub fn c_header() -> String {
+    let mut out = String::new();
+    out.push_str("/* Generated by security-scanner-format. Do not edit. */\n");
+    out.push_str("#ifndef SECURITY_SCANNER_FORMAT_H\n#define SECURITY_SCANNER_FORMAT_H\n\n");
+    out.push_str("#include <stdint.h>\n\n");
+    out.push_str("/* All multi-byte fields are little-endian on every target. */\n");
+    writeln!(out, "#define SECSCAN_SCHEMA_VERSION {}", SCHEMA_VERSION).unwrap();
+    writeln!(out, "#define SECSCAN_RECORD_SIZE {}", RECORD_SIZE).unwrap();
+    writeln!(
+        out,
+        "#define SECSCAN_RECORD_MAGIC UINT64_C(0x{:016X})",
+        RECORD_MAGIC
+    )
+    .unwrap();
+    writeln!(out, "#define SECSCAN_CHECKSUM_OFFSET {}", CHECKSUM_OFFSET).unwrap();
+
+    for field in FIELDS {
+        let name = field.name.to_uppercase();
+        writeln!(out, "\n/* {} */", field.description).unwrap();
+        writeln!(out, "#define SECSCAN_OFFSET_{} {}", name, field.offset).unwrap();
+        writeln!(out, "#define SECSCAN_SIZE_{} {}", name, field.size).unwrap();
+    }
+
+    writeln!(out, "\n/* Version 2 records */").unwrap();
+    writeln!(
+        out,
+        "#define SECSCAN_TLV_MAGIC UINT64_C(0x{:016X})",
+        tlv::MAGIC
+    )
+    .unwrap();
+    writeln!(out, "#define SECSCAN_TLV_VERSION {}", tlv::VERSION).unwrap();
+    writeln!(out, "#define SECSCAN_TLV_HEADER_LEN {}", tlv::HEADER_LEN).unwrap();
+    writeln!(
+        out,
+        "#define SECSCAN_TLV_ENTRY_HEADER_LEN {}",
+        tlv::ENTRY_HEADER_LEN
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "#define SECSCAN_TLV_CHECKSUM_LEN {}",
+        tlv::CHECKSUM_LEN
+    )
+    .unwrap();
+    for field in TLV_HEADER {
+        let name = field.name.to_uppercase();
+        writeln!(out, "\n/* {} */", field.description).unwrap();
+        writeln!(out, "#define SECSCAN_TLV_OFFSET_{} {}", name, field.offset).unwrap();
+        writeln!(out, "#define SECSCAN_TLV_SIZE_{} {}", name, field.size).unwrap();
+    }
+    for tag in TAGS {
+        writeln!(
+            out,
+            "\n/* {} ({}{}) */",
+            tag.description,
+            tag.kind.as_str(),
+            if tag.repeated { ", repeated" } else { "" }
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "#define SECSCAN_TLV_TAG_{} {}",
+            tag.name.to_uppercase(),
+            tag.tag
+        )
+        .unwrap();
+    }
+
+    for section in SECTIONS {
+        let name = section.name.to_uppercase();
+        writeln!(
+            out,
+            "\n/* {} ({}) */",
+            section.description,
+            section.layout.as_str()
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "#define SECSCAN_SECTION_{}_ELF \"{}\"",
+            name, section.elf
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "#define SECSCAN_SECTION_{}_MACHO \"{}\"",
+            name, section.macho
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "#define SECSCAN_SECTION_{}_COFF \"{}\"",
+            name, section.coff
+        )
+        .unwrap();
+        if let Some(wasm) = section.wasm {
+            writeln!(out, "#define SECSCAN_SECTION_{}_WASM \"{}\"", name, wasm).unwrap();
+        }
+    }
+
+    out.push_str("\n#endif /* SECURITY_SCANNER_FORMAT_H */\n");
+    out
+}
+
+/// Renders the format as a JSON descriptor.
+#[cfg(feature = "std")]
+pub fn json() -> String {
+    let mut out = String::from("{\n");
+    writeln!(out, "  \"schema_version\": {},", SCHEMA_VERSION).unwrap();
+    writeln!(out, "  \"byte_order\": \"little\",").unwrap();
+    writeln!(out, "  \"record_size\": {},", RECORD_SIZE).unwrap();
+    writeln!(out, "  \"magic\": \"0x{:016X}\",", RECORD_MAGIC).unwrap();
+    writeln!(
+        out,
+        "  \"checksum\": {{ \"algorithm\": \"crc32-ieee\", \"offset\": {}, \"covers\": [0, {}], \"zero_means_unchecked\": true }},",
+        CHECKSUM_OFFSET, CHECKSUM_OFFSET
+    )
+    .unwrap();
+
+    out.push_str("  \"fields\": [\n");
+    for (index, field) in FIELDS.iter().enumerate() {
+        writeln!(
+            out,
+            "    {{ \"name\": \"{}\", \"offset\": {}, \"size\": {}, \"description\": \"{}\" }}{}",
+            field.name,
+            field.offset,
+            field.size,
+            field.description,
+            if index + 1 < FIELDS.len() { "," } else { "" }
+        )
+        .unwrap();
+    }
+    out.push_str("  ],\n");
+    out.push_str("  \"tlv\": {\n");
+    writeln!(out, "    \"magic\": \"0x{:016X}\",", tlv::MAGIC).unwrap();
+    writeln!(out, "    \"version\": {},", tlv::VERSION).unwrap();
+    writeln!(out, "    \"entry_header_len\": {},", tlv::ENTRY_HEADER_LEN).unwrap();
+    writeln!(
+        out,
+        "    \"checksum\": {{ \"algorithm\": \"crc32-ieee\", \"size\": {}, \"position\": \"end\" }},",
+        tlv::CHECKSUM_LEN
+    )
+    .unwrap();
+    out.push_str("    \"header\": [\n");
+    for (index, field) in TLV_HEADER.iter().enumerate() {
+        writeln!(
+            out,
+            "      {{ \"name\": \"{}\", \"offset\": {}, \"size\": {}, \"description\": \"{}\" }}{}",
+            field.name,
+            field.offset,
+            field.size,
+            field.description,
+            if index + 1 < TLV_HEADER.len() {
+                ","
+            } else {
+                ""
+            }
+        )
+        .unwrap();
+    }
+    out.push_str("    ],\n");
+    out.push_str("    \"tags\": [\n");
+    for (index, tag) in TAGS.iter().enumerate() {
+        writeln!(
+            out,
+            "      {{ \"name\": \"{}\", \"tag\": {}, \"kind\": \"{}\", \"repeated\": {}, \"description\": \"{}\" }}{}",
+            tag.name,
+            tag.tag,
+            tag.kind.as_str(),
+            tag.repeated,
+            tag.description,
+            if index + 1 < TAGS.len() { "," } else { "" }
+        )
+        .unwrap();
+    }
+    out.push_str("    ]\n  },\n");
+
+    out.push_str("  \"sections\": [\n");
+    for (index, section) in SECTIONS.iter().enumerate() {
+        writeln!(
+            out,
+            "    {{ \"name\": \"{}\", \"layout\": \"{}\", \"elf\": \"{}\", \"macho\": \"{}\", \"coff\": \"{}\", \"wasm\": {}, \"description\": \"{}\" }}{}",
+            section.name,
+            section.layout.as_str(),
+            section.elf,
+            section.macho,
+            section.coff,
+            section
+                .wasm
+                .map_or("null".to_string(), |wasm| format!("\"{}\"", wasm)),
+            section.description,
+            if index + 1 < SECTIONS.len() { "," } else { "" }
+        )
+        .unwrap();
+    }
+    out.push_str("  ]\n}\n");
+    out
+}
@@ -0,0 +1,760 @@
+//! Version 2 of the record format: variable-length records of tagged values.
+//!
+//! Version 1 records have a fixed size, so everything that doesn't fit in 64
+//! bytes (the function path, lists of names, the source file) lives in side
+//! sections of pointers that readers have to resolve through relocations.
+//! A version 2 record carries all of it inline. It is a header, a sequence of
+//! entries and a checksum:
+//!
+//! | Offset  | Size | Field                                          |
+//! |---------|------|------------------------------------------------|
+//! | 0       | 8    | Magic bytes, `0xDEADBEEFCAFEF00D` (LE)         |
+//! | 8       | 2    | Format version, [`VERSION`]                    |
+//! | 10      | 2    | Header length, [`HEADER_LEN`] in this version  |
+//! | 12      | 4    | Record length, including header and checksum   |
+//! | 16      | ...  | Entries                                        |
+//! | len - 4 | 4    | CRC-32 of bytes 0..len - 4 (LE), or 0          |
+//!
+//! Each entry is a `u16` tag, a `u16` value length and the value; [`tags`]
+//! lists the tags and how their values are encoded. Strings are UTF-8 without
+//! a terminator and list fields repeat their tag once per element.
+//!
+//! Records written from C and C++ by `include/security_test.h` store a zero
+//! checksum, as C can't compute one in a constant initializer, and are
+//! accepted unchecked like version 1 records without a checksum. Their
+//! strings end in the NUL of the C literal, which [`Entry::as_str`] drops.
+//!
+//! ## Compatibility
+//!
+//! The magic bytes, the three header fields and the trailing checksum are
+//! the same in every version; readers skip the header bytes past the ones
+//! they know and entries whose tag they don't know. New fields therefore
+//! don't need a new version. The version changes only when an existing
+//! entry changes meaning, and [`check_record`] rejects versions it doesn't
+//! know with [`RecordError::UnsupportedVersion`] after verifying the
+//! checksum.
+//!
+//! [`scan_section`](crate::scan_section) accepts sections holding records of
+//! both versions, as produced when crates built with different releases of
+//! the macro are linked together.
+//!
+//! ## Generic Instances
+//!
+//! A record may describe one instance of a generic function, naming its
+//! arguments in a [`tags::INSTANCE`] entry, so a function can have several
+//! records. Readers group records by definition site (path, file, line and
+//! column) into one function, listing the instances of each. Records with
+//! the same site and no instance, such as those a C header annotation
+//! writes into every translation unit that includes it, merge the same way.
+//! `#[security_test]` writes one record per definition, without an
+//! instance, as statics can't depend on a function's type parameters.
+//!
+//! ## Encryption
+//!
+//! [`encrypt`] wraps an encoded record in a record whose only entry is
+//! [`tags::ENCRYPTED`]: a [`SIV_LEN`]-byte synthetic IV followed by the
+//! whole inner record, header and checksum included, encrypted under a
+//! 256-bit key. The IV is an HMAC-SHA-256 of the inner record, truncated,
+//! and the keystream is HMAC-SHA-256 of the IV and a block counter, each
+//! under its own key derived from the record key. The outer header and
+//! checksum are in the clear, so sections can be scanned and checked
+//! without the key, and readers that don't know the tag skip the record as
+//! one without a threat level.
+//!
+//! [`decrypt`] returns the inner record, which decodes like any other,
+//! after checking that its IV matches: the IV doubles as an authentication
+//! tag, so a changed byte or another key is rejected. Encryption is
+//! reproducible, as builds should be; only records that are identical
+//! encrypt identically, and without the key nothing about the contents can
+//! be confirmed, not even a guess of a short record.
+
+use crate::{cipher, crc32, read_u16, read_u32, read_u64, RecordError};
+
+/// Magic value at the start of every versioned record.
+pub const MAGIC: u64 = 0xDEAD_BEEF_CAFE_F00D;
+
+/// The format version written by [`Record::encode`].
+pub const VERSION: u16 = 2;
+
+/// Length of the header written by this version.
+pub const HEADER_LEN: usize = 16;
+
+/// Length of the trailing CRC-32.
+pub const CHECKSUM_LEN: usize = 4;
+
+/// Length of an entry's tag and value length.
+pub const ENTRY_HEADER_LEN: usize = 4;
+
+/// Byte offsets of the header fields.
+pub mod header {
+    pub const MAGIC: usize = 0;
+    pub const VERSION: usize = 8;
+    pub const HEADER_LEN: usize = 10;
+    pub const RECORD_LEN: usize = 12;
+}
+
+/// Entry tags. Integers are little-endian.
+pub mod tags {
+    /// Fully-qualified function path, string
+    pub const PATH: u16 = 1;
+    /// Requested test types, `u32` with bit `n` set for the test type with
+    /// discriminant `n`
+    pub const TESTS: u16 = 2;
+    /// Threat level, `u8` as in version 1
+    pub const THREAT_LEVEL: u16 = 3;
+    /// Declared capabilities, `u8` as in version 1
+    pub const CAPABILITIES: u16 = 4;
+    /// STRIDE categories, `u8` as in version 1
+    pub const STRIDE: u16 = 5;
+    /// Remediation deadline in days, `u16`, 0 = unset
+    pub const SLA_DAYS: u16 = 6;
+    /// Test context flag, `u8`
+    pub const TEST_CONTEXT: u16 = 7;
+    /// Source line, `u32`, 0 = unknown
+    pub const LINE: u16 = 8;
+    /// Source column, `u32`, 0 = unknown
+    pub const COLUMN: u16 = 9;
+    /// Source file as given by `file!()`, string
+    pub const FILE: u16 = 10;
+    /// An environment variable the function reads, string, repeated
+    pub const READS_ENV: u16 = 11;
+    /// A fixture the function's harness requires, string, repeated
+    pub const FIXTURE: u16 = 12;
+    /// A MITRE ATT&CK technique ID, string, repeated
+    pub const ATTACK_TECHNIQUE: u16 = 13;
+    /// A parameter as `name: Type`, string, repeated
+    pub const INPUT_PARAM: u16 = 14;
+    /// Compiler settings, string of `key=value` pairs separated by `; `
+    pub const BUILD: u16 = 15;
+    /// Threat level declared for debug builds, `u8`, only present with
+    /// [`RELEASE_THREAT_LEVEL`] when the levels differ by profile
+    pub const DEBUG_THREAT_LEVEL: u16 = 16;
+    /// Threat level declared for release builds, `u8`
+    pub const RELEASE_THREAT_LEVEL: u16 = 17;
+    /// Name of a test type defined outside this crate, given with
+    /// `custom = "name"`, string
+    pub const CUSTOM_TEST: u16 = 18;
+    /// CWE weakness ID given with `cwe = [...]`, `u32`
+    pub const CWE: u16 = 19;
+    /// OWASP Top 10 category given with `owasp = ...`, string such as
+    /// `A03:2021`
+    pub const OWASP: u16 = 20;
+    /// CVSS base score in tenths, `u8` from 0 to 100, only present when
+    /// `cvss = ...` was given
+    pub const CVSS_SCORE: u16 = 21;
+    /// CVSS v3 base vector with its `CVSS:3.x/` prefix, string, only
+    /// present when `cvss = "..."` gave a vector
+    pub const CVSS_VECTOR: u16 = 22;
+    /// Marks a [`Program`] record rather than a function's, `u8` = 1
+    pub const PROGRAM: u16 = 23;
+    /// Exposure of the program, `u8`: 0 airgapped, 1 internal, 2 partner,
+    /// 3 internet
+    pub const EXPOSURE: u16 = 24;
+    /// Classes of data the program handles, `u8`: 0x01 pii, 0x02 phi,
+    /// 0x04 pci, 0x08 credentials
+    pub const DATA_CLASSES: u16 = 25;
+    /// Banned API the function calls, as listed in `banned_calls` of the
+    /// crate's manifest, string such as `std::mem::transmute`
+    pub const BANNED_CALL: u16 = 26;
+    /// An encrypted record, the only entry of its record: a
+    /// [`SIV_LEN`](super::SIV_LEN)-byte synthetic IV followed by the
+    /// encrypted bytes, see [`encrypt`](super::encrypt)
+    pub const ENCRYPTED: u16 = 27;
+    /// Type and const arguments of the generic instance the record was
+    /// written for, string such as `T = u32, N = 4`, see
+    /// [generic instances](super#generic-instances)
+    pub const INSTANCE: u16 = 28;
+}
+
+/// The contents of one record, as written by `#[security_test]`.
+///
+/// Encoding is `const`, so the macro can build records from values that are
+/// only known when the annotated crate compiles, such as `module_path!()`
+/// and `cfg!(test)`:
+///
+/// ```rust
+/// use security_scanner_format::tlv::{self, Record};
+///
+/// const RECORD: Record<'static> = Record {
+///     path: concat!(module_path!(), "::login"),
+///     threat_level: 3,
+///     reads_env: &["DATABASE_URL"],
+///     ..Record::EMPTY
+/// };
+/// static BYTES: [u8; RECORD.encoded_len()] = RECORD.encode();
+///
+/// let record = tlv::check_record(&BYTES).unwrap();
+/// assert!(tlv::entries(record).any(|entry| entry.value == b"DATABASE_URL"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Record<'a> {
+    pub path: &'a str,
+    /// Bit per test type, see [`tags::TESTS`]
+    pub tests: u32,
+    /// Threat level of the profile being compiled
+    pub threat_level: u8,
+    /// Declared debug and release threat levels, when they differ by
+    /// profile
+    pub profile_threat_levels: Option<[u8; 2]>,
+    pub capabilities: u8,
+    pub stride: u8,
+    pub sla_days: u16,
+    pub test_context: bool,
+    pub line: u32,
+    pub column: u32,
+    pub file: &'a str,
+    pub reads_env: &'a [&'a str],
+    pub fixtures: &'a [&'a str],
+    pub attack_techniques: &'a [&'a str],
+    pub input_params: &'a [&'a str],
+    pub build: &'a str,
+    /// Names of custom test types, see [`tags::CUSTOM_TEST`]
+    pub custom_tests: &'a [&'a str],
+    /// CWE IDs, see [`tags::CWE`]
+    pub cwe: &'a [u32],
+    /// OWASP Top 10 categories, see [`tags::OWASP`]
+    pub owasp: &'a [&'a str],
+    /// CVSS base score in tenths, see [`tags::CVSS_SCORE`]
+    pub cvss_score: Option<u8>,
+    /// CVSS vector, written only when non-empty
+    pub cvss_vector: &'a str,
+    /// Banned APIs called, see [`tags::BANNED_CALL`]
+    pub banned_calls: &'a [&'a str],
+    /// Generic arguments of the instance, written only when non-empty, see
+    /// [`tags::INSTANCE`]
+    pub instance: &'a str,
+}
+
+impl Record<'static> {
+    /// A record with every field empty or zero, for `..Record::EMPTY`.
+    pub const EMPTY: Self = Record {
+        path: "",
+        tests: 0,
+        threat_level: 0,
+        profile_threat_levels: None,
+        capabilities: 0,
+        stride: 0,
+        sla_days: 0,
+        test_context: false,
+        line: 0,
+        column: 0,
+        file: "",
+        reads_env: &[],
+        fixtures: &[],
+        attack_techniques: &[],
+        input_params: &[],
+        build: "",
+        custom_tests: &[],
+        cwe: &[],
+        owasp: &[],
+        cvss_score: None,
+        cvss_vector: "",
+        banned_calls: &[],
+        instance: "",
+    };
+}
+
+impl Record<'_> {
+    /// Returns the length of the encoded record.
+    pub const fn encoded_len(&self) -> usize {
+        HEADER_LEN
+            + entry_len(self.path.len())
+            + entry_len(4)
+            + 4 * entry_len(1)
+            + entry_len(2)
+            + 2 * entry_len(4)
+            + entry_len(self.file.len())
+            + list_len(self.reads_env)
+            + list_len(self.fixtures)
+            + list_len(self.attack_techniques)
+            + list_len(self.input_params)
+            + entry_len(self.build.len())
+            + match self.profile_threat_levels {
+                Some(_) => 2 * entry_len(1),
+                None => 0,
+            }
+            + list_len(self.custom_tests)
+            + self.cwe.len() * entry_len(4)
+            + list_len(self.owasp)
+            + match self.cvss_score {
+                Some(_) => entry_len(1),
+                None => 0,
+            }
+            + match self.cvss_vector.len() {
+                0 => 0,
+                len => entry_len(len),
+            }
+            + list_len(self.banned_calls)
+            + match self.instance.len() {
+                0 => 0,
+                len => entry_len(len),
+            }
+            + CHECKSUM_LEN
+    }
+
+    /// Encodes and seals the record.
+    ///
+    /// Panics (a compile error in const context) if `N` isn't
+    /// [`encoded_len`](Record::encoded_len) or a string is longer than
+    /// 65535 bytes.
+    pub const fn encode<const N: usize>(&self) -> [u8; N] {
+        assert!(
+            N == self.encoded_len(),
+            "record length doesn't match the array"
+        );
+        assert!(N <= u32::MAX as usize, "record longer than 4 GiB");
+
+        let mut out = [0u8; N];
+        let at = put_header(&mut out);
+        let at = put_entry(&mut out, at, tags::PATH, self.path.as_bytes());
+        let at = put_entry(&mut out, at, tags::TESTS, &self.tests.to_le_bytes());
+        let at = put_entry(&mut out, at, tags::THREAT_LEVEL, &[self.threat_level]);
+        let at = put_entry(&mut out, at, tags::CAPABILITIES, &[self.capabilities]);
+        let at = put_entry(&mut out, at, tags::STRIDE, &[self.stride]);
+        let at = put_entry(&mut out, at, tags::SLA_DAYS, &self.sla_days.to_le_bytes());
+        let at = put_entry(&mut out, at, tags::TEST_CONTEXT, &[self.test_context as u8]);
+        let at = put_entry(&mut out, at, tags::LINE, &self.line.to_le_bytes());
+        let at = put_entry(&mut out, at, tags::COLUMN, &self.column.to_le_bytes());
+        let at = put_entry(&mut out, at, tags::FILE, self.file.as_bytes());
+        let at = put_list(&mut out, at, tags::READS_ENV, self.reads_env);
+        let at = put_list(&mut out, at, tags::FIXTURE, self.fixtures);
+        let at = put_list(&mut out, at, tags::ATTACK_TECHNIQUE, self.attack_techniques);
+        let at = put_list(&mut out, at, tags::INPUT_PARAM, self.input_params);
+        let at = put_entry(&mut out, at, tags::BUILD, self.build.as_bytes());
+        let at = match self.profile_threat_levels {
+            Some([debug, release]) => {
+                let at = put_entry(&mut out, at, tags::DEBUG_THREAT_LEVEL, &[debug]);
+                put_entry(&mut out, at, tags::RELEASE_THREAT_LEVEL, &[release])
+            }
+            None => at,
+        };
+        let at = put_list(&mut out, at, tags::CUSTOM_TEST, self.custom_tests);
+        let at = put_u32_list(&mut out, at, tags::CWE, self.cwe);
+        let at = put_list(&mut out, at, tags::OWASP, self.owasp);
+        let at = match self.cvss_score {
+            Some(score) => put_entry(&mut out, at, tags::CVSS_SCORE, &[score]),
+            None => at,
+        };
+        let at = match self.cvss_vector.len() {
+            0 => at,
+            _ => put_entry(&mut out, at, tags::CVSS_VECTOR, self.cvss_vector.as_bytes()),
+        };
+        let at = put_list(&mut out, at, tags::BANNED_CALL, self.banned_calls);
+        let at = match self.instance.len() {
+            0 => at,
+            _ => put_entry(&mut out, at, tags::INSTANCE, self.instance.as_bytes()),
+        };
+
+        seal(&mut out, at);
+        out
+    }
+}
+
+/// The context of a whole program, as written by `#[security_program]`.
+///
+/// A program record shares the records section with those of functions. It
+/// has a [`tags::PROGRAM`] entry and no threat level, so readers that only
+/// know function records skip it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Program<'a> {
+    /// Path of the annotated entry point, such as `my_app::main`
+    pub path: &'a str,
+    /// See [`tags::EXPOSURE`], written only when declared
+    pub exposure: Option<u8>,
+    /// See [`tags::DATA_CLASSES`]
+    pub data_classes: u8,
+    pub line: u32,
+    pub column: u32,
+    pub file: &'a str,
+}
+
+impl Program<'static> {
+    /// A program record with every field empty or zero.
+    pub const EMPTY: Self = Program {
+        path: "",
+        exposure: None,
+        data_classes: 0,
+        line: 0,
+        column: 0,
+        file: "",
+    };
+}
+
+impl Program<'_> {
+    /// Returns the length of the encoded record.
+    pub const fn encoded_len(&self) -> usize {
+        HEADER_LEN
+            + entry_len(1)
+            + entry_len(self.path.len())
+            + match self.exposure {
+                Some(_) => entry_len(1),
+                None => 0,
+            }
+            + entry_len(1)
+            + 2 * entry_len(4)
+            + entry_len(self.file.len())
+            + CHECKSUM_LEN
+    }
+
+    /// Encodes and seals the record; panics under the same conditions as
+    /// [`Record::encode`].
+    pub const fn encode<const N: usize>(&self) -> [u8; N] {
+        assert!(
+            N == self.encoded_len(),
+            "record length doesn't match the array"
+        );
+
+        let mut out = [0u8; N];
+        let at = put_header(&mut out);
+        let at = put_entry(&mut out, at, tags::PROGRAM, &[1]);
+        let at = put_entry(&mut out, at, tags::PATH, self.path.as_bytes());
+        let at = match self.exposure {
+            Some(exposure) => put_entry(&mut out, at, tags::EXPOSURE, &[exposure]),
+            None => at,
+        };
+        let at = put_entry(&mut out, at, tags::DATA_CLASSES, &[self.data_classes]);
+        let at = put_entry(&mut out, at, tags::LINE, &self.line.to_le_bytes());
+        let at = put_entry(&mut out, at, tags::COLUMN, &self.column.to_le_bytes());
+        let at = put_entry(&mut out, at, tags::FILE, self.file.as_bytes());
+        seal(&mut out, at);
+        out
+    }
+}
+
+/// The address of a function's code along with that of its record, as
+/// written by `#[security_test]` to the `addresses` section for functions
+/// with `anchor`.
+///
+/// A record is plain bytes, built in const context, so it can't hold a
+/// pointer; this entry holds two, which the linker relocates. Readers
+/// resolve both and attach the function's address to the record found at
+/// the first.
+///
+/// ```rust
+/// use security_scanner_format::tlv::{FunctionAddress, Record};
+///
+/// fn login() {}
+///
+/// const RECORD: Record<'static> = Record {
+///     path: "my_app::login",
+///     ..Record::EMPTY
+/// };
+/// static BYTES: [u8; RECORD.encoded_len()] = RECORD.encode();
+/// static ADDRESS: FunctionAddress = FunctionAddress {
+///     record: BYTES.as_ptr(),
+///     code: login as *const (),
+/// };
+///
+/// assert_eq!(ADDRESS.code as usize, login as usize);
+/// ```
+#[derive(Debug)]
+#[repr(C)]
+pub struct FunctionAddress {
+    /// First byte of the function's record
+    pub record: *const u8,
+    /// The function's code
+    pub code: *const (),
+}
+
+// Both pointers are to immutable statics and code
+unsafe impl Sync for FunctionAddress {}
+
+/// Length of a key for [`encrypt`].
+pub const KEY_LEN: usize = 32;
+
+/// Length of the synthetic IV at the start of a [`tags::ENCRYPTED`] value.
+pub const SIV_LEN: usize = cipher::SIV_LEN;
+
+/// Returns the length of the record [`encrypt`] makes of an encoded record
+/// of `plain_len` bytes.
+pub const fn encrypted_len(plain_len: usize) -> usize {
+    HEADER_LEN + entry_len(SIV_LEN + plain_len) + CHECKSUM_LEN
+}
+
+/// Encrypts the encoded record `plain` with `key`, see the
+/// [module documentation](self#encryption).
+///
+/// `#[security_test]` encrypts its records with this when
+/// `SECURITY_SCANNER_METADATA_KEY` is set:
+///
+/// ```rust
+/// use security_scanner_format::tlv::{self, Record};
+///
+/// const RECORD: Record<'static> = Record {
+///     path: "my_app::login",
+///     ..Record::EMPTY
+/// };
+/// const KEY: [u8; tlv::KEY_LEN] = [7; tlv::KEY_LEN];
+/// static BYTES: [u8; tlv::encrypted_len(RECORD.encoded_len())] =
+///     tlv::encrypt(&RECORD.encode::<{ RECORD.encoded_len() }>(), &KEY);
+///
+/// let record = tlv::check_record(&BYTES).unwrap();
+/// assert!(tlv::is_encrypted(record));
+/// # #[cfg(feature = "std")]
+/// assert_eq!(tlv::decrypt(record, &KEY).unwrap(), RECORD.encode::<{ RECORD.encoded_len() }>());
+/// ```
+///
+/// Panics (a compile error in const context) if `N` isn't
+/// [`encrypted_len`] of `plain` or `plain` is longer than 65523 bytes.
+pub const fn encrypt<const N: usize>(plain: &[u8], key: &[u8; KEY_LEN]) -> [u8; N] {
+    assert!(
+        N == encrypted_len(plain.len()),
+        "record length doesn't match the array"
+    );
+    assert!(
+        SIV_LEN + plain.len() <= u16::MAX as usize,
+        "record too long to encrypt"
+    );
+
+    let siv = cipher::siv(plain, key);
+    let value_len = (SIV_LEN + plain.len()) as u16;
+    let mut out = [0u8; N];
+    let at = put_header(&mut out);
+    let at = put(&mut out, at, &tags::ENCRYPTED.to_le_bytes());
+    let at = put(&mut out, at, &value_len.to_le_bytes());
+    let start = put(&mut out, at, &siv);
+    let at = put(&mut out, start, plain);
+    let (entries, _) = out.split_at_mut(at);
+    cipher::apply_keystream(entries.split_at_mut(start).1, key, &siv);
+    seal(&mut out, at);
+    out
+}
+
+/// Returns whether a record accepted by [`check_record`] is encrypted.
+pub fn is_encrypted(record: &[u8]) -> bool {
+    entries(record).any(|entry| entry.tag == tags::ENCRYPTED)
+}
+
+/// Decrypts a record written by [`encrypt`], returning the encoded record it
+/// holds, or `None` if `record` isn't encrypted, was encrypted with another
+/// key or was changed since.
+#[cfg(feature = "std")]
+pub fn decrypt(record: &[u8], key: &[u8; KEY_LEN]) -> Option<Vec<u8>> {
+    let entry = entries(record).find(|entry| entry.tag == tags::ENCRYPTED)?;
+    let (siv, encrypted) = entry.value.split_at_checked(SIV_LEN)?;
+    let siv: &[u8; SIV_LEN] = siv.try_into().ok()?;
+    let mut plain = encrypted.to_vec();
+    cipher::apply_keystream(&mut plain, key, siv);
+    if !cipher::siv_eq(&cipher::siv(&plain, key), siv) {
+        return None;
+    }
+    (check_record(&plain).ok()?.len() == plain.len()).then_some(plain)
+}
+
+/// Parses a key written as 64 hex digits, as `SECURITY_SCANNER_METADATA_KEY`
+/// holds it.
+pub fn parse_key(hex: &str) -> Option<[u8; KEY_LEN]> {
+    let hex = hex.trim().as_bytes();
+    if hex.len() != 2 * KEY_LEN {
+        return None;
+    }
+    let digit = |byte: u8| char::from(byte).to_digit(16);
+    let mut key = [0; KEY_LEN];
+    for (byte, pair) in key.iter_mut().zip(hex.chunks(2)) {
+        *byte = (digit(pair[0])? << 4 | digit(pair[1])?) as u8;
+    }
+    Some(key)
+}
+
+/// Writes the header of a record filling all of `out`.
+const fn put_header(out: &mut [u8]) -> usize {
+    let len = out.len() as u32;
+    let at = put(out, 0, &MAGIC.to_le_bytes());
+    let at = put(out, at, &VERSION.to_le_bytes());
+    let at = put(out, at, &(HEADER_LEN as u16).to_le_bytes());
+    put(out, at, &len.to_le_bytes())
+}
+
+/// Writes the checksum of the bytes before `at`, which must be the last
+/// [`CHECKSUM_LEN`] bytes of `out`.
+const fn seal(out: &mut [u8], at: usize) {
+    let crc = crc32(out.split_at(at).0);
+    put(out, at, &crc.to_le_bytes());
+}
+
+const fn entry_len(value_len: usize) -> usize {
+    ENTRY_HEADER_LEN + value_len
+}
+
+const fn list_len(values: &[&str]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < values.len() {
+        len += entry_len(values[i].len());
+        i += 1;
+    }
+    len
+}
+
+const fn put(out: &mut [u8], mut at: usize, bytes: &[u8]) -> usize {
+    let mut i = 0;
+    while i < bytes.len() {
+        out[at] = bytes[i];
+        at += 1;
+        i += 1;
+    }
+    at
+}
+
+const fn put_entry(out: &mut [u8], at: usize, tag: u16, value: &[u8]) -> usize {
+    assert!(
+        value.len() <= u16::MAX as usize,
+        "metadata value longer than 65535 bytes"
+    );
+    let at = put(out, at, &tag.to_le_bytes());
+    let at = put(out, at, &(value.len() as u16).to_le_bytes());
+    put(out, at, value)
+}
+
+const fn put_list(out: &mut [u8], mut at: usize, tag: u16, values: &[&str]) -> usize {
+    let mut i = 0;
+    while i < values.len() {
+        at = put_entry(out, at, tag, values[i].as_bytes());
+        i += 1;
+    }
+    at
+}
+
+const fn put_u32_list(out: &mut [u8], mut at: usize, tag: u16, values: &[u32]) -> usize {
+    let mut i = 0;
+    while i < values.len() {
+        at = put_entry(out, at, tag, &values[i].to_le_bytes());
+        i += 1;
+    }
+    at
+}
+
+/// Returns whether `bytes` start with the magic value of versioned records,
+/// in either byte order.
+pub fn has_magic(bytes: &[u8]) -> bool {
+    if bytes.len() < 8 {
+        return false;
+    }
+    let magic = read_u64(bytes, header::MAGIC);
+    magic == MAGIC || magic == MAGIC.swap_bytes()
+}
+
+/// Returns the record length stored in the header at the start of `bytes`,
+/// or `None` if the header is incomplete or the length is too short to hold
+/// a header and checksum. Byte-swapped headers are read big-endian.
+pub fn record_len(bytes: &[u8]) -> Option<usize> {
+    if bytes.len() < HEADER_LEN {
+        return None;
+    }
+    let mut header_len = read_u16(bytes, header::HEADER_LEN);
+    let mut record_len = read_u32(bytes, header::RECORD_LEN);
+    if read_u64(bytes, header::MAGIC) == MAGIC.swap_bytes() {
+        header_len = header_len.swap_bytes();
+        record_len = record_len.swap_bytes();
+    }
+
+    let header_len = usize::from(header_len);
+    let record_len = usize::try_from(record_len).ok()?;
+    (header_len >= HEADER_LEN && record_len >= header_len + CHECKSUM_LEN).then_some(record_len)
+}
+
+/// Checks the header and checksum of the record at the start of `bytes` and
+/// returns the record's bytes. A zero checksum isn't checked.
+pub fn check_record(bytes: &[u8]) -> Result<&[u8], RecordError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(RecordError::Truncated {
+            len: bytes.len(),
+            expected: HEADER_LEN,
+        });
+    }
+
+    let magic = read_u64(bytes, header::MAGIC);
+    if magic == MAGIC.swap_bytes() {
+        return Err(RecordError::ForeignEndian);
+    }
+    if magic != MAGIC {
+        return Err(RecordError::BadMagic);
+    }
+
+    let Some(len) = record_len(bytes) else {
+        return Err(RecordError::BadLength {
+            len: read_u32(bytes, header::RECORD_LEN),
+        });
+    };
+    if bytes.len() < len {
+        return Err(RecordError::Truncated {
+            len: bytes.len(),
+            expected: len,
+        });
+    }
+
+    let record = &bytes[..len];
+    let stored = read_u32(record, len - CHECKSUM_LEN);
+    let computed = crc32(&record[..len - CHECKSUM_LEN]);
+    if stored != 0 && stored != computed {
+        return Err(RecordError::ChecksumMismatch { stored, computed });
+    }
+
+    let version = read_u16(record, header::VERSION);
+    if version != VERSION {
+        return Err(RecordError::UnsupportedVersion { version });
+    }
+
+    Ok(record)
+}
+
+/// One entry of a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry<'a> {
+    /// One of the [`tags`], or a tag from a later release
+    pub tag: u16,
+    pub value: &'a [u8],
+}
+
+impl<'a> Entry<'a> {
+    /// Returns the value as a string, or `None` if it isn't UTF-8. A
+    /// trailing NUL, as written from C, isn't part of the string.
+    pub fn as_str(&self) -> Option<&'a str> {
+        let value = self.value.strip_suffix(&[0]).unwrap_or(self.value);
+        core::str::from_utf8(value).ok()
+    }
+
+    /// Returns the value as an unsigned integer, or `None` if it is longer
+    /// than eight bytes. Shorter values are zero-extended, so a field can
+    /// widen without a new tag.
+    pub fn as_uint(&self) -> Option<u64> {
+        let mut buf = [0u8; 8];
+        buf.get_mut(..self.value.len())?.copy_from_slice(self.value);
+        Some(u64::from_le_bytes(buf))
+    }
+}
+
+/// Iterator over the entries of a record, returned by [`entries`].
+#[derive(Debug, Clone)]
+pub struct Entries<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = Entry<'a>;
+
+    fn next(&mut self) -> Option<Entry<'a>> {
+        if self.rest.len() < ENTRY_HEADER_LEN {
+            return None;
+        }
+        let tag = read_u16(self.rest, 0);
+        let len = usize::from(read_u16(self.rest, 2));
+        let Some(value) = self.rest.get(ENTRY_HEADER_LEN..ENTRY_HEADER_LEN + len) else {
+            self.rest = &[];
+            return None;
+        };
+        self.rest = &self.rest[ENTRY_HEADER_LEN + len..];
+        Some(Entry { tag, value })
+    }
+}
+
+/// Returns the entries of a record accepted by [`check_record`], in the
+/// order they were written. An entry running past the end of the record
+/// ends the iteration.
+pub fn entries(record: &[u8]) -> Entries<'_> {
+    let start = usize::from(read_u16(record, header::HEADER_LEN)).min(record.len());
+    let end = record.len().saturating_sub(CHECKSUM_LEN).max(start);
+    Entries {
+        rest: &record[start..end],
+    }
+}
@@ -0,0 +1,155 @@
+//! Keeps the checked-in C header and JSON descriptor in sync with the crate,
+//! and runs the C reference parser against records sealed in Rust.
+//!
+//! After changing the format, regenerate the checked-in files with
+//! `SECURITY_SCANNER_BLESS=1 cargo test -p security-scanner-format`.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use security_scanner_format::schema::{self, FIELDS, SECTIONS, TAGS, TLV_HEADER};
+use security_scanner_format::tlv::{self, Record};
+use security_scanner_format::{empty_record, offsets, seal, write_u32, RECORD_SIZE};
+
+fn crate_path(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(relative)
+}
+
+fn check_generated(relative: &str, generated: &str) {
+    let path = crate_path(relative);
+    if std::env::var_os("SECURITY_SCANNER_BLESS").is_some() {
+        std::fs::write(&path, generated).unwrap();
+        return;
+    }
+
+    let checked_in = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        checked_in == generated,
+        "{} is out of date; rerun with SECURITY_SCANNER_BLESS=1",
+        relative
+    );
+}
+
+#[test]
+fn c_header_is_up_to_date() {
+    check_generated("include/security_scanner_format.h", &schema::c_header());
+}
+
+#[test]
+fn json_descriptor_is_up_to_date_and_valid() {
+    let json = schema::json();
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed["record_size"], RECORD_SIZE);
+    assert_eq!(parsed["fields"].as_array().unwrap().len(), FIELDS.len());
+    assert_eq!(parsed["sections"].as_array().unwrap().len(), SECTIONS.len());
+    assert_eq!(parsed["tlv"]["tags"].as_array().unwrap().len(), TAGS.len());
+
+    check_generated("schema/record.json", &json);
+}
+
+#[test]
+fn fields_are_ordered_and_fit_the_record() {
+    let mut end = 0;
+    for field in FIELDS {
+        assert!(
+            field.offset >= end,
+            "{} overlaps the previous field",
+            field.name
+        );
+        end = field.offset + field.size;
+    }
+    assert_eq!(end, RECORD_SIZE);
+
+    let mut end = 0;
+    for field in TLV_HEADER {
+        assert_eq!(field.offset, end, "{} leaves a gap", field.name);
+        end = field.offset + field.size;
+    }
+    assert_eq!(end, tlv::HEADER_LEN);
+}
+
+#[test]
+fn tags_are_unique_and_ordered() {
+    for pair in TAGS.windows(2) {
+        assert!(
+            pair[0].tag < pair[1].tag,
+            "{} is out of order",
+            pair[1].name
+        );
+    }
+}
+
+#[test]
+fn c_reference_parser_agrees() {
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+    if Command::new(&cc).arg("--version").output().is_err() {
+        eprintln!("skipping: no C compiler ({})", cc);
+        return;
+    }
+
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("reference-parser");
+    std::fs::create_dir_all(&dir).unwrap();
+    let binary = dir.join("reference_parser");
+    let status = Command::new(&cc)
+        .args(["-std=c99", "-Wall", "-Werror", "-I"])
+        .arg(crate_path("include"))
+        .arg(crate_path("c/reference_parser.c"))
+        .arg("-o")
+        .arg(&binary)
+        .status()
+        .unwrap();
+    assert!(status.success(), "reference parser failed to compile");
+
+    let mut first = empty_record();
+    first[offsets::NAME_LEN] = 5;
+    first[offsets::SQL_INJECTION] = 1;
+    first[offsets::THREAT_LEVEL] = 3;
+    seal(&mut first);
+
+    let mut corrupted = first;
+    corrupted[offsets::TIMING_ATTACK] = 1;
+
+    const TAGGED: Record<'static> = Record {
+        path: "app::login",
+        tests: 0b101,
+        threat_level: 2,
+        line: 12,
+        column: 8,
+        reads_env: &["HOME"],
+        ..Record::EMPTY
+    };
+    let tagged: [u8; TAGGED.encoded_len()] = TAGGED.encode();
+    // As written from C: a NUL-terminated path and no checksum
+    const FROM_C: Record<'static> = Record {
+        path: "app::parse\0",
+        ..TAGGED
+    };
+    let mut from_c: [u8; FROM_C.encoded_len()] = FROM_C.encode();
+    write_u32(&mut from_c, FROM_C.encoded_len() - tlv::CHECKSUM_LEN, 0);
+
+    let mut section = first.to_vec();
+    section.extend_from_slice(&[0; 8]);
+    section.extend_from_slice(&corrupted);
+    section.extend_from_slice(&empty_record());
+    section.extend_from_slice(&tagged);
+    section.extend_from_slice(&from_c);
+
+    let input = dir.join("records.bin");
+    std::fs::write(&input, &section).unwrap();
+    let output = Command::new(&binary).arg(&input).output().unwrap();
+    assert!(output.status.success());
+
+    let fields = "name_len=5 threat_level=3 sql_injection=1 race_condition=0";
+    let expected = format!(
+        "offset=0 status=verified {fields} timing_attack=0 buffer_overflow=0\n\
+         offset=72 status=corrupted {fields} timing_attack=1 buffer_overflow=0\n\
+         offset=136 status=unchecked name_len=0 threat_level=0 sql_injection=0 race_condition=0 \
+         timing_attack=0 buffer_overflow=0\n\
+         offset=200 status=verified version=2 path=app::login tests=0x05 threat_level=2 \
+         line=12 column=8\n\
+         offset={} status=unchecked version=2 path=app::parse tests=0x05 threat_level=2 \
+         line=12 column=8\n",
+        200 + tagged.len()
+    );
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+}
@@ -0,0 +1,38 @@
+//! Records the version of the compiler building this crate, which is the
+//! compiler that builds every crate using the macro, and the environment
+//! variables that decide what the macros emit.
+//!
+//! Cargo only tracks the environment of build scripts, so each variable is
+//! registered here and forwarded to the macros' own compilation. Changing
+//! one rebuilds this crate and with it every crate using the macros.
+
+use std::process::Command;
+
+/// Variables read by `strategy`.
+const TRACKED_ENV: &[&str] = &[
+    "SECURITY_SCANNER_STRIP",
+    "SECURITY_SCANNER_METADATA_STRATEGY",
+    "SECURITY_SCANNER_METADATA_KEY",
+];
+
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default();
+    let version = version.strip_prefix("rustc ").unwrap_or(&version);
+
+    println!("cargo:rustc-env=SECURITY_SCANNER_RUSTC_VERSION={}", version);
+    println!("cargo:rerun-if-changed=build.rs");
+
+    for name in TRACKED_ENV {
+        println!("cargo:rerun-if-env-changed={}", name);
+        if let Ok(value) = std::env::var(name) {
+            println!("cargo:rustc-env={}={}", name, value);
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! Detection of banned API calls in the bodies of annotated functions.
+//!
+//! A crate lists the APIs its annotated functions must not call in its
+//! manifest, and whether a call is a warning (the default) or an error:
+//!
+//! ```toml
+//! [package.metadata.security-scanner]
+//! banned_calls = ["std::process::Command", "std::mem::transmute", "str::from_utf8_unchecked"]
+//! banned_calls_policy = "deny"
+//! ```
+//!
+//! A macro can't resolve imports, so calls are matched by the path they are
+//! written with: `Command::new(...)` after `use std::process::Command`, and
+//! `core::mem::transmute(...)`, both match the list above, as `core` and
+//! `alloc` count as `std`. Method calls match by name, so banning
+//! `Option::unwrap` bans every `.unwrap()`. Calls inside macro invocations
+//! such as `println!` aren't seen.
+//!
+//! Walking the bodies needs syn's `visit` feature, so it is behind the
+//! `banned` feature. Without it a crate that configures `banned_calls` gets
+//! an error instead of silently unchecked functions.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote_spanned;
+use syn::punctuated::Punctuated;
+#[cfg(feature = "banned")]
+use syn::spanned::Spanned;
+#[cfg(feature = "banned")]
+use syn::visit::Visit;
+use syn::{LitStr, Token};
+
+use crate::strategy::manifest_value;
+
+/// The banned calls configured for the crate being compiled.
+#[derive(Default)]
+pub struct BannedCalls {
+    /// Each banned path as written and split into segments
+    paths: Vec<(String, Vec<String>)>,
+    /// Whether calls are errors rather than warnings
+    deny: bool,
+}
+
+/// The banned calls found in one function.
+pub struct Findings {
+    /// Banned paths called, in configuration order
+    pub calls: Vec<String>,
+    /// Items raising a warning at each call
+    pub warnings: TokenStream,
+}
+
+impl BannedCalls {
+    /// Reads `banned_calls` and `banned_calls_policy` from the manifest of
+    /// the crate being compiled.
+    pub fn for_current_crate() -> Result<Self, String> {
+        let Some(list) = manifest_value("banned_calls") else {
+            return Ok(BannedCalls::default());
+        };
+        let invalid = || {
+            format!(
+                "banned_calls must be an array of paths such as [\"std::mem::transmute\"], \
+                 found {}",
+                list
+            )
+        };
+        let entries = syn::parse::Parser::parse_str(
+            |input: syn::parse::ParseStream| {
+                let content;
+                syn::bracketed!(content in input);
+                Punctuated::<LitStr, Token![,]>::parse_terminated(&content)
+            },
+            &list,
+        )
+        .map_err(|_| invalid())?;
+
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path: syn::Path = entry.parse().map_err(|_| invalid())?;
+            paths.push((entry.value(), segments(&path)));
+        }
+
+        let deny = match manifest_value("banned_calls_policy").as_deref() {
+            None | Some("\"warn\"") => false,
+            Some("\"deny\"") => true,
+            Some(other) => {
+                return Err(format!(
+                    "unknown banned_calls_policy {}, expected \"warn\" or \"deny\"",
+                    other
+                ))
+            }
+        };
+
+        Ok(BannedCalls { paths, deny })
+    }
+
+    /// Looks for banned calls in `body`, failing with an error per call
+    /// under the `deny` policy.
+    pub fn check(&self, body: &syn::Block) -> syn::Result<Findings> {
+        let mut findings = Findings {
+            calls: Vec::new(),
+            warnings: TokenStream::new(),
+        };
+        if self.paths.is_empty() {
+            return Ok(findings);
+        }
+
+        let mut errors: Option<syn::Error> = None;
+        let mut found = vec![false; self.paths.len()];
+        for (path, span) in calls(body)? {
+            let Some(index) = self
+                .paths
+                .iter()
+                .position(|(_, banned)| matches(banned, &path))
+            else {
+                continue;
+            };
+            found[index] = true;
+            let name = &self.paths[index].0;
+            if self.deny {
+                let error = syn::Error::new(
+                    span,
+                    format!(
+                        "call to `{}`, banned by `banned_calls` in \
+                         [package.metadata.security-scanner]",
+                        name
+                    ),
+                );
+                match &mut errors {
+                    Some(errors) => errors.combine(error),
+                    None => errors = Some(error),
+                }
+            } else {
+                let note = format!(
+                    "calls `{}`, banned by `banned_calls` in [package.metadata.security-scanner]",
+                    name
+                );
+                // Stable proc macros can't warn; using a deprecated item can
+                findings.warnings.extend(quote_spanned! {span=>
+                    const _: () = {
+                        #[deprecated(note = #note)]
+                        #[allow(non_upper_case_globals)]
+                        const banned_call: () = ();
+                        banned_call
+                    };
+                });
+            }
+        }
+        if let Some(errors) = errors {
+            return Err(errors);
+        }
+
+        findings.calls = self
+            .paths
+            .iter()
+            .zip(found)
+            .filter(|(_, found)| *found)
+            .map(|((name, _), _)| name.clone())
+            .collect();
+        Ok(findings)
+    }
+}
+
+/// Returns whether a call written as `path` may call `banned`: either the
+/// path contains the banned one (`core::mem::transmute` for
+/// `mem::transmute`), or it starts with the end of the banned path, as after
+/// an import (`Command::new` for `std::process::Command`).
+fn matches(banned: &[String], path: &[String]) -> bool {
+    path.windows(banned.len()).any(|window| window == banned)
+        || (1..=banned.len().min(path.len())).any(|len| path[..len] == banned[banned.len() - len..])
+}
+
+/// Splits a path into the names of its segments, spelling a leading `core`
+/// or `alloc` as `std`, which re-exports both.
+fn segments(path: &syn::Path) -> Vec<String> {
+    let mut segments: Vec<String> = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    if let Some(first) = segments
+        .first_mut()
+        .filter(|first| *first == "core" || *first == "alloc")
+    {
+        *first = "std".to_string();
+    }
+    segments
+}
+
+/// Returns the callee path of every call in `body`, with the span to report
+/// it at.
+#[cfg(feature = "banned")]
+fn calls(body: &syn::Block) -> syn::Result<Vec<(Vec<String>, Span)>> {
+    let mut visitor = Calls::default();
+    visitor.visit_block(body);
+    Ok(visitor.calls)
+}
+
+#[cfg(not(feature = "banned"))]
+fn calls(_body: &syn::Block) -> syn::Result<Vec<(Vec<String>, Span)>> {
+    Err(syn::Error::new(
+        Span::call_site(),
+        "`banned_calls` needs the `banned` feature of security-scanner",
+    ))
+}
+
+/// Collects the callee path of every call in a function body.
+#[cfg(feature = "banned")]
+#[derive(Default)]
+struct Calls {
+    calls: Vec<(Vec<String>, Span)>,
+}
+
+#[cfg(feature = "banned")]
+impl<'ast> Visit<'ast> for Calls {
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(callee) = &*call.func {
+            // Spans can't be joined on stable, so point at the function name
+            let span = callee
+                .path
+                .segments
+                .last()
+                .map_or_else(|| callee.span(), |segment| segment.ident.span());
+            self.calls.push((segments(&callee.path), span));
+        }
+        syn::visit::visit_expr_call(self, call);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        self.calls
+            .push((vec![call.method.to_string()], call.method.span()));
+        syn::visit::visit_expr_method_call(self, call);
+    }
+}
@@ -0,0 +1,169 @@
+//! CVSS v3.1 base scores for `cvss = "..."`.
+//!
+//! The vector is checked and scored while the annotated crate compiles, so a
+//! typo is a compile error rather than a function silently rated `low`. Only
+//! the eight base metrics are accepted; temporal and environmental metrics
+//! depend on the deployment, not on the code.
+
+/// Base metrics in the order of the canonical vector string.
+const METRICS: [&str; 8] = ["AV", "AC", "PR", "UI", "S", "C", "I", "A"];
+
+/// A validated base vector and its score.
+pub struct Cvss {
+    /// Base score in tenths, 0 to 100
+    pub score: u8,
+    /// The vector in canonical metric order with a `CVSS:3.1/` prefix
+    pub vector: String,
+}
+
+/// Parses and scores a base vector such as `AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`,
+/// with or without a `CVSS:3.0/` or `CVSS:3.1/` prefix. The error is the
+/// message for the offending literal.
+pub fn parse_vector(vector: &str) -> Result<Cvss, String> {
+    let (prefix, body) = match vector.split_once('/') {
+        Some((prefix @ ("CVSS:3.0" | "CVSS:3.1"), body)) => (prefix, body),
+        Some((prefix, _)) if prefix.starts_with("CVSS:") => {
+            return Err(format!(
+                "unsupported CVSS version `{}`, expected CVSS:3.0 or CVSS:3.1",
+                prefix
+            ))
+        }
+        _ => ("CVSS:3.1", vector),
+    };
+
+    let mut values = [""; 8];
+    for part in body.split('/') {
+        let (metric, value) = part
+            .split_once(':')
+            .ok_or_else(|| format!("invalid CVSS metric `{}`, expected `NAME:VALUE`", part))?;
+        let index = METRICS
+            .iter()
+            .position(|name| *name == metric)
+            .ok_or_else(|| {
+                format!(
+                    "unknown CVSS base metric `{}`, expected one of: {}",
+                    metric,
+                    METRICS.join(", ")
+                )
+            })?;
+        if !values[index].is_empty() {
+            return Err(format!("CVSS metric `{}` is given more than once", metric));
+        }
+        values[index] = value;
+    }
+    if let Some(index) = values.iter().position(|value| value.is_empty()) {
+        return Err(format!("CVSS vector lacks the `{}` metric", METRICS[index]));
+    }
+
+    let [av, ac, pr, ui, s, c, i, a] = values;
+    let changed = match s {
+        "U" => false,
+        "C" => true,
+        _ => return Err(invalid("S", s)),
+    };
+    let av = match av {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return Err(invalid("AV", av)),
+    };
+    let ac = match ac {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return Err(invalid("AC", ac)),
+    };
+    let pr = match (pr, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return Err(invalid("PR", pr)),
+    };
+    let ui = match ui {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return Err(invalid("UI", ui)),
+    };
+    let impact_of = |metric: &str, value: &str| match value {
+        "H" => Ok(0.56),
+        "L" => Ok(0.22),
+        "N" => Ok(0.0),
+        _ => Err(invalid(metric, value)),
+    };
+    let (c, i, a) = (impact_of("C", c)?, impact_of("I", i)?, impact_of("A", a)?);
+
+    let iss: f64 = 1.0 - (1.0 - c) * (1.0 - i) * (1.0 - a);
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02).powi(15)
+    } else {
+        6.42 * iss
+    };
+    let exploitability = 8.22 * av * ac * pr * ui;
+    let score = if impact <= 0.0 {
+        0
+    } else if changed {
+        round_up((1.08 * (impact + exploitability)).min(10.0))
+    } else {
+        round_up((impact + exploitability).min(10.0))
+    };
+
+    Ok(Cvss {
+        score,
+        vector: format!(
+            "{}/{}",
+            prefix,
+            METRICS
+                .iter()
+                .zip(values)
+                .map(|(metric, value)| format!("{}:{}", metric, value))
+                .collect::<Vec<_>>()
+                .join("/")
+        ),
+    })
+}
+
+/// Parses a numeric score such as `9.8`, returning it in tenths.
+pub fn parse_score(score: &str) -> Result<u8, String> {
+    let invalid = || "invalid CVSS score, expected 0.0 to 10.0 with one decimal".to_string();
+    let (whole, tenths) = score.split_once('.').unwrap_or((score, "0"));
+    if tenths.len() != 1 {
+        return Err(invalid());
+    }
+    let whole: u8 = whole.parse().map_err(|_| invalid())?;
+    let tenths: u8 = tenths.parse().map_err(|_| invalid())?;
+    match whole
+        .checked_mul(10)
+        .and_then(|whole| whole.checked_add(tenths))
+    {
+        Some(score) if score <= 100 => Ok(score),
+        _ => Err(invalid()),
+    }
+}
+
+/// Returns the threat level byte for a score in tenths, using the CVSS
+/// qualitative ratings; scores of 0.0 (none) map to `low`.
+pub fn threat_level(score: u8) -> u8 {
+    match score {
+        90.. => 3,
+        70..=89 => 2,
+        40..=69 => 1,
+        _ => 0,
+    }
+}
+
+fn invalid(metric: &str, value: &str) -> String {
+    format!("invalid value `{}` for CVSS metric `{}`", value, metric)
+}
+
+/// The CVSS v3.1 `Roundup`: the smallest one-decimal number not below
+/// `value`, in tenths, computed on integers to avoid floating-point drift.
+fn round_up(value: f64) -> u8 {
+    let scaled = (value * 100_000.0).round() as u64;
+    if scaled.is_multiple_of(10_000) {
+        (scaled / 10_000) as u8
+    } else {
+        (scaled / 10_000 + 1) as u8
+    }
+}
@@ -0,0 +1,216 @@
+//! Tests generated by `generate_tests`.
+//!
+//! The macro adds a `#[cfg(test)]` module next to the function, holding a
+//! `#[test]` per string parameter and test type that varies the input in
+//! that parameter and passes `"test"` to the others:
+//!
+//! - `sql_injection` calls the function with every payload of
+//!   `security_scanner::payloads::SQL_INJECTION`
+//! - `timing_attack` checks with `security_scanner::timing::assert_constant_time`
+//!   that the function's timing doesn't depend on the input. Timings vary
+//!   with the load of the machine, so these tests are `#[ignore]`d and run
+//!   with `cargo test -- --ignored`
+//!
+//! and, with `race_condition`, one `#[test]` that calls the function with
+//! `"test"` for every parameter from many threads at once, through
+//! `security_scanner::concurrency::stress`.
+//!
+//! The module is named after the function, as in `__security_test_login`,
+//! so the tests show up as `__security_test_login::sql_injection_user`,
+//! `__security_test_login::timing_attack_user` and
+//! `__security_test_login::race_condition`.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{FnArg, Pat, Signature, Type};
+
+use crate::parser::SecurityTestArgs;
+
+/// Bits of `sql_injection`, `race_condition` and `timing_attack` in the
+/// tests bitfield.
+const SQL_INJECTION: u32 = 1 << 0;
+const RACE_CONDITION: u32 = 1 << 1;
+const TIMING_ATTACK: u32 = 1 << 2;
+
+/// Value passed to the parameters not under test.
+const FILLER: &str = "test";
+
+/// How an input is passed to a parameter.
+enum StringParam {
+    Str,
+    String,
+}
+
+/// Builds the generated tests of a free function, nothing when `args`
+/// doesn't ask for them.
+pub fn generated_tests(args: &SecurityTestArgs, sig: &Signature) -> syn::Result<TokenStream> {
+    if !args.generate_tests {
+        return Ok(quote!());
+    }
+    if args.tests & (SQL_INJECTION | RACE_CONDITION | TIMING_ATTACK) == 0 {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "`generate_tests` generates SQL injection, timing and race condition tests, add \
+             `sql_injection`, `timing_attack` or `race_condition`",
+        ));
+    }
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "`generate_tests` is not supported on async functions",
+        ));
+    }
+    if let Some(unsafety) = &sig.unsafety {
+        return Err(syn::Error::new_spanned(
+            unsafety,
+            "`generate_tests` is not supported on unsafe functions",
+        ));
+    }
+    if let Some(param) = sig.generics.type_params().next() {
+        return Err(syn::Error::new_spanned(
+            param,
+            "`generate_tests` is not supported on generic functions",
+        ));
+    }
+    if let Some(param) = sig.generics.const_params().next() {
+        return Err(syn::Error::new_spanned(
+            param,
+            "`generate_tests` is not supported on generic functions",
+        ));
+    }
+
+    let mut params = Vec::new();
+    for (index, input) in sig.inputs.iter().enumerate() {
+        let FnArg::Typed(typed) = input else {
+            return Err(syn::Error::new_spanned(
+                input,
+                "`generate_tests` is not supported on methods",
+            ));
+        };
+        let Some(kind) = string_param(&typed.ty) else {
+            return Err(syn::Error::new_spanned(
+                &typed.ty,
+                "`generate_tests` needs parameters of type `&str` or `String`, which it fills \
+                 with test inputs",
+            ));
+        };
+        let name = match &*typed.pat {
+            Pat::Ident(ident) => ident.ident.unraw().to_string(),
+            _ => format!("arg{}", index),
+        };
+        params.push((name, kind));
+    }
+    // Stress tests alone need no input to vary
+    if params.is_empty() && args.tests & (SQL_INJECTION | TIMING_ATTACK) != 0 {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "`generate_tests` needs a function with a `&str` or `String` parameter to fill \
+             with test inputs",
+        ));
+    }
+
+    let fn_name = &sig.ident;
+    let name = fn_name.unraw().to_string();
+    let module = format_ident!("__security_test_{}", name);
+    let mut tests = Vec::new();
+    for (target, (param, _)) in params.iter().enumerate() {
+        let arguments: Vec<TokenStream> = params
+            .iter()
+            .enumerate()
+            .map(|(index, (_, kind))| {
+                let value = if index == target {
+                    quote!(input)
+                } else {
+                    quote!(#FILLER)
+                };
+                match kind {
+                    StringParam::Str => value,
+                    StringParam::String => quote!(::std::string::ToString::to_string(#value)),
+                }
+            })
+            .collect();
+
+        if args.tests & SQL_INJECTION != 0 {
+            let test = format_ident!("sql_injection_{}", param);
+            tests.push(quote! {
+                #[test]
+                fn #test() {
+                    ::security_scanner::payloads::run(
+                        #name,
+                        #param,
+                        ::security_scanner::payloads::SQL_INJECTION,
+                        |input| {
+                            let _ = super::#fn_name(#(#arguments),*);
+                        },
+                    );
+                }
+            });
+        }
+        if args.tests & TIMING_ATTACK != 0 {
+            let test = format_ident!("timing_attack_{}", param);
+            tests.push(quote! {
+                #[test]
+                #[ignore = "timing test; run with --ignored"]
+                fn #test() {
+                    ::security_scanner::timing::assert_constant_time(|input| {
+                        ::std::hint::black_box(super::#fn_name(#(#arguments),*));
+                    });
+                }
+            });
+        }
+    }
+
+    if args.tests & RACE_CONDITION != 0 {
+        let arguments = params.iter().map(|(_, kind)| match kind {
+            StringParam::Str => quote!(#FILLER),
+            StringParam::String => quote!(::std::string::ToString::to_string(#FILLER)),
+        });
+        tests.push(quote! {
+            #[test]
+            fn race_condition() {
+                ::security_scanner::concurrency::stress(
+                    |_| {
+                        ::std::hint::black_box(super::#fn_name(#(#arguments),*));
+                    },
+                    ::security_scanner::concurrency::THREADS,
+                    ::security_scanner::concurrency::ITERATIONS,
+                );
+            }
+        });
+    }
+
+    Ok(quote! {
+        // Tests requested with `generate_tests`
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod #module {
+            #(#tests)*
+        }
+    })
+}
+
+/// Returns how to pass an input to a parameter of type `ty`, if it takes
+/// strings.
+fn string_param(ty: &Type) -> Option<StringParam> {
+    match ty {
+        Type::Reference(reference) if reference.mutability.is_none() => {
+            is_path(&reference.elem, "str").then_some(StringParam::Str)
+        }
+        ty => is_path(ty, "String").then_some(StringParam::String),
+    }
+}
+
+/// Returns whether `ty` is a path ending in `name`, without generic
+/// arguments.
+fn is_path(ty: &Type, name: &str) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    path.qself.is_none()
+        && path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name && segment.arguments.is_none())
+}
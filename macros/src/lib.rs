@@ -0,0 +1,1143 @@
+//! # Security Scanner Macros
+//!
+//! Procedural macros for the `security-scanner` crate.
+//!
+//! This crate provides the `#[security_test]` attribute macro that embeds security
+//! test metadata directly in functions, and `#[security_program]` for context that
+//! applies to the whole program. Depend on `security-scanner` rather than on
+//! this crate directly; it re-exports the macro alongside the runtime types used to
+//! interpret the embedded metadata.
+
+mod banned;
+mod cvss;
+#[cfg(feature = "generate")]
+mod generate;
+mod params;
+mod parser;
+mod program;
+mod settings;
+mod strategy;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenTree;
+use quote::{quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::{parse_macro_input, ImplItem, Item, ItemFn, ItemImpl, ItemMod};
+
+use banned::BannedCalls;
+use parser::SecurityTestArgs;
+use program::ProgramArgs;
+use strategy::MetadataStrategy;
+
+/// Embeds security test metadata in Rust functions for automated vulnerability scanning.
+///
+/// This attribute macro allows developers to specify what types of security tests
+/// should be performed on a function, along with the threat level.
+///
+/// ## Supported Test Types
+///
+/// - `sql_injection` - Tests for SQL injection vulnerabilities
+/// - `race_condition` - Tests for race condition vulnerabilities
+/// - `timing_attack` - Tests for timing side-channel attacks
+/// - `buffer_overflow` - Tests for buffer overflow vulnerabilities
+/// - `env_injection` - Tests configuration read from environment variables
+///   (overlong values, path-traversal content, unset variables)
+/// - `xss` - Tests for cross-site scripting in rendered output
+/// - `command_injection` - Tests for OS command injection
+/// - `path_traversal` - Tests for path traversal in file paths
+/// - `ssrf` - Tests for server-side request forgery
+/// - `xxe` - Tests for XML external entity injection
+/// - `deserialization` - Tests for insecure deserialization of untrusted data
+/// - `open_redirect` - Tests for redirects to attacker-chosen URLs
+///
+/// ## Environment Variables
+///
+/// Functions that read configuration from the environment can declare the
+/// variables they read with `reads_env("NAME", ...)`. The names are stored in
+/// the record so scanners know which variables to mutate.
+///
+/// ## Fixtures
+///
+/// `fixtures("NAME", ...)` declares the fixtures a function's harness needs
+/// (a database pool, a seeded test user, ...). Runners match these against
+/// their registered fixtures and report unmet requirements. The names are
+/// stored like those of `reads_env`.
+///
+/// ## Input Parameters
+///
+/// The function's parameters are stored as `name: Type` strings, so
+/// scanners know which inputs to fuzz. Types are
+/// simplified by dropping lifetimes: `user: &'a str` is stored as
+/// `user: &str`.
+///
+/// ## Capabilities
+///
+/// `capabilities(...)` declares what a function is allowed to do, so a
+/// dynamic harness can compare declared and observed behavior:
+///
+/// - `net` - Opens network connections
+/// - `fs_read` - Reads from the filesystem
+/// - `fs_write` - Writes to the filesystem
+/// - `spawn` - Spawns processes
+///
+/// An empty `capabilities()` declares that the function does none of these.
+///
+/// ## STRIDE Categories
+///
+/// `stride(...)` ties the annotation to the threats a STRIDE-based threat
+/// model lists for the function:
+///
+/// - `spoofing`
+/// - `tampering`
+/// - `repudiation`
+/// - `information_disclosure`
+/// - `denial_of_service`
+/// - `elevation_of_privilege`
+///
+/// ## ATT&CK Techniques
+///
+/// `attack("T1190", "T1078.004", ...)` tags the function with MITRE ATT&CK
+/// technique IDs (`T` followed by four digits, optionally with a three-digit
+/// sub-technique). The IDs are checked at compile time and stored like the
+/// names of `reads_env`.
+///
+/// ## Custom Test Types
+///
+/// `custom = "tenant_isolation"` requests a test type this crate doesn't
+/// know, for scanner plugins that key off their own names. Names may use
+/// ASCII letters, digits, `_`, `-` and `.`, and must not be a built-in test
+/// type; give `custom` once per name. Each name is stored in the record as
+/// its own entry, which scanners that don't know the plugin skip.
+///
+/// ## Compliance Tags
+///
+/// `cwe = [89, 79]` maps the function to CWE weaknesses by number and
+/// `owasp = "A03:2021"` to OWASP Top 10 categories; both also take a single
+/// value or a bracketed list. OWASP categories are checked for the
+/// `A01:2021` format at compile time. Reports list them as `CWE-89` and
+/// `OWASP-A03:2021` tags, so compliance teams can map annotated functions to
+/// their taxonomy.
+///
+/// ## Threat Levels
+///
+/// - `critical` - Critical security function (authentication, payment, etc.)
+/// - `high` - High-risk function (user data access, admin operations)
+/// - `medium` - Medium-risk function (data processing, business logic)
+/// - `low` - Low-risk function (logging, display, etc.)
+///
+/// Without a threat level the function is `low`; giving two different levels
+/// is an error. Unknown arguments are rejected at compile time too.
+///
+/// ## CVSS Scores
+///
+/// `cvss = "AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"` rates the function with a
+/// CVSS v3.1 base vector instead, optionally prefixed with `CVSS:3.1/`. The
+/// vector is validated and scored at compile time, and the threat level
+/// follows from the score's qualitative rating: 9.0 and up is `critical`,
+/// 7.0 to 8.9 `high`, 4.0 to 6.9 `medium` and lower scores `low`. A known
+/// score can be given as `cvss = 9.8`. The record holds the score and the
+/// vector; a threat level keyword given as well must match the rating.
+///
+/// ## Per-Profile Threat Levels
+///
+/// `threat_level(debug = low, release = critical)` sets a different level per
+/// build profile, for functions that are only reachable in release
+/// deployments. Builds with debug assertions use the `debug` level and others
+/// the `release` level; a plain threat level applies to a profile left out of
+/// the list. The record holds the level of the profile being compiled, so
+/// scanners see the severity of the binary they inspect, along with both
+/// declared levels.
+///
+/// ## Remediation Deadlines
+///
+/// `sla_days = 30` sets how many days a finding in this function may stay open
+/// before reports flag it as overdue, overriding the per-threat-level default
+/// of the reporting policy. The value must be between 1 and 65535.
+///
+/// ## Names
+///
+/// The record stores each function's fully-qualified path, such as
+/// `my_crate::auth::login`, so scanners can tell apart same-named functions in
+/// different modules.
+///
+/// Async and generic functions are recorded once under their name as
+/// written: `async fn fetch` as `fetch` rather than its desugared future, and
+/// `fn handle<T: DeserializeOwned>` as `handle` however many types it is
+/// instantiated with. Methods of generic impl blocks keep the block's type
+/// parameters, as in `Cache<T>::get`.
+///
+/// ## Methods
+///
+/// Statics can't be declared inside an `impl` block, so methods need
+/// `#[security_test]` on the block as well as on each method to record. The
+/// block's attribute takes no arguments; it records the annotated methods as
+/// `Type::method`, or `<Type as Trait>::method` in trait impls, with the
+/// statics placed after the block. A `#[cfg]` on a method also applies to
+/// its record. `anchor` and `generate_tests` aren't available on methods.
+///
+/// ## Modules
+///
+/// `#[security_test_module(...)]` on an inline module records every function
+/// in it with default arguments, which a `#[security_test(...)]` on a
+/// function overrides; see its documentation.
+///
+/// ## Source Locations
+///
+/// The record stores the line and column of the function name and the path
+/// of its file as given by `file!()`, so reports can link to the code.
+/// Cargo passes paths relative to the workspace root.
+///
+/// ## Build Settings
+///
+/// The record also holds the compiler version, the panic strategy and
+/// whether overflow checks and debug assertions were on for the crate, so
+/// reports can flag critical code built without overflow checks.
+///
+/// ## Record Format
+///
+/// Each function gets one variable-length version 2 record in the
+/// `.security_tests` section (`__DATA,__sectests` on macOS, `.sectests` on
+/// Windows, the `security_tests` custom section on WebAssembly), laid out as
+/// described in `security_scanner::format::tlv`. The record is encoded by
+/// `const` code in the annotated crate, since the path and the test context
+/// are only known there.
+///
+/// ## Bare-Metal Targets
+///
+/// The generated code only uses `core`, so `#![no_std]` crates can depend
+/// on `security-scanner` with `default-features = false`. On targets without
+/// an operating system (`target_os = "none"`) records go into the
+/// `.security_tests` ELF section as on Linux. A linker script that discards
+/// unknown sections needs a line such as
+/// `.security_tests (INFO) : { KEEP(*(.security_tests)) }`, which keeps the
+/// section in the ELF file without loading it into flash.
+///
+/// ## Registry
+///
+/// With the `registry` feature of `security-scanner`, each function is also
+/// listed by `security_scanner::registry::iter()`, for programs that inspect
+/// their own annotated functions at runtime. The registry isn't available on
+/// WebAssembly.
+///
+/// ## Banned Calls
+///
+/// A crate can ban APIs in the bodies of its annotated functions with
+/// `banned_calls` in `[package.metadata.security-scanner]` of its
+/// `Cargo.toml`, such as `["std::process::Command", "std::mem::transmute"]`.
+/// Each call raises a warning, or an error with
+/// `banned_calls_policy = "deny"`, and the banned APIs a function calls are
+/// recorded for reports. Calls are matched by the path they are written
+/// with, since imports can't be resolved by a macro: `Command::new` counts
+/// after `use std::process::Command`, and methods match by name.
+/// Checking bodies needs the `banned` feature of `security-scanner`, on by
+/// default.
+///
+/// ## Test Context
+///
+/// Records compiled with `cfg(test)` (unit and integration test binaries) carry
+/// a test-context flag so scanners and reports can include or exclude them.
+/// Test-only code compiled without `cfg(test)`, such as helpers living in a
+/// dev-dependency crate, can opt in with the `test_context` keyword.
+///
+/// ## Optimized Builds
+///
+/// In heavily inlined or LTO builds an annotated function can be folded into
+/// its callers, leaving metadata that points at no code. Two opt-in keywords
+/// keep the function addressable:
+///
+/// - `inline_never` - Adds `#[inline(never)]` to the function
+/// - `anchor` - Emits a `#[used]` function pointer to the function, which keeps
+///   an out-of-line copy in the binary even when call sites are inlined (not
+///   available on async, generic or `impl Trait` functions). Scanners read
+///   the function's address from it, even in stripped binaries
+///
+/// ## Generated Tests
+///
+/// `generate_tests`, with the `generate` feature of `security-scanner` (on
+/// by default), adds unit tests of the function to `cfg(test)` builds,
+/// one per `&str` or `String` parameter and test type, which vary the input
+/// of that parameter and pass `"test"` to the others:
+///
+/// - with `sql_injection`, the function is called with each payload of
+///   `security_scanner::payloads::SQL_INJECTION` (tautologies, stacked
+///   queries, comment and Unicode tricks). A test fails if a call panics;
+///   whatever the function returns, an error included, passes
+/// - with `timing_attack`, `security_scanner::timing::assert_constant_time`
+///   times the function on a fixed and on random inputs and fails when
+///   Welch's t-test tells them apart. Other tests running in parallel skew
+///   the timings, so these tests are ignored unless run with
+///   `cargo test -- --ignored`
+///
+/// With `race_condition` it adds one more test, which passes `"test"` to
+/// every parameter and calls the function from many threads at once with
+/// `security_scanner::concurrency::stress`, failing if any call panics.
+///
+/// The function must take only string parameters, at least one unless
+/// `race_condition` is the only type tested, and can't be async, unsafe
+/// or generic, and the tests need the `std` feature of `security-scanner`.
+///
+/// ## Metadata Strategy
+///
+/// Each crate chooses how metadata is embedded with a `metadata_strategy` key in
+/// `[package.metadata.security-scanner]` of its `Cargo.toml`, or with the
+/// `SECURITY_SCANNER_METADATA_STRATEGY` environment variable:
+///
+/// | Strategy  | Linux / macOS / Windows / WebAssembly / bare metal | Other targets | Miri    |
+/// |-----------|----------------------------------------------------|---------------|---------|
+/// | `auto`    | section statics                                    | nothing       | nothing |
+/// | `section` | section statics                                    | plain statics | statics |
+/// | `none`    | nothing                                            | nothing       | nothing |
+///
+/// `auto` is the default. `none` turns the attribute into a pass-through, which
+/// is useful for crates built with runners that dislike `#[used]` statics.
+///
+/// ## Stripping
+///
+/// Release binaries built for shipping can leave the metadata out entirely
+/// with the `strip` feature of `security-scanner` or
+/// `SECURITY_SCANNER_STRIP=1`, which override the strategy with `none` in
+/// every crate, so the binary doesn't advertise which of its functions are
+/// security-sensitive. Scan builds leave both unset. The arguments are still
+/// validated.
+///
+/// ## Encryption
+///
+/// Binaries that ship with their metadata, so it can be audited in the
+/// field, can keep it from casual inspection instead: with
+/// `SECURITY_SCANNER_METADATA_KEY` set to 64 hex digits, every record is
+/// encrypted with that 256-bit key as the crate compiles, and only the
+/// ciphertext is embedded. `cargo security-scan scan --metadata-key` (or the
+/// same variable) and `security_scanner::extract::extract_with_key` read it
+/// back. Encrypted records are also authenticated: one changed after it
+/// was built doesn't decrypt, and counts with the records the key can't
+/// read. The registry can't read encrypted records and leaves them out.
+///
+/// ## Examples
+///
+/// ```rust
+/// use security_scanner::security_test;
+///
+/// // Basic security testing
+/// #[security_test]
+/// fn process_data(data: &str) -> String {
+///     data.to_string()
+/// }
+///
+/// // Specific vulnerability tests
+/// #[security_test(sql_injection)]
+/// fn query_database(user_input: &str) -> Vec<String> {
+///     // Potentially vulnerable to SQL injection
+///     vec![]
+/// }
+///
+/// // Multiple tests with threat level and compliance tags
+/// #[security_test(sql_injection, timing_attack, cwe = [89, 208], owasp = "A03:2021", critical)]
+/// fn authenticate(username: &str, password: &str) -> bool {
+///     // Critical authentication function
+///     true
+/// }
+///
+/// // Test type of an in-house scanner plugin
+/// #[security_test(custom = "tenant_isolation", high)]
+/// fn load_tenant(tenant_id: u64) -> Option<String> {
+///     None
+/// }
+///
+/// // Methods, recorded as `Session::verify`
+/// struct Session;
+///
+/// #[security_test]
+/// impl Session {
+///     #[security_test(timing_attack, critical)]
+///     fn verify(&self, token: &str) -> bool {
+///         !token.is_empty()
+///     }
+/// }
+///
+/// // Race condition testing
+/// #[security_test(race_condition, high)]
+/// fn transfer_funds(from: u64, to: u64, amount: f64) -> Result<(), String> {
+///     // High-risk financial operation
+///     Ok(())
+/// }
+///
+/// // Environment variable injection testing
+/// #[security_test(env_injection, reads_env("DATABASE_URL"), high)]
+/// fn connect() -> Option<String> {
+///     std::env::var("DATABASE_URL").ok()
+/// }
+///
+/// // Kept out of line in release builds
+/// #[security_test(buffer_overflow, inline_never, anchor, high)]
+/// fn decode_frame(frame: &[u8]) -> Option<u16> {
+///     Some(u16::from_le_bytes([*frame.first()?, *frame.get(1)?]))
+/// }
+///
+/// // Harness fixtures
+/// #[security_test(sql_injection, fixtures("db_pool", "test_user"), high)]
+/// fn delete_account(user_id: u64) -> bool {
+///     true
+/// }
+///
+/// // Declared capabilities
+/// #[security_test(capabilities(fs_read, net), medium)]
+/// fn sync_config(path: &str) -> bool {
+///     // Reads a local file and uploads it
+///     true
+/// }
+///
+/// // Web handler rendering user content and following a return URL
+/// #[security_test(xss, open_redirect, high)]
+/// fn render_comment(body: &str, return_to: &str) -> String {
+///     format!("{}{}", body, return_to)
+/// }
+///
+/// // STRIDE categories from the threat model
+/// #[security_test(timing_attack, stride(spoofing, information_disclosure), critical)]
+/// fn verify_token(token: &str) -> bool {
+///     // Compares the token against the stored value
+///     true
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn security_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Decide whether and where this crate embeds metadata
+    let strategy = match MetadataStrategy::for_current_crate() {
+        Ok(strategy) => strategy,
+        Err(message) => {
+            return syn::Error::new(proc_macro2::Span::call_site(), message)
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    let expanded = match parse_macro_input!(item as Item) {
+        Item::Fn(input_fn) => {
+            let args = parse_macro_input!(attr as SecurityTestArgs);
+            security_fn(&args, input_fn, strategy)
+        }
+        Item::Impl(input_impl) => security_impl(attr.into(), input_impl, strategy),
+        item => Err(syn::Error::new_spanned(
+            item,
+            "`#[security_test]` applies to functions and impl blocks",
+        )),
+    };
+    expanded
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Applies default `#[security_test]` arguments to every function in a module.
+///
+/// Put it on an inline module to record all its functions without annotating
+/// each one; it takes the same arguments as `#[security_test]`, except for
+/// the per-function `inline_never`, `anchor` and `generate_tests`:
+///
+/// ```rust
+/// use security_scanner::security_test_module;
+///
+/// #[security_test_module(sql_injection, reads_env("DATABASE_URL"), high)]
+/// mod handlers {
+///     // Recorded with the module's defaults
+///     pub fn list_orders(customer: &str) -> Vec<String> {
+///         vec![customer.to_string()]
+///     }
+///
+///     // Own tests and threat level, still reading DATABASE_URL
+///     #[security_test(timing_attack, critical)]
+///     pub fn login(user: &str, password: &str) -> bool {
+///         !user.is_empty() && !password.is_empty()
+///     }
+/// }
+/// ```
+///
+/// A `#[security_test(...)]` on a function overrides the defaults setting by
+/// setting: the test types (built-in and `custom`) and the threat level
+/// (with `cvss` and `threat_level(...)`) each count as one setting, and the
+/// keyword options add up. Functions in nested inline modules get the same
+/// defaults, and so do the methods of impl blocks that carry their own
+/// `#[security_test(...)]`; those don't need the attribute on the block.
+/// Functions marked `#[test]` are left alone. The module attribute expands
+/// the nested ones itself, so they don't need to be imported.
+#[proc_macro_attribute]
+pub fn security_test_module(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let strategy = match MetadataStrategy::for_current_crate() {
+        Ok(strategy) => strategy,
+        Err(message) => {
+            return syn::Error::new(proc_macro2::Span::call_site(), message)
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    let defaults = parse_macro_input!(attr as SecurityTestArgs);
+    let module = parse_macro_input!(item as ItemMod);
+    security_module(&defaults, module, strategy)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Records context that applies to the whole program, such as who can reach
+/// it.
+///
+/// Put it on `main`, or on any free function of a binary or library that
+/// stands for its entry point; scanners then report the context next to the
+/// program's functions instead of each function repeating it:
+///
+/// ```rust
+/// use security_scanner::security_program;
+///
+/// #[security_program(exposure = internet, data_class(pii, credentials))]
+/// fn main() {
+///     // Serves the public API
+/// }
+/// ```
+///
+/// `exposure` is one of `airgapped`, `internal`, `partner` and `internet`;
+/// `data_class(...)` takes `pii`, `phi`, `pci` and `credentials`. A program
+/// should have one annotation; scanners use the first one they find.
+#[proc_macro_attribute]
+pub fn security_program(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let strategy = match MetadataStrategy::for_current_crate() {
+        Ok(strategy) => strategy,
+        Err(message) => {
+            return syn::Error::new(proc_macro2::Span::call_site(), message)
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    let args = parse_macro_input!(attr as ProgramArgs);
+    let input_fn = parse_macro_input!(item as ItemFn);
+    security_program_fn(&args, input_fn, strategy)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Expands `#[security_test]` on a free function.
+fn security_fn(
+    args: &SecurityTestArgs,
+    mut input_fn: ItemFn,
+    strategy: MetadataStrategy,
+) -> syn::Result<proc_macro2::TokenStream> {
+    // The statics can't live in an impl block, and only the block knows the
+    // type's name
+    if let Some(receiver) = input_fn.sig.receiver() {
+        return Err(syn::Error::new_spanned(
+            receiver,
+            "methods are recorded through their impl block, add `#[security_test]` to the \
+             enclosing `impl` as well",
+        ));
+    }
+    let banned = banned_calls()?.check(&input_fn.block)?;
+    let warnings = &banned.warnings;
+    // Tests aren't metadata, so stripped builds keep them
+    let generated_tests = generated_tests(args, &input_fn.sig)?;
+    if strategy == MetadataStrategy::None {
+        return Ok(quote!(#input_fn #warnings #generated_tests));
+    }
+
+    let fn_name = &input_fn.sig.ident;
+    // `r#match` is stored and referred to by scanners as `match`
+    let name = fn_name.unraw().to_string();
+    let anchor = quote!(#fn_name);
+    let statics = function_statics(
+        args,
+        &mut input_fn.attrs,
+        &input_fn.sig,
+        &name,
+        &target_gate(strategy),
+        Some(anchor),
+        &banned.calls,
+    )?;
+
+    Ok(quote! {
+        // Original function, unchanged apart from an optional #[inline(never)]
+        #input_fn
+        #warnings
+        #generated_tests
+
+        // Keep the statics out of the caller's namespace
+        const _: () = {
+            #statics
+        };
+    })
+}
+
+/// Expands `#[security_program]`, recording the program's context in a
+/// record of its own. Programs aren't functions under test, so the record
+/// has no registry entry.
+fn security_program_fn(
+    args: &ProgramArgs,
+    input_fn: ItemFn,
+    strategy: MetadataStrategy,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if let Some(receiver) = input_fn.sig.receiver() {
+        return Err(syn::Error::new_spanned(
+            receiver,
+            "`#[security_program]` applies to the program's entry point, not to methods",
+        ));
+    }
+    if strategy == MetadataStrategy::None {
+        return Ok(quote!(#input_fn));
+    }
+
+    let name = input_fn.sig.ident.unraw().to_string();
+    let target_gate = target_gate(strategy);
+    let exposure = match args.exposure {
+        Some(exposure) => quote!(Some(#exposure)),
+        None => quote!(None),
+    };
+    let data_classes = args.data_classes;
+    let location = input_fn.sig.ident.span().unwrap();
+    let line = location.line() as u32;
+    let column = location.column() as u32;
+    let (record_type, record_bytes) = record_bytes(&quote::format_ident!("__SEC_PROGRAM"))?;
+
+    Ok(quote! {
+        #input_fn
+
+        const _: () = {
+            #target_gate
+            const __SEC_PROGRAM: ::security_scanner::format::tlv::Program<'static> =
+                ::security_scanner::format::tlv::Program {
+                    path: concat!(module_path!(), "::", #name),
+                    exposure: #exposure,
+                    data_classes: #data_classes,
+                    line: #line,
+                    column: #column,
+                    file: file!(),
+                };
+
+            // Shares the section with the function records
+            #target_gate
+            #[cfg_attr(target_os = "linux", link_section = ".security_tests")]
+            #[cfg_attr(target_os = "macos", link_section = "__DATA,__sectests")]
+            #[cfg_attr(target_os = "windows", link_section = ".sectests")]
+            #[cfg_attr(target_family = "wasm", link_section = "security_tests")]
+            #[cfg_attr(
+                all(target_os = "none", not(target_family = "wasm")),
+                link_section = ".security_tests"
+            )]
+            #[used]
+            static __SEC_PROGRAM_RECORD: #record_type = #record_bytes;
+        };
+    })
+}
+
+/// Expands `#[security_test]` on an impl block, recording each method that
+/// carries its own `#[security_test(...)]` as `Type::method`, or
+/// `<Type as Trait>::method` in trait impls. Associated items can't be
+/// statics, so the statics follow the impl block.
+fn security_impl(
+    attr: proc_macro2::TokenStream,
+    input_impl: ItemImpl,
+    strategy: MetadataStrategy,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if !attr.is_empty() {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "`#[security_test]` on an impl block takes no arguments, give them to the methods",
+        ));
+    }
+    impl_methods(input_impl, None, strategy)
+}
+
+/// Records the annotated methods of an impl block, filling in `defaults`
+/// when the block is inside a `#[security_test_module]`.
+fn impl_methods(
+    mut input_impl: ItemImpl,
+    defaults: Option<&SecurityTestArgs>,
+    strategy: MetadataStrategy,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let self_ty = params::type_string(&input_impl.self_ty);
+    let owner = match &input_impl.trait_ {
+        Some((_, path, _)) => format!("<{} as {}>", self_ty, params::path_string(path)),
+        None => self_ty,
+    };
+    let target_gate = target_gate(strategy);
+    let banned_calls = banned_calls()?;
+
+    let mut hoisted = Vec::new();
+    for item in &mut input_impl.items {
+        let ImplItem::Fn(method) = item else {
+            continue;
+        };
+        let Some(index) = method.attrs.iter().position(is_security_test) else {
+            continue;
+        };
+        let attr = method.attrs.remove(index);
+        let banned = banned_calls.check(&method.block)?;
+        hoisted.push(banned.warnings);
+
+        // Validated even when nothing is emitted
+        let mut args = nested_args(&attr)?;
+        if let Some(defaults) = defaults {
+            args.inherit(defaults);
+        }
+        if args.generate_tests {
+            return Err(syn::Error::new_spanned(
+                &method.sig.ident,
+                "`generate_tests` is not supported on methods",
+            ));
+        }
+        if strategy == MetadataStrategy::None {
+            continue;
+        }
+
+        let name = format!("{}::{}", owner, method.sig.ident.unraw());
+        let statics = function_statics(
+            &args,
+            &mut method.attrs,
+            &method.sig,
+            &name,
+            &target_gate,
+            None,
+            &banned.calls,
+        )?;
+        // A method compiled out takes its record with it
+        let cfgs = method
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("cfg"));
+        hoisted.push(quote! {
+            #(#cfgs)*
+            const _: () = {
+                #statics
+            };
+        });
+    }
+
+    Ok(quote! {
+        #input_impl
+
+        #(#hoisted)*
+    })
+}
+
+/// Returns whether an attribute is a nested `#[security_test]`, with or
+/// without a path.
+fn is_security_test(attr: &syn::Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "security_test")
+}
+
+/// Removes and returns the `#[security_test]` among `attrs`, if any.
+fn take_security_test(attrs: &mut Vec<syn::Attribute>) -> Option<syn::Attribute> {
+    let index = attrs.iter().position(is_security_test)?;
+    Some(attrs.remove(index))
+}
+
+/// Parses the arguments of a nested `#[security_test]`, which may have none.
+fn nested_args(attr: &syn::Attribute) -> syn::Result<SecurityTestArgs> {
+    match &attr.meta {
+        syn::Meta::Path(_) => Ok(SecurityTestArgs::default()),
+        _ => attr.parse_args(),
+    }
+}
+
+/// Applies defaults to every function of a module, as [`security_test_module`].
+fn security_module(
+    defaults: &SecurityTestArgs,
+    mut module: ItemMod,
+    strategy: MetadataStrategy,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if defaults.inline_never || defaults.anchor || defaults.generate_tests {
+        return Err(syn::Error::new_spanned(
+            &module.ident,
+            "`inline_never`, `anchor` and `generate_tests` are per-function options, give \
+             them to the functions",
+        ));
+    }
+    let Some((_, items)) = &mut module.content else {
+        return Err(syn::Error::new_spanned(
+            &module,
+            "`#[security_test_module]` needs the module's body, as in `mod name { ... }`",
+        ));
+    };
+
+    for item in items.iter_mut() {
+        let placeholder = Item::Verbatim(proc_macro2::TokenStream::new());
+        *item = match std::mem::replace(item, placeholder) {
+            Item::Fn(mut input_fn) => {
+                // Tests run the code, they aren't the code under test
+                let is_test = input_fn.attrs.iter().any(|attr| {
+                    attr.path()
+                        .segments
+                        .last()
+                        .is_some_and(|segment| segment.ident == "test")
+                });
+                if is_test {
+                    Item::Fn(input_fn)
+                } else {
+                    let mut args = match take_security_test(&mut input_fn.attrs) {
+                        Some(attr) => nested_args(&attr)?,
+                        None => SecurityTestArgs::default(),
+                    };
+                    args.inherit(defaults);
+                    Item::Verbatim(security_fn(&args, input_fn, strategy)?)
+                }
+            }
+            Item::Impl(mut input_impl) => {
+                if let Some(attr) = take_security_test(&mut input_impl.attrs) {
+                    if !matches!(attr.meta, syn::Meta::Path(_)) {
+                        return Err(syn::Error::new_spanned(
+                            attr,
+                            "`#[security_test]` on an impl block takes no arguments, give \
+                             them to the methods",
+                        ));
+                    }
+                }
+                Item::Verbatim(impl_methods(input_impl, Some(defaults), strategy)?)
+            }
+            Item::Mod(nested) if nested.content.is_some() => {
+                Item::Verbatim(security_module(defaults, nested, strategy)?)
+            }
+            item => item,
+        };
+    }
+
+    Ok(quote!(#module))
+}
+
+/// Reads the crate's banned calls, as a compile error if they are invalid.
+fn banned_calls() -> syn::Result<BannedCalls> {
+    BannedCalls::for_current_crate()
+        .map_err(|message| syn::Error::new(proc_macro2::Span::call_site(), message))
+}
+
+/// Builds the tests asked for with `generate_tests`.
+#[cfg(feature = "generate")]
+fn generated_tests(
+    args: &SecurityTestArgs,
+    sig: &syn::Signature,
+) -> syn::Result<proc_macro2::TokenStream> {
+    generate::generated_tests(args, sig)
+}
+
+#[cfg(not(feature = "generate"))]
+fn generated_tests(
+    args: &SecurityTestArgs,
+    sig: &syn::Signature,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if !args.generate_tests {
+        return Ok(quote!());
+    }
+    Err(syn::Error::new_spanned(
+        &sig.ident,
+        "`generate_tests` needs the `generate` feature of security-scanner",
+    ))
+}
+
+/// Returns the type and value of the static embedding the encoded `record`,
+/// a `const` record, encrypting it when `SECURITY_SCANNER_METADATA_KEY` is
+/// set. The key is only used in const context, so it doesn't end up in the
+/// binary.
+fn record_bytes(
+    record: &syn::Ident,
+) -> syn::Result<(proc_macro2::TokenStream, proc_macro2::TokenStream)> {
+    let key = strategy::metadata_key()
+        .map_err(|message| syn::Error::new(proc_macro2::Span::call_site(), message))?;
+    Ok(match key {
+        Some(key) => (
+            quote!([u8; ::security_scanner::format::tlv::encrypted_len(#record.encoded_len())]),
+            quote! {
+                ::security_scanner::format::tlv::encrypt(
+                    &#record.encode::<{ #record.encoded_len() }>(),
+                    &[#(#key),*],
+                )
+            },
+        ),
+        None => (
+            quote!([u8; #record.encoded_len()]),
+            quote!(#record.encode()),
+        ),
+    })
+}
+
+/// Returns the `#[cfg]` restricting section statics to supported targets.
+fn target_gate(strategy: MetadataStrategy) -> proc_macro2::TokenStream {
+    match strategy {
+        MetadataStrategy::None | MetadataStrategy::Section => quote!(),
+        MetadataStrategy::Auto => quote! {
+            #[cfg(all(
+                not(miri),
+                any(
+                    target_os = "linux",
+                    target_os = "macos",
+                    target_os = "windows",
+                    target_family = "wasm",
+                    target_os = "none"
+                )
+            ))]
+        },
+    }
+}
+
+/// Builds the statics recording one function named `name`, relative to the
+/// module, adding `#[inline(never)]` to `attrs` if requested. `anchor` is
+/// the path of the function for `anchor`, `None` for methods, and
+/// `banned_calls` the banned APIs its body calls.
+fn function_statics(
+    args: &SecurityTestArgs,
+    attrs: &mut Vec<syn::Attribute>,
+    sig: &syn::Signature,
+    name: &str,
+    target_gate: &proc_macro2::TokenStream,
+    anchor: Option<proc_macro2::TokenStream>,
+    banned_calls: &[String],
+) -> syn::Result<proc_macro2::TokenStream> {
+    // Generate unique variable names for this function. Hashing the exact
+    // name keeps `foo` and `FOO` apart, works for raw and non-ASCII
+    // identifiers, and makes clashes with user-defined items implausible
+    let name_hash = fnv1a(name.as_bytes());
+    let metadata_var_name = quote::format_ident!("__SEC_TEST_{:016X}", name_hash);
+
+    let record_var_name = quote::format_ident!("__SEC_RECORD_{:016X}", name_hash);
+
+    let anchor_var_name = quote::format_ident!("__SEC_ANCHOR_{:016X}", name_hash);
+
+    let entry_var_name = quote::format_ident!("__SEC_ENTRY_{:016X}", name_hash);
+
+    if args.inline_never {
+        if let Some(existing) = attrs.iter().find(|attr| attr.path().is_ident("inline")) {
+            return Err(syn::Error::new_spanned(
+                existing,
+                "`inline_never` conflicts with this #[inline] attribute",
+            ));
+        }
+        attrs.push(syn::parse_quote!(#[inline(never)]));
+    }
+
+    let code = if args.anchor {
+        let Some(fn_name) = anchor else {
+            return Err(syn::Error::new_spanned(
+                &sig.ident,
+                "`anchor` is not supported on methods",
+            ));
+        };
+        let fn_pointer = anchor_fn_type(sig)?;
+        Some(quote!(#fn_name as #fn_pointer as *const ()))
+    } else {
+        None
+    };
+    let anchor_static = match &code {
+        Some(code) => quote! {
+            // Pointer to the function, keeping an out-of-line copy alive and
+            // telling scanners where its code is; custom sections can't hold
+            // pointers on WebAssembly
+            #target_gate
+            #[cfg_attr(target_os = "linux", link_section = ".security_addrs")]
+            #[cfg_attr(target_os = "macos", link_section = "__DATA,__secaddrs")]
+            #[cfg_attr(target_os = "windows", link_section = ".secaddr")]
+            #[cfg_attr(
+                all(target_os = "none", not(target_family = "wasm")),
+                link_section = ".security_addrs"
+            )]
+            #[used]
+            static #anchor_var_name: ::security_scanner::format::tlv::FunctionAddress =
+                ::security_scanner::format::tlv::FunctionAddress {
+                    record: #metadata_var_name.as_ptr(),
+                    code: #code,
+                };
+        },
+        None => quote!(),
+    };
+
+    let tests = args.tests;
+    // The level of the profile being compiled, with either mapping recorded
+    let (threat_level, profile_threat_levels) = match args.profile_threat_levels {
+        Some([debug, release]) => (
+            quote!(if cfg!(debug_assertions) { #debug } else { #release }),
+            quote!(Some([#debug, #release])),
+        ),
+        None => {
+            let level = args.threat_level;
+            (quote!(#level), quote!(None))
+        }
+    };
+    let capabilities = args.capabilities;
+    let stride = args.stride;
+    let sla_days = args.sla_days;
+
+    // Position of the function name
+    let location = sig.ident.span().unwrap();
+    let line = location.line() as u32;
+    let column = location.column() as u32;
+
+    let test_context = if args.test_context {
+        quote!(true)
+    } else {
+        quote!(cfg!(test))
+    };
+
+    let env_vars = &args.env_vars;
+    let fixtures = &args.fixtures;
+    let attack_techniques = &args.attack_techniques;
+    let custom_tests = &args.custom_tests;
+    let cwe = &args.cwe;
+    let cvss_score = match args.cvss_score {
+        Some(score) => quote!(Some(#score)),
+        None => quote!(None),
+    };
+    let cvss_vector = args.cvss_vector.as_deref().unwrap_or_default();
+    let owasp = &args.owasp;
+    let input_params = params::input_params(sig);
+    let build_settings = settings::build_settings();
+    let (record_type, record_bytes) = record_bytes(&record_var_name)?;
+
+    let registry_code = code.unwrap_or_else(|| quote!(::core::ptr::null()));
+    // Entry in security_scanner::registry, pointing at the record above,
+    // when that crate has the `registry` feature
+    let registry_entry = quote! {
+        ::security_scanner::__registry_entry! {
+            #target_gate
+            static #entry_var_name = {
+                record: &#metadata_var_name,
+                code: #registry_code,
+            };
+        }
+    };
+
+    Ok(quote! {
+        #anchor_static
+
+        // The function's metadata, encoded in const context because the
+        // path and the test context are only known in the annotated crate
+        #target_gate
+        const #record_var_name: ::security_scanner::format::tlv::Record<'static> =
+            ::security_scanner::format::tlv::Record {
+                path: concat!(module_path!(), "::", #name),
+                tests: #tests,
+                threat_level: #threat_level,
+                profile_threat_levels: #profile_threat_levels,
+                capabilities: #capabilities,
+                stride: #stride,
+                sla_days: #sla_days,
+                test_context: #test_context,
+                line: #line,
+                column: #column,
+                file: file!(),
+                reads_env: &[#(#env_vars),*],
+                fixtures: &[#(#fixtures),*],
+                attack_techniques: &[#(#attack_techniques),*],
+                input_params: &[#(#input_params),*],
+                custom_tests: &[#(#custom_tests),*],
+                cwe: &[#(#cwe),*],
+                cvss_score: #cvss_score,
+                cvss_vector: #cvss_vector,
+                owasp: &[#(#owasp),*],
+                build: #build_settings,
+                banned_calls: &[#(#banned_calls),*],
+                instance: "",
+            };
+
+        // Embed the sealed record in the metadata section
+        #target_gate
+        #[cfg_attr(target_os = "linux", link_section = ".security_tests")]
+        #[cfg_attr(target_os = "macos", link_section = "__DATA,__sectests")]
+        #[cfg_attr(target_os = "windows", link_section = ".sectests")]
+        #[cfg_attr(target_family = "wasm", link_section = "security_tests")]
+        #[cfg_attr(
+            all(target_os = "none", not(target_family = "wasm")),
+            link_section = ".security_tests"
+        )]
+        #[used]
+        static #metadata_var_name: #record_type = #record_bytes;
+
+        #registry_entry
+    })
+}
+
+/// 64-bit FNV-1a hash, used to derive generated identifiers.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Builds the function pointer type matching a signature, for `anchor`.
+fn anchor_fn_type(sig: &syn::Signature) -> syn::Result<proc_macro2::TokenStream> {
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "`anchor` is not supported on async functions",
+        ));
+    }
+    if let Some(param) = sig.generics.type_params().next() {
+        return Err(syn::Error::new_spanned(
+            param,
+            "`anchor` is not supported on generic functions",
+        ));
+    }
+    if let Some(param) = sig.generics.const_params().next() {
+        return Err(syn::Error::new_spanned(
+            param,
+            "`anchor` is not supported on generic functions",
+        ));
+    }
+    if let Some(variadic) = &sig.variadic {
+        return Err(syn::Error::new_spanned(
+            variadic,
+            "`anchor` is not supported on variadic functions",
+        ));
+    }
+
+    let mut inputs = Vec::new();
+    for input in &sig.inputs {
+        match input {
+            syn::FnArg::Typed(typed) => {
+                if contains_impl_trait(typed.ty.to_token_stream()) {
+                    return Err(syn::Error::new_spanned(
+                        &typed.ty,
+                        "`anchor` is not supported with `impl Trait` arguments",
+                    ));
+                }
+                inputs.push(typed.ty.clone());
+            }
+            syn::FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "`anchor` is not supported on methods",
+                ))
+            }
+        }
+    }
+
+    let output = &sig.output;
+    if contains_impl_trait(output.to_token_stream()) {
+        return Err(syn::Error::new_spanned(
+            output,
+            "`anchor` is not supported with an `impl Trait` return type",
+        ));
+    }
+
+    let lifetimes: Vec<_> = sig
+        .generics
+        .lifetimes()
+        .map(|param| &param.lifetime)
+        .collect();
+    let for_lifetimes = if lifetimes.is_empty() {
+        quote!()
+    } else {
+        quote!(for<#(#lifetimes),*>)
+    };
+    let unsafety = &sig.unsafety;
+    let abi = &sig.abi;
+
+    Ok(quote!(#for_lifetimes #unsafety #abi fn(#(#inputs),*) #output))
+}
+
+/// Returns whether a type mentions `impl Trait` anywhere.
+fn contains_impl_trait(tokens: proc_macro2::TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == "impl",
+        TokenTree::Group(group) => contains_impl_trait(group.stream()),
+        _ => false,
+    })
+}
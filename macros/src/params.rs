@@ -0,0 +1,112 @@
+//! Simplified parameter lists of annotated functions, for `input_params`.
+//!
+//! Each parameter is rendered as `name: Type`, with lifetimes removed and
+//! tokens joined the way rustfmt would print them, so `user: &'a str` becomes
+//! `user: &str` and `query: Option<&'static [u8]>` becomes
+//! `query: Option<&[u8]>`. Patterns other than a plain identifier are kept
+//! as written, such as `(x, y): (u32, u32)`; receivers become `self: &Self`.
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::visit_mut::{self, VisitMut};
+use syn::{FnArg, GenericArgument, Pat, PathArguments, Signature, Type, TypeParamBound};
+
+/// Renders every parameter of `sig` as `name: Type`.
+pub fn input_params(sig: &Signature) -> Vec<String> {
+    sig.inputs
+        .iter()
+        .map(|input| match input {
+            FnArg::Receiver(receiver) => format!("self: {}", type_string(&receiver.ty)),
+            FnArg::Typed(typed) => {
+                let name = match &*typed.pat {
+                    // `mut user` and `ref user` are still called `user`
+                    Pat::Ident(ident) => ident.ident.to_string(),
+                    pat => compact(pat.to_token_stream()),
+                };
+                format!("{}: {}", name, type_string(&typed.ty))
+            }
+        })
+        .collect()
+}
+
+/// Renders a type without lifetimes, as in a parameter.
+pub fn type_string(ty: &Type) -> String {
+    let mut ty = ty.clone();
+    StripLifetimes.visit_type_mut(&mut ty);
+    compact(ty.to_token_stream())
+}
+
+/// Renders a path, such as the trait of an impl block, without lifetimes.
+pub fn path_string(path: &syn::Path) -> String {
+    let mut path = path.clone();
+    StripLifetimes.visit_path_mut(&mut path);
+    compact(path.to_token_stream())
+}
+
+/// Removes lifetimes, which say nothing about what to feed a function.
+struct StripLifetimes;
+
+impl VisitMut for StripLifetimes {
+    fn visit_type_reference_mut(&mut self, reference: &mut syn::TypeReference) {
+        reference.lifetime = None;
+        visit_mut::visit_type_reference_mut(self, reference);
+    }
+
+    fn visit_path_arguments_mut(&mut self, arguments: &mut PathArguments) {
+        if let PathArguments::AngleBracketed(angle) = arguments {
+            let args = std::mem::take(&mut angle.args);
+            angle.args = args
+                .into_iter()
+                .filter(|arg| !matches!(arg, GenericArgument::Lifetime(_)))
+                .collect();
+            if angle.args.is_empty() {
+                *arguments = PathArguments::None;
+            }
+        }
+        visit_mut::visit_path_arguments_mut(self, arguments);
+    }
+
+    fn visit_type_trait_object_mut(&mut self, object: &mut syn::TypeTraitObject) {
+        strip_lifetime_bounds(&mut object.bounds);
+        visit_mut::visit_type_trait_object_mut(self, object);
+    }
+
+    fn visit_type_impl_trait_mut(&mut self, imp: &mut syn::TypeImplTrait) {
+        strip_lifetime_bounds(&mut imp.bounds);
+        visit_mut::visit_type_impl_trait_mut(self, imp);
+    }
+}
+
+fn strip_lifetime_bounds(bounds: &mut syn::punctuated::Punctuated<TypeParamBound, syn::Token![+]>) {
+    let kept = std::mem::take(bounds);
+    *bounds = kept
+        .into_iter()
+        .filter(|bound| !matches!(bound, TypeParamBound::Lifetime(_)))
+        .collect();
+}
+
+/// Joins tokens, keeping a space only between words, after `,`, `;`, `mut`,
+/// `dyn` and `impl`, and around `+`, `=` and `->`.
+fn compact(tokens: TokenStream) -> String {
+    let rendered = tokens.to_string();
+    let mut out = String::with_capacity(rendered.len());
+    let mut previous: Option<&str> = None;
+    for token in rendered.split_whitespace() {
+        if let Some(previous) = previous {
+            let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+            let spaced = |token: &str| matches!(token, "+" | "=" | "->");
+            if (word(previous.chars().last()) && word(token.chars().next()))
+                || previous.ends_with(',')
+                || previous.ends_with(';')
+                || matches!(previous, "mut" | "dyn" | "impl")
+                || spaced(previous)
+                || spaced(token)
+            {
+                out.push(' ');
+            }
+        }
+        out.push_str(token);
+        previous = Some(token);
+    }
+    out
+}
@@ -0,0 +1,586 @@
+//! Parsing of `#[security_test(...)]` arguments.
+//!
+//! Each comma-separated argument is one of:
+//!
+//! - a bare keyword: a test type, a threat level, or one of the
+//!   `inline_never`, `anchor`, `test_context` and `generate_tests` options
+//! - a list: `reads_env(...)`, `fixtures(...)` and `attack(...)` take string
+//!   literals, `capabilities(...)` and `stride(...)` take identifiers, and
+//!   `threat_level(...)` takes `profile = level` pairs
+//! - an assignment: `sla_days = N`, `cvss = "AV:N/..."` or `cvss = 9.8`,
+//!   `cwe = N` or `cwe = [N, ...]`, `owasp = "A03:2021"` or
+//!   `owasp = ["...", ...]`, or `custom = "name"`, which may be repeated
+//!
+//! Anything else is rejected with an error pointing at the offending token.
+
+use proc_macro2::Span;
+use security_scanner_format::TEST_TYPES;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{bracketed, parenthesized, Ident, Lit, LitInt, LitStr, Token};
+
+use crate::cvss;
+
+/// Threat level keywords, indexed by their record byte.
+const THREAT_LEVELS: &[&str] = &["low", "medium", "high", "critical"];
+
+/// Build profiles accepted by `threat_level(...)`.
+const PROFILES: &[&str] = &["debug", "release"];
+
+/// Capability names and their bits in the capability byte.
+const CAPABILITIES: &[(&str, u8)] = &[
+    ("net", 0x01),
+    ("fs_read", 0x02),
+    ("fs_write", 0x04),
+    ("spawn", 0x08),
+];
+
+/// STRIDE category names, one bit each in the order of the acronym.
+const STRIDE: &[&str] = &[
+    "spoofing",
+    "tampering",
+    "repudiation",
+    "information_disclosure",
+    "denial_of_service",
+    "elevation_of_privilege",
+];
+
+/// Set when `capabilities(...)` was given at all, even empty.
+const CAPABILITIES_DECLARED: u8 = 0x80;
+
+/// The validated arguments of one `#[security_test(...)]`.
+#[derive(Default)]
+pub struct SecurityTestArgs {
+    /// Bit per requested test type, indexed like [`TEST_TYPES`]
+    pub tests: u32,
+    /// Record byte of the threat level, low (0) when none was given
+    pub threat_level: u8,
+    /// Record bytes of the debug and release threat levels, when
+    /// `threat_level(...)` was given
+    pub profile_threat_levels: Option<[u8; 2]>,
+    pub env_vars: Vec<String>,
+    pub fixtures: Vec<String>,
+    /// Capability byte, 0 when `capabilities(...)` wasn't given
+    pub capabilities: u8,
+    pub stride: u8,
+    pub attack_techniques: Vec<String>,
+    /// Remediation deadline in days, 0 when unset
+    pub sla_days: u16,
+    /// CVSS base score in tenths, when `cvss = ...` was given
+    pub cvss_score: Option<u8>,
+    /// Canonical CVSS vector, when `cvss = "..."` gave one
+    pub cvss_vector: Option<String>,
+    /// Names given with `custom = "..."`, in order
+    pub custom_tests: Vec<String>,
+    /// CWE IDs given with `cwe = ...`
+    pub cwe: Vec<u32>,
+    /// OWASP Top 10 categories given with `owasp = ...`
+    pub owasp: Vec<String>,
+    pub inline_never: bool,
+    pub anchor: bool,
+    pub test_context: bool,
+    /// Whether `generate_tests` asked for payload tests of the function
+    pub generate_tests: bool,
+    /// Whether a threat level, `cvss` or `threat_level(...)` was given
+    pub rated: bool,
+}
+
+impl SecurityTestArgs {
+    /// Fills in the settings this function leaves out from the defaults of
+    /// `#[security_test_module(...)]`. Test types (built-in and custom) and
+    /// the threat level with its CVSS score and per-profile levels are each
+    /// taken as a whole; every other setting on its own. The keyword options
+    /// add up.
+    pub fn inherit(&mut self, defaults: &SecurityTestArgs) {
+        if self.tests == 0 && self.custom_tests.is_empty() {
+            self.tests = defaults.tests;
+            self.custom_tests = defaults.custom_tests.clone();
+        }
+        if !self.rated {
+            self.threat_level = defaults.threat_level;
+            self.profile_threat_levels = defaults.profile_threat_levels;
+            self.cvss_score = defaults.cvss_score;
+            self.cvss_vector = defaults.cvss_vector.clone();
+            self.rated = defaults.rated;
+        }
+        let inherit_list = |own: &mut Vec<String>, default: &Vec<String>| {
+            if own.is_empty() {
+                own.clone_from(default);
+            }
+        };
+        inherit_list(&mut self.env_vars, &defaults.env_vars);
+        inherit_list(&mut self.fixtures, &defaults.fixtures);
+        inherit_list(&mut self.attack_techniques, &defaults.attack_techniques);
+        inherit_list(&mut self.owasp, &defaults.owasp);
+        if self.cwe.is_empty() {
+            self.cwe.clone_from(&defaults.cwe);
+        }
+        if self.capabilities == 0 {
+            self.capabilities = defaults.capabilities;
+        }
+        if self.stride == 0 {
+            self.stride = defaults.stride;
+        }
+        if self.sla_days == 0 {
+            self.sla_days = defaults.sla_days;
+        }
+        self.test_context |= defaults.test_context;
+    }
+}
+
+impl Parse for SecurityTestArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = SecurityTestArgs::default();
+        let mut threat_level: Option<Ident> = None;
+        let mut sla_days_given = false;
+        let mut cwe_given = false;
+        let mut owasp_given = false;
+        let mut cvss_span: Option<Span> = None;
+        let mut profile_levels: Option<(Ident, [Option<u8>; 2])> = None;
+
+        while !input.is_empty() {
+            let name = input.call(Ident::parse_any)?;
+            let key = name.to_string();
+
+            if input.peek(syn::token::Paren) {
+                let content;
+                parenthesized!(content in input);
+                match key.as_str() {
+                    "reads_env" => args.env_vars.extend(string_list(&content)?),
+                    "fixtures" => args.fixtures.extend(string_list(&content)?),
+                    "attack" => args.attack_techniques.extend(attack_techniques(&content)?),
+                    "capabilities" => {
+                        args.capabilities |= CAPABILITIES_DECLARED | capabilities(&content)?
+                    }
+                    "stride" => args.stride |= stride(&content)?,
+                    "threat_level" => {
+                        if profile_levels.is_some() {
+                            return Err(syn::Error::new(
+                                name.span(),
+                                "`threat_level(...)` is given more than once",
+                            ));
+                        }
+                        profile_levels = Some((name, profile_threat_levels(&content)?));
+                    }
+                    _ => {
+                        return Err(syn::Error::new(
+                            name.span(),
+                            format!(
+                                "unknown argument list `{}(...)`, expected one of: reads_env, \
+                                 fixtures, attack, capabilities, stride, threat_level",
+                                key
+                            ),
+                        ))
+                    }
+                }
+            } else if input.peek(Token![=]) {
+                input.parse::<Token![=]>()?;
+                match key.as_str() {
+                    "sla_days" => {
+                        if sla_days_given {
+                            return Err(syn::Error::new(
+                                name.span(),
+                                "`sla_days` is given more than once",
+                            ));
+                        }
+                        sla_days_given = true;
+                        args.sla_days = sla(&input.parse()?)?;
+                    }
+                    "cvss" => {
+                        if cvss_span.is_some() {
+                            return Err(syn::Error::new(
+                                name.span(),
+                                "`cvss` is given more than once",
+                            ));
+                        }
+                        let value: Lit = input.parse()?;
+                        let parsed =
+                            match &value {
+                                Lit::Str(vector) => cvss::parse_vector(&vector.value())
+                                    .map(|cvss| (cvss.score, Some(cvss.vector))),
+                                Lit::Float(score) => cvss::parse_score(score.base10_digits())
+                                    .map(|score| (score, None)),
+                                Lit::Int(score) => cvss::parse_score(score.base10_digits())
+                                    .map(|score| (score, None)),
+                                _ => Err("expected a CVSS vector string or a score such as 9.8"
+                                    .to_string()),
+                            };
+                        let (score, vector) =
+                            parsed.map_err(|message| syn::Error::new(value.span(), message))?;
+                        args.cvss_score = Some(score);
+                        args.cvss_vector = vector;
+                        cvss_span = Some(value.span());
+                    }
+                    "cwe" => {
+                        if cwe_given {
+                            return Err(syn::Error::new(
+                                name.span(),
+                                "`cwe` is given more than once, list the IDs in one `cwe = [...]`",
+                            ));
+                        }
+                        cwe_given = true;
+                        args.cwe = cwe_ids(input)?;
+                    }
+                    "owasp" => {
+                        if owasp_given {
+                            return Err(syn::Error::new(
+                                name.span(),
+                                "`owasp` is given more than once, list the categories in one \
+                                 `owasp = [...]`",
+                            ));
+                        }
+                        owasp_given = true;
+                        args.owasp = owasp_categories(input)?;
+                    }
+                    "custom" => {
+                        let custom = custom_test(&input.parse()?)?;
+                        if args.custom_tests.contains(&custom) {
+                            return Err(syn::Error::new(
+                                name.span(),
+                                format!("custom test type `{}` is given more than once", custom),
+                            ));
+                        }
+                        args.custom_tests.push(custom);
+                    }
+                    _ => {
+                        return Err(syn::Error::new(
+                            name.span(),
+                            format!(
+                                "unknown setting `{}`, expected one of: sla_days, cvss, cwe, \
+                                 owasp, custom",
+                                key
+                            ),
+                        ))
+                    }
+                }
+            } else if let Some(level) = THREAT_LEVELS.iter().position(|level| name == level) {
+                if let Some(previous) = threat_level.as_ref().filter(|previous| **previous != name)
+                {
+                    let mut err = syn::Error::new(
+                        name.span(),
+                        format!("conflicting threat levels `{}` and `{}`", previous, name),
+                    );
+                    err.combine(syn::Error::new(
+                        previous.span(),
+                        "first threat level given here",
+                    ));
+                    return Err(err);
+                }
+                args.threat_level = level as u8;
+                threat_level = Some(name);
+            } else if let Some(bit) = TEST_TYPES.iter().position(|test_type| name == test_type) {
+                args.tests |= 1 << bit;
+            } else {
+                match key.as_str() {
+                    "inline_never" => args.inline_never = true,
+                    "anchor" => args.anchor = true,
+                    "test_context" => args.test_context = true,
+                    "generate_tests" => args.generate_tests = true,
+                    _ => {
+                        return Err(syn::Error::new(
+                            name.span(),
+                            format!(
+                                "unknown argument `{}`, expected a test type ({}), a threat \
+                                 level ({}), or one of: inline_never, anchor, test_context, \
+                                 generate_tests",
+                                key,
+                                TEST_TYPES.join(", "),
+                                THREAT_LEVELS.join(", ")
+                            ),
+                        ))
+                    }
+                }
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+
+        args.rated = threat_level.is_some() || cvss_span.is_some() || profile_levels.is_some();
+
+        // A CVSS score sets the plain threat level, which must agree with one
+        // given as a keyword
+        if let (Some(span), Some(score)) = (cvss_span, args.cvss_score) {
+            let derived = cvss::threat_level(score);
+            if let Some(level) = threat_level
+                .as_ref()
+                .filter(|level| **level != THREAT_LEVELS[usize::from(derived)])
+            {
+                let mut err = syn::Error::new(
+                    level.span(),
+                    format!(
+                        "threat level `{}` conflicts with the CVSS score {}.{}, which rates \
+                         the function `{}`",
+                        level,
+                        score / 10,
+                        score % 10,
+                        THREAT_LEVELS[usize::from(derived)]
+                    ),
+                );
+                err.combine(syn::Error::new(span, "CVSS given here"));
+                return Err(err);
+            }
+            args.threat_level = derived;
+        }
+
+        // A plain threat level applies to the profiles the list leaves out
+        if let Some((name, [debug, release])) = profile_levels {
+            if let (Some(_), Some(_), Some(level)) = (debug, release, &threat_level) {
+                let mut err = syn::Error::new(
+                    level.span(),
+                    format!(
+                        "threat level `{}` is overridden for every profile by `threat_level(...)`",
+                        level
+                    ),
+                );
+                err.combine(syn::Error::new(name.span(), "overridden here"));
+                return Err(err);
+            }
+            args.profile_threat_levels = Some([
+                debug.unwrap_or(args.threat_level),
+                release.unwrap_or(args.threat_level),
+            ]);
+        }
+
+        Ok(args)
+    }
+}
+
+/// Parses the string literals of `reads_env(...)` and `fixtures(...)`.
+fn string_list(input: ParseStream) -> syn::Result<Vec<String>> {
+    let names = Punctuated::<LitStr, Token![,]>::parse_terminated(input)?;
+    Ok(names.iter().map(LitStr::value).collect())
+}
+
+/// Parses `attack(...)`, checking each ID has the ATT&CK technique format
+/// `T1234` or `T1234.567`.
+fn attack_techniques(input: ParseStream) -> syn::Result<Vec<String>> {
+    let ids = Punctuated::<LitStr, Token![,]>::parse_terminated(input)?;
+    let mut techniques = Vec::new();
+    for id in ids {
+        let value = id.value();
+        if !is_attack_technique(&value) {
+            return Err(syn::Error::new(
+                id.span(),
+                "invalid ATT&CK technique ID, expected `T` and four digits with an \
+                 optional `.` and three-digit sub-technique, e.g. \"T1190\" or \"T1078.004\"",
+            ));
+        }
+        techniques.push(value);
+    }
+    Ok(techniques)
+}
+
+fn is_attack_technique(id: &str) -> bool {
+    let digits =
+        |part: &str, len: usize| part.len() == len && part.bytes().all(|b| b.is_ascii_digit());
+    let Some(rest) = id.strip_prefix('T') else {
+        return false;
+    };
+    match rest.split_once('.') {
+        Some((technique, sub)) => digits(technique, 4) && digits(sub, 3),
+        None => digits(rest, 4),
+    }
+}
+
+/// Parses the names of `capabilities(...)` into capability bits.
+fn capabilities(input: ParseStream) -> syn::Result<u8> {
+    let names = Punctuated::<Ident, Token![,]>::parse_terminated(input)?;
+    let mut bits = 0;
+    for name in names {
+        bits |= match CAPABILITIES
+            .iter()
+            .find(|(capability, _)| name == capability)
+        {
+            Some((_, bit)) => bit,
+            None => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "unknown capability, expected one of: net, fs_read, fs_write, spawn",
+                ))
+            }
+        };
+    }
+    Ok(bits)
+}
+
+/// Parses the names of `stride(...)` into the STRIDE byte.
+fn stride(input: ParseStream) -> syn::Result<u8> {
+    let names = Punctuated::<Ident, Token![,]>::parse_terminated(input)?;
+    let mut bits = 0u8;
+    for name in names {
+        bits |= match STRIDE.iter().position(|category| name == category) {
+            Some(index) => 1 << index,
+            None => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!(
+                        "unknown STRIDE category, expected one of: {}",
+                        STRIDE.join(", ")
+                    ),
+                ))
+            }
+        };
+    }
+    Ok(bits)
+}
+
+/// Parses the `profile = level` pairs of `threat_level(...)` into the
+/// record bytes of the debug and release levels.
+fn profile_threat_levels(input: ParseStream) -> syn::Result<[Option<u8>; 2]> {
+    let mut levels = [None; 2];
+    while !input.is_empty() {
+        let profile: Ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let level: Ident = input.parse()?;
+
+        let Some(index) = PROFILES.iter().position(|name| profile == name) else {
+            return Err(syn::Error::new(
+                profile.span(),
+                format!(
+                    "unknown build profile, expected one of: {}",
+                    PROFILES.join(", ")
+                ),
+            ));
+        };
+        if levels[index].is_some() {
+            return Err(syn::Error::new(
+                profile.span(),
+                format!("the `{}` threat level is given more than once", profile),
+            ));
+        }
+        let Some(byte) = THREAT_LEVELS.iter().position(|name| level == name) else {
+            return Err(syn::Error::new(
+                level.span(),
+                format!(
+                    "unknown threat level, expected one of: {}",
+                    THREAT_LEVELS.join(", ")
+                ),
+            ));
+        };
+        levels[index] = Some(byte as u8);
+
+        if input.is_empty() {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+    }
+    Ok(levels)
+}
+
+/// Parses the value of `cwe = N` or `cwe = [N, ...]`.
+fn cwe_ids(input: ParseStream) -> syn::Result<Vec<u32>> {
+    let ids: Vec<LitInt> = if input.peek(syn::token::Bracket) {
+        let content;
+        bracketed!(content in input);
+        Punctuated::<LitInt, Token![,]>::parse_terminated(&content)?
+            .into_iter()
+            .collect()
+    } else {
+        vec![input.parse()?]
+    };
+
+    let mut cwe = Vec::new();
+    for id in ids {
+        match id.base10_parse::<u32>() {
+            Ok(value) if value > 0 && !cwe.contains(&value) => cwe.push(value),
+            Ok(value) if value > 0 => {
+                return Err(syn::Error::new(
+                    id.span(),
+                    format!("CWE-{} is given more than once", value),
+                ))
+            }
+            _ => {
+                return Err(syn::Error::new(
+                    id.span(),
+                    "invalid CWE ID, expected the number of a weakness, e.g. 89 for CWE-89",
+                ))
+            }
+        }
+    }
+    Ok(cwe)
+}
+
+/// Parses the value of `owasp = "..."` or `owasp = ["...", ...]`, checking
+/// each category has the OWASP Top 10 format `A03:2021`.
+fn owasp_categories(input: ParseStream) -> syn::Result<Vec<String>> {
+    let ids: Vec<LitStr> = if input.peek(syn::token::Bracket) {
+        let content;
+        bracketed!(content in input);
+        Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?
+            .into_iter()
+            .collect()
+    } else {
+        vec![input.parse()?]
+    };
+
+    let mut categories = Vec::new();
+    for id in ids {
+        let value = id.value();
+        if !is_owasp_category(&value) {
+            return Err(syn::Error::new(
+                id.span(),
+                "invalid OWASP Top 10 category, expected `A`, two digits, `:` and the \
+                 edition's year, e.g. \"A03:2021\"",
+            ));
+        }
+        if categories.contains(&value) {
+            return Err(syn::Error::new(
+                id.span(),
+                format!("OWASP category {} is given more than once", value),
+            ));
+        }
+        categories.push(value);
+    }
+    Ok(categories)
+}
+
+fn is_owasp_category(id: &str) -> bool {
+    let digits =
+        |part: &str, len: usize| part.len() == len && part.bytes().all(|b| b.is_ascii_digit());
+    let Some((category, year)) = id.strip_prefix('A').and_then(|rest| rest.split_once(':')) else {
+        return false;
+    };
+    digits(category, 2) && digits(year, 4)
+}
+
+/// Checks the name of `custom = "name"`: non-empty ASCII letters, digits,
+/// `_`, `-` and `.`, and not one of the built-in test types.
+fn custom_test(name: &LitStr) -> syn::Result<String> {
+    let value = name.value();
+    if value.is_empty()
+        || !value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.'))
+    {
+        return Err(syn::Error::new(
+            name.span(),
+            "invalid custom test type, expected ASCII letters, digits, `_`, `-` or `.`, \
+             e.g. \"tenant_isolation\"",
+        ));
+    }
+    if TEST_TYPES.contains(&value.as_str()) {
+        return Err(syn::Error::new(
+            name.span(),
+            format!(
+                "`{}` is a built-in test type, use the `{}` keyword instead",
+                value, value
+            ),
+        ));
+    }
+    Ok(value)
+}
+
+/// Checks the value of `sla_days = N`.
+fn sla(days: &LitInt) -> syn::Result<u16> {
+    match days.base10_parse::<u16>() {
+        Ok(days) if days > 0 => Ok(days),
+        _ => Err(syn::Error::new(
+            days.span(),
+            "`sla_days` must be between 1 and 65535",
+        )),
+    }
+}
@@ -0,0 +1,103 @@
+//! Parsing of `#[security_program(...)]` arguments.
+//!
+//! The arguments describe the whole program rather than one function:
+//! `exposure = name` says who can reach it and `data_class(...)` lists the
+//! kinds of data it handles. Both are optional, but each may only be given
+//! once.
+
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parenthesized, Ident, Token};
+
+/// Exposure names, indexed by their record byte, from least to most exposed.
+const EXPOSURES: &[&str] = &["airgapped", "internal", "partner", "internet"];
+
+/// Data class names and their bits in the data classes byte.
+const DATA_CLASSES: &[(&str, u8)] = &[
+    ("pii", 0x01),
+    ("phi", 0x02),
+    ("pci", 0x04),
+    ("credentials", 0x08),
+];
+
+/// The validated arguments of one `#[security_program(...)]`.
+#[derive(Default)]
+pub struct ProgramArgs {
+    /// Record byte of the exposure, indexed like [`EXPOSURES`]
+    pub exposure: Option<u8>,
+    /// Bits of the declared data classes
+    pub data_classes: u8,
+}
+
+impl Parse for ProgramArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = ProgramArgs::default();
+        let mut data_classes_given = false;
+
+        while !input.is_empty() {
+            let name: Ident = input.parse()?;
+
+            if name == "exposure" {
+                input.parse::<Token![=]>()?;
+                let value: Ident = input.parse()?;
+                if args.exposure.is_some() {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "`exposure` is given more than once",
+                    ));
+                }
+                let Some(index) = EXPOSURES.iter().position(|exposure| value == exposure) else {
+                    return Err(syn::Error::new(
+                        value.span(),
+                        format!(
+                            "unknown exposure, expected one of: {}",
+                            EXPOSURES.join(", ")
+                        ),
+                    ));
+                };
+                args.exposure = Some(index as u8);
+            } else if name == "data_class" {
+                let content;
+                parenthesized!(content in input);
+                if data_classes_given {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "`data_class(...)` is given more than once",
+                    ));
+                }
+                data_classes_given = true;
+                args.data_classes = data_classes(&content)?;
+            } else {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "unknown argument, expected `exposure = ...` or `data_class(...)`",
+                ));
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(args)
+    }
+}
+
+/// Parses the names of `data_class(...)` into data class bits.
+fn data_classes(input: ParseStream) -> syn::Result<u8> {
+    let names = Punctuated::<Ident, Token![,]>::parse_terminated(input)?;
+    let mut bits = 0;
+    for name in names {
+        bits |= match DATA_CLASSES.iter().find(|(class, _)| name == class) {
+            Some((_, bit)) => bit,
+            None => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "unknown data class, expected one of: pii, phi, pci, credentials",
+                ))
+            }
+        };
+    }
+    Ok(bits)
+}
@@ -0,0 +1,62 @@
+//! Compiler settings of the crate being expanded, for the build entry of
+//! its records.
+//!
+//! Whether integer overflow panics decides how much an arithmetic or
+//! `buffer_overflow` finding matters, and `-C overflow-checks` has no `cfg`
+//! on stable. The macro runs inside the rustc process compiling the
+//! annotated crate, so it reads the flag from that process's command line;
+//! without one, overflow checks follow debug assertions, as in rustc. The
+//! panic strategy and debug assertions are exact `cfg!` checks in the
+//! generated code.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Version of the compiler, recorded by the build script.
+const RUSTC_VERSION: &str = env!("SECURITY_SCANNER_RUSTC_VERSION");
+
+/// Builds the `&'static str` expression stored in the build entry.
+pub fn build_settings() -> TokenStream {
+    let overflow_checks = match codegen_flag(std::env::args(), "overflow-checks") {
+        Some(enabled) => quote!(#enabled),
+        None => quote!(cfg!(debug_assertions)),
+    };
+    quote! {
+        concat!(
+            "rustc=", #RUSTC_VERSION,
+            "; panic-unwind=", cfg!(panic = "unwind"),
+            "; overflow-checks=", #overflow_checks,
+            "; debug-assertions=", cfg!(debug_assertions)
+        )
+    }
+}
+
+/// Returns the last value given to the boolean codegen option `name`, in
+/// either the `-C name=value` or the `-Cname=value` form.
+fn codegen_flag(mut args: impl Iterator<Item = String>, name: &str) -> Option<bool> {
+    let mut value = None;
+    while let Some(arg) = args.next() {
+        let option = match arg.as_str() {
+            "-C" | "--codegen" => match args.next() {
+                Some(option) => option,
+                None => break,
+            },
+            arg => match arg
+                .strip_prefix("-C")
+                .or_else(|| arg.strip_prefix("--codegen="))
+            {
+                Some(option) => option.to_string(),
+                None => continue,
+            },
+        };
+        let (key, setting) = option.split_once('=').unwrap_or((&option, "on"));
+        if key == name {
+            value = match setting {
+                "on" | "yes" | "y" | "true" => Some(true),
+                "off" | "no" | "n" | "false" => Some(false),
+                _ => value,
+            };
+        }
+    }
+    value
+}
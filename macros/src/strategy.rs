@@ -0,0 +1,155 @@
+//! Selection of how (and whether) metadata is embedded for a crate.
+//!
+//! The strategy is read per crate from the user's manifest:
+//!
+//! ```toml
+//! [package.metadata.security-scanner]
+//! metadata_strategy = "none"
+//! ```
+//!
+//! The `SECURITY_SCANNER_METADATA_STRATEGY` environment variable overrides the
+//! manifest, which is handy for one-off Miri or coverage runs in CI.
+//!
+//! Production builds that must not advertise their weak spots strip the
+//! metadata instead: the `strip` feature of `security-scanner` or
+//! `SECURITY_SCANNER_STRIP=1` selects [`MetadataStrategy::None`] whatever the
+//! manifest says, while scan builds leave both unset. Builds that must
+//! keep the metadata for auditors in the field can encrypt it instead, with a
+//! key of 64 hex digits in `SECURITY_SCANNER_METADATA_KEY`.
+//!
+//! The variables are read as they were when this crate was built, which the
+//! build script registers with cargo: changing one rebuilds the macros and
+//! every crate using them, so no build keeps metadata of an earlier setting.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use security_scanner_format::tlv;
+
+/// Environment variable stripping metadata, overriding the two below.
+const STRIP_ENV: &str = "SECURITY_SCANNER_STRIP";
+
+/// Environment variable holding the key records are encrypted with.
+const KEY_ENV: &str = "SECURITY_SCANNER_METADATA_KEY";
+
+/// `SECURITY_SCANNER_STRIP` as forwarded by the build script.
+const STRIP_VALUE: Option<&str> = option_env!("SECURITY_SCANNER_STRIP");
+
+/// `SECURITY_SCANNER_METADATA_STRATEGY`, overriding the manifest setting.
+const STRATEGY_VALUE: Option<&str> = option_env!("SECURITY_SCANNER_METADATA_STRATEGY");
+
+/// `SECURITY_SCANNER_METADATA_KEY` as forwarded by the build script.
+const KEY_VALUE: Option<&str> = option_env!("SECURITY_SCANNER_METADATA_KEY");
+
+/// How the macro emits metadata for annotated functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataStrategy {
+    /// Emit section statics only on targets known to support them
+    /// (Linux, macOS, Windows, WebAssembly) and never under Miri.
+    Auto,
+    /// Emit section statics on every target, as earlier versions did.
+    Section,
+    /// Emit nothing, leaving the function untouched.
+    None,
+}
+
+impl MetadataStrategy {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.trim() {
+            "auto" => Ok(MetadataStrategy::Auto),
+            "section" => Ok(MetadataStrategy::Section),
+            "none" => Ok(MetadataStrategy::None),
+            other => Err(format!(
+                "unknown metadata_strategy \"{}\", expected one of: auto, section, none",
+                other
+            )),
+        }
+    }
+
+    /// Resolves the strategy for the crate currently being compiled.
+    pub fn for_current_crate() -> Result<Self, String> {
+        if cfg!(feature = "strip") || strip_requested()? {
+            return Ok(MetadataStrategy::None);
+        }
+
+        if let Some(value) = STRATEGY_VALUE {
+            return Self::parse(value);
+        }
+
+        match manifest_value("metadata_strategy") {
+            Some(value) => Self::parse(value.trim_matches('"')),
+            None => Ok(MetadataStrategy::Auto),
+        }
+    }
+}
+
+/// Reads `SECURITY_SCANNER_STRIP`, which is off when unset.
+fn strip_requested() -> Result<bool, String> {
+    match STRIP_VALUE.map(str::trim) {
+        None | Some("" | "0" | "false") => Ok(false),
+        Some("1" | "true") => Ok(true),
+        Some(other) => Err(format!(
+            "unknown {} value \"{}\", expected 1 or 0",
+            STRIP_ENV, other
+        )),
+    }
+}
+
+/// Reads the 256-bit key of `SECURITY_SCANNER_METADATA_KEY`, `None` when it
+/// is unset or empty.
+pub fn metadata_key() -> Result<Option<[u8; tlv::KEY_LEN]>, String> {
+    match KEY_VALUE.filter(|hex| !hex.trim().is_empty()) {
+        Some(hex) => tlv::parse_key(hex)
+            .map(Some)
+            .ok_or_else(|| format!("{} must be 64 hex digits, a 256-bit key", KEY_ENV)),
+        None => Ok(None),
+    }
+}
+
+/// Reads the raw value of `key` in `[package.metadata.security-scanner]` of
+/// the manifest of the crate being compiled, such as `"none"` with its
+/// quotes, or `None` if the manifest or the key is missing.
+pub fn manifest_value(key: &str) -> Option<String> {
+    let dir = env::var("CARGO_MANIFEST_DIR").ok()?;
+    let contents = fs::read_to_string(Path::new(&dir).join("Cargo.toml")).ok()?;
+    find_value(&contents, key)
+}
+
+/// Finds `key = ...` inside `[package.metadata.security-scanner]`. An array
+/// value may span several lines.
+///
+/// This is intentionally a line scanner rather than a TOML parser, to keep the
+/// macro's dependency tree small.
+fn find_value(contents: &str, key: &str) -> Option<String> {
+    let mut in_table = false;
+    let mut lines = contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim());
+
+    while let Some(line) = lines.next() {
+        if line.starts_with('[') {
+            in_table = line == "[package.metadata.security-scanner]";
+            continue;
+        }
+
+        if !in_table {
+            continue;
+        }
+
+        if let Some((name, value)) = line.split_once('=') {
+            if name.trim() == key {
+                let mut value = value.trim().to_string();
+                if value.starts_with('[') {
+                    while !value.contains(']') {
+                        value.push(' ');
+                        value.push_str(lines.next()?);
+                    }
+                }
+                return Some(value);
+            }
+        }
+    }
+
+    None
+}
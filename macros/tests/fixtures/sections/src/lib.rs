@@ -0,0 +1,16 @@
+//! Minimal crate whose object file is inspected per target.
+
+#![no_std]
+
+use security_scanner::security_test;
+
+#[security_test(sql_injection, timing_attack, reads_env("DATABASE_URL"), fixtures("users"), high, anchor)]
+pub fn login(user: &str, password: &str) -> bool {
+    !user.is_empty() && !password.is_empty()
+}
+
+/// Calls an API banned in `Cargo.toml`, which is recorded and warned about.
+#[security_test(buffer_overflow)]
+pub fn pack(halves: [u16; 2]) -> [u8; 4] {
+    unsafe { core::mem::transmute::<[u16; 2], [u8; 4]>(halves) }
+}
@@ -0,0 +1,272 @@
+//! Builds `fixtures/sections` for a representative list of targets and
+//! snapshots the metadata sections in the resulting object file, along with
+//! what `security_scanner::extract` reads back out of it.
+//!
+//! Only the compiler runs, never the linker, so any target whose standard
+//! library is installed (`rustup target add <triple>`) can be checked from any
+//! host. Targets without an installed standard library are skipped with a
+//! note; CI installs all of them.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use object::{Object, ObjectSection};
+use security_scanner::format::schema::{TagKind, TAGS};
+use security_scanner::format::{read_u16, scan_section, tlv};
+use security_scanner::{extract, ScannerError};
+
+/// One target per object format, pointer width and byte order we emit for,
+/// and a bare-metal one.
+const TARGETS: &[&str] = &[
+    "x86_64-unknown-linux-gnu",
+    "aarch64-unknown-linux-gnu",
+    "i686-unknown-linux-gnu",
+    "s390x-unknown-linux-gnu",
+    "x86_64-apple-darwin",
+    "aarch64-apple-darwin",
+    "x86_64-pc-windows-msvc",
+    "x86_64-pc-windows-gnu",
+    "wasm32-unknown-unknown",
+    "thumbv7em-none-eabihf",
+];
+
+/// Section names used by the macro, across ELF, Mach-O, COFF and WebAssembly.
+const SECTION_PREFIXES: &[&str] = &[".security_", "__sec", ".sec", "security_"];
+
+#[test]
+fn metadata_sections_per_target() {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sections");
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("sections-fixture");
+
+    for target in TARGETS {
+        if !std_installed(target) {
+            eprintln!("skipping {}: standard library not installed", target);
+            continue;
+        }
+
+        let object_path = build_object(&fixture, &target_dir, target, &[]);
+        let data = std::fs::read(&object_path).unwrap();
+        let file = object::File::parse(&*data).unwrap();
+
+        insta::assert_snapshot!(*target, describe(&file, &data));
+    }
+}
+
+#[test]
+fn stripped_builds_have_no_metadata() {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sections");
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("sections-fixture-stripped");
+    let target = "x86_64-unknown-linux-gnu";
+    if !std_installed(target) {
+        eprintln!("skipping {}: standard library not installed", target);
+        return;
+    }
+
+    // Both builds share a directory, so the second one only strips the
+    // metadata if cargo rebuilds the fixture for the changed variable
+    for (envs, stripped) in [
+        (&[][..], false),
+        (&[("SECURITY_SCANNER_STRIP", "1")][..], true),
+    ] {
+        let object_path = build_object(&fixture, &target_dir, target, envs);
+        let data = std::fs::read(&object_path).unwrap();
+        let file = object::File::parse(&*data).unwrap();
+
+        let description = describe(&file, &data);
+        assert_eq!(
+            description.contains("no metadata sections\n"),
+            stripped,
+            "{}",
+            description
+        );
+    }
+}
+
+#[test]
+fn encrypted_builds_need_the_key() {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sections");
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("sections-fixture-encrypted");
+    let target = "x86_64-unknown-linux-gnu";
+    if !std_installed(target) {
+        eprintln!("skipping {}: standard library not installed", target);
+        return;
+    }
+
+    let hex = "5ca1ab1e".repeat(8);
+    let key = tlv::parse_key(&hex).unwrap();
+    let object_path = build_object(
+        &fixture,
+        &target_dir,
+        target,
+        &[("SECURITY_SCANNER_METADATA_KEY", &hex)],
+    );
+    let data = std::fs::read(&object_path).unwrap();
+    assert!(!data
+        .windows(17)
+        .any(|window| window == b"sections_fixture:"));
+
+    let locked = extract::extract(&data).unwrap();
+    assert!(locked.functions.is_empty());
+    assert!(locked.program.is_none());
+    assert!(locked.encrypted > 0);
+    let err = ScannerError::from(locked.verify().unwrap_err());
+    assert_eq!(err.code(), "format.encrypted");
+
+    let wrong = extract::extract_with_key(&data, Some(&[0; tlv::KEY_LEN])).unwrap();
+    assert_eq!(wrong.encrypted, locked.encrypted);
+
+    let unlocked = extract::extract_with_key(&data, Some(&key)).unwrap();
+    unlocked.verify().unwrap();
+    assert_eq!(
+        unlocked.functions.len() + usize::from(unlocked.program.is_some()),
+        locked.encrypted
+    );
+    assert!(unlocked
+        .functions
+        .iter()
+        .any(|function| function.function_name == "sections_fixture::pack"));
+}
+
+fn std_installed(target: &str) -> bool {
+    let output = Command::new(rustc())
+        .args(["--print", "target-libdir", "--target", target])
+        .output()
+        .unwrap();
+    output.status.success() && Path::new(String::from_utf8_lossy(&output.stdout).trim()).is_dir()
+}
+
+/// Compiles the fixture to a single object file without linking, with
+/// `envs` set for the macro.
+fn build_object(fixture: &Path, target_dir: &Path, target: &str, envs: &[(&str, &str)]) -> PathBuf {
+    let status = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+        .current_dir(fixture)
+        .args([
+            "rustc",
+            "--quiet",
+            "--lib",
+            "--target",
+            target,
+            "--target-dir",
+        ])
+        .arg(target_dir)
+        .args(["--", "--emit=obj", "-C", "codegen-units=1"])
+        .env("CARGO_INCREMENTAL", "0")
+        .env_remove("SECURITY_SCANNER_METADATA_STRATEGY")
+        .env_remove("SECURITY_SCANNER_STRIP")
+        .env_remove("SECURITY_SCANNER_METADATA_KEY")
+        .envs(envs.iter().copied())
+        .status()
+        .unwrap();
+    assert!(status.success(), "fixture failed to build for {}", target);
+
+    let deps = target_dir.join(target).join("debug/deps");
+    std::fs::read_dir(&deps)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.starts_with("sections_fixture-") && name.ends_with(".o")
+        })
+        .max_by_key(|path| path.metadata().unwrap().modified().unwrap())
+        .unwrap_or_else(|| panic!("no object file for {} in {}", target, deps.display()))
+}
+
+fn rustc() -> String {
+    std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into())
+}
+
+/// Renders the object format, every metadata section and what the scanner
+/// extracts. Records are shown entry by entry. The compiler version in the
+/// build entry is redacted, and with it the size of the records section and
+/// the checksums, so snapshots don't change with the toolchain.
+fn describe(file: &object::File, data: &[u8]) -> String {
+    let mut out = format!(
+        "format: {:?}\nendianness: {}\n",
+        file.format(),
+        if file.is_little_endian() {
+            "little"
+        } else {
+            "big"
+        }
+    );
+
+    let mut sections: Vec<_> = file
+        .sections()
+        .filter(|section| {
+            let name = section.name().unwrap_or("");
+            SECTION_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+        })
+        .collect();
+    sections.sort_by_key(|section| section.name().unwrap_or("").to_string());
+
+    if sections.is_empty() {
+        out.push_str("no metadata sections\n");
+    }
+    for section in sections {
+        let name = match section.segment_name().ok().flatten() {
+            Some(segment) => format!("{},{}", segment, section.name().unwrap()),
+            None => section.name().unwrap().to_string(),
+        };
+        if !name.ends_with("tests") {
+            writeln!(out, "{} size={}", name, section.size()).unwrap();
+            continue;
+        }
+
+        let scan = scan_section(section.data().unwrap());
+        writeln!(out, "{} records={}", name, scan.records.len()).unwrap();
+        for record in scan.records {
+            let version = read_u16(record, tlv::header::VERSION);
+            writeln!(out, "  record version={}", version).unwrap();
+            for entry in tlv::entries(record) {
+                writeln!(out, "    {}", describe_entry(&entry)).unwrap();
+            }
+        }
+    }
+
+    match extract::extract(data) {
+        Ok(extraction) => {
+            for function in extraction.functions {
+                writeln!(
+                    out,
+                    "extracted {} reads_env={:?} fixtures={:?} location={} params={:?}",
+                    function.function_name,
+                    function.config.reads_env,
+                    function.config.fixtures,
+                    function
+                        .location
+                        .map_or("none".to_string(), |location| location.to_string()),
+                    function
+                        .config
+                        .input_params
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                )
+                .unwrap();
+            }
+        }
+        Err(err) => writeln!(out, "extract: {}", err).unwrap(),
+    }
+
+    out
+}
+
+fn describe_entry(entry: &tlv::Entry) -> String {
+    let Some(tag) = TAGS.iter().find(|tag| tag.tag == entry.tag) else {
+        return format!("unknown tag {} = {:02x?}", entry.tag, entry.value);
+    };
+    let value = match tag.kind {
+        TagKind::Str if tag.tag == tlv::tags::BUILD => {
+            let settings = entry.as_str().unwrap();
+            let (_, rest) = settings.split_once("; ").unwrap();
+            format!("\"rustc=[version]; {}\"", rest)
+        }
+        TagKind::Str => format!("{:?}", entry.as_str().unwrap()),
+        TagKind::U8 | TagKind::U16 | TagKind::U32 => entry.as_uint().unwrap().to_string(),
+        TagKind::Bytes => format!("{} bytes", entry.value.len()),
+    };
+    format!("{} = {}", tag.name, value)
+}
@@ -0,0 +1,12 @@
+//! Compile errors for invalid `#[security_test(...)]` arguments.
+//!
+//! Regenerate the expected output with `TRYBUILD=overwrite`. `strip` skips
+//! the checks that need metadata emitted; see `ui_stripped` for the rest.
+
+#![cfg(not(feature = "strip"))]
+
+#[test]
+fn invalid_arguments() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,6 @@
+use security_scanner::security_test;
+
+#[security_test(custom = "sql_injection", high)]
+fn login() {}
+
+fn main() {}
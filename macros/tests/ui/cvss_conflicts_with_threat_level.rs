@@ -0,0 +1,6 @@
+use security_scanner::security_test;
+
+#[security_test(sql_injection, cvss = "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H", high)]
+fn login() {}
+
+fn main() {}
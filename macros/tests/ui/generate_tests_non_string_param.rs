@@ -0,0 +1,8 @@
+use security_scanner::security_test;
+
+#[security_test(sql_injection, generate_tests)]
+fn load_order(customer: &str, order_id: u64) -> Option<String> {
+    Some(format!("{}/{}", customer, order_id))
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use security_scanner::security_test;
+
+#[security_test(xss, generate_tests)]
+fn render(body: &str) -> String {
+    body.replace('<', "&lt;")
+}
+
+fn main() {}
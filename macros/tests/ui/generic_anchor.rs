@@ -0,0 +1,8 @@
+use security_scanner::security_test;
+
+#[security_test(deserialization, anchor)]
+fn handle<T: Default>() -> T {
+    T::default()
+}
+
+fn main() {}
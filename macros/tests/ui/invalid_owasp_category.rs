@@ -0,0 +1,6 @@
+use security_scanner::security_test;
+
+#[security_test(sql_injection, cwe = 89, owasp = "A3-Injection", high)]
+fn login() {}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use security_scanner::security_test;
+
+struct Session;
+
+impl Session {
+    #[security_test(timing_attack, high)]
+    fn verify(&self, token: &str) -> bool {
+        !token.is_empty()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use security_scanner::security_test_module;
+
+#[security_test_module(sql_injection, anchor, high)]
+mod handlers {
+    pub fn login() {}
+}
+
+fn main() {}
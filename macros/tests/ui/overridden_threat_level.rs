@@ -0,0 +1,6 @@
+use security_scanner::security_test;
+
+#[security_test(sql_injection, high, threat_level(debug = low, release = critical))]
+fn login() {}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use security_scanner::security_test;
+
+// Used to set the sql_injection flag by substring match
+#[security_test(not_sql_injection)]
+fn lookup() {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use security_scanner::security_test;
+
+#[security_test(sql_injection, reads_envs("DATABASE_URL"))]
+fn connect() {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use security_scanner::security_program;
+
+#[security_program(exposure = public, data_class(pii))]
+fn serve() {}
+
+fn main() {}
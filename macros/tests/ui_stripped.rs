@@ -0,0 +1,12 @@
+//! Compile errors for invalid `#[security_test(...)]` arguments when no
+//! metadata is emitted, which must still be caught.
+//!
+//! Regenerate the expected output with `TRYBUILD=overwrite`.
+
+#[test]
+fn invalid_arguments_without_metadata() {
+    // Inherited by the compiler that trybuild runs, and read by the macro
+    std::env::set_var("SECURITY_SCANNER_METADATA_STRATEGY", "none");
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui_stripped/*.rs");
+}
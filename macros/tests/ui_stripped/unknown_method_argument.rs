@@ -0,0 +1,13 @@
+use security_scanner::security_test;
+
+struct Session;
+
+#[security_test]
+impl Session {
+    #[security_test(timing_atack, high)]
+    fn verify(&self, token: &str) -> bool {
+        !token.is_empty()
+    }
+}
+
+fn main() {}
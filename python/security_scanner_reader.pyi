@@ -0,0 +1,67 @@
+"""Read security-scanner metadata out of compiled binaries."""
+
+from os import PathLike
+from typing import Literal, Optional, Union
+
+ThreatLevel = Literal["low", "medium", "high", "critical"]
+
+class ScannerError(Exception):
+    """Reading a binary failed."""
+
+    code: str
+    """Stable code of the failure, such as ``format.no_metadata``."""
+
+class Integrity:
+    """What integrity checking found in a binary's records section."""
+
+    verified: int
+    unchecked: int
+    corrupted: int
+    foreign_endian: int
+    unsupported: int
+    truncated: int
+    unrecognized: int
+    def valid(self) -> int: ...
+
+class Function:
+    """One annotated function."""
+
+    name: str
+    test_types: list[str]
+    custom_tests: list[str]
+    threat_level: ThreatLevel
+    file: Optional[str]
+    line: Optional[int]
+    column: Optional[int]
+    address: Optional[int]
+    input_params: list[str]
+    reads_env: list[str]
+    fixtures: list[str]
+    attack_techniques: list[str]
+    stride: list[str]
+    compliance_tags: list[str]
+    banned_calls: list[str]
+    sla_days: Optional[int]
+    test_context: bool
+    instances: list[str]
+
+class Binary:
+    """The annotated functions found in one binary."""
+
+    functions: list[Function]
+    integrity: Integrity
+    encrypted: int
+    image_base: int
+    def verify(self) -> None: ...
+    def query(
+        self,
+        *,
+        min_threat_level: Optional[ThreatLevel] = None,
+        test_type: Optional[str] = None,
+        name: Optional[str] = None,
+    ) -> list[Function]: ...
+    def sarif(self) -> str: ...
+    def __len__(self) -> int: ...
+
+def scan(path: Union[str, PathLike[str]], metadata_key: Optional[str] = None) -> Binary: ...
+def scan_bytes(data: bytes, metadata_key: Optional[str] = None) -> Binary: ...
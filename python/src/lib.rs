@@ -0,0 +1,329 @@
+//! # Security Scanner Python Bindings
+//!
+//! The `security_scanner_reader` extension module, over
+//! [`security_scanner::extract`] and [`security_scanner::report`], for
+//! security automation written in Python. It hands back typed objects
+//! rather than the CLI's JSON, with the signatures in
+//! `security_scanner_reader.pyi`.
+//!
+//! ```python
+//! import security_scanner_reader as reader
+//!
+//! binary = reader.scan("target/debug/my_app")
+//! binary.verify()
+//! for function in binary.query(min_threat_level="high", test_type="sql_injection"):
+//!     print(function.name, function.threat_level, function.file, function.line)
+//! open("scan.sarif", "w").write(binary.sarif())
+//! ```
+//!
+//! Failures raise `ScannerError`, whose `code` is the stable code of
+//! [`ScannerError::code`](security_scanner::ScannerError::code), such as
+//! `format.no_metadata`.
+//!
+//! ## Building
+//!
+//! The module is behind the `python` feature, so the workspace builds where
+//! Python isn't installed. `maturin build --release` in this directory
+//! builds the wheel with it, for the stable ABI of Python 3.9 and later.
+
+#![cfg(feature = "python")]
+
+use std::path::PathBuf;
+
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use security_scanner::extract::{self, Extraction};
+use security_scanner::format::{tlv, IntegrityStats};
+use security_scanner::report::sarif;
+use security_scanner::{SecurityTestMetadata, TestType, ThreatLevel};
+
+pyo3::create_exception!(
+    security_scanner_reader,
+    ScannerError,
+    PyException,
+    "Reading a binary failed; `code` tells how, as `cargo security-scan --json` does."
+);
+
+/// The annotated functions found in one binary.
+#[pyclass(frozen, module = "security_scanner_reader")]
+pub struct Binary {
+    functions: Vec<Py<Function>>,
+    #[pyo3(get)]
+    integrity: Py<Integrity>,
+    /// Intact records that weren't decrypted
+    #[pyo3(get)]
+    encrypted: usize,
+    /// Address the binary is linked to load at
+    #[pyo3(get)]
+    image_base: u64,
+    extraction: Extraction,
+}
+
+/// One annotated function.
+#[pyclass(frozen, get_all, module = "security_scanner_reader")]
+pub struct Function {
+    /// Fully-qualified path
+    name: String,
+    /// Requested test types, such as `sql_injection`
+    test_types: Vec<&'static str>,
+    /// Test types defined by other tools
+    custom_tests: Vec<String>,
+    threat_level: &'static str,
+    /// Source file, `None` if the record has no location
+    file: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    /// Address of the function's code in the file, when known
+    address: Option<usize>,
+    /// Parameters as `name: Type`
+    input_params: Vec<String>,
+    reads_env: Vec<String>,
+    fixtures: Vec<String>,
+    attack_techniques: Vec<String>,
+    stride: Vec<&'static str>,
+    /// `CWE-89`, `OWASP-A03:2021` and the like
+    compliance_tags: Vec<String>,
+    banned_calls: Vec<String>,
+    sla_days: Option<u16>,
+    test_context: bool,
+    /// Generic instances merged into the function
+    instances: Vec<String>,
+}
+
+/// What integrity checking found in a binary's records section.
+#[pyclass(frozen, get_all, module = "security_scanner_reader")]
+pub struct Integrity {
+    verified: usize,
+    unchecked: usize,
+    corrupted: usize,
+    foreign_endian: usize,
+    unsupported: usize,
+    truncated: usize,
+    unrecognized: usize,
+}
+
+#[pymethods]
+impl Binary {
+    /// Every annotated function, in section order.
+    #[getter]
+    fn functions(&self, py: Python<'_>) -> Vec<Py<Function>> {
+        self.functions
+            .iter()
+            .map(|function| function.clone_ref(py))
+            .collect()
+    }
+
+    /// Raises `ScannerError` if any record was damaged or couldn't be
+    /// decrypted, for callers that don't accept partial results.
+    fn verify(&self) -> PyResult<()> {
+        self.extraction.verify().map_err(|err| error(err.into()))
+    }
+
+    /// The functions matching every criterion given: at `min_threat_level`
+    /// or above, requesting `test_type`, and named `name`, the full path or
+    /// its last segments such as `db::lookup`.
+    #[pyo3(signature = (*, min_threat_level = None, test_type = None, name = None))]
+    fn query(
+        &self,
+        py: Python<'_>,
+        min_threat_level: Option<&str>,
+        test_type: Option<&str>,
+        name: Option<&str>,
+    ) -> PyResult<Vec<Py<Function>>> {
+        let min_threat_level = min_threat_level.map(threat_level).transpose()?;
+        let test_type = test_type
+            .map(|test_type| {
+                test_type
+                    .parse::<TestType>()
+                    .map_err(|err| PyValueError::new_err(err.to_string()))
+            })
+            .transpose()?;
+        let suffix = name.map(|name| format!("::{}", name));
+        Ok(self
+            .extraction
+            .functions
+            .iter()
+            .zip(&self.functions)
+            .filter(|(metadata, _)| {
+                let config = &metadata.config;
+                min_threat_level.is_none_or(|level| config.threat_level >= level)
+                    && test_type.is_none_or(|test_type| config.has(test_type))
+                    && name.is_none_or(|name| {
+                        metadata.function_name == name
+                            || suffix
+                                .as_deref()
+                                .is_some_and(|suffix| metadata.function_name.ends_with(suffix))
+                    })
+            })
+            .map(|(_, function)| function.clone_ref(py))
+            .collect())
+    }
+
+    /// A SARIF 2.1.0 log of the functions, as `scan --format sarif` writes.
+    fn sarif(&self) -> PyResult<String> {
+        let log = sarif::Log::from_metadata(&self.extraction.functions);
+        serde_json::to_string_pretty(&log).map_err(|err| ScannerError::new_err(err.to_string()))
+    }
+
+    fn __len__(&self) -> usize {
+        self.functions.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Binary with {} function(s)>", self.functions.len())
+    }
+}
+
+#[pymethods]
+impl Function {
+    fn __repr__(&self) -> String {
+        format!("<Function {} ({})>", self.name, self.threat_level)
+    }
+}
+
+#[pymethods]
+impl Integrity {
+    /// Records that can be trusted.
+    fn valid(&self) -> usize {
+        self.verified + self.unchecked
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<Integrity verified={} unchecked={} corrupted={}>",
+            self.verified, self.unchecked, self.corrupted
+        )
+    }
+}
+
+/// Reads the metadata of the binary at `path`, decrypting records with
+/// `metadata_key`, 64 hex digits, when given.
+#[pyfunction]
+#[pyo3(signature = (path, metadata_key = None))]
+fn scan(py: Python<'_>, path: PathBuf, metadata_key: Option<&str>) -> PyResult<Binary> {
+    let key = key(metadata_key)?;
+    let data = std::fs::read(&path)
+        .map_err(|err| error(security_scanner::ScannerError::io("read", &path, err)))?;
+    binary(py, &data, key.as_ref())
+}
+
+/// Reads the metadata of a binary already in memory.
+#[pyfunction]
+#[pyo3(signature = (data, metadata_key = None))]
+fn scan_bytes(py: Python<'_>, data: &[u8], metadata_key: Option<&str>) -> PyResult<Binary> {
+    let key = key(metadata_key)?;
+    binary(py, data, key.as_ref())
+}
+
+/// The module, also for `append_to_inittab!` when embedding Python.
+#[pymodule]
+pub fn security_scanner_reader(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(scan, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_bytes, m)?)?;
+    m.add_class::<Binary>()?;
+    m.add_class::<Function>()?;
+    m.add_class::<Integrity>()?;
+    m.add("ScannerError", m.py().get_type::<ScannerError>())?;
+    Ok(())
+}
+
+fn binary(py: Python<'_>, data: &[u8], key: Option<&[u8; tlv::KEY_LEN]>) -> PyResult<Binary> {
+    // Decoding doesn't need Python, and large binaries take a while
+    let extraction = py
+        .detach(|| extract::extract_with_key(data, key))
+        .map_err(|err| error(err.into()))?;
+    let functions = extraction
+        .functions
+        .iter()
+        .map(|metadata| Py::new(py, function(metadata)))
+        .collect::<PyResult<_>>()?;
+    Ok(Binary {
+        functions,
+        integrity: Py::new(py, integrity(&extraction.integrity))?,
+        encrypted: extraction.encrypted,
+        image_base: extraction.image_base,
+        extraction,
+    })
+}
+
+fn function(metadata: &SecurityTestMetadata) -> Function {
+    let config = &metadata.config;
+    let location = metadata.location.as_ref();
+    Function {
+        name: metadata.function_name.clone(),
+        test_types: config
+            .test_types()
+            .into_iter()
+            .map(TestType::as_str)
+            .collect(),
+        custom_tests: config.custom_tests.clone(),
+        threat_level: config.threat_level.as_str(),
+        file: location.map(|location| location.file.clone()),
+        line: location.map(|location| location.line),
+        column: location
+            .map(|location| location.column)
+            .filter(|&column| column > 0),
+        address: metadata.function_address,
+        input_params: config
+            .input_params
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        reads_env: config.reads_env.clone(),
+        fixtures: config.fixtures.clone(),
+        attack_techniques: config.attack_techniques.clone(),
+        stride: config
+            .stride
+            .iter()
+            .map(|category| category.as_str())
+            .collect(),
+        compliance_tags: config.compliance_tags.clone(),
+        banned_calls: config.banned_calls.clone(),
+        sla_days: config.sla_days,
+        test_context: config.test_context,
+        instances: metadata.instances.clone(),
+    }
+}
+
+fn integrity(stats: &IntegrityStats) -> Integrity {
+    Integrity {
+        verified: stats.verified,
+        unchecked: stats.unchecked,
+        corrupted: stats.corrupted,
+        foreign_endian: stats.foreign_endian,
+        unsupported: stats.unsupported,
+        truncated: stats.truncated,
+        unrecognized: stats.unrecognized,
+    }
+}
+
+fn threat_level(name: &str) -> PyResult<ThreatLevel> {
+    [
+        ThreatLevel::Low,
+        ThreatLevel::Medium,
+        ThreatLevel::High,
+        ThreatLevel::Critical,
+    ]
+    .into_iter()
+    .find(|level| level.as_str() == name)
+    .ok_or_else(|| PyValueError::new_err(format!("unknown threat level \"{}\"", name)))
+}
+
+fn key(hex: Option<&str>) -> PyResult<Option<[u8; tlv::KEY_LEN]>> {
+    match hex {
+        Some(hex) => tlv::parse_key(hex).map(Some).ok_or_else(|| {
+            PyValueError::new_err("metadata_key must be 64 hex digits, a 256-bit key")
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Raises `ScannerError` with the error's stable code as `code`.
+fn error(err: security_scanner::ScannerError) -> PyErr {
+    let raised = ScannerError::new_err(err.to_string());
+    Python::attach(|py| {
+        // Setting an attribute on a fresh exception can't fail
+        let _ = raised.value(py).setattr("code", err.code());
+    });
+    raised
+}
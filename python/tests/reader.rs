@@ -0,0 +1,81 @@
+//! Imports the extension module into an embedded interpreter and reads this
+//! test binary with it.
+
+// `strip` leaves no records to read
+#![cfg(all(
+    any(target_os = "linux", target_os = "macos", target_os = "windows"),
+    not(feature = "strip")
+))]
+
+use std::ffi::CString;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use security_scanner::security_test;
+use security_scanner_reader::security_scanner_reader;
+
+#[security_test(sql_injection, reads_env("DATABASE_URL"), sla_days = 30, high)]
+fn lookup(name: &str) -> usize {
+    name.len()
+}
+
+#[security_test(xss, low)]
+fn render(page: &str) -> usize {
+    page.len() + 1
+}
+
+const SCRIPT: &str = r#"
+import security_scanner_reader as reader
+
+binary = reader.scan(path)
+binary.verify()
+assert len(binary) == 2, binary.functions
+assert binary.integrity.corrupted == 0
+assert binary.integrity.valid() == 2
+
+lookup = binary.query(name="reader::lookup")[0]
+assert lookup.test_types == ["sql_injection"], lookup.test_types
+assert lookup.threat_level == "high"
+assert lookup.reads_env == ["DATABASE_URL"]
+assert lookup.sla_days == 30
+assert lookup.input_params == ["name: &str"]
+assert lookup.test_context
+assert lookup.file == "python/tests/reader.rs", lookup.file
+
+assert [f.name for f in binary.query(min_threat_level="high")] == ["reader::lookup"]
+assert [f.name for f in binary.query(test_type="xss", name="render")] == ["reader::render"]
+assert binary.query(test_type="xss", min_threat_level="critical") == []
+
+sarif = json.loads(binary.sarif())
+assert sorted(r["ruleId"] for r in sarif["runs"][0]["results"]) == ["sql_injection", "xss"]
+
+try:
+    binary.query(test_type="sql")
+    raise AssertionError("unknown test type accepted")
+except ValueError as err:
+    assert "sql" in str(err)
+
+try:
+    reader.scan_bytes(b"not a binary")
+    raise AssertionError("garbage read")
+except reader.ScannerError as err:
+    assert err.code == "format.unsupported", err.code
+"#;
+
+#[test]
+fn reads_own_binary_from_python() {
+    lookup("alice");
+    render("home");
+    pyo3::append_to_inittab!(security_scanner_reader);
+    Python::initialize();
+    Python::attach(|py| {
+        let locals = PyDict::new(py);
+        let path = std::env::current_exe().unwrap();
+        locals.set_item("path", path).unwrap();
+        locals.set_item("json", py.import("json").unwrap()).unwrap();
+        let script = CString::new(SCRIPT).unwrap();
+        if let Err(err) = py.run(&script, None, Some(&locals)) {
+            panic!("{}", err);
+        }
+    });
+}
@@ -0,0 +1,100 @@
+//! Stress tests for the claims of `race_condition`.
+//!
+//! A function annotated for race conditions shares state between threads
+//! and is expected to stay correct however their calls interleave.
+//! [`stress`] calls it from several threads at once, released together by a
+//! barrier and yielding at staggered iterations so that each run interleaves
+//! differently, and fails when any call panics. The function's own
+//! assertions, and the panics of poisoned locks and `RefCell` borrows, are
+//! what catch a race:
+//!
+//! ```rust
+//! use std::sync::Mutex;
+//!
+//! use security_scanner::concurrency;
+//!
+//! static BALANCE: Mutex<u64> = Mutex::new(0);
+//!
+//! fn deposit(amount: u64) {
+//!     let mut balance = BALANCE.lock().unwrap();
+//!     *balance += amount;
+//! }
+//!
+//! concurrency::stress(|_| deposit(1), 4, 100);
+//! assert_eq!(*BALANCE.lock().unwrap(), 400);
+//! ```
+//!
+//! `#[security_test(race_condition, generate_tests)]` generates a test that
+//! stresses the function with [`THREADS`] threads of [`ITERATIONS`] calls.
+//! Exploring every interleaving, as loom does, needs the function to use
+//! loom's synchronization types under `cfg(loom)`, which a generated test
+//! can't arrange; stress tests run the real types on real threads instead,
+//! so a passing run makes a race unlikely rather than impossible.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Barrier, Mutex};
+use std::thread;
+
+/// Threads the generated `race_condition` tests run.
+pub const THREADS: usize = 8;
+
+/// Calls each thread of the generated `race_condition` tests makes.
+pub const ITERATIONS: usize = 1_000;
+
+/// Calls `f` `iterations` times on each of `threads` threads, all started
+/// at once, passing the index of the calling thread.
+///
+/// # Panics
+///
+/// Panics if `threads` is 0, or with the thread, iteration and message of
+/// the first call that panicked. The other threads stop at their next call.
+pub fn stress(f: impl Fn(usize) + Sync, threads: usize, iterations: usize) {
+    assert!(threads > 0, "concurrency::stress needs at least one thread");
+
+    let barrier = Barrier::new(threads);
+    let stop = AtomicBool::new(false);
+    let failure: Mutex<Option<(usize, usize, String)>> = Mutex::new(None);
+    thread::scope(|scope| {
+        for thread in 0..threads {
+            let (f, barrier, stop, failure) = (&f, &barrier, &stop, &failure);
+            scope.spawn(move || {
+                barrier.wait();
+                for iteration in 0..iterations {
+                    if stop.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    if let Err(cause) = panic::catch_unwind(AssertUnwindSafe(|| f(thread))) {
+                        stop.store(true, Ordering::Relaxed);
+                        let message = cause
+                            .downcast_ref::<&str>()
+                            .copied()
+                            .or_else(|| cause.downcast_ref::<String>().map(String::as_str))
+                            .unwrap_or("non-string panic payload")
+                            .to_string();
+                        failure
+                            .lock()
+                            .unwrap_or_else(|poisoned| poisoned.into_inner())
+                            .get_or_insert((thread, iteration, message));
+                        return;
+                    }
+                    // Threads yield at different iterations, shifting how
+                    // their calls line up
+                    if iteration % (thread + 2) == 0 {
+                        thread::yield_now();
+                    }
+                }
+            });
+        }
+    });
+
+    let failure = failure
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some((thread, iteration, message)) = failure {
+        panic!(
+            "call {} of thread {} of {} panicked: {}",
+            iteration, thread, threads, message
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! Reading the crash state of a process out of its core dump.
+//!
+//! [`CoreDump::parse`] reads an ELF core dump of a Linux process on x86-64
+//! or AArch64: the signal that killed it, the program counter and stack
+//! pointer of the thread that received it, the files mapped into the
+//! process and the memory the dump holds. [`CoreDump::load_map`] then tells
+//! where a binary of that process was loaded, so addresses of the crashed
+//! process can be looked up in the binary's symbols and records. Requires
+//! the `scanner` feature.
+//!
+//! ```rust,no_run
+//! use security_scanner::coredump::CoreDump;
+//!
+//! let core = std::fs::read("core.1234")?;
+//! let binary = std::fs::read("target/release/my_app")?;
+//! let core = CoreDump::parse(&core)?;
+//! if let Some(load_map) = core.load_map("my_app", &binary)? {
+//!     println!("crashed at {:?}", load_map.link_address(core.pc));
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The thread that received the signal is the one whose `NT_PRSTATUS` note
+//! comes first, as the kernel writes it. Mapped files come from the
+//! `NT_FILE` note, which kernels since 3.7 write. Cores written by other
+//! systems, or for other architectures, are [`FormatError::Unsupported`].
+
+use std::path::Path;
+
+use object::elf::{ET_CORE, PT_LOAD, PT_NOTE};
+use object::read::elf::{ElfFile64, FileHeader, ProgramHeader};
+use object::{Endian, Endianness, Object, ObjectSegment};
+
+use crate::error::FormatError;
+
+/// `NT_PRSTATUS`: the status and registers of a thread.
+const NT_PRSTATUS: u32 = 1;
+/// `NT_FILE`: the files mapped into the process.
+const NT_FILE: u32 = 0x4649_4c45;
+/// Offset of the signal in `elf_prstatus`.
+const PRSTATUS_SIGNAL: usize = 12;
+/// Offset of the registers in `elf_prstatus` on 64-bit Linux.
+const PRSTATUS_REGISTERS: usize = 112;
+
+/// A file mapped into the crashed process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mapping {
+    /// First address of the mapping
+    pub start: u64,
+    /// Address just past the mapping
+    pub end: u64,
+    /// Offset in the file of the byte mapped at `start`
+    pub file_offset: u64,
+    /// Path of the file on the machine that crashed
+    pub path: String,
+}
+
+/// The crash state of a process.
+#[derive(Debug, Clone)]
+pub struct CoreDump<'data> {
+    /// Signal that killed the process, such as 11 for `SIGSEGV`
+    pub signal: u32,
+    /// Program counter of the thread that received the signal
+    pub pc: u64,
+    /// Stack pointer of the thread that received the signal
+    pub sp: u64,
+    /// Files mapped into the process, in address order
+    pub mappings: Vec<Mapping>,
+    /// Memory held by the dump, by start address
+    memory: Vec<(u64, &'data [u8])>,
+    endian: Endianness,
+}
+
+impl<'data> CoreDump<'data> {
+    /// Reads a core dump.
+    pub fn parse(data: &'data [u8]) -> Result<Self, FormatError> {
+        let unsupported = |message: &str| FormatError::Unsupported(message.to_string());
+        let file = ElfFile64::<Endianness>::parse(data)
+            .map_err(|_| unsupported("not a 64-bit ELF core dump"))?;
+        let endian = file.endian();
+        let header = file.elf_header();
+        if header.e_type(endian) != ET_CORE {
+            return Err(unsupported("not a core dump"));
+        }
+        // Indices of the program counter and stack pointer in `pr_reg`
+        let (pc_index, sp_index) = match header.e_machine(endian) {
+            object::elf::EM_X86_64 => (16, 19),
+            object::elf::EM_AARCH64 => (32, 31),
+            _ => return Err(unsupported("core dumps are read for x86-64 and AArch64")),
+        };
+
+        let mut registers = None;
+        let mut mappings = Vec::new();
+        let mut memory = Vec::new();
+        for segment in file.elf_program_headers() {
+            match segment.p_type(endian) {
+                PT_LOAD => {
+                    if let Ok(bytes) = segment.data(endian, data) {
+                        memory.push((segment.p_vaddr(endian), bytes));
+                    }
+                }
+                PT_NOTE => {
+                    let Ok(Some(mut notes)) = segment.notes(endian, data) else {
+                        continue;
+                    };
+                    while let Ok(Some(note)) = notes.next() {
+                        match note.n_type(endian) {
+                            NT_PRSTATUS if registers.is_none() => {
+                                registers = Some(note.desc());
+                            }
+                            NT_FILE => mappings = read_mappings(note.desc(), endian),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let status = registers.ok_or_else(|| unsupported("core dump has no thread status"))?;
+        let word = |offset: usize| {
+            let bytes = status.get(offset..offset + 8)?;
+            Some(endian.read_u64_bytes(bytes.try_into().ok()?))
+        };
+        let register = |index: usize| word(PRSTATUS_REGISTERS + 8 * index);
+        let (Some(pc), Some(sp)) = (register(pc_index), register(sp_index)) else {
+            return Err(unsupported("core dump has a truncated thread status"));
+        };
+        let signal = status
+            .get(PRSTATUS_SIGNAL..PRSTATUS_SIGNAL + 2)
+            .map_or(0, |bytes| endian.read_u16_bytes([bytes[0], bytes[1]]));
+        mappings.sort_by_key(|mapping: &Mapping| mapping.start);
+        memory.sort_by_key(|&(start, _)| start);
+
+        Ok(CoreDump {
+            signal: u32::from(signal),
+            pc,
+            sp,
+            mappings,
+            memory,
+            endian,
+        })
+    }
+
+    /// Returns the `len` bytes at `address` of the crashed process, `None`
+    /// when the dump doesn't hold them all.
+    pub fn read(&self, address: u64, len: usize) -> Option<&'data [u8]> {
+        let index = self
+            .memory
+            .partition_point(|&(start, _)| start <= address)
+            .checked_sub(1)?;
+        let (start, bytes) = self.memory[index];
+        let offset = usize::try_from(address - start).ok()?;
+        bytes.get(offset..offset.checked_add(len)?)
+    }
+
+    /// Returns the words on the stack of the crashed thread, from the stack
+    /// pointer up, at most `limit` of them.
+    pub fn stack_words(&self, limit: usize) -> Vec<u64> {
+        let mut words = Vec::new();
+        let mut address = self.sp;
+        while words.len() < limit {
+            let Some(bytes) = self.read(address, 8) else {
+                break;
+            };
+            words.push(
+                self.endian
+                    .read_u64_bytes(bytes.try_into().expect("8 bytes")),
+            );
+            address += 8;
+        }
+        words
+    }
+
+    /// Returns where `binary`, whose file is named `file_name`, was loaded
+    /// in the crashed process, `None` when no file of that name was mapped.
+    ///
+    /// Files are matched on their name only, since the binary is usually
+    /// examined on another machine than the one that crashed.
+    pub fn load_map(&self, file_name: &str, binary: &[u8]) -> Result<Option<LoadMap>, FormatError> {
+        let mapped: Vec<&Mapping> = self
+            .mappings
+            .iter()
+            .filter(|mapping| {
+                Path::new(&mapping.path)
+                    .file_name()
+                    .is_some_and(|name| name == file_name)
+            })
+            .collect();
+        if mapped.is_empty() {
+            return Ok(None);
+        }
+
+        let file =
+            object::File::parse(binary).map_err(|err| FormatError::Unsupported(err.to_string()))?;
+        let mut ranges = Vec::new();
+        for segment in file.segments() {
+            let (offset, size) = segment.file_range();
+            for mapping in &mapped {
+                // The part of the segment this mapping holds
+                let mapping_end = mapping.file_offset + (mapping.end - mapping.start);
+                let start = offset.max(mapping.file_offset);
+                let end = (offset + size).min(mapping_end);
+                if start < end {
+                    ranges.push(LoadRange {
+                        start: mapping.start + (start - mapping.file_offset),
+                        end: mapping.start + (end - mapping.file_offset),
+                        link_start: segment.address() + (start - offset),
+                    });
+                }
+            }
+        }
+        ranges.sort_by_key(|range| range.start);
+        Ok(Some(LoadMap { ranges }))
+    }
+}
+
+/// Where a binary was loaded in a crashed process.
+#[derive(Debug, Clone, Default)]
+pub struct LoadMap {
+    ranges: Vec<LoadRange>,
+}
+
+/// A range of a crashed process holding part of a binary.
+#[derive(Debug, Clone, Copy)]
+struct LoadRange {
+    start: u64,
+    end: u64,
+    /// Address of the byte at `start` in the binary
+    link_start: u64,
+}
+
+impl LoadMap {
+    /// Returns the address in the binary of `address` of the crashed
+    /// process, as symbols and [`CodeRange`](crate::CodeRange)s give
+    /// addresses, `None` when it isn't inside the binary.
+    pub fn link_address(&self, address: u64) -> Option<u64> {
+        self.ranges
+            .iter()
+            .find(|range| (range.start..range.end).contains(&address))
+            .map(|range| range.link_start + (address - range.start))
+    }
+}
+
+/// Reads the mappings of an `NT_FILE` note: a count and the page size, a
+/// start, end and page offset per file, then their NUL-terminated names.
+fn read_mappings(desc: &[u8], endian: Endianness) -> Vec<Mapping> {
+    let word = |index: usize| {
+        let bytes = desc.get(8 * index..8 * index + 8)?;
+        Some(endian.read_u64_bytes(bytes.try_into().ok()?))
+    };
+    let (Some(count), Some(page_size)) = (word(0), word(1)) else {
+        return Vec::new();
+    };
+    let Some(names) = usize::try_from(count)
+        .ok()
+        .and_then(|count| desc.get(8 * (2 + 3 * count)..))
+    else {
+        return Vec::new();
+    };
+
+    let mut names = names.split(|&byte| byte == 0);
+    (0..count as usize)
+        .map_while(|index| {
+            let field = |field: usize| word(2 + 3 * index + field);
+            Some(Mapping {
+                start: field(0)?,
+                end: field(1)?,
+                file_offset: field(2)?.checked_mul(page_size)?,
+                path: String::from_utf8_lossy(names.next()?).into_owned(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,355 @@
+//! Source locations of functions, read from the DWARF debug info of
+//! binaries.
+//!
+//! [`DebugInfo::read`] finds every function with code in a binary's debug
+//! info, with its compilation unit, source file and the lines its code
+//! spans. [`DebugInfo::apply`] adds them to extracted metadata: functions
+//! whose records have no location get one, and every function gets the
+//! last line of its code, which SARIF logs give as the end of a result's
+//! region. Requires the `debuginfo` feature.
+//!
+//! ```rust,no_run
+//! use security_scanner::debuginfo::DebugInfo;
+//! use security_scanner::extract;
+//!
+//! let data = std::fs::read("target/debug/my_app")?;
+//! let mut extraction = extract::extract(&data)?;
+//! if let Some(debug_info) = DebugInfo::read(&data)? {
+//!     debug_info.apply(&mut extraction.functions);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Functions are matched by path through their linkage names, as
+//! [`extract::defined_functions`](crate::extract::defined_functions) matches
+//! symbols, so the same functions are found. Debug info is read from ELF
+//! files and from the DWARF files in the `.dSYM` bundles of Mach-O
+//! binaries: macOS linkers leave it in the object files until `dsymutil`
+//! collects it. PE images keep theirs in a PDB, which isn't read, and
+//! WebAssembly modules are read as having none. Units that fail to parse
+//! are skipped.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use gimli::{AttributeValue, EndianSlice, RunTimeEndian};
+use object::{Object, ObjectSection};
+
+use crate::error::FormatError;
+use crate::extract::symbol_path;
+use crate::types::{SecurityTestMetadata, SourceLocation};
+
+type Reader<'data> = EndianSlice<'data, RunTimeEndian>;
+
+/// Where debug info places one function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSource {
+    /// Name of the compilation unit the function was compiled in, such as
+    /// `src/lib.rs/@/my_app.5f3b1c-cgu.0` for Rust
+    pub unit: String,
+    /// Source file of the declaration, relative to the compilation
+    /// directory when under it, as `file!()` paths are
+    pub file: String,
+    /// Line of the declaration
+    pub first_line: u32,
+    /// Last line of that file the function's code comes from, at least
+    /// `first_line`
+    pub last_line: u32,
+    /// Column of the declaration, 0 if not recorded
+    pub column: u32,
+}
+
+/// The functions found in a binary's debug info.
+#[derive(Debug, Clone, Default)]
+pub struct DebugInfo {
+    functions: HashMap<String, FunctionSource>,
+}
+
+impl DebugInfo {
+    /// Reads the debug info of a binary already in memory, returning `None`
+    /// if it has none.
+    pub fn read(data: &[u8]) -> Result<Option<Self>, FormatError> {
+        if data.starts_with(crate::extract::WASM_MAGIC) {
+            return Ok(None);
+        }
+        let file =
+            object::File::parse(data).map_err(|err| FormatError::Unsupported(err.to_string()))?;
+        if file.section_by_name(".debug_info").is_none() {
+            return Ok(None);
+        }
+
+        let endian = if file.is_little_endian() {
+            RunTimeEndian::Little
+        } else {
+            RunTimeEndian::Big
+        };
+        let sections = gimli::DwarfSections::load(|id| {
+            Ok::<_, gimli::Error>(
+                file.section_by_name(id.name())
+                    .and_then(|section| section.uncompressed_data().ok())
+                    .unwrap_or(Cow::Borrowed(&[])),
+            )
+        })
+        .expect("loading sections doesn't fail");
+        let dwarf = sections.borrow(|section| EndianSlice::new(section, endian));
+
+        let mut debug_info = DebugInfo::default();
+        let mut headers = dwarf.units();
+        while let Ok(Some(header)) = headers.next() {
+            if let Ok(unit) = dwarf.unit(header) {
+                // A damaged unit doesn't affect the others
+                let _ = debug_info.read_unit(&dwarf, &unit);
+            }
+        }
+        Ok(Some(debug_info))
+    }
+
+    /// Returns where debug info places the function with `path`, such as
+    /// `my_app::auth::login`. The copies of a generic function are one
+    /// function spanning all of their lines.
+    pub fn function(&self, path: &str) -> Option<&FunctionSource> {
+        self.functions.get(path)
+    }
+
+    /// Returns the number of functions found.
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    /// Returns whether no function was found.
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Gives each function found in the debug info the location found
+    /// there if its record has none, and otherwise the end line, when the
+    /// debug info places it in the same file.
+    pub fn apply(&self, functions: &mut [SecurityTestMetadata]) {
+        for function in functions {
+            let Some(source) = self.function(&function.function_name) else {
+                continue;
+            };
+            match &mut function.location {
+                None => {
+                    function.location = Some(SourceLocation {
+                        file: source.file.clone(),
+                        line: source.first_line,
+                        column: source.column,
+                        end_line: source.last_line,
+                    })
+                }
+                Some(location) => {
+                    if location.file.is_empty() {
+                        location.file = source.file.clone();
+                    }
+                    if same_file(&location.file, &source.file) && source.last_line >= location.line
+                    {
+                        location.end_line = source.last_line;
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_unit(
+        &mut self,
+        dwarf: &gimli::Dwarf<Reader<'_>>,
+        unit: &gimli::Unit<Reader<'_>>,
+    ) -> gimli::Result<()> {
+        // Each function's code, as (start, end, index into `found`)
+        let mut ranges = Vec::new();
+        let mut found = Vec::new();
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            if entry.tag() != gimli::DW_TAG_subprogram {
+                continue;
+            }
+            let mut code = dwarf.die_ranges(unit, entry)?;
+            let index = found.len();
+            let start = ranges.len();
+            while let Some(range) = code.next()? {
+                if range.begin < range.end {
+                    ranges.push((range.begin, range.end, index));
+                }
+            }
+            // Declarations and inlined-only functions have no code
+            if ranges.len() == start {
+                continue;
+            }
+            match Subprogram::read(dwarf, unit, entry)? {
+                Some(subprogram) => found.push(subprogram),
+                None => ranges.truncate(start),
+            }
+        }
+        if found.is_empty() {
+            return Ok(());
+        }
+
+        // The last line of each function is that of its last row in the
+        // declaring file; rows from other files are inlined code
+        let mut last_lines: Vec<u32> = found.iter().map(|subprogram| subprogram.line).collect();
+        ranges.sort_unstable();
+        if let Some(program) = unit.line_program.clone() {
+            let mut rows = program.rows();
+            while let Some((_, row)) = rows.next_row()? {
+                let Some(line) = row.line() else {
+                    continue;
+                };
+                let address = row.address();
+                let at = ranges.partition_point(|&(start, _, _)| start <= address);
+                let Some(&(_, end, index)) = at.checked_sub(1).map(|at| &ranges[at]) else {
+                    continue;
+                };
+                if address < end && row.file_index() == found[index].file_index {
+                    let line = u32::try_from(line.get()).unwrap_or(u32::MAX);
+                    last_lines[index] = last_lines[index].max(line);
+                }
+            }
+        }
+
+        let unit_name = unit
+            .name
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        for (subprogram, last_line) in found.into_iter().zip(last_lines) {
+            let file = file_path(dwarf, unit, subprogram.file_index).unwrap_or_default();
+            let source = self
+                .functions
+                .entry(subprogram.path)
+                .or_insert_with(|| FunctionSource {
+                    unit: unit_name.clone(),
+                    file: file.clone(),
+                    first_line: subprogram.line,
+                    last_line,
+                    column: subprogram.column,
+                });
+            if source.file == file {
+                source.first_line = source.first_line.min(subprogram.line);
+                source.last_line = source.last_line.max(last_line);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A function with code in a unit.
+struct Subprogram {
+    path: String,
+    file_index: u64,
+    line: u32,
+    column: u32,
+}
+
+impl Subprogram {
+    /// Reads a function's name and declaration, following the declaration
+    /// or abstract instance that concrete copies of methods and generic
+    /// and inlined functions point to. Returns `None` for functions whose
+    /// name can't be read.
+    fn read(
+        dwarf: &gimli::Dwarf<Reader<'_>>,
+        unit: &gimli::Unit<Reader<'_>>,
+        entry: &gimli::DebuggingInformationEntry<'_, '_, Reader<'_>>,
+    ) -> gimli::Result<Option<Self>> {
+        let mut linkage_name = None;
+        let mut name = None;
+        let mut file_index = None;
+        let mut line = None;
+        let mut column = None;
+
+        let mut current = entry.clone();
+        for _ in 0..4 {
+            let string = |at| {
+                let value = current.attr_value(at).ok().flatten()?;
+                let value = dwarf.attr_string(unit, value).ok()?;
+                Some(value.to_string_lossy().into_owned())
+            };
+            linkage_name = linkage_name
+                .or_else(|| string(gimli::DW_AT_linkage_name))
+                .or_else(|| string(gimli::DW_AT_MIPS_linkage_name));
+            name = name.or_else(|| string(gimli::DW_AT_name));
+            let number = |at| current.attr_value(at).ok().flatten()?.udata_value();
+            file_index = file_index.or_else(|| match current.attr_value(gimli::DW_AT_decl_file) {
+                Ok(Some(AttributeValue::FileIndex(index))) => Some(index),
+                _ => None,
+            });
+            line = line.or_else(|| number(gimli::DW_AT_decl_line));
+            column = column.or_else(|| number(gimli::DW_AT_decl_column));
+
+            let origin = match current.attr_value(gimli::DW_AT_specification)? {
+                Some(origin) => Some(origin),
+                None => current.attr_value(gimli::DW_AT_abstract_origin)?,
+            };
+            let Some(AttributeValue::UnitRef(offset)) = origin else {
+                break;
+            };
+            current = unit.entry(offset)?;
+        }
+
+        let Some(path) = linkage_name
+            .as_deref()
+            .and_then(symbol_path)
+            .or_else(|| name.as_deref().and_then(symbol_path))
+        else {
+            return Ok(None);
+        };
+        let number =
+            |value: Option<u64>| value.map_or(0, |value| u32::try_from(value).unwrap_or(0));
+        Ok(Some(Subprogram {
+            path,
+            file_index: file_index.unwrap_or(0),
+            line: number(line),
+            column: number(column),
+        }))
+    }
+}
+
+/// Returns the path of a file of the unit's line program, relative to the
+/// compilation directory when under it.
+fn file_path(
+    dwarf: &gimli::Dwarf<Reader<'_>>,
+    unit: &gimli::Unit<Reader<'_>>,
+    index: u64,
+) -> Option<String> {
+    let header = unit.line_program.as_ref()?.header();
+    let file = header.file(index)?;
+    let string = |value| {
+        dwarf
+            .attr_string(unit, value)
+            .ok()
+            .map(|value| value.to_string_lossy().into_owned())
+    };
+    let name = string(file.path_name())?;
+    let path = match file.directory(header).and_then(string) {
+        Some(directory) if !directory.is_empty() && !is_absolute(&name) => {
+            format!("{}/{}", directory.trim_end_matches(['/', '\\']), name)
+        }
+        _ => name,
+    };
+
+    let comp_dir = unit.comp_dir.map(|dir| dir.to_string_lossy().into_owned());
+    let relative = comp_dir.as_deref().and_then(|dir| {
+        path.strip_prefix(dir.trim_end_matches(['/', '\\']))?
+            .strip_prefix(['/', '\\'])
+    });
+    Some(relative.map_or_else(|| path.clone(), str::to_string))
+}
+
+/// Returns whether a path is absolute on Unix or Windows.
+fn is_absolute(path: &str) -> bool {
+    path.starts_with(['/', '\\']) || path.as_bytes().get(1) == Some(&b':')
+}
+
+/// Returns whether two paths name the same file, one possibly relative to
+/// a directory of the other.
+fn same_file(a: &str, b: &str) -> bool {
+    let (a, b) = (a.replace('\\', "/"), b.replace('\\', "/"));
+    let (short, long) = if a.len() <= b.len() {
+        (&a, &b)
+    } else {
+        (&b, &a)
+    };
+    long == short
+        || long
+            .strip_suffix(short.as_str())
+            .is_some_and(|prefix| prefix.ends_with('/'))
+}
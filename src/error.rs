@@ -0,0 +1,95 @@
+//! Errors shared by the reader and `cargo security-scan`.
+//!
+//! Every [`ScannerError`] has a stable, machine-readable [`code`](ScannerError::code),
+//! printed by the CLI's `--json` output, so automation can tell apart a
+//! binary without metadata (`format.no_metadata`) from one whose metadata is
+//! damaged (`format.corrupted`) without matching on messages:
+//!
+//! | Code                 | Meaning                                              |
+//! |----------------------|------------------------------------------------------|
+//! | `io`                 | A file or directory couldn't be read or written      |
+//! | `format.unsupported` | Not an ELF, Mach-O or PE/COFF file, or malformed     |
+//! | `format.no_metadata` | The binary has no metadata sections                  |
+//! | `format.corrupted`   | Metadata records failed integrity checking           |
+//! | `format.encrypted`   | Metadata records are encrypted with a key not given  |
+//! | `parse`              | Source code couldn't be parsed                       |
+//! | `usage`              | An argument is out of range or inconsistent          |
+//! | `policy`             | A policy check, such as a coverage threshold, failed |
+//! | `integration`        | An external tool the scanner drives failed           |
+
+use std::io;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// An error from reading binaries, scanning sources or checking policies.
+#[derive(Debug, Error)]
+pub enum ScannerError {
+    /// A file or directory couldn't be read or written
+    #[error("failed to {action} {}: {source}", path.display())]
+    Io {
+        /// What was being done, e.g. `read` or `write`
+        action: &'static str,
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// A binary is unsupported, lacks metadata or has damaged metadata
+    #[error(transparent)]
+    Format(#[from] FormatError),
+    /// Source code couldn't be parsed
+    #[error("failed to parse {}: {message}", path.display())]
+    Parse { path: PathBuf, message: String },
+    /// An argument is out of range or inconsistent
+    #[error("{0}")]
+    Usage(String),
+    /// A policy check failed
+    #[error("{0}")]
+    Policy(String),
+    /// An external tool failed
+    #[error("{0}")]
+    Integration(String),
+}
+
+/// What is wrong with a binary's metadata.
+#[derive(Debug, Error)]
+pub enum FormatError {
+    /// The file isn't a supported binary or is malformed
+    #[error("unsupported binary: {0}")]
+    Unsupported(String),
+    /// The binary has no metadata records section
+    #[error("binary contains no security test metadata")]
+    NoMetadata,
+    /// Records failed integrity checking
+    #[error("{count} metadata record(s) failed integrity checking")]
+    Corrupted { count: usize },
+    /// Records are encrypted and no key, or another key, was given
+    #[error("{count} metadata record(s) are encrypted with a key that wasn't given")]
+    Encrypted { count: usize },
+}
+
+impl ScannerError {
+    /// Creates an [`ScannerError::Io`] for `action` on `path`.
+    pub fn io(action: &'static str, path: impl Into<PathBuf>, source: io::Error) -> Self {
+        ScannerError::Io {
+            action,
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Returns the stable code identifying the kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ScannerError::Io { .. } => "io",
+            ScannerError::Format(FormatError::Unsupported(_)) => "format.unsupported",
+            ScannerError::Format(FormatError::NoMetadata) => "format.no_metadata",
+            ScannerError::Format(FormatError::Corrupted { .. }) => "format.corrupted",
+            ScannerError::Format(FormatError::Encrypted { .. }) => "format.encrypted",
+            ScannerError::Parse { .. } => "parse",
+            ScannerError::Usage(_) => "usage",
+            ScannerError::Policy(_) => "policy",
+            ScannerError::Integration(_) => "integration",
+        }
+    }
+}
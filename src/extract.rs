@@ -0,0 +1,925 @@
+//! Reading embedded metadata back out of compiled binaries.
+//!
+//! [`extract_file`] opens an ELF, Mach-O, PE/COFF or WebAssembly file (an
+//! executable, a shared library or an object file), finds the sections
+//! written by `#[security_test]`, validates every record and returns the
+//! decoded [`SecurityTestMetadata`] of each annotated function, along with
+//! the [`ProgramContext`] of `#[security_program]`. Requires the `scanner`
+//! feature.
+//!
+//! ```rust,no_run
+//! use security_scanner::extract;
+//!
+//! let extraction = extract::extract_file("target/debug/my_app")?;
+//! for function in &extraction.functions {
+//!     println!("{}: {}", function.function_name, function.config.threat_level);
+//! }
+//! if extraction.integrity.corrupted > 0 {
+//!     eprintln!("{} corrupted record(s) skipped", extraction.integrity.corrupted);
+//! }
+//! # Ok::<(), security_scanner::ScannerError>(())
+//! ```
+//!
+//! ## Format Versions
+//!
+//! Version 2 records carry every field inline and are decoded on their own.
+//! Version 1 records, written by earlier releases of the macro, hold the
+//! four original test flags and the threat level, with the function name in
+//! the names section. They can share the records section with version 2
+//! records when crates built by different releases are linked together.
+//!
+//! ## C and C++
+//!
+//! Functions annotated with `SECURITY_TEST(...)` from the format crate's
+//! `include/security_test.h` have version 2 records in the same section,
+//! read like those of Rust functions, so a binary linking C or C++ code
+//! yields one list. Their records have no checksum and count as
+//! [`unchecked`](IntegrityStats::unchecked).
+//!
+//! ## Generic Instances
+//!
+//! Records of one definition site, such as one per instance of a generic
+//! function, are merged into one function listing the instances, see
+//! [`SecurityTestMetadata::merge_instances`].
+//!
+//! ## Encrypted Records
+//!
+//! Records encrypted with `SECURITY_SCANNER_METADATA_KEY` at build time are
+//! read with [`extract_with_key`] and the same key. Without it they pass
+//! integrity checking but can't be decoded, and are counted in
+//! [`Extraction::encrypted`].
+//!
+//! ## Function Addresses
+//!
+//! Functions annotated with `anchor` have an entry in the addresses section,
+//! pointing at their record and their code, which gives
+//! [`SecurityTestMetadata::function_address`] even in stripped binaries.
+//! Other functions get theirs from the symbol table when it has a single
+//! copy of their code. Addresses are read from executables and shared
+//! libraries, not object files, and are those the file was linked with:
+//! a process that loaded the binary at some other address, as
+//! position-independent executables are under ASLR, has the function at
+//! `function_address - image_base + <load address>`. ARM addresses don't
+//! have the Thumb bit set.
+//!
+//! [`function_symbols`] reads the symbol and the file offset of each copy
+//! of a function's code as well, which uprobes attach to.
+//!
+//! ## WebAssembly
+//!
+//! WebAssembly modules keep the records in the `security_tests` custom
+//! section, read with `wasmparser`. Only version 2 records are written
+//! there, and the registry isn't available.
+//!
+//! ## Names of Version 1 Records
+//!
+//! The names section holds a `&'static str` per version 1 record, in record
+//! order. Its pointers, like those of the addresses section, are resolved
+//! through the file's relocations when it has them (object files,
+//! position-independent executables) and read directly otherwise. Mach-O
+//! chained fixups are decoded for rebases. A name that can't be resolved is
+//! left empty rather than failing the whole extraction.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+use object::{
+    Architecture, BinaryFormat, Object, ObjectKind, ObjectSection, ObjectSymbol, RelocationTarget,
+    SectionIndex, SymbolKind,
+};
+
+use crate::error::{FormatError, ScannerError};
+use crate::format::schema::SECTIONS;
+use crate::format::{scan_section, tlv, IntegrityStats, RECORD_SIZE};
+use crate::types::{CodeRange, ProgramContext, SecurityTestConfig, SecurityTestMetadata};
+
+/// Leading bytes of every WebAssembly module.
+pub(crate) const WASM_MAGIC: &[u8] = b"\0asm";
+
+/// The metadata found in one binary.
+#[derive(Debug, Clone, Default)]
+pub struct Extraction {
+    /// Decoded metadata of every valid record, in section order, with the
+    /// records of each definition site merged into one function
+    pub functions: Vec<SecurityTestMetadata>,
+    /// Context from the first `#[security_program]` record, if any
+    pub program: Option<ProgramContext>,
+    /// What integrity checking found in the records section
+    pub integrity: IntegrityStats,
+    /// Intact records that are encrypted and weren't decrypted, because no
+    /// key was given or they were encrypted with another
+    pub encrypted: usize,
+    /// Address the binary is linked to load at, 0 for position-independent
+    /// ELF files; see the [module documentation](self#function-addresses)
+    pub image_base: u64,
+}
+
+impl Extraction {
+    /// Fails with [`FormatError::Corrupted`] if any record in the section
+    /// was damaged or of an unsupported version, or else with
+    /// [`FormatError::Encrypted`] if any couldn't be decrypted, for callers
+    /// that don't accept partial results.
+    pub fn verify(&self) -> Result<(), FormatError> {
+        let stats = &self.integrity;
+        match stats.corrupted
+            + stats.foreign_endian
+            + stats.unsupported
+            + stats.truncated
+            + stats.unrecognized
+        {
+            0 if self.encrypted > 0 => Err(FormatError::Encrypted {
+                count: self.encrypted,
+            }),
+            0 => Ok(()),
+            count => Err(FormatError::Corrupted { count }),
+        }
+    }
+}
+
+/// Reads the metadata of the binary at `path`.
+pub fn extract_file(path: impl AsRef<Path>) -> Result<Extraction, ScannerError> {
+    let path = path.as_ref();
+    let data = std::fs::read(path).map_err(|err| ScannerError::io("read", path, err))?;
+    Ok(extract(&data)?)
+}
+
+/// Reads the metadata of a binary already in memory.
+pub fn extract(data: &[u8]) -> Result<Extraction, FormatError> {
+    extract_with_key(data, None)
+}
+
+/// Reads the metadata of a binary already in memory, decrypting encrypted
+/// records with `key`, as parsed by [`tlv::parse_key`].
+pub fn extract_with_key(
+    data: &[u8],
+    key: Option<&[u8; tlv::KEY_LEN]>,
+) -> Result<Extraction, FormatError> {
+    if data.starts_with(WASM_MAGIC) {
+        return extract_wasm(data, key);
+    }
+
+    let unsupported = |err: object::Error| FormatError::Unsupported(err.to_string());
+    let file = object::File::parse(data).map_err(unsupported)?;
+    let binary = Binary::new(&file);
+
+    let names = find_sections(&file, "names").next();
+    // Object files can have a records section per static, which the linker
+    // concatenates
+    let records: Vec<_> = find_sections(&file, "records").collect();
+    let records_data = match &records[..] {
+        [] => return Err(FormatError::NoMetadata),
+        [records] => Cow::Borrowed(records.data().map_err(unsupported)?),
+        all => {
+            let mut data = Vec::new();
+            for records in all {
+                data.extend_from_slice(records.data().map_err(unsupported)?);
+            }
+            Cow::Owned(data)
+        }
+    };
+    let scan = scan_section(&records_data);
+
+    let mut decoded = Decoded::new(scan.records.len());
+    // Where each function's version 2 record starts, for its address entry
+    let mut record_starts = HashMap::new();
+    // Bytes of version 2 records seen so far, which have no names entries
+    let mut tagged_bytes = 0;
+    for record in scan.records {
+        let offset = record.as_ptr() as usize - records_data.as_ptr() as usize;
+        if tlv::has_magic(record) {
+            tagged_bytes += record.len();
+            let index = decoded.functions.len();
+            decoded.add_tagged(record, key);
+            if decoded.functions.len() > index {
+                record_starts.insert(record_location(&records, offset), index);
+            }
+            continue;
+        }
+        let Some(config) = SecurityTestConfig::from_record(record) else {
+            continue;
+        };
+        // Names are in version 1 record order
+        let index = (offset - tagged_bytes) / RECORD_SIZE;
+        let name = names
+            .as_ref()
+            .map(|names| Location::new(names.index(), (index * 2 * binary.pointer_size) as u64));
+
+        decoded.functions.push(SecurityTestMetadata {
+            function_name: name
+                .and_then(|location| binary.read_str(location))
+                .unwrap_or_default(),
+            config,
+            location: None,
+            build: None,
+            function_address: None,
+            instances: Vec::new(),
+            exposure: None,
+        });
+    }
+
+    // Addresses in object files are offsets into sections that haven't
+    // been placed yet
+    if file.kind() != ObjectKind::Relocatable {
+        add_addresses(&file, &binary, &record_starts, &mut decoded.functions);
+    }
+    let mut extraction = decoded.finish(scan.stats);
+    extraction.image_base = file.relative_address_base();
+    Ok(extraction)
+}
+
+/// Finds the record at `offset` into the concatenated `records` sections.
+fn record_location(records: &[object::Section<'_, '_>], offset: usize) -> Location {
+    let mut start = 0;
+    for section in records {
+        let size = section.data().map_or(0, <[u8]>::len);
+        if offset < start + size {
+            return Location::new(section.index(), (offset - start) as u64);
+        }
+        start += size;
+    }
+    unreachable!("records are inside their sections")
+}
+
+/// Fills in the addresses of `functions` from the addresses section, where
+/// `record_starts` gives the function of each record, and from the symbol
+/// table for functions with a single copy of their code.
+fn add_addresses(
+    file: &object::File<'_>,
+    binary: &Binary<'_, '_>,
+    record_starts: &HashMap<Location, usize>,
+    functions: &mut [SecurityTestMetadata],
+) {
+    let pointer_size = binary.pointer_size as u64;
+    for section in find_sections(file, "addresses") {
+        for offset in (0..section.size() / (2 * pointer_size)).map(|index| index * 2 * pointer_size)
+        {
+            let entry = Location::new(section.index(), offset);
+            let Some(&index) = binary
+                .read_pointer(entry)
+                .and_then(|record| record_starts.get(&record))
+            else {
+                continue;
+            };
+            let Some(code) = binary.read_pointer(entry.add(pointer_size)) else {
+                continue;
+            };
+            let Ok(code_section) = file.section_by_index(code.section) else {
+                continue;
+            };
+            functions[index].function_address =
+                usize::try_from(code_address(file, code_section.address() + code.offset)).ok();
+        }
+    }
+
+    let Some(ranges) = code_ranges(file) else {
+        return;
+    };
+    for function in functions
+        .iter_mut()
+        .filter(|function| function.function_address.is_none())
+    {
+        if let Some([range]) = ranges.get(&function.function_name).map(Vec::as_slice) {
+            function.function_address = usize::try_from(range.address).ok();
+        }
+    }
+}
+
+/// Clears the bit of an ARM function address that marks Thumb code.
+fn code_address(file: &object::File<'_>, address: u64) -> u64 {
+    if file.architecture() == Architecture::Arm {
+        address & !1
+    } else {
+        address
+    }
+}
+
+/// Reads the records of a WebAssembly module from its custom section. Only
+/// version 2 records are written there, so there is no names section.
+fn extract_wasm(data: &[u8], key: Option<&[u8; tlv::KEY_LEN]>) -> Result<Extraction, FormatError> {
+    let name = SECTIONS
+        .iter()
+        .find_map(|section| section.wasm)
+        .unwrap_or_default();
+    // The linker concatenates same-named custom sections; object files may
+    // still have several
+    let mut records_data = Vec::new();
+    let mut found = false;
+    for payload in wasmparser::Parser::new(0).parse_all(data) {
+        let payload = payload.map_err(|err| FormatError::Unsupported(err.to_string()))?;
+        if let wasmparser::Payload::CustomSection(section) = payload {
+            if section.name() == name {
+                records_data.extend_from_slice(section.data());
+                found = true;
+            }
+        }
+    }
+    if !found {
+        return Err(FormatError::NoMetadata);
+    }
+
+    let scan = scan_section(&records_data);
+    let mut decoded = Decoded::new(scan.records.len());
+    for record in scan.records {
+        if tlv::has_magic(record) {
+            decoded.add_tagged(record, key);
+        }
+    }
+    Ok(decoded.finish(scan.stats))
+}
+
+/// The records decoded so far.
+struct Decoded {
+    functions: Vec<SecurityTestMetadata>,
+    /// The first program record found
+    program: Option<ProgramContext>,
+    /// Encrypted records that couldn't be decrypted
+    encrypted: usize,
+}
+
+impl Decoded {
+    fn new(records: usize) -> Self {
+        Decoded {
+            functions: Vec::with_capacity(records),
+            program: None,
+            encrypted: 0,
+        }
+    }
+
+    /// Decodes a version 2 record, decrypting it first if it is encrypted.
+    fn add_tagged(&mut self, record: &[u8], key: Option<&[u8; tlv::KEY_LEN]>) {
+        if tlv::is_encrypted(record) {
+            match key.and_then(|key| tlv::decrypt(record, key)) {
+                Some(plain) => self.add_tagged(&plain, None),
+                None => self.encrypted += 1,
+            }
+            return;
+        }
+        match ProgramContext::from_record(record) {
+            Some(context) => {
+                self.program.get_or_insert(context);
+            }
+            None => self
+                .functions
+                .extend(SecurityTestMetadata::from_record(record)),
+        }
+    }
+
+    /// Merges the records of each definition site, applies the program's
+    /// context to the functions and bundles the results.
+    fn finish(mut self, integrity: IntegrityStats) -> Extraction {
+        self.functions = SecurityTestMetadata::merge_instances(self.functions);
+        // The program's context applies to every function linked into it
+        if let Some(exposure) = self.program.as_ref().and_then(|program| program.exposure) {
+            for function in &mut self.functions {
+                function.exposure = Some(exposure);
+            }
+        }
+
+        Extraction {
+            functions: self.functions,
+            program: self.program,
+            integrity,
+            encrypted: self.encrypted,
+            image_base: 0,
+        }
+    }
+}
+
+/// Returns the functions that have code in the binary, read from its symbol
+/// table, by path such as `my_app::auth::login`, with the range of each copy
+/// of their code: generic functions have one per instance.
+///
+/// Annotated functions missing from the map were removed as dead code or
+/// inlined into every caller: their records survive through `#[used]`, but
+/// there is no code left to test. The ranges let binary-analysis engines
+/// inspect exactly the instructions of a function. Symbols without a size,
+/// as on Mach-O, extend to the next symbol of their section.
+///
+/// Returns `None` when the binary can't tell, because it has no symbols
+/// (stripped, or a PE image whose symbols are in a PDB) or is a WebAssembly
+/// module. Both legacy and v0 symbol mangling are read, along with the
+/// symbols of C functions and of C++ functions in namespaces and classes,
+/// for functions annotated with `SECURITY_TEST(...)`. Methods of trait impls
+/// and generic types, such as `<T as Trait>::method`, are spelled
+/// differently in symbols and won't be found; free functions and inherent
+/// methods of other types will.
+pub fn defined_functions(
+    data: &[u8],
+) -> Result<Option<HashMap<String, Vec<CodeRange>>>, FormatError> {
+    if data.starts_with(WASM_MAGIC) {
+        return Ok(None);
+    }
+    let file =
+        object::File::parse(data).map_err(|err| FormatError::Unsupported(err.to_string()))?;
+    Ok(code_ranges(&file))
+}
+
+/// Reads the ranges of [`defined_functions`] from a parsed file.
+fn code_ranges(file: &object::File<'_>) -> Option<HashMap<String, Vec<CodeRange>>> {
+    let symbols = symbol_table(file)?;
+    Some(
+        symbols
+            .into_iter()
+            .map(|(path, symbols)| (path, symbols.iter().map(|symbol| symbol.range).collect()))
+            .collect(),
+    )
+}
+
+/// A function symbol of a binary, for tools that attach to the symbol
+/// rather than reading its code, such as uprobes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSymbol {
+    /// The symbol as the symbol table spells it, mangled
+    pub symbol: String,
+    /// Where the function's code lies
+    pub range: CodeRange,
+    /// Offset of the first byte in the file, `None` when the code isn't
+    /// stored in the file
+    pub file_offset: Option<u64>,
+}
+
+/// Returns the function symbols of a binary by function path, like
+/// [`defined_functions`], with the symbol and the file offset of each copy.
+///
+/// uprobes are attached at a symbol of an ELF file or at the file offset of
+/// the code, which `bpftrace` and libbpf take as they are.
+pub fn function_symbols(
+    data: &[u8],
+) -> Result<Option<HashMap<String, Vec<FunctionSymbol>>>, FormatError> {
+    if data.starts_with(WASM_MAGIC) {
+        return Ok(None);
+    }
+    let file =
+        object::File::parse(data).map_err(|err| FormatError::Unsupported(err.to_string()))?;
+    Ok(symbol_table(&file))
+}
+
+/// Reads the symbols of [`function_symbols`] from a parsed file.
+fn symbol_table(file: &object::File<'_>) -> Option<HashMap<String, Vec<FunctionSymbol>>> {
+    let mut symbols = Vec::new();
+    for symbol in file.symbols() {
+        if symbol.kind() != SymbolKind::Text || !symbol.is_definition() {
+            continue;
+        }
+        let (Ok(name), Some(section)) = (symbol.name(), symbol.section_index()) else {
+            continue;
+        };
+        let address = code_address(file, symbol.address());
+        symbols.push((section, address, symbol.size(), name));
+    }
+    if symbols.is_empty() {
+        return None;
+    }
+    symbols.sort_unstable_by_key(|&(section, address, _, _)| (section.0, address));
+
+    let mut functions: HashMap<String, Vec<FunctionSymbol>> = HashMap::new();
+    for (index, &(section, address, size, name)) in symbols.iter().enumerate() {
+        // Mach-O prefixes every symbol with `_`
+        let path = name
+            .strip_prefix('_')
+            .filter(|_| file.format() == BinaryFormat::MachO)
+            .unwrap_or(name);
+        let Some(path) = symbol_path(path) else {
+            continue;
+        };
+        let code_section = file.section_by_index(section).ok();
+        let size = if size > 0 {
+            size
+        } else {
+            let next = symbols[index + 1..]
+                .iter()
+                .find(|&&(other, next, _, _)| other != section || next > address)
+                .filter(|&&(other, _, _, _)| other == section)
+                .map(|&(_, next, _, _)| next)
+                .or_else(|| {
+                    let section = code_section.as_ref()?;
+                    Some(section.address() + section.size())
+                });
+            next.map_or(0, |next| next.saturating_sub(address))
+        };
+        let file_offset = code_section.as_ref().and_then(|section| {
+            let (offset, _) = section.file_range()?;
+            Some(offset + address.checked_sub(section.address())?)
+        });
+        functions.entry(path).or_default().push(FunctionSymbol {
+            symbol: name.to_string(),
+            range: CodeRange { address, size },
+            file_offset,
+        });
+    }
+    Some(functions)
+}
+
+/// Returns the path of the function a symbol names, for Rust, C and C++
+/// symbols, without the `_` Mach-O prefixes symbols with.
+pub(crate) fn symbol_path(symbol: &str) -> Option<String> {
+    demangle_legacy(symbol)
+        .or_else(|| demangle_v0(symbol))
+        .or_else(|| c_name(symbol))
+}
+
+/// Decodes a legacy Rust symbol such as `_ZN3app4auth5login17h0123456789abcdefE`
+/// into `app::auth::login`, without the trailing hash.
+fn demangle_legacy(symbol: &str) -> Option<String> {
+    let mut rest = symbol.strip_prefix("_ZN")?;
+    let mut segments = Vec::new();
+    while !rest.starts_with('E') {
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let len: usize = rest[..digits].parse().ok()?;
+        let segment = rest.get(digits..digits + len)?;
+        rest = &rest[digits + len..];
+        segments.push(segment);
+    }
+    if segments.last().is_some_and(|last| {
+        last.len() == 17
+            && last.starts_with('h')
+            && last[1..].chars().all(|c| c.is_ascii_hexdigit())
+    }) {
+        segments.pop();
+    }
+
+    let mut path = String::new();
+    for segment in segments {
+        if !path.is_empty() {
+            path.push_str("::");
+        }
+        path.push_str(&unescape_segment(segment)?);
+    }
+    Some(path)
+}
+
+/// Returns the name of the C function whose symbol is `symbol`, or of the
+/// C++ function outside any namespace, such as `parse` for `_Z5parsePKc`.
+/// Functions in namespaces use the nested names of legacy Rust symbols.
+fn c_name(symbol: &str) -> Option<String> {
+    if let Some(rest) = symbol.strip_prefix("_Z") {
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let len: usize = rest[..digits].parse().ok()?;
+        return rest.get(digits..digits + len).map(str::to_string);
+    }
+    let identifier = symbol
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && symbol
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    identifier.then(|| symbol.to_string())
+}
+
+/// Decodes a v0 Rust symbol naming a function in a module or an inherent
+/// method, such as `_RNvNtCs1234_3app4auth5login`, into `app::auth::login`.
+/// Trait impls, generic instances, closures and shims aren't decoded.
+fn demangle_v0(symbol: &str) -> Option<String> {
+    let mut parser = V0 {
+        symbol: symbol.strip_prefix("_R")?,
+        at: 0,
+        depth: 0,
+    };
+    parser.path().map(|segments| segments.join("::"))
+}
+
+/// Cursor over a v0 symbol after its `_R` prefix; backreferences are offsets
+/// from there.
+struct V0<'a> {
+    symbol: &'a str,
+    at: usize,
+    depth: usize,
+}
+
+impl V0<'_> {
+    fn next(&mut self) -> Option<u8> {
+        let byte = *self.symbol.as_bytes().get(self.at)?;
+        self.at += 1;
+        Some(byte)
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let matched = self.symbol.as_bytes().get(self.at) == Some(&byte);
+        self.at += usize::from(matched);
+        matched
+    }
+
+    fn path(&mut self) -> Option<Vec<String>> {
+        // Backreferences can't loop in valid symbols, but this is input
+        self.depth += 1;
+        if self.depth > 64 {
+            return None;
+        }
+        let segments = match self.next()? {
+            b'C' => {
+                self.disambiguator()?;
+                vec![self.ident()?]
+            }
+            b'N' => {
+                // Upper-case namespaces are closures and shims
+                if !self.next()?.is_ascii_lowercase() {
+                    return None;
+                }
+                let mut segments = self.path()?;
+                self.disambiguator()?;
+                segments.push(self.ident()?);
+                segments
+            }
+            // An inherent impl: the module it's in, then the type
+            b'M' => {
+                self.disambiguator()?;
+                self.path()?;
+                self.path()?
+            }
+            b'B' => {
+                let offset = self.base62()?;
+                let resume = std::mem::replace(&mut self.at, offset);
+                let segments = self.path()?;
+                self.at = resume;
+                segments
+            }
+            _ => return None,
+        };
+        self.depth -= 1;
+        Some(segments)
+    }
+
+    /// Skips an `s<base-62>` disambiguator, if there is one.
+    fn disambiguator(&mut self) -> Option<()> {
+        if self.eat(b's') {
+            self.base62()?;
+        }
+        Some(())
+    }
+
+    /// Reads a `_`-terminated base-62 number, `_` alone being 0.
+    fn base62(&mut self) -> Option<usize> {
+        if self.eat(b'_') {
+            return Some(0);
+        }
+        let mut value = 0usize;
+        loop {
+            let digit = match self.next()? {
+                b'_' => return value.checked_add(1),
+                byte @ b'0'..=b'9' => byte - b'0',
+                byte @ b'a'..=b'z' => byte - b'a' + 10,
+                byte @ b'A'..=b'Z' => byte - b'A' + 36,
+                _ => return None,
+            };
+            value = value.checked_mul(62)?.checked_add(usize::from(digit))?;
+        }
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        // Punycode identifiers start with `u`; they aren't decoded
+        let rest = self.symbol.get(self.at..)?;
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let len: usize = rest[..digits].parse().ok()?;
+        // An `_` separates the length from identifiers starting with a digit
+        // or `_`
+        let start = digits + usize::from(rest[digits..].starts_with('_'));
+        let ident = rest.get(start..start + len)?;
+        self.at += start + len;
+        Some(ident.to_string())
+    }
+}
+
+/// Undoes the `$..$` escapes and `..` separators of a legacy symbol segment.
+fn unescape_segment(segment: &str) -> Option<String> {
+    // A leading `_` keeps segments from starting with `$`
+    let mut rest = segment
+        .strip_prefix("_$")
+        .map_or(segment, |_| &segment[1..]);
+    let mut out = String::with_capacity(rest.len());
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("..") {
+            out.push_str("::");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('$') {
+            let end = after.find('$')?;
+            out.push(match &after[..end] {
+                "SP" => '@',
+                "BP" => '*',
+                "RF" => '&',
+                "LT" => '<',
+                "GT" => '>',
+                "LP" => '(',
+                "RP" => ')',
+                "C" => ',',
+                code => char::from_u32(u32::from_str_radix(code.strip_prefix('u')?, 16).ok()?)?,
+            });
+            rest = &after[end + 1..];
+        } else {
+            let end = rest.find(['$', '.']).unwrap_or(rest.len()).max(1);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+    Some(out)
+}
+
+/// Finds the metadata sections with a schema name, using the section name of
+/// the file's object format.
+fn find_sections<'data, 'file>(
+    file: &'file object::File<'data>,
+    name: &str,
+) -> impl Iterator<Item = object::Section<'data, 'file>> {
+    let section = SECTIONS.iter().find(|section| section.name == name);
+    file.sections().filter(move |candidate| {
+        let Some(section) = section else {
+            return false;
+        };
+        let Ok(name) = candidate.name() else {
+            return false;
+        };
+        match file.format() {
+            BinaryFormat::Elf => name == section.elf,
+            BinaryFormat::MachO => {
+                let (segment, sectname) = section.macho.split_once(',').unwrap_or_default();
+                name == sectname && candidate.segment_name().ok().flatten() == Some(segment)
+            }
+            // Linkers truncate section names to 8 bytes in PE images; object
+            // files keep longer names in the string table
+            BinaryFormat::Coff | BinaryFormat::Pe => {
+                name == section.coff || (name.len() == 8 && section.coff.starts_with(name))
+            }
+            _ => false,
+        }
+    })
+}
+
+/// A position within a section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Location {
+    section: SectionIndex,
+    offset: u64,
+}
+
+impl Location {
+    fn new(section: SectionIndex, offset: u64) -> Self {
+        Location { section, offset }
+    }
+
+    fn add(self, bytes: u64) -> Self {
+        Location::new(self.section, self.offset + bytes)
+    }
+}
+
+/// Resolves pointers stored in a binary's data sections.
+struct Binary<'data, 'file> {
+    file: &'file object::File<'data>,
+    pointer_size: usize,
+    little_endian: bool,
+    /// Where each relocated pointer of an object file points
+    relocations: HashMap<Location, Location>,
+    /// Targets of an executable's dynamic relocations, by address
+    dynamic_relocations: HashMap<u64, u64>,
+}
+
+impl<'data, 'file> Binary<'data, 'file> {
+    fn new(file: &'file object::File<'data>) -> Self {
+        let mut binary = Binary {
+            file,
+            // `is_64` is always false for COFF objects, whatever the machine
+            pointer_size: match file.architecture().address_size() {
+                Some(size) => usize::from(size.bytes()),
+                None if file.is_64() => 8,
+                None => 4,
+            },
+            little_endian: file.is_little_endian(),
+            relocations: HashMap::new(),
+            dynamic_relocations: HashMap::new(),
+        };
+
+        for section in file.sections() {
+            for (offset, relocation) in section.relocations() {
+                let location = Location::new(section.index(), offset);
+                let addend = if relocation.has_implicit_addend() {
+                    match binary.read_uint(location, usize::from(relocation.size() / 8)) {
+                        Some(value) => value as i64,
+                        None => continue,
+                    }
+                } else {
+                    relocation.addend()
+                };
+                if let Some(target) = binary.relocation_target(relocation.target(), addend) {
+                    binary.relocations.insert(location, target);
+                }
+            }
+        }
+
+        if let Some(relocations) = file.dynamic_relocations() {
+            for (address, relocation) in relocations {
+                // Relative relocations: the addend is the target address
+                if relocation.target() == RelocationTarget::Absolute {
+                    binary
+                        .dynamic_relocations
+                        .insert(address, relocation.addend() as u64);
+                }
+            }
+        }
+
+        binary
+    }
+
+    fn relocation_target(&self, target: RelocationTarget, addend: i64) -> Option<Location> {
+        match target {
+            RelocationTarget::Symbol(index) => {
+                let symbol = self.file.symbol_by_index(index).ok()?;
+                let section = self.file.section_by_index(symbol.section_index()?).ok()?;
+                let offset = symbol.address().wrapping_sub(section.address());
+                Some(Location::new(
+                    section.index(),
+                    offset.wrapping_add(addend as u64),
+                ))
+            }
+            RelocationTarget::Section(index) => {
+                let section = self.file.section_by_index(index).ok()?;
+                // Mach-O section relocations store the absolute target
+                // address in place of an addend
+                let offset = if self.file.format() == BinaryFormat::MachO {
+                    (addend as u64).wrapping_sub(section.address())
+                } else {
+                    addend as u64
+                };
+                Some(Location::new(index, offset))
+            }
+            _ => None,
+        }
+    }
+
+    fn bytes(&self, location: Location, len: usize) -> Option<&'data [u8]> {
+        let data = self
+            .file
+            .section_by_index(location.section)
+            .ok()?
+            .data()
+            .ok()?;
+        let start = usize::try_from(location.offset).ok()?;
+        data.get(start..start.checked_add(len)?)
+    }
+
+    /// Reads an unsigned integer of `size` bytes, `None` for sizes over 8.
+    fn read_uint(&self, location: Location, size: usize) -> Option<u64> {
+        if size > 8 {
+            return None;
+        }
+        let bytes = self.bytes(location, size)?;
+        let mut buf = [0u8; 8];
+        if self.little_endian {
+            buf[..size].copy_from_slice(bytes);
+            Some(u64::from_le_bytes(buf))
+        } else {
+            buf[8 - size..].copy_from_slice(bytes);
+            Some(u64::from_be_bytes(buf))
+        }
+    }
+
+    /// Reads the pointer at `location` and finds where it points.
+    fn read_pointer(&self, location: Location) -> Option<Location> {
+        if let Some(target) = self.relocations.get(&location) {
+            return Some(*target);
+        }
+
+        let section = self.file.section_by_index(location.section).ok()?;
+        let address = section.address() + location.offset;
+        if let Some(target) = self.dynamic_relocations.get(&address) {
+            return self.locate(*target);
+        }
+
+        let value = self.read_uint(location, self.pointer_size)?;
+        self.locate(value)
+            .or_else(|| self.chained_fixup_target(value))
+    }
+
+    /// Decodes a Mach-O chained fixup rebase, which packs the target (an
+    /// address or an offset from the image base) into the low 36 bits.
+    fn chained_fixup_target(&self, value: u64) -> Option<Location> {
+        if self.file.format() != BinaryFormat::MachO || value >> 63 != 0 {
+            return None;
+        }
+        let target = value & 0xF_FFFF_FFFF;
+        self.locate(target)
+            .or_else(|| self.locate(self.file.relative_address_base() + target))
+    }
+
+    /// Finds the section containing a virtual address.
+    fn locate(&self, address: u64) -> Option<Location> {
+        if address == 0 {
+            return None;
+        }
+        self.file
+            .sections()
+            .find(|section| {
+                section.address() <= address && address < section.address() + section.size()
+            })
+            .map(|section| Location::new(section.index(), address - section.address()))
+    }
+
+    /// Reads a `&'static str` stored at `location`.
+    fn read_str(&self, location: Location) -> Option<String> {
+        let len = self.read_uint(location.add(self.pointer_size as u64), self.pointer_size)?;
+        if len == 0 {
+            return Some(String::new());
+        }
+        let bytes = self.bytes(self.read_pointer(location)?, usize::try_from(len).ok()?)?;
+        std::str::from_utf8(bytes).ok().map(str::to_string)
+    }
+}
@@ -0,0 +1,225 @@
+//! Stable fingerprints identifying a finding across scans.
+//!
+//! A fingerprint identifies *what* was found, not *where* the code currently
+//! lives. It is derived from:
+//!
+//! 1. the crate name,
+//! 2. the function path within that crate,
+//! 3. the [`TestType`] that produced the finding, and
+//! 4. a normalized payload class (e.g. `union_select`, `stacked_query`).
+//!
+//! File names, line numbers and concrete payload bytes are deliberately left
+//! out, so moving a function within its module, splitting a file or retrying
+//! with a different payload of the same class keeps the fingerprint (and any
+//! triage decision attached to it) intact. Moving a function to a different
+//! module changes its path and therefore its fingerprint.
+//!
+//! ## Normalization
+//!
+//! - A leading `crate::` or `<crate name>::` is stripped from the path, so
+//!   `my_crate::auth::login` and `auth::login` are the same function.
+//! - Whitespace and generic arguments (`::<T>`, `<T>`) are removed, so
+//!   `parse::<u32>` and `parse` are the same function.
+//! - The payload class is lowercased, and runs of spaces, `-` and `_`
+//!   collapse to a single `_`.
+//!
+//! The normalized fields are joined with `0x1F` separators under a `v1` prefix
+//! and hashed with 64-bit FNV-1a. The algorithm is versioned through that
+//! prefix; changing any step above requires a new version.
+//!
+//! ```rust
+//! use security_scanner::fingerprint::Fingerprint;
+//! use security_scanner::TestType;
+//!
+//! let before = Fingerprint::new(
+//!     "shop",
+//!     "crate::auth::login",
+//!     TestType::SqlInjection,
+//!     "Union Select",
+//! );
+//! let after = Fingerprint::new(
+//!     "shop",
+//!     "shop::auth::login",
+//!     TestType::SqlInjection,
+//!     "union-select",
+//! );
+//! assert_eq!(before, after);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::types::TestType;
+
+/// Version prefix mixed into every fingerprint.
+const ALGORITHM_VERSION: &str = "v1";
+
+/// Separator between normalized fields (ASCII unit separator).
+const FIELD_SEPARATOR: u8 = 0x1F;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A stable identifier for a finding, rendered as 16 lowercase hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint(u64);
+
+impl Fingerprint {
+    /// Computes the fingerprint of a finding.
+    pub fn new(
+        crate_name: &str,
+        function_path: &str,
+        test_type: TestType,
+        payload_class: &str,
+    ) -> Self {
+        let crate_name = crate_name.trim().replace('-', "_");
+        let function_path = normalize_path(&crate_name, function_path);
+        let payload_class = normalize_payload_class(payload_class);
+
+        let fields = [
+            ALGORITHM_VERSION,
+            crate_name.as_str(),
+            function_path.as_str(),
+            test_type.as_str(),
+            payload_class.as_str(),
+        ];
+
+        let mut hash = FNV_OFFSET_BASIS;
+        for (index, field) in fields.iter().enumerate() {
+            if index > 0 {
+                hash = fnv1a(hash, &[FIELD_SEPARATOR]);
+            }
+            hash = fnv1a(hash, field.as_bytes());
+        }
+
+        Fingerprint(hash)
+    }
+
+    /// Returns the raw 64-bit value.
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Error returned when parsing a malformed fingerprint string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFingerprintError(String);
+
+impl fmt::Display for ParseFingerprintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid fingerprint \"{}\", expected 16 hex digits",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseFingerprintError {}
+
+impl FromStr for Fingerprint {
+    type Err = ParseFingerprintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 16 {
+            return Err(ParseFingerprintError(s.to_string()));
+        }
+        u64::from_str_radix(s, 16)
+            .map(Fingerprint)
+            .map_err(|_| ParseFingerprintError(s.to_string()))
+    }
+}
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Strips whitespace, generic arguments and the crate prefix from a path.
+///
+/// A leading qualified-self segment (`<Session as Drop>::drop`) is kept, since
+/// it is what distinguishes one trait impl's method from another's.
+fn normalize_path(crate_name: &str, path: &str) -> String {
+    let path: String = path.chars().filter(|c| !c.is_whitespace()).collect();
+    let (qualified_self, rest) = split_qualified_self(&path);
+
+    let mut normalized = String::with_capacity(rest.len());
+    let mut generic_depth = 0usize;
+    for c in rest.chars() {
+        match c {
+            '<' => generic_depth += 1,
+            '>' => generic_depth = generic_depth.saturating_sub(1),
+            c if generic_depth == 0 => normalized.push(c),
+            _ => {}
+        }
+    }
+
+    // Generic arguments written as `::<T>` leave a dangling `::` behind
+    let mut normalized = normalized.replace("::::", "::");
+    while normalized.ends_with("::") {
+        normalized.truncate(normalized.len() - 2);
+    }
+
+    if !qualified_self.is_empty() {
+        return format!("{}{}", qualified_self, normalized);
+    }
+
+    let stripped = normalized.strip_prefix("crate::").or_else(|| {
+        normalized
+            .strip_prefix(crate_name)
+            .and_then(|rest| rest.strip_prefix("::"))
+    });
+    stripped.map(str::to_string).unwrap_or(normalized)
+}
+
+/// Splits a leading `<...>` qualified-self segment off a whitespace-free path.
+fn split_qualified_self(path: &str) -> (&str, &str) {
+    if !path.starts_with('<') {
+        return ("", path);
+    }
+
+    let mut depth = 0usize;
+    for (index, c) in path.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return path.split_at(index + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    (path, "")
+}
+
+/// Lowercases a payload class and collapses separators to single `_`.
+fn normalize_payload_class(class: &str) -> String {
+    let mut normalized = String::with_capacity(class.len());
+    let mut pending_separator = false;
+
+    for c in class.trim().chars() {
+        if c.is_whitespace() || c == '-' || c == '_' {
+            pending_separator = !normalized.is_empty();
+        } else {
+            if pending_separator {
+                normalized.push('_');
+                pending_separator = false;
+            }
+            normalized.extend(c.to_lowercase());
+        }
+    }
+
+    normalized
+}
@@ -0,0 +1,250 @@
+//! Exploit mitigations a binary was built with.
+//!
+//! [`Hardening::read`] tells which of the usual mitigations against memory
+//! corruption a binary has, the way `checksec` does, so scan reports can
+//! show them next to the annotations. Requires the `scanner` feature.
+//!
+//! ```rust,no_run
+//! use security_scanner::hardening::Hardening;
+//!
+//! let data = std::fs::read("target/release/my_app")?;
+//! if let Some(hardening) = Hardening::read(&data)? {
+//!     println!("{}", hardening);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Each property is `None` where the format has no such mitigation or the
+//! binary can't tell:
+//!
+//! | Property          | ELF                        | Mach-O                        | PE                 |
+//! |-------------------|----------------------------|-------------------------------|--------------------|
+//! | `pie`             | `ET_DYN`                   | `MH_PIE`                      | `DYNAMIC_BASE`     |
+//! | `relro`           | `PT_GNU_RELRO`, `BIND_NOW` | -                             | -                  |
+//! | `nx`              | `PT_GNU_STACK` without `X` | no `MH_ALLOW_STACK_EXECUTION` | `NX_COMPAT`        |
+//! | `stack_protector` | `__stack_chk_fail` symbol  | `___stack_chk_fail` symbol    | load config cookie |
+//! | `fortify`         | `__*_chk` symbols          | `___*_chk` symbols            | -                  |
+//! | `cfg`             | -                          | -                             | `GUARD_CF`         |
+//!
+//! Stack protectors and fortified calls are found by the symbols of the
+//! functions they call, so they say whether any code in the binary has
+//! them, usually C code. rustc only emits stack protectors with the
+//! unstable `-Z stack-protector`, and the MSVC C runtime brings a security
+//! cookie into every PE image, so neither says much about the Rust code
+//! of a binary.
+
+use std::fmt;
+
+use object::elf;
+use object::pe;
+use object::read::elf::{Dyn, ElfFile, FileHeader, ProgramHeader};
+use object::read::pe::{ImageNtHeaders, ImageOptionalHeader, PeFile};
+use object::{FileFlags, Object, ObjectSymbol};
+
+use crate::error::FormatError;
+use crate::extract::WASM_MAGIC;
+
+/// How much of the relocation tables is made read-only after loading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Relro {
+    /// Nothing
+    None,
+    /// Everything but the GOT entries bound lazily
+    Partial,
+    /// Everything, with every symbol bound at load time
+    Full,
+}
+
+impl fmt::Display for Relro {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Relro::None => "none",
+            Relro::Partial => "partial",
+            Relro::Full => "full",
+        })
+    }
+}
+
+/// The mitigations of one binary, `None` where they don't apply.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Hardening {
+    /// Position independent, so ASLR can load it anywhere
+    pub pie: Option<bool>,
+    /// Read-only relocations
+    pub relro: Option<Relro>,
+    /// Non-executable stack and data
+    pub nx: Option<bool>,
+    /// Stack canaries
+    pub stack_protector: Option<bool>,
+    /// `_FORTIFY_SOURCE` checked libc calls
+    pub fortify: Option<bool>,
+    /// Windows Control Flow Guard
+    pub cfg: Option<bool>,
+}
+
+impl Hardening {
+    /// Reads the mitigations of a binary, `None` for WebAssembly modules
+    /// and object files, which have none of their own.
+    pub fn read(data: &[u8]) -> Result<Option<Self>, FormatError> {
+        if data.starts_with(WASM_MAGIC) {
+            return Ok(None);
+        }
+        let unsupported = |err: object::Error| FormatError::Unsupported(err.to_string());
+        let file = object::File::parse(data).map_err(unsupported)?;
+        if file.kind() == object::ObjectKind::Relocatable {
+            return Ok(None);
+        }
+
+        let mut hardening = match &file {
+            object::File::Elf32(elf) => read_elf(elf),
+            object::File::Elf64(elf) => read_elf(elf),
+            object::File::Pe32(pe) => read_pe(pe),
+            object::File::Pe64(pe) => read_pe(pe),
+            _ => match file.flags() {
+                FileFlags::MachO { flags } => Hardening {
+                    pie: Some(flags & object::macho::MH_PIE != 0),
+                    nx: Some(flags & object::macho::MH_ALLOW_STACK_EXECUTION == 0),
+                    ..Hardening::default()
+                },
+                _ => Hardening::default(),
+            },
+        };
+
+        if matches!(
+            file.format(),
+            object::BinaryFormat::Elf | object::BinaryFormat::MachO
+        ) {
+            // Mach-O prefixes every symbol with `_`
+            let prefix = match file.format() {
+                object::BinaryFormat::MachO => "___",
+                _ => "__",
+            };
+            let mut stack_protector = false;
+            let mut fortify = false;
+            for symbol in file.symbols().chain(file.dynamic_symbols()) {
+                let Some(name) = symbol
+                    .name()
+                    .ok()
+                    .and_then(|name| name.strip_prefix(prefix))
+                else {
+                    continue;
+                };
+                let name = name.split('@').next().unwrap_or(name);
+                if name == "stack_chk_fail" {
+                    stack_protector = true;
+                } else if name.ends_with("_chk") {
+                    fortify = true;
+                }
+            }
+            hardening.stack_protector = Some(stack_protector);
+            hardening.fortify = Some(fortify);
+        }
+        Ok(Some(hardening))
+    }
+
+    /// Returns the mitigations against buffer overflows that the binary
+    /// lacks: stack canaries, a non-executable stack and ASLR.
+    pub fn missing_overflow_mitigations(&self) -> Vec<&'static str> {
+        [
+            (self.stack_protector, "stack canaries"),
+            (self.nx, "NX"),
+            (self.pie, "PIE"),
+        ]
+        .into_iter()
+        .filter(|&(present, _)| present == Some(false))
+        .map(|(_, name)| name)
+        .collect()
+    }
+}
+
+impl fmt::Display for Hardening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let yes_no = |value: bool| if value { "yes" } else { "no" };
+        let properties = [
+            ("PIE", self.pie.map(yes_no).map(str::to_string)),
+            ("RELRO", self.relro.map(|relro| relro.to_string())),
+            ("NX", self.nx.map(yes_no).map(str::to_string)),
+            (
+                "stack canaries",
+                self.stack_protector.map(yes_no).map(str::to_string),
+            ),
+            ("fortify", self.fortify.map(yes_no).map(str::to_string)),
+            ("CFG", self.cfg.map(yes_no).map(str::to_string)),
+        ];
+        let mut first = true;
+        for (name, value) in properties {
+            let Some(value) = value else {
+                continue;
+            };
+            if !first {
+                f.write_str(", ")?;
+            }
+            write!(f, "{} {}", name, value)?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the mitigations of an ELF file from its program headers and
+/// dynamic table.
+fn read_elf<Elf: FileHeader<Endian = object::Endianness>>(file: &ElfFile<'_, Elf>) -> Hardening {
+    let endian = file.endian();
+    let data = file.data();
+    let headers = file.elf_program_headers();
+    let has = |kind: u32| headers.iter().any(|header| header.p_type(endian) == kind);
+
+    let mut bind_now = false;
+    for header in headers {
+        let Ok(Some(dynamic)) = header.dynamic(endian, data) else {
+            continue;
+        };
+        for entry in dynamic {
+            let value: u64 = entry.d_val(endian).into();
+            bind_now |= match entry.tag32(endian) {
+                Some(elf::DT_BIND_NOW) => true,
+                Some(elf::DT_FLAGS) => value & u64::from(elf::DF_BIND_NOW) != 0,
+                Some(elf::DT_FLAGS_1) => value & u64::from(elf::DF_1_NOW) != 0,
+                _ => false,
+            };
+        }
+    }
+
+    let stack = headers
+        .iter()
+        .find(|header| header.p_type(endian) == elf::PT_GNU_STACK);
+    Hardening {
+        pie: Some(file.elf_header().e_type(endian) == elf::ET_DYN),
+        relro: Some(match (has(elf::PT_GNU_RELRO), bind_now) {
+            (false, _) => Relro::None,
+            (true, false) => Relro::Partial,
+            (true, true) => Relro::Full,
+        }),
+        // Without the header, loaders make the stack executable
+        nx: Some(stack.is_some_and(|stack| stack.p_flags(endian) & elf::PF_X == 0)),
+        ..Hardening::default()
+    }
+}
+
+/// Reads the mitigations of a PE image from its DLL characteristics and
+/// load configuration.
+fn read_pe<Pe: ImageNtHeaders>(file: &PeFile<'_, Pe>) -> Hardening {
+    let characteristics = file.nt_headers().optional_header().dll_characteristics();
+    let has = |flag: u16| Some(characteristics & flag != 0);
+
+    // Offset of `SecurityCookie` in the load configuration directory
+    let (cookie_offset, pointer_size) = if file.is_64() { (88, 8) } else { (60, 4) };
+    let cookie = file
+        .data_directory(pe::IMAGE_DIRECTORY_ENTRY_LOAD_CONFIG)
+        .and_then(|directory| directory.data(file.data(), &file.section_table()).ok())
+        .and_then(|config| config.get(cookie_offset..cookie_offset + pointer_size))
+        .map(|cookie| cookie.iter().any(|&byte| byte != 0));
+
+    Hardening {
+        pie: has(pe::IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE),
+        nx: has(pe::IMAGE_DLLCHARACTERISTICS_NX_COMPAT),
+        stack_protector: Some(cookie.unwrap_or(false)),
+        cfg: has(pe::IMAGE_DLLCHARACTERISTICS_GUARD_CF),
+        ..Hardening::default()
+    }
+}
@@ -0,0 +1,86 @@
+//! Display names of threat levels and test types.
+//!
+//! Reports show threat levels and test types in prose, where `sql_injection`
+//! reads worse than "SQL injection" and teams may want their own language or
+//! terminology. [`DisplayNames`] holds a name for each, English by default,
+//! that can be replaced one at a time:
+//!
+//! ```rust
+//! use security_scanner::names::DisplayNames;
+//! use security_scanner::{TestType, ThreatLevel};
+//!
+//! let mut names = DisplayNames::default();
+//! assert_eq!(names.test_type(TestType::SqlInjection), "SQL injection");
+//!
+//! names.rename_threat_level(ThreatLevel::Critical, "kritisch");
+//! names.rename_test_type(TestType::SqlInjection, "SQL-Injektion");
+//! assert_eq!(names.threat_level(ThreatLevel::Critical), "kritisch");
+//! assert_eq!(names.threat_level(ThreatLevel::High), "high");
+//! assert_eq!(names.test_type(TestType::SqlInjection), "SQL-Injektion");
+//! ```
+//!
+//! Only text meant for people uses them. Identifiers that tools match on,
+//! such as SARIF rule IDs, JSON reports and the arguments of
+//! `#[security_test(...)]`, keep [`ThreatLevel::as_str`] and
+//! [`TestType::as_str`].
+
+use crate::types::{TestType, ThreatLevel};
+
+/// A display name for every threat level and test type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DisplayNames {
+    /// Replaced names, by threat level byte
+    threat_levels: [Option<String>; 4],
+    /// Replaced names, by test type discriminant
+    test_types: [Option<String>; TestType::ALL.len()],
+}
+
+impl DisplayNames {
+    /// Returns the name shown for a threat level.
+    pub fn threat_level(&self, threat_level: ThreatLevel) -> &str {
+        self.threat_levels[threat_level as usize]
+            .as_deref()
+            .unwrap_or_else(|| english_threat_level(threat_level))
+    }
+
+    /// Returns the name shown for a test type.
+    pub fn test_type(&self, test_type: TestType) -> &str {
+        self.test_types[test_type as usize]
+            .as_deref()
+            .unwrap_or_else(|| english_test_type(test_type))
+    }
+
+    /// Shows `name` for a threat level from now on.
+    pub fn rename_threat_level(&mut self, threat_level: ThreatLevel, name: impl Into<String>) {
+        self.threat_levels[threat_level as usize] = Some(name.into());
+    }
+
+    /// Shows `name` for a test type from now on.
+    pub fn rename_test_type(&mut self, test_type: TestType, name: impl Into<String>) {
+        self.test_types[test_type as usize] = Some(name.into());
+    }
+}
+
+/// Returns the default name of a threat level.
+pub fn english_threat_level(threat_level: ThreatLevel) -> &'static str {
+    // The identifiers are already English words
+    threat_level.as_str()
+}
+
+/// Returns the default name of a test type.
+pub fn english_test_type(test_type: TestType) -> &'static str {
+    match test_type {
+        TestType::SqlInjection => "SQL injection",
+        TestType::RaceCondition => "race condition",
+        TestType::TimingAttack => "timing attack",
+        TestType::BufferOverflow => "buffer overflow",
+        TestType::EnvInjection => "environment variable injection",
+        TestType::Xss => "cross-site scripting",
+        TestType::CommandInjection => "OS command injection",
+        TestType::PathTraversal => "path traversal",
+        TestType::Ssrf => "server-side request forgery",
+        TestType::Xxe => "XML external entity injection",
+        TestType::Deserialization => "untrusted deserialization",
+        TestType::OpenRedirect => "open redirect",
+    }
+}
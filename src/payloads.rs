@@ -0,0 +1,82 @@
+//! Payload corpora for the tests that `generate_tests` adds to annotated
+//! functions.
+//!
+//! `#[security_test(sql_injection, generate_tests)]` generates a test per
+//! string parameter, which passes every payload of [`SQL_INJECTION`] to the
+//! function through [`run`]. The corpora can also drive hand-written tests:
+//!
+//! ```rust
+//! use security_scanner::payloads::{self, SQL_INJECTION};
+//!
+//! fn find_user(name: &str) -> Result<u64, String> {
+//!     if name.chars().all(|c| c.is_ascii_alphanumeric()) {
+//!         Ok(1)
+//!     } else {
+//!         Err(format!("invalid user name {:?}", name))
+//!     }
+//! }
+//!
+//! payloads::run("find_user", "name", SQL_INJECTION, |payload| {
+//!     assert!(find_user(payload).is_err());
+//! });
+//! ```
+
+use std::panic::{self, AssertUnwindSafe};
+
+/// Classic SQL injection payloads: tautologies, comment and quote
+/// terminators, stacked queries, `UNION` extraction, time-based blind
+/// probes, and encodings that slip quotes past naive filters.
+pub const SQL_INJECTION: &[&str] = &[
+    // Tautologies
+    "' OR 1=1 --",
+    "' OR '1'='1",
+    "\" OR \"1\"=\"1",
+    "1 OR 1=1",
+    "admin' --",
+    "admin' #",
+    "') OR ('1'='1",
+    // Stacked queries
+    "'; DROP TABLE users; --",
+    "1; DELETE FROM users",
+    "'; INSERT INTO users (name) VALUES ('mallory'); --",
+    // UNION extraction
+    "' UNION SELECT NULL --",
+    "' UNION SELECT username, password FROM users --",
+    // Blind probes
+    "' AND SLEEP(5) --",
+    "'; WAITFOR DELAY '0:0:5' --",
+    "' AND 1=(SELECT COUNT(*) FROM pg_sleep(5)) --",
+    // Comments and terminators
+    "'/**/OR/**/1=1/**/--",
+    "';",
+    "\\'",
+    "%",
+    "_",
+    // Encodings and Unicode tricks
+    "%27 OR 1=1 --",
+    "\u{2019} OR 1=1 --",
+    "\u{ff07} OR \u{ff07}1\u{ff07}=\u{ff07}1",
+    "\u{02bc} OR 1=1 --",
+    "' OR 1=1\u{0000}",
+    "\u{00bf}' OR 1=1 --",
+];
+
+/// Calls `call` with each of `payloads`, passed to the parameter `param` of
+/// `function`. A panic from `call` fails with the function, parameter and
+/// payload that caused it; returning normally passes, whatever `call` does
+/// with the function's result.
+pub fn run(function: &str, param: &str, payloads: &[&str], mut call: impl FnMut(&str)) {
+    for payload in payloads {
+        if let Err(cause) = panic::catch_unwind(AssertUnwindSafe(|| call(payload))) {
+            let message = cause
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| cause.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("non-string panic payload");
+            panic!(
+                "`{}` panicked with the payload {:?} as `{}`: {}",
+                function, payload, param, message
+            );
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Link-time registry of the annotated functions in the current binary.
+//!
+//! With the `registry` feature, `#[security_test]` also adds an entry for each
+//! function to a [`linkme`] distributed slice, so a program can enumerate its
+//! own annotated functions without parsing its executable, for example to
+//! serve a self-audit endpoint or to assert coverage in a test.
+//!
+//! Entries follow the crate's metadata strategy: functions that embed no
+//! section metadata are not registered either. Descriptors of dynamically
+//! loaded plugins are not linked into the slice; see [`crate::runtime`].
+//!
+//! ```rust
+//! use security_scanner::{registry, security_test};
+//!
+//! #[security_test(sql_injection, critical)]
+//! fn authenticate(user: &str) -> bool {
+//!     !user.is_empty()
+//! }
+//!
+//! # if cfg!(all(
+//! #     any(target_os = "linux", target_os = "macos", target_os = "windows"),
+//! #     not(feature = "strip")
+//! # )) {
+//! assert!(registry::iter().any(|metadata| metadata.function_name.ends_with("::authenticate")));
+//! # }
+//! ```
+
+use std::sync::OnceLock;
+
+use linkme::distributed_slice;
+
+use crate::types::SecurityTestMetadata;
+
+#[doc(hidden)]
+pub use linkme;
+
+/// One function's entry, written by `#[security_test]`.
+#[doc(hidden)]
+pub struct Entry {
+    /// The function's version 2 record
+    pub record: &'static [u8],
+    /// The function's code if it has `anchor`, else null
+    pub code: *const (),
+}
+
+// The code pointer is only read as an address
+unsafe impl Sync for Entry {}
+
+#[doc(hidden)]
+#[distributed_slice]
+pub static ENTRIES: [Entry];
+
+/// Returns the metadata of every annotated function linked into the binary,
+/// in no particular order.
+///
+/// `function_name` is the fully-qualified path, such as
+/// `my_crate::auth::login`, and `function_address` the function's address in
+/// the running process for functions with `anchor`. The entries are decoded
+/// on first use.
+pub fn iter() -> impl Iterator<Item = &'static SecurityTestMetadata> {
+    static DECODED: OnceLock<Vec<SecurityTestMetadata>> = OnceLock::new();
+    DECODED
+        .get_or_init(|| {
+            SecurityTestMetadata::merge_instances(ENTRIES.iter().filter_map(decode).collect())
+        })
+        .iter()
+}
+
+fn decode(entry: &Entry) -> Option<SecurityTestMetadata> {
+    let mut metadata = SecurityTestMetadata::from_record(entry.record)?;
+    metadata.function_address = (!entry.code.is_null()).then_some(entry.code as usize);
+    Some(metadata)
+}
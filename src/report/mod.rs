@@ -0,0 +1,6 @@
+//! Report formats for extracted metadata.
+//!
+//! - [`sarif`] - SARIF 2.1.0 logs, as ingested by GitHub code scanning and
+//!   most CI dashboards
+
+pub mod sarif;
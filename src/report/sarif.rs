@@ -0,0 +1,355 @@
+//! SARIF 2.1.0 logs of annotated functions.
+//!
+//! [`Log::from_metadata`] turns extracted metadata into a log with one run.
+//! The tool's rules are the [`TestType`]s, in discriminant order, so a rule's
+//! index and ID never change between scans; each requested test type of a
+//! function becomes one result:
+//!
+//! | Threat level        | SARIF level |
+//! |---------------------|-------------|
+//! | `critical`, `high`  | `error`     |
+//! | `medium`            | `warning`   |
+//! | `low`               | `note`      |
+//!
+//! using the [effective threat level](crate::severity::effective_threat_level)
+//! of functions whose program declared its exposure, so code scanning gates a
+//! `critical` function of an airgapped tool as a warning.
+//!
+//! Results are located by the function's path as a logical location and,
+//! when the metadata has one, its source file and line relative to
+//! `%SRCROOT%`, through the last line of the function's code when debug info
+//! gave it. They carry the function's [`Fingerprint`] under
+//! `partialFingerprints` so code scanning keeps triage decisions across
+//! scans. Result messages name the threat level and test type in English,
+//! or with the [`DisplayNames`] given to [`Log::from_metadata_with_names`];
+//! rule IDs and properties keep the identifiers. The log only needs
+//! serializing:
+//!
+//! ```rust
+//! use security_scanner::report::sarif::Log;
+//! use security_scanner::{SecurityTestConfig, SecurityTestMetadata, ThreatLevel};
+//!
+//! let login = SecurityTestMetadata {
+//!     function_name: "shop::auth::login".to_string(),
+//!     config: SecurityTestConfig {
+//!         sql_injection: true,
+//!         threat_level: ThreatLevel::Critical,
+//!         ..SecurityTestConfig::default()
+//!     },
+//!     ..SecurityTestMetadata::default()
+//! };
+//!
+//! let log = Log::from_metadata(&[login]);
+//! let finding = &log.runs[0].results[0];
+//! assert_eq!(finding.rule_id, "sql_injection");
+//! assert_eq!(finding.level, "error");
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::fingerprint::Fingerprint;
+use crate::names::DisplayNames;
+use crate::severity::effective_threat_level;
+use crate::types::{Cvss, SecurityTestMetadata, SourceLocation, TestType, ThreatLevel};
+
+/// Version of the SARIF specification the log follows.
+pub const SARIF_VERSION: &str = "2.1.0";
+
+/// URI of the SARIF 2.1.0 JSON schema.
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Key of the fingerprint in a result's `partialFingerprints`.
+pub const FINGERPRINT_KEY: &str = "securityScanner/v1";
+
+/// A SARIF log file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Log {
+    #[serde(rename = "$schema")]
+    pub schema: &'static str,
+    pub version: &'static str,
+    pub runs: Vec<Run>,
+}
+
+/// One invocation of the scanner.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Run {
+    pub tool: Tool,
+    pub results: Vec<Finding>,
+}
+
+/// The tool that produced a run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Tool {
+    pub driver: Driver,
+}
+
+/// The tool's main component and the rules it reports against.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Driver {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub information_uri: &'static str,
+    pub rules: Vec<Rule>,
+}
+
+/// A rule (`reportingDescriptor`), one per [`TestType`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rule {
+    pub id: String,
+    pub name: String,
+    pub short_description: Message,
+}
+
+/// Plain-text message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Message {
+    pub text: String,
+}
+
+/// A result: one requested test type of one function.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub rule_id: String,
+    pub rule_index: usize,
+    /// `error`, `warning` or `note`
+    pub level: &'static str,
+    pub message: Message,
+    pub locations: Vec<Location>,
+    pub partial_fingerprints: BTreeMap<String, String>,
+    pub properties: Properties,
+}
+
+/// Where a result applies.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub physical_location: Option<PhysicalLocation>,
+    pub logical_locations: Vec<LogicalLocation>,
+}
+
+/// A position in a source file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhysicalLocation {
+    pub artifact_location: ArtifactLocation,
+    pub region: Region,
+}
+
+/// A source file, relative to `uri_base_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactLocation {
+    pub uri: String,
+    pub uri_base_id: &'static str,
+}
+
+/// The start of a function name, to the end of its code when known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    pub start_line: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_column: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u32>,
+}
+
+/// A function, identified by its path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogicalLocation {
+    pub fully_qualified_name: String,
+    pub kind: &'static str,
+}
+
+/// Annotation fields SARIF has no place for.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Properties {
+    pub threat_level: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sla_days: Option<u16>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attack_techniques: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stride: Vec<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub custom_tests: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cvss_score: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cvss_vector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exposure: Option<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub banned_calls: Vec<String>,
+}
+
+impl Log {
+    /// Builds a log with one run reporting the given functions.
+    pub fn from_metadata(functions: &[SecurityTestMetadata]) -> Self {
+        Self::from_metadata_with_names(functions, &DisplayNames::default())
+    }
+
+    /// Builds a log like [`Log::from_metadata`], naming threat levels and
+    /// test types in result messages with `names`.
+    pub fn from_metadata_with_names(
+        functions: &[SecurityTestMetadata],
+        names: &DisplayNames,
+    ) -> Self {
+        let results = functions
+            .iter()
+            .flat_map(|metadata| {
+                metadata
+                    .config
+                    .test_types()
+                    .into_iter()
+                    .map(move |test_type| finding(metadata, test_type, names))
+            })
+            .collect();
+
+        Log {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: vec![Run {
+                tool: Tool {
+                    driver: Driver {
+                        name: "security-scanner",
+                        version: env!("CARGO_PKG_VERSION"),
+                        information_uri: env!("CARGO_PKG_REPOSITORY"),
+                        rules: TestType::ALL
+                            .iter()
+                            .map(|&test_type| rule(test_type))
+                            .collect(),
+                    },
+                },
+                results,
+            }],
+        }
+    }
+}
+
+/// Returns the SARIF level for a threat level.
+pub fn level(threat_level: ThreatLevel) -> &'static str {
+    match threat_level {
+        ThreatLevel::Critical | ThreatLevel::High => "error",
+        ThreatLevel::Medium => "warning",
+        ThreatLevel::Low => "note",
+    }
+}
+
+fn rule(test_type: TestType) -> Rule {
+    let (name, description) = match test_type {
+        TestType::SqlInjection => ("SqlInjection", "Function is exposed to SQL injection"),
+        TestType::RaceCondition => ("RaceCondition", "Function is exposed to race conditions"),
+        TestType::TimingAttack => (
+            "TimingAttack",
+            "Function is exposed to timing side channels",
+        ),
+        TestType::BufferOverflow => ("BufferOverflow", "Function is exposed to buffer overflows"),
+        TestType::EnvInjection => (
+            "EnvInjection",
+            "Function is exposed to injection through environment variables",
+        ),
+        TestType::Xss => ("Xss", "Function is exposed to cross-site scripting"),
+        TestType::CommandInjection => (
+            "CommandInjection",
+            "Function is exposed to OS command injection",
+        ),
+        TestType::PathTraversal => ("PathTraversal", "Function is exposed to path traversal"),
+        TestType::Ssrf => ("Ssrf", "Function is exposed to server-side request forgery"),
+        TestType::Xxe => (
+            "Xxe",
+            "Function is exposed to XML external entity injection",
+        ),
+        TestType::Deserialization => ("Deserialization", "Function deserializes untrusted data"),
+        TestType::OpenRedirect => ("OpenRedirect", "Function is exposed to open redirects"),
+    };
+
+    Rule {
+        id: test_type.as_str().to_string(),
+        name: name.to_string(),
+        short_description: Message {
+            text: description.to_string(),
+        },
+    }
+}
+
+fn finding(metadata: &SecurityTestMetadata, test_type: TestType, names: &DisplayNames) -> Finding {
+    let config = &metadata.config;
+    let path = &metadata.function_name;
+    // Paths start with the crate name (`module_path!()`)
+    let crate_name = path.split("::").next().unwrap_or_default();
+    let fingerprint = Fingerprint::new(crate_name, path, test_type, "");
+
+    Finding {
+        rule_id: test_type.as_str().to_string(),
+        rule_index: test_type as usize,
+        level: level(effective_threat_level(
+            config.threat_level,
+            metadata.exposure,
+        )),
+        message: Message {
+            text: format!(
+                "`{}` ({}) is annotated for {} testing",
+                path,
+                names.threat_level(config.threat_level),
+                names.test_type(test_type)
+            ),
+        },
+        locations: vec![Location {
+            physical_location: metadata.location.as_ref().and_then(physical_location),
+            logical_locations: vec![LogicalLocation {
+                fully_qualified_name: path.clone(),
+                kind: "function",
+            }],
+        }],
+        partial_fingerprints: BTreeMap::from([(
+            FINGERPRINT_KEY.to_string(),
+            fingerprint.to_string(),
+        )]),
+        properties: Properties {
+            threat_level: config.threat_level.as_str(),
+            sla_days: config.sla_days,
+            attack_techniques: config.attack_techniques.clone(),
+            stride: config
+                .stride
+                .iter()
+                .map(|category| category.as_str())
+                .collect(),
+            tags: config.compliance_tags.clone(),
+            custom_tests: config.custom_tests.clone(),
+            cvss_score: config.cvss.as_ref().map(Cvss::score),
+            cvss_vector: config.cvss.as_ref().and_then(|cvss| cvss.vector.clone()),
+            exposure: metadata.exposure.map(|exposure| exposure.as_str()),
+            banned_calls: config.banned_calls.clone(),
+        },
+    }
+}
+
+fn physical_location(location: &SourceLocation) -> Option<PhysicalLocation> {
+    if location.file.is_empty() {
+        return None;
+    }
+
+    Some(PhysicalLocation {
+        artifact_location: ArtifactLocation {
+            // `file!()` uses the host's separators
+            uri: location.file.replace('\\', "/"),
+            uri_base_id: "%SRCROOT%",
+        },
+        region: Region {
+            start_line: location.line,
+            start_column: Some(location.column).filter(|&column| column > 0),
+            end_line: Some(location.end_line).filter(|&line| line > 0),
+        },
+    })
+}
@@ -0,0 +1,184 @@
+//! Process-wide registry of annotated functions, for code loaded at runtime.
+//!
+//! Metadata compiled into the main binary is found by scanning its sections,
+//! but dynamically loaded plugins bring their own sections that a scan of the
+//! executable never sees. A plugin registers its descriptors when loaded and
+//! unregisters them before it is unloaded; hosts read the merged set with
+//! [`descriptors`] and can [`subscribe`] to changes.
+//!
+//! The registry is created on first use and is safe to use from any thread.
+//! Subscribers are called after the registry lock is released, so they may
+//! call back into this module.
+//!
+//! ```rust
+//! use security_scanner::runtime::{self, RegistryEvent};
+//! use security_scanner::SecurityTestMetadata;
+//!
+//! let subscription = runtime::subscribe(|event| {
+//!     if let RegistryEvent::Registered { source, count, .. } = event {
+//!         println!("{} registered {} function(s)", source, count);
+//!     }
+//! });
+//!
+//! let registration = runtime::register(
+//!     "auth_plugin",
+//!     vec![SecurityTestMetadata {
+//!         function_name: "login".to_string(),
+//!         ..SecurityTestMetadata::default()
+//!     }],
+//! );
+//! assert!(runtime::descriptors()
+//!     .iter()
+//!     .any(|descriptor| descriptor.function_name == "login"));
+//!
+//! // Before the plugin is unloaded
+//! assert!(runtime::unregister(registration));
+//! runtime::unsubscribe(subscription);
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::types::SecurityTestMetadata;
+
+/// Handle returned by [`register`], used to [`unregister`] the descriptors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegistrationId(u64);
+
+/// Handle returned by [`subscribe`], used to [`unsubscribe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+/// A change to the registry, passed to subscribers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryEvent {
+    /// A source added descriptors
+    Registered {
+        id: RegistrationId,
+        source: String,
+        count: usize,
+    },
+    /// A source's descriptors were removed
+    Unregistered {
+        id: RegistrationId,
+        source: String,
+        count: usize,
+    },
+}
+
+type Subscriber = Arc<dyn Fn(&RegistryEvent) + Send + Sync>;
+
+struct Registration {
+    id: RegistrationId,
+    source: String,
+    descriptors: Vec<SecurityTestMetadata>,
+}
+
+#[derive(Default)]
+struct Registry {
+    next_id: AtomicU64,
+    registrations: RwLock<Vec<Registration>>,
+    subscribers: RwLock<Vec<(SubscriptionId, Subscriber)>>,
+}
+
+impl Registry {
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Registry::default)
+}
+
+/// Adds descriptors for functions in `source` (typically the plugin's name),
+/// returning a handle for [`unregister`].
+pub fn register(
+    source: impl Into<String>,
+    descriptors: Vec<SecurityTestMetadata>,
+) -> RegistrationId {
+    let registry = registry();
+    let id = RegistrationId(registry.next_id());
+    let source = source.into();
+    let count = descriptors.len();
+
+    write(&registry.registrations).push(Registration {
+        id,
+        source: source.clone(),
+        descriptors,
+    });
+
+    notify(&RegistryEvent::Registered { id, source, count });
+    id
+}
+
+/// Removes the descriptors added by a [`register`] call, returning `false` if
+/// they were already removed.
+pub fn unregister(id: RegistrationId) -> bool {
+    let removed = {
+        let mut registrations = write(&registry().registrations);
+        registrations
+            .iter()
+            .position(|registration| registration.id == id)
+            .map(|index| registrations.remove(index))
+    };
+
+    match removed {
+        Some(registration) => {
+            notify(&RegistryEvent::Unregistered {
+                id,
+                source: registration.source,
+                count: registration.descriptors.len(),
+            });
+            true
+        }
+        None => false,
+    }
+}
+
+/// Returns every registered descriptor, in registration order.
+pub fn descriptors() -> Vec<SecurityTestMetadata> {
+    read(&registry().registrations)
+        .iter()
+        .flat_map(|registration| registration.descriptors.iter().cloned())
+        .collect()
+}
+
+/// Calls `callback` after every future registry change, returning a handle
+/// for [`unsubscribe`].
+pub fn subscribe(callback: impl Fn(&RegistryEvent) + Send + Sync + 'static) -> SubscriptionId {
+    let registry = registry();
+    let id = SubscriptionId(registry.next_id());
+    write(&registry.subscribers).push((id, Arc::new(callback)));
+    id
+}
+
+/// Stops calling a subscriber, returning `false` if it was already removed.
+pub fn unsubscribe(id: SubscriptionId) -> bool {
+    let mut subscribers = write(&registry().subscribers);
+    let before = subscribers.len();
+    subscribers.retain(|(subscription, _)| *subscription != id);
+    subscribers.len() != before
+}
+
+fn notify(event: &RegistryEvent) {
+    // Clone the subscriber list so callbacks run without any lock held
+    let subscribers: Vec<Subscriber> = read(&registry().subscribers)
+        .iter()
+        .map(|(_, subscriber)| Arc::clone(subscriber))
+        .collect();
+    for subscriber in subscribers {
+        subscriber(event);
+    }
+}
+
+// No user code runs while a lock is held, so a poisoned lock can't guard
+// half-updated lists; recover instead of failing every later caller
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|err| err.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|err| err.into_inner())
+}
@@ -0,0 +1,207 @@
+//! Final severity of a finding, combining the annotation's threat level with
+//! how the finding manifested.
+//!
+//! The threat level says how much is at stake in a function; the
+//! [`Exploitability`] says what an attacker actually achieved. Each
+//! exploitability kind adds a fixed number of steps to the threat level:
+//!
+//! | Exploitability        | Steps |
+//! |-----------------------|-------|
+//! | `hang`                | +0    |
+//! | `crash`               | +1    |
+//! | `info_leak`           | +1    |
+//! | `predicate_violation` | +2    |
+//!
+//! with `low` = 0 up to `critical` = 3, and the sum mapped onto
+//! `info`, `low`, `medium`, `high` and `critical` (4 and above). Written out:
+//!
+//! | Threat level \ Kind | hang   | crash    | info_leak | predicate_violation |
+//! |---------------------|--------|----------|-----------|---------------------|
+//! | `low`               | info   | low      | low       | medium              |
+//! | `medium`            | low    | medium   | medium    | high                |
+//! | `high`              | medium | high     | high      | critical            |
+//! | `critical`          | high   | critical | critical  | critical            |
+//!
+//! A predicate violation (an auth check failing open, a tenant boundary
+//! crossed) is the strongest signal because the security property itself was
+//! broken; a hang is the weakest because it is at most a denial of service.
+//!
+//! ## Exposure
+//!
+//! The matrix assumes the function can be reached from the internet. A
+//! program declaring less with `#[security_program(exposure = ...)]` takes
+//! steps off the sum, so the same annotation weighs less in an internal tool
+//! than in a public service:
+//!
+//! | Exposure                 | Steps |
+//! |--------------------------|-------|
+//! | `internet` or undeclared | +0    |
+//! | `partner`                | -1    |
+//! | `internal`               | -1    |
+//! | `airgapped`              | -2    |
+//!
+//! Policies keyed on threat levels apply the same steps to the annotated
+//! level through [`effective_threat_level`], bottoming out at `low`.
+//!
+//! ```rust
+//! use security_scanner::severity::{Exploitability, Severity};
+//! use security_scanner::{Exposure, ThreatLevel};
+//!
+//! let severity = Severity::derive(ThreatLevel::High, Exploitability::PredicateViolation);
+//! assert_eq!(severity, Severity::Critical);
+//!
+//! let internal = Severity::derive_exposed(
+//!     ThreatLevel::High,
+//!     Exploitability::PredicateViolation,
+//!     Some(Exposure::Internal),
+//! );
+//! assert_eq!(internal, Severity::High);
+//! ```
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Exposure, ThreatLevel};
+
+/// How a finding manifested when the harness exercised the function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Exploitability {
+    /// The function stopped making progress (timeout, infinite loop)
+    Hang,
+    /// The function panicked or the process crashed
+    Crash,
+    /// Data that should have stayed private was observable
+    InfoLeak,
+    /// A security predicate was violated (e.g. auth failed open)
+    PredicateViolation,
+}
+
+impl Exploitability {
+    /// Every exploitability kind, from weakest to strongest.
+    pub const ALL: [Exploitability; 4] = [
+        Exploitability::Hang,
+        Exploitability::Crash,
+        Exploitability::InfoLeak,
+        Exploitability::PredicateViolation,
+    ];
+
+    /// Returns the snake_case name of this kind.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Exploitability::Hang => "hang",
+            Exploitability::Crash => "crash",
+            Exploitability::InfoLeak => "info_leak",
+            Exploitability::PredicateViolation => "predicate_violation",
+        }
+    }
+
+    /// Number of severity steps this kind adds to the threat level.
+    fn steps(self) -> u8 {
+        match self {
+            Exploitability::Hang => 0,
+            Exploitability::Crash | Exploitability::InfoLeak => 1,
+            Exploitability::PredicateViolation => 2,
+        }
+    }
+}
+
+impl fmt::Display for Exploitability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Final severity of a finding, as used by exit-code policies and exports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Severity {
+    /// Worth recording, not worth acting on
+    Info,
+    /// Fix when convenient
+    Low,
+    /// Fix in the normal course of work
+    Medium,
+    /// Fix before the next release
+    High,
+    /// Fix immediately
+    Critical,
+}
+
+impl Severity {
+    /// Combines a threat level and an exploitability kind using the matrix in
+    /// the [module documentation](self).
+    pub fn derive(threat_level: ThreatLevel, exploitability: Exploitability) -> Self {
+        Severity::derive_exposed(threat_level, exploitability, None)
+    }
+
+    /// Like [`Severity::derive`], for a function in a program with the given
+    /// exposure; see [Exposure](self#exposure).
+    pub fn derive_exposed(
+        threat_level: ThreatLevel,
+        exploitability: Exploitability,
+        exposure: Option<Exposure>,
+    ) -> Self {
+        let steps = threat_level.as_byte() + exploitability.steps();
+        match steps.saturating_sub(exposure_steps(exposure)) {
+            0 => Severity::Info,
+            1 => Severity::Low,
+            2 => Severity::Medium,
+            3 => Severity::High,
+            _ => Severity::Critical,
+        }
+    }
+
+    /// Returns the snake_case name of this severity.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Returns the threat level that policies should apply to a function
+/// annotated with `threat_level` in a program with the given exposure; see
+/// [Exposure](self#exposure).
+///
+/// ```rust
+/// use security_scanner::severity::effective_threat_level;
+/// use security_scanner::{Exposure, ThreatLevel};
+///
+/// let level = effective_threat_level(ThreatLevel::Critical, Some(Exposure::Airgapped));
+/// assert_eq!(level, ThreatLevel::Medium);
+/// assert_eq!(effective_threat_level(ThreatLevel::Critical, None), ThreatLevel::Critical);
+/// ```
+pub fn effective_threat_level(
+    threat_level: ThreatLevel,
+    exposure: Option<Exposure>,
+) -> ThreatLevel {
+    let byte = threat_level
+        .as_byte()
+        .saturating_sub(exposure_steps(exposure));
+    ThreatLevel::from_byte(byte).unwrap_or_default()
+}
+
+/// Number of severity steps an exposure takes off, undeclared counting as
+/// the internet.
+fn exposure_steps(exposure: Option<Exposure>) -> u8 {
+    match exposure {
+        None | Some(Exposure::Internet) => 0,
+        Some(Exposure::Partner) | Some(Exposure::Internal) => 1,
+        Some(Exposure::Airgapped) => 2,
+    }
+}
@@ -0,0 +1,119 @@
+//! Remediation deadlines for findings.
+//!
+//! A function's deadline comes from its `sla_days = N` argument when given,
+//! and otherwise from the [`SlaPolicy`] default for its threat level. The
+//! default policy is:
+//!
+//! | Threat level | Days |
+//! |--------------|------|
+//! | `critical`   | 7    |
+//! | `high`       | 30   |
+//! | `medium`     | 90   |
+//! | `low`        | none |
+//!
+//! Findings are overdue once they have been open, counted from when they were
+//! first recorded, for longer than the deadline. The default of a function in
+//! a program declaring its exposure is that of its
+//! [effective threat level](crate::severity::effective_threat_level), so an
+//! internal tool's `critical` function gets the `high` deadline.
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use security_scanner::sla::SlaPolicy;
+//! use security_scanner::{SecurityTestConfig, ThreatLevel};
+//!
+//! let config = SecurityTestConfig {
+//!     threat_level: ThreatLevel::High,
+//!     ..SecurityTestConfig::default()
+//! };
+//! let policy = SlaPolicy::default();
+//! assert_eq!(policy.deadline_days(&config), Some(30));
+//!
+//! let open_for = Duration::from_secs(31 * 24 * 60 * 60);
+//! assert!(policy.is_overdue(&config, open_for));
+//! ```
+
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::severity::effective_threat_level;
+use crate::types::{Exposure, SecurityTestConfig, ThreatLevel};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Default remediation deadlines per threat level, in days. `None` means
+/// findings at that level never become overdue unless the function sets
+/// `sla_days` itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct SlaPolicy {
+    pub low: Option<u16>,
+    pub medium: Option<u16>,
+    pub high: Option<u16>,
+    pub critical: Option<u16>,
+}
+
+impl Default for SlaPolicy {
+    fn default() -> Self {
+        SlaPolicy {
+            low: None,
+            medium: Some(90),
+            high: Some(30),
+            critical: Some(7),
+        }
+    }
+}
+
+impl SlaPolicy {
+    /// Returns the policy default for a threat level.
+    pub fn default_days(&self, threat_level: ThreatLevel) -> Option<u16> {
+        match threat_level {
+            ThreatLevel::Low => self.low,
+            ThreatLevel::Medium => self.medium,
+            ThreatLevel::High => self.high,
+            ThreatLevel::Critical => self.critical,
+        }
+    }
+
+    /// Returns the deadline for findings in a function: its own `sla_days`
+    /// if set, otherwise the default for its threat level.
+    pub fn deadline_days(&self, config: &SecurityTestConfig) -> Option<u16> {
+        self.deadline_days_exposed(config, None)
+    }
+
+    /// Like [`SlaPolicy::deadline_days`], for a function in a program with
+    /// the given exposure. A function's own `sla_days` still wins.
+    pub fn deadline_days_exposed(
+        &self,
+        config: &SecurityTestConfig,
+        exposure: Option<Exposure>,
+    ) -> Option<u16> {
+        config
+            .sla_days
+            .or_else(|| self.default_days(effective_threat_level(config.threat_level, exposure)))
+    }
+
+    /// Returns whether a finding that has been open for `open_for` is past
+    /// its deadline.
+    pub fn is_overdue(&self, config: &SecurityTestConfig, open_for: Duration) -> bool {
+        self.is_overdue_exposed(config, None, open_for)
+    }
+
+    /// Like [`SlaPolicy::is_overdue`], for a function in a program with the
+    /// given exposure.
+    pub fn is_overdue_exposed(
+        &self,
+        config: &SecurityTestConfig,
+        exposure: Option<Exposure>,
+        open_for: Duration,
+    ) -> bool {
+        match self.deadline_days_exposed(config, exposure) {
+            Some(days) => open_for.as_secs() > u64::from(days) * SECONDS_PER_DAY,
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Statistical checks for timing side channels, after dudect.
+//!
+//! A function leaks through its timing when how long it takes depends on
+//! secret data. [`measure`] times a function on two classes of inputs, one
+//! fixed input and a set of random ones, calling it in a random order so
+//! that noise from the rest of the system hits both classes alike. The
+//! slowest 5% of the calls, mostly interrupted ones, are dropped, and Welch's
+//! t-test compares the two classes' timings: a |t| of [`THRESHOLD`] or more
+//! means the classes differ, so the function's timing depends on its input.
+//!
+//! [`assert_constant_time`] runs that test on a function of a string with
+//! [`MEASUREMENTS`] calls and panics on a difference.
+//! `#[security_test(timing_attack, generate_tests)]` generates a test that
+//! does so for each string parameter of a function, ignored by default
+//! since its outcome depends on the machine's noise: run it with
+//! `cargo test -- --ignored`, ideally alone.
+//!
+//! ```rust
+//! use security_scanner::timing;
+//!
+//! /// Compares every byte, whatever the input.
+//! fn verify(token: &str) -> bool {
+//!     let expected = b"0123456789abcdef0123456789abcdef";
+//!     let difference = token
+//!         .bytes()
+//!         .zip(expected)
+//!         .fold(0, |difference, (a, b)| difference | (a ^ b));
+//!     token.len() == expected.len() && difference == 0
+//! }
+//!
+//! let measurement = timing::measure(
+//!     &["0123456789abcdef0123456789abcdef"],
+//!     &["fedcba9876543210fedcba9876543210", "ffffffffffffffffffffffffffffffff"],
+//!     2_000,
+//!     |token| verify(token),
+//! );
+//! println!("t = {:.2}", measurement.t);
+//! ```
+//!
+//! Timings come from [`std::time::Instant`], so differences smaller than
+//! the clock's resolution need more measurements to show. A passing test
+//! doesn't prove constant time: it only means no difference was measured
+//! between these inputs, on this machine, with this build.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+/// |t| from which the two classes are taken to differ, dudect's threshold.
+pub const THRESHOLD: f64 = 4.5;
+
+/// Calls [`assert_constant_time`] makes.
+pub const MEASUREMENTS: usize = 10_000;
+
+/// Length of the strings [`assert_constant_time`] passes.
+const INPUT_LEN: usize = 32;
+
+/// Number of random strings [`assert_constant_time`] draws from.
+const RANDOM_INPUTS: usize = 256;
+
+/// Share of the slowest calls dropped before the test.
+const CROP: f64 = 0.05;
+
+/// The outcome of [`measure`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    /// Welch's t statistic of the fixed class against the random class
+    pub t: f64,
+    /// Mean time of a call for the fixed and the random class, in
+    /// nanoseconds
+    pub means: [f64; 2],
+    /// Number of calls of each class kept for the test
+    pub samples: [usize; 2],
+}
+
+impl Measurement {
+    /// Returns whether no timing difference between the classes was found.
+    pub fn is_constant_time(&self) -> bool {
+        self.t.abs() < THRESHOLD
+    }
+}
+
+/// Times `measurements` calls of `f`, each on one of `fixed` or one of
+/// `random` picked at random, and compares the two classes with Welch's
+/// t-test.
+///
+/// `fixed` holds copies of the fixed input. Giving as many copies as there
+/// are random inputs keeps the classes apart only by their contents, since
+/// inputs spread over more memory are slower to read.
+///
+/// # Panics
+///
+/// Panics if either class is empty.
+pub fn measure<I: ?Sized, R>(
+    fixed: &[&I],
+    random: &[&I],
+    measurements: usize,
+    mut f: impl FnMut(&I) -> R,
+) -> Measurement {
+    assert!(
+        !fixed.is_empty() && !random.is_empty(),
+        "timing::measure needs inputs of both classes"
+    );
+
+    let mut rng = XorShift::new(0x5eed_d0de_c0de);
+    let mut timings: Vec<(bool, u64)> = Vec::with_capacity(measurements);
+    for _ in 0..measurements {
+        let word = rng.next();
+        let is_random = word & 1 == 1;
+        let class = if is_random { random } else { fixed };
+        let input = class[(word >> 1) as usize % class.len()];
+
+        let start = Instant::now();
+        black_box(f(black_box(input)));
+        let elapsed = start.elapsed();
+        timings.push((
+            is_random,
+            u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX),
+        ));
+    }
+
+    // Calls slower than nearly all others were interrupted or preempted
+    let mut sorted: Vec<u64> = timings.iter().map(|(_, nanos)| *nanos).collect();
+    sorted.sort_unstable();
+    let cutoff = sorted
+        .get(((sorted.len() as f64) * (1.0 - CROP)) as usize)
+        .copied()
+        .unwrap_or(u64::MAX);
+
+    let mut classes = [Welford::default(), Welford::default()];
+    for (is_random, nanos) in timings {
+        if nanos <= cutoff {
+            classes[usize::from(is_random)].push(nanos as f64);
+        }
+    }
+
+    let [fixed, random] = &classes;
+    let spread =
+        (fixed.variance() / fixed.count as f64 + random.variance() / random.count as f64).sqrt();
+    let t = if fixed.count < 2 || random.count < 2 || spread == 0.0 {
+        // Too few calls left in a class, or every call took the same time
+        0.0
+    } else {
+        (fixed.mean - random.mean) / spread
+    };
+
+    Measurement {
+        t,
+        means: [fixed.mean, random.mean],
+        samples: [fixed.count, random.count],
+    }
+}
+
+/// Checks that `f` takes as long on any string as on a fixed one.
+///
+/// The fixed input is 32 `a`s and the random ones 32 printable ASCII
+/// characters each, so only their contents differ.
+///
+/// # Panics
+///
+/// Panics with the t statistic and mean timings if [`measure`] finds a
+/// difference.
+pub fn assert_constant_time<R>(mut f: impl FnMut(&str) -> R) {
+    let mut rng = XorShift::new(0x7e57_1e55);
+    let random: Vec<String> = (0..RANDOM_INPUTS)
+        .map(|_| {
+            (0..INPUT_LEN)
+                .map(|_| char::from(b' ' + (rng.next() % 95) as u8))
+                .collect()
+        })
+        .collect();
+    let fixed: Vec<String> = (0..RANDOM_INPUTS).map(|_| "a".repeat(INPUT_LEN)).collect();
+    let random: Vec<&str> = random.iter().map(String::as_str).collect();
+    let fixed: Vec<&str> = fixed.iter().map(String::as_str).collect();
+
+    let measurement = measure(&fixed, &random, MEASUREMENTS, |input| f(input));
+    assert!(
+        measurement.is_constant_time(),
+        "timing depends on the input: t = {:.2} (threshold {}), {:.0} ns for the fixed input \
+         against {:.0} ns for random ones",
+        measurement.t,
+        THRESHOLD,
+        measurement.means[0],
+        measurement.means[1]
+    );
+}
+
+/// Running mean and variance, by Welford's algorithm.
+#[derive(Default)]
+struct Welford {
+    count: usize,
+    mean: f64,
+    /// Sum of squared differences from the mean
+    m2: f64,
+}
+
+impl Welford {
+    fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Sample variance, 0 with fewer than two values.
+    fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.m2 / (self.count - 1) as f64
+        }
+    }
+}
+
+/// Small fixed-seed generator, so runs are repeatable; the order of the
+/// calls needs to be unpredictable to the function, not to an attacker.
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        XorShift(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
@@ -0,0 +1,984 @@
+//! Types describing the metadata embedded by `#[security_test]`.
+//!
+//! The macro writes one record per annotated function into the
+//! `.security_tests` section (`__DATA,__sectests` on macOS, `.sectests` on
+//! Windows). The byte layouts of both format versions and the integrity
+//! checks live in [`format`](crate::format).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::format::tlv::{self, tags};
+use crate::format::{offsets, TEST_TYPES};
+pub use crate::format::{RECORD_MAGIC, RECORD_SIZE};
+
+/// A vulnerability class that `#[security_test(...)]` can request testing for.
+///
+/// The discriminants are stable and give each type's bit in the tests
+/// bitfield of version 2 records ([`TestType::bit`]). The first four types
+/// also have a flag byte in version 1 records; use
+/// [`TestType::record_offset`] to find it.
+///
+/// ```rust
+/// use security_scanner::TestType;
+///
+/// let test_type: TestType = "sql_injection".parse().unwrap();
+/// assert_eq!(test_type, TestType::SqlInjection);
+/// assert_eq!(test_type.to_string(), "sql_injection");
+/// assert_eq!(TestType::ALL.len(), 12);
+/// assert_eq!(TestType::Ssrf.bit(), 1 << 8);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TestType {
+    /// SQL injection vulnerabilities (`sql_injection`)
+    SqlInjection = 0,
+    /// Race condition vulnerabilities (`race_condition`)
+    RaceCondition = 1,
+    /// Timing side-channel attacks (`timing_attack`)
+    TimingAttack = 2,
+    /// Buffer overflow vulnerabilities (`buffer_overflow`)
+    BufferOverflow = 3,
+    /// Injection through environment variables (`env_injection`)
+    EnvInjection = 4,
+    /// Cross-site scripting (`xss`)
+    Xss = 5,
+    /// OS command injection (`command_injection`)
+    CommandInjection = 6,
+    /// Path traversal (`path_traversal`)
+    PathTraversal = 7,
+    /// Server-side request forgery (`ssrf`)
+    Ssrf = 8,
+    /// XML external entity injection (`xxe`)
+    Xxe = 9,
+    /// Insecure deserialization of untrusted data (`deserialization`)
+    Deserialization = 10,
+    /// Open redirects to attacker-chosen URLs (`open_redirect`)
+    OpenRedirect = 11,
+}
+
+impl TestType {
+    /// Every supported test type, in discriminant order.
+    pub const ALL: [TestType; TEST_TYPES.len()] = [
+        TestType::SqlInjection,
+        TestType::RaceCondition,
+        TestType::TimingAttack,
+        TestType::BufferOverflow,
+        TestType::EnvInjection,
+        TestType::Xss,
+        TestType::CommandInjection,
+        TestType::PathTraversal,
+        TestType::Ssrf,
+        TestType::Xxe,
+        TestType::Deserialization,
+        TestType::OpenRedirect,
+    ];
+
+    /// Returns the identifier used in `#[security_test(...)]` for this type.
+    pub fn as_str(self) -> &'static str {
+        TEST_TYPES[self as usize]
+    }
+
+    /// Returns the offset of this type's flag byte within a version 1
+    /// record, or `None` for types added after version 1.
+    pub fn record_offset(self) -> Option<usize> {
+        match self {
+            TestType::SqlInjection => Some(offsets::SQL_INJECTION),
+            TestType::RaceCondition => Some(offsets::RACE_CONDITION),
+            TestType::TimingAttack => Some(offsets::TIMING_ATTACK),
+            TestType::BufferOverflow => Some(offsets::BUFFER_OVERFLOW),
+            _ => None,
+        }
+    }
+
+    /// Returns this type's bit in the tests entry of version 2 records.
+    pub fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+impl fmt::Display for TestType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when parsing an unknown test type name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTestTypeError(String);
+
+impl fmt::Display for ParseTestTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown test type \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for ParseTestTypeError {}
+
+impl FromStr for TestType {
+    type Err = ParseTestTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TestType::ALL
+            .iter()
+            .copied()
+            .find(|test_type| test_type.as_str() == s)
+            .ok_or_else(|| ParseTestTypeError(s.to_string()))
+    }
+}
+
+/// How security-critical an annotated function is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum ThreatLevel {
+    /// Low-risk function (logging, display, etc.)
+    #[default]
+    Low = 0,
+    /// Medium-risk function (data processing, business logic)
+    Medium = 1,
+    /// High-risk function (user data access, admin operations)
+    High = 2,
+    /// Critical security function (authentication, payment, etc.)
+    Critical = 3,
+}
+
+impl ThreatLevel {
+    /// Converts the threat level byte stored in a record.
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(ThreatLevel::Low),
+            1 => Some(ThreatLevel::Medium),
+            2 => Some(ThreatLevel::High),
+            3 => Some(ThreatLevel::Critical),
+            _ => None,
+        }
+    }
+
+    /// Returns the byte stored in a record for this threat level.
+    pub fn as_byte(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the name used in `#[security_test(...)]` for this threat level.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ThreatLevel::Low => "low",
+            ThreatLevel::Medium => "medium",
+            ThreatLevel::High => "high",
+            ThreatLevel::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for ThreatLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What an annotated function declared it is allowed to do, via
+/// `capabilities(...)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Capabilities {
+    /// Opens network connections (`net`)
+    pub net: bool,
+    /// Reads from the filesystem (`fs_read`)
+    pub fs_read: bool,
+    /// Writes to the filesystem (`fs_write`)
+    pub fs_write: bool,
+    /// Spawns processes (`spawn`)
+    pub spawn: bool,
+}
+
+impl Capabilities {
+    /// Decodes the capability byte of a record, returning `None` when the
+    /// function declared no capabilities at all.
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        if byte & 0x80 == 0 {
+            return None;
+        }
+
+        Some(Capabilities {
+            net: byte & 0x01 != 0,
+            fs_read: byte & 0x02 != 0,
+            fs_write: byte & 0x04 != 0,
+            spawn: byte & 0x08 != 0,
+        })
+    }
+
+    /// Encodes the capabilities as the capability byte of a record, the
+    /// inverse of [`Capabilities::from_byte`].
+    pub fn to_byte(self) -> u8 {
+        0x80 | self.net as u8
+            | (self.fs_read as u8) << 1
+            | (self.fs_write as u8) << 2
+            | (self.spawn as u8) << 3
+    }
+}
+
+/// A STRIDE threat category, as listed in `stride(...)`.
+///
+/// ```rust
+/// use security_scanner::StrideCategory;
+///
+/// let category: StrideCategory = "information_disclosure".parse().unwrap();
+/// assert_eq!(category, StrideCategory::InformationDisclosure);
+/// assert_eq!(StrideCategory::from_byte(0x03), vec![
+///     StrideCategory::Spoofing,
+///     StrideCategory::Tampering,
+/// ]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum StrideCategory {
+    /// Impersonating a user or component (`spoofing`)
+    Spoofing = 0,
+    /// Modifying data or code (`tampering`)
+    Tampering = 1,
+    /// Denying having performed an action (`repudiation`)
+    Repudiation = 2,
+    /// Exposing data to unauthorized parties (`information_disclosure`)
+    InformationDisclosure = 3,
+    /// Degrading or denying service (`denial_of_service`)
+    DenialOfService = 4,
+    /// Gaining capabilities without authorization (`elevation_of_privilege`)
+    ElevationOfPrivilege = 5,
+}
+
+impl StrideCategory {
+    /// Every category, in the order of the acronym.
+    pub const ALL: [StrideCategory; 6] = [
+        StrideCategory::Spoofing,
+        StrideCategory::Tampering,
+        StrideCategory::Repudiation,
+        StrideCategory::InformationDisclosure,
+        StrideCategory::DenialOfService,
+        StrideCategory::ElevationOfPrivilege,
+    ];
+
+    /// Returns the identifier used in `stride(...)` for this category.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StrideCategory::Spoofing => "spoofing",
+            StrideCategory::Tampering => "tampering",
+            StrideCategory::Repudiation => "repudiation",
+            StrideCategory::InformationDisclosure => "information_disclosure",
+            StrideCategory::DenialOfService => "denial_of_service",
+            StrideCategory::ElevationOfPrivilege => "elevation_of_privilege",
+        }
+    }
+
+    /// Returns this category's bit in the record's STRIDE byte.
+    pub fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Decodes the STRIDE byte of a record, in the order of the acronym.
+    pub fn from_byte(byte: u8) -> Vec<Self> {
+        StrideCategory::ALL
+            .iter()
+            .copied()
+            .filter(|category| byte & category.bit() != 0)
+            .collect()
+    }
+}
+
+impl fmt::Display for StrideCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when parsing an unknown STRIDE category name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseStrideCategoryError(String);
+
+impl fmt::Display for ParseStrideCategoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown STRIDE category \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for ParseStrideCategoryError {}
+
+impl FromStr for StrideCategory {
+    type Err = ParseStrideCategoryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        StrideCategory::ALL
+            .iter()
+            .copied()
+            .find(|category| category.as_str() == s)
+            .ok_or_else(|| ParseStrideCategoryError(s.to_string()))
+    }
+}
+
+/// Threat levels declared per build profile with
+/// `threat_level(debug = .., release = ..)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProfileThreatLevels {
+    /// Level of builds with debug assertions
+    pub debug: ThreatLevel,
+    /// Level of builds without debug assertions
+    pub release: ThreatLevel,
+}
+
+/// A CVSS base score given with `cvss = ...`.
+///
+/// ```rust
+/// use security_scanner::{Cvss, ThreatLevel};
+///
+/// let cvss = Cvss {
+///     score_tenths: 98,
+///     vector: Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H".to_string()),
+/// };
+/// assert_eq!(cvss.score(), 9.8);
+/// assert_eq!(cvss.threat_level(), ThreatLevel::Critical);
+/// assert_eq!(cvss.to_string(), "9.8 (CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H)");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cvss {
+    /// Base score in tenths, 0 to 100
+    pub score_tenths: u8,
+    /// The base vector, when one was given rather than a bare score
+    pub vector: Option<String>,
+}
+
+impl Cvss {
+    /// Returns the base score, 0.0 to 10.0.
+    pub fn score(&self) -> f32 {
+        f32::from(self.score_tenths) / 10.0
+    }
+
+    /// Returns the threat level of the score's CVSS qualitative rating;
+    /// scores of 0.0 are `low`.
+    pub fn threat_level(&self) -> ThreatLevel {
+        match self.score_tenths {
+            90.. => ThreatLevel::Critical,
+            70..=89 => ThreatLevel::High,
+            40..=69 => ThreatLevel::Medium,
+            _ => ThreatLevel::Low,
+        }
+    }
+}
+
+impl fmt::Display for Cvss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.score_tenths / 10, self.score_tenths % 10)?;
+        match &self.vector {
+            Some(vector) => write!(f, " ({})", vector),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The security tests requested for a function and how critical it is.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SecurityTestConfig {
+    /// Test for SQL injection vulnerabilities
+    pub sql_injection: bool,
+    /// Test for race condition vulnerabilities
+    pub race_condition: bool,
+    /// Test for timing side-channel attacks
+    pub timing_attack: bool,
+    /// Test for buffer overflow vulnerabilities
+    pub buffer_overflow: bool,
+    /// Test configuration read from environment variables
+    pub env_injection: bool,
+    /// Test for cross-site scripting vulnerabilities
+    pub xss: bool,
+    /// Test for OS command injection
+    pub command_injection: bool,
+    /// Test for path traversal
+    pub path_traversal: bool,
+    /// Test for server-side request forgery
+    pub ssrf: bool,
+    /// Test for XML external entity injection
+    pub xxe: bool,
+    /// Test for insecure deserialization
+    pub deserialization: bool,
+    /// Test for open redirects
+    pub open_redirect: bool,
+    /// Test types defined by other tools, given with `custom = "name"`
+    pub custom_tests: Vec<String>,
+    /// How security-critical the function is in the build it was read from
+    pub threat_level: ThreatLevel,
+    /// Declared levels, when they differ by build profile
+    pub profile_threat_levels: Option<ProfileThreatLevels>,
+    /// CVSS rating the threat level was derived from, when given
+    pub cvss: Option<Cvss>,
+    /// Declared capabilities, if any were declared
+    pub capabilities: Option<Capabilities>,
+    /// STRIDE categories the function is exposed to
+    pub stride: Vec<StrideCategory>,
+    /// MITRE ATT&CK technique IDs (`T1190`, `T1078.004`, ...)
+    pub attack_techniques: Vec<String>,
+    /// Days a finding may stay open, when set with `sla_days`
+    pub sla_days: Option<u16>,
+    /// Environment variables the function declares it reads
+    pub reads_env: Vec<String>,
+    /// Fixtures the function's harness requires
+    pub fixtures: Vec<String>,
+    /// The function's parameters
+    pub input_params: Vec<InputParam>,
+    /// Compliance taxonomy tags: `CWE-89` for `cwe = [89]` and
+    /// `OWASP-A03:2021` for `owasp = "A03:2021"`, CWE IDs first
+    pub compliance_tags: Vec<String>,
+    /// Whether the record was compiled for test code (`cfg(test)` or the
+    /// `test_context` keyword)
+    pub test_context: bool,
+    /// Banned APIs the function calls, as listed in `banned_calls` of its
+    /// crate's manifest
+    pub banned_calls: Vec<String>,
+}
+
+impl SecurityTestConfig {
+    /// Returns whether the given test type was requested.
+    pub fn has(&self, test_type: TestType) -> bool {
+        match test_type {
+            TestType::SqlInjection => self.sql_injection,
+            TestType::RaceCondition => self.race_condition,
+            TestType::TimingAttack => self.timing_attack,
+            TestType::BufferOverflow => self.buffer_overflow,
+            TestType::EnvInjection => self.env_injection,
+            TestType::Xss => self.xss,
+            TestType::CommandInjection => self.command_injection,
+            TestType::PathTraversal => self.path_traversal,
+            TestType::Ssrf => self.ssrf,
+            TestType::Xxe => self.xxe,
+            TestType::Deserialization => self.deserialization,
+            TestType::OpenRedirect => self.open_redirect,
+        }
+    }
+
+    /// Sets whether the given test type was requested.
+    pub fn set(&mut self, test_type: TestType, requested: bool) {
+        let flag = match test_type {
+            TestType::SqlInjection => &mut self.sql_injection,
+            TestType::RaceCondition => &mut self.race_condition,
+            TestType::TimingAttack => &mut self.timing_attack,
+            TestType::BufferOverflow => &mut self.buffer_overflow,
+            TestType::EnvInjection => &mut self.env_injection,
+            TestType::Xss => &mut self.xss,
+            TestType::CommandInjection => &mut self.command_injection,
+            TestType::PathTraversal => &mut self.path_traversal,
+            TestType::Ssrf => &mut self.ssrf,
+            TestType::Xxe => &mut self.xxe,
+            TestType::Deserialization => &mut self.deserialization,
+            TestType::OpenRedirect => &mut self.open_redirect,
+        };
+        *flag = requested;
+    }
+
+    /// Returns the requested test types in discriminant order.
+    pub fn test_types(&self) -> Vec<TestType> {
+        TestType::ALL
+            .iter()
+            .copied()
+            .filter(|test_type| self.has(*test_type))
+            .collect()
+    }
+
+    /// Decodes a raw version 1 metadata record: its four test flags and
+    /// threat level. Version 1 records carry nothing else, so every other
+    /// field is left at its default.
+    ///
+    /// Returns `None` if the record fails [`check_record`](crate::format::check_record)
+    /// (too short, no [`RECORD_MAGIC`], or a checksum mismatch); call it
+    /// directly to find out why.
+    pub fn from_record(record: &[u8]) -> Option<Self> {
+        crate::format::check_record(record).ok()?;
+
+        let flag = |test_type: TestType| {
+            test_type
+                .record_offset()
+                .is_some_and(|offset| record[offset] != 0)
+        };
+
+        Some(SecurityTestConfig {
+            sql_injection: flag(TestType::SqlInjection),
+            race_condition: flag(TestType::RaceCondition),
+            timing_attack: flag(TestType::TimingAttack),
+            buffer_overflow: flag(TestType::BufferOverflow),
+            threat_level: ThreatLevel::from_byte(record[offsets::THREAT_LEVEL])?,
+            ..SecurityTestConfig::default()
+        })
+    }
+}
+
+/// One parameter of an annotated function.
+///
+/// ```rust
+/// use security_scanner::InputParam;
+///
+/// let param = InputParam::parse("query: Option<&str>");
+/// assert_eq!(param.name, "query");
+/// assert_eq!(param.ty, "Option<&str>");
+/// assert_eq!(param.to_string(), "query: Option<&str>");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct InputParam {
+    /// Parameter name, or the pattern as written for destructured parameters
+    pub name: String,
+    /// Type without lifetimes, such as `&str` or `Vec<u8>`
+    pub ty: String,
+}
+
+impl InputParam {
+    /// Splits a `name: Type` parameter entry of a record. An entry without a
+    /// type is taken as a bare name.
+    pub fn parse(entry: &str) -> Self {
+        let (name, ty) = entry.split_once(": ").unwrap_or((entry, ""));
+        InputParam {
+            name: name.to_string(),
+            ty: ty.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for InputParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.ty)
+    }
+}
+
+/// Where an annotated function is defined.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct SourceLocation {
+    /// Source file as given by `file!()`, usually relative to the workspace
+    /// root; empty if it couldn't be read
+    pub file: String,
+    /// 1-based line of the function name
+    pub line: u32,
+    /// 1-based column of the function name, 0 if unknown
+    pub column: u32,
+    /// Last line of the function's code, 0 if unknown; records don't
+    /// carry it, debug info read with the `debuginfo` feature does
+    pub end_line: u32,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.column {
+            0 => write!(f, "{}:{}", self.file, self.line),
+            column => write!(f, "{}:{}:{}", self.file, self.line, column),
+        }
+    }
+}
+
+/// Where the machine code of an annotated function lies in one binary, read
+/// from its symbol table after linking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CodeRange {
+    /// Address of the first byte: a virtual address in executables and
+    /// shared libraries, an offset into the section in object files
+    pub address: u64,
+    /// Size in bytes
+    pub size: u64,
+}
+
+impl CodeRange {
+    /// Address just past the last byte.
+    pub fn end(&self) -> u64 {
+        self.address + self.size
+    }
+
+    /// Returns whether `address` lies inside the range.
+    pub fn contains(&self, address: u64) -> bool {
+        (self.address..self.end()).contains(&address)
+    }
+}
+
+impl fmt::Display for CodeRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}..{:#x}", self.address, self.end())
+    }
+}
+
+/// Compiler version and settings of the crate an annotated function was
+/// compiled in.
+///
+/// Stored as `key=value` pairs separated by `; `:
+///
+/// ```rust
+/// use security_scanner::BuildSettings;
+///
+/// let settings = BuildSettings::parse(
+///     "rustc=1.80.0 (051478957 2024-07-21); panic-unwind=true; \
+///      overflow-checks=false; debug-assertions=false",
+/// )
+/// .unwrap();
+/// assert_eq!(settings.rustc_version, "1.80.0 (051478957 2024-07-21)");
+/// assert!(settings.panic_unwind);
+/// assert!(!settings.overflow_checks);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BuildSettings {
+    /// Output of `rustc --version` without the `rustc ` prefix
+    pub rustc_version: String,
+    /// Whether panics unwind (`panic = "unwind"`) rather than abort
+    pub panic_unwind: bool,
+    /// Whether integer overflow panics (`-C overflow-checks`)
+    pub overflow_checks: bool,
+    /// Whether `debug_assert!` is checked (`-C debug-assertions`)
+    pub debug_assertions: bool,
+}
+
+impl BuildSettings {
+    /// Parses the settings string of a record, returning `None` if a
+    /// setting is missing or malformed. Unknown keys are ignored.
+    pub fn parse(settings: &str) -> Option<Self> {
+        let mut rustc_version = None;
+        let mut flags = [None; 3];
+        for pair in settings.split("; ") {
+            let (key, value) = pair.split_once('=')?;
+            let flag = match key {
+                "rustc" => {
+                    rustc_version = Some(value.to_string());
+                    continue;
+                }
+                "panic-unwind" => &mut flags[0],
+                "overflow-checks" => &mut flags[1],
+                "debug-assertions" => &mut flags[2],
+                _ => continue,
+            };
+            *flag = Some(value.parse::<bool>().ok()?);
+        }
+
+        let [panic_unwind, overflow_checks, debug_assertions] = flags;
+        Some(BuildSettings {
+            rustc_version: rustc_version?,
+            panic_unwind: panic_unwind?,
+            overflow_checks: overflow_checks?,
+            debug_assertions: debug_assertions?,
+        })
+    }
+}
+
+impl fmt::Display for BuildSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rustc={}; panic-unwind={}; overflow-checks={}; debug-assertions={}",
+            self.rustc_version, self.panic_unwind, self.overflow_checks, self.debug_assertions
+        )
+    }
+}
+
+/// Everything known about one annotated function.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SecurityTestMetadata {
+    /// Name of the annotated function
+    pub function_name: String,
+    /// Requested tests and threat level
+    pub config: SecurityTestConfig,
+    /// Where the function is defined, when the record carries a location
+    pub location: Option<SourceLocation>,
+    /// Compiler settings of the function's crate, when recorded
+    pub build: Option<BuildSettings>,
+    /// Address of the function's code, when known: in the running process
+    /// for registry entries, and in the file for extracted metadata, which
+    /// moves by the binary's load offset at run time
+    pub function_address: Option<usize>,
+    /// Generic instances that had records of their own, such as `T = u32`,
+    /// merged by [`merge_instances`](Self::merge_instances); empty when one
+    /// record covers the function, as `#[security_test]` writes
+    pub instances: Vec<String>,
+    /// Exposure declared by the `#[security_program]` of the binary the
+    /// function was found in
+    pub exposure: Option<Exposure>,
+}
+
+impl SecurityTestMetadata {
+    /// Decodes a raw version 2 record, which carries everything about the
+    /// function inline.
+    ///
+    /// Returns `None` if the record fails
+    /// [`tlv::check_record`](crate::format::tlv::check_record), lacks a valid
+    /// threat level or holds a malformed value, as program records do. Entries
+    /// with unknown tags are skipped. Version 1 records keep their strings in other sections; decode
+    /// their fixed fields with [`SecurityTestConfig::from_record`].
+    pub fn from_record(record: &[u8]) -> Option<Self> {
+        let record = tlv::check_record(record).ok()?;
+        let byte = |entry: &tlv::Entry<'_>| u8::try_from(entry.as_uint()?).ok();
+
+        let mut metadata = SecurityTestMetadata::default();
+        let config = &mut metadata.config;
+        let mut tests = 0;
+        let mut threat_level = None;
+        let mut profile_levels = [None; 2];
+        let mut cwe = Vec::new();
+        let mut cvss_score = None;
+        let mut cvss_vector = None;
+        let mut owasp = Vec::new();
+        let mut location = SourceLocation::default();
+        for entry in tlv::entries(record) {
+            match entry.tag {
+                tags::PATH => metadata.function_name = entry.as_str()?.to_string(),
+                tags::TESTS => tests = entry.as_uint()?,
+                tags::THREAT_LEVEL => threat_level = ThreatLevel::from_byte(byte(&entry)?),
+                tags::DEBUG_THREAT_LEVEL => {
+                    profile_levels[0] = ThreatLevel::from_byte(byte(&entry)?)
+                }
+                tags::RELEASE_THREAT_LEVEL => {
+                    profile_levels[1] = ThreatLevel::from_byte(byte(&entry)?)
+                }
+                tags::CAPABILITIES => config.capabilities = Capabilities::from_byte(byte(&entry)?),
+                tags::STRIDE => config.stride = StrideCategory::from_byte(byte(&entry)?),
+                tags::SLA_DAYS => {
+                    config.sla_days = match u16::try_from(entry.as_uint()?).ok()? {
+                        0 => None,
+                        days => Some(days),
+                    }
+                }
+                tags::TEST_CONTEXT => config.test_context = byte(&entry)? != 0,
+                tags::LINE => location.line = u32::try_from(entry.as_uint()?).ok()?,
+                tags::COLUMN => location.column = u32::try_from(entry.as_uint()?).ok()?,
+                tags::FILE => location.file = entry.as_str()?.to_string(),
+                tags::READS_ENV => config.reads_env.push(entry.as_str()?.to_string()),
+                tags::FIXTURE => config.fixtures.push(entry.as_str()?.to_string()),
+                tags::ATTACK_TECHNIQUE => {
+                    config.attack_techniques.push(entry.as_str()?.to_string())
+                }
+                tags::INPUT_PARAM => config.input_params.push(InputParam::parse(entry.as_str()?)),
+                tags::BUILD => metadata.build = BuildSettings::parse(entry.as_str()?),
+                tags::CUSTOM_TEST => config.custom_tests.push(entry.as_str()?.to_string()),
+                tags::CWE => cwe.push(format!("CWE-{}", entry.as_uint()?)),
+                tags::OWASP => owasp.push(format!("OWASP-{}", entry.as_str()?)),
+                tags::CVSS_SCORE => cvss_score = Some(byte(&entry)?).filter(|score| *score <= 100),
+                tags::CVSS_VECTOR => cvss_vector = Some(entry.as_str()?.to_string()),
+                tags::BANNED_CALL => config.banned_calls.push(entry.as_str()?.to_string()),
+                tags::INSTANCE => metadata.instances = vec![entry.as_str()?.to_string()],
+                _ => {}
+            }
+        }
+
+        for test_type in TestType::ALL {
+            config.set(test_type, tests & u64::from(test_type.bit()) != 0);
+        }
+        config.threat_level = threat_level?;
+        config.compliance_tags = cwe;
+        config.compliance_tags.append(&mut owasp);
+        config.cvss = cvss_score.map(|score_tenths| Cvss {
+            score_tenths,
+            vector: cvss_vector,
+        });
+        if let [Some(debug), Some(release)] = profile_levels {
+            config.profile_threat_levels = Some(ProfileThreatLevels { debug, release });
+        }
+        if location.line != 0 {
+            metadata.location = Some(location);
+        }
+        Some(metadata)
+    }
+
+    /// Merges the functions decoded from several records of one definition
+    /// site, the same path and location, into the first of them, as one
+    /// function per generic instance would have. Its `instances` lists
+    /// theirs, each once, and its address is dropped unless they all share
+    /// it. Functions stay in the order of their first record.
+    ///
+    /// ```rust
+    /// use security_scanner::SecurityTestMetadata;
+    ///
+    /// let instance = |instance: &str| SecurityTestMetadata {
+    ///     function_name: "app::decode".to_string(),
+    ///     instances: vec![instance.to_string()],
+    ///     ..SecurityTestMetadata::default()
+    /// };
+    /// let merged = SecurityTestMetadata::merge_instances(vec![
+    ///     instance("T = u32"),
+    ///     instance("T = String"),
+    ///     instance("T = u32"),
+    /// ]);
+    /// assert_eq!(merged.len(), 1);
+    /// assert_eq!(merged[0].instances, ["T = u32", "T = String"]);
+    /// ```
+    pub fn merge_instances(functions: Vec<Self>) -> Vec<Self> {
+        let mut merged: Vec<Self> = Vec::with_capacity(functions.len());
+        let mut sites = HashMap::new();
+        for function in functions {
+            let site = (function.function_name.clone(), function.location.clone());
+            let Some(&index) = sites.get(&site) else {
+                sites.insert(site, merged.len());
+                merged.push(function);
+                continue;
+            };
+            let first = &mut merged[index];
+            for instance in function.instances {
+                if !first.instances.contains(&instance) {
+                    first.instances.push(instance);
+                }
+            }
+            if first.function_address != function.function_address {
+                first.function_address = None;
+            }
+        }
+        merged
+    }
+}
+
+/// Who can reach a program, as declared with `#[security_program(exposure = ...)]`.
+///
+/// Ordered from least to most exposed:
+///
+/// ```rust
+/// use security_scanner::types::Exposure;
+///
+/// assert!(Exposure::Internet > Exposure::Internal);
+/// assert_eq!(Exposure::from_byte(2), Some(Exposure::Partner));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Exposure {
+    /// No network path from outside its enclave (`airgapped`)
+    Airgapped = 0,
+    /// Reachable from the organization's own network (`internal`)
+    Internal = 1,
+    /// Reachable by partners over dedicated or authenticated links (`partner`)
+    Partner = 2,
+    /// Reachable from the public internet (`internet`)
+    Internet = 3,
+}
+
+impl Exposure {
+    /// Every exposure, from least to most exposed.
+    pub const ALL: [Exposure; 4] = [
+        Exposure::Airgapped,
+        Exposure::Internal,
+        Exposure::Partner,
+        Exposure::Internet,
+    ];
+
+    /// Converts the exposure byte stored in a program record.
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        Exposure::ALL.get(usize::from(byte)).copied()
+    }
+
+    /// Returns the name used in `#[security_program(...)]` for this exposure.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Exposure::Airgapped => "airgapped",
+            Exposure::Internal => "internal",
+            Exposure::Partner => "partner",
+            Exposure::Internet => "internet",
+        }
+    }
+}
+
+impl fmt::Display for Exposure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A kind of data a program handles, as listed in `data_class(...)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DataClass {
+    /// Personally identifiable information (`pii`)
+    Pii = 0,
+    /// Protected health information (`phi`)
+    Phi = 1,
+    /// Payment card data (`pci`)
+    Pci = 2,
+    /// Passwords, keys and tokens (`credentials`)
+    Credentials = 3,
+}
+
+impl DataClass {
+    /// Every data class, in the order of their bits.
+    pub const ALL: [DataClass; 4] = [
+        DataClass::Pii,
+        DataClass::Phi,
+        DataClass::Pci,
+        DataClass::Credentials,
+    ];
+
+    /// Returns the identifier used in `data_class(...)` for this class.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DataClass::Pii => "pii",
+            DataClass::Phi => "phi",
+            DataClass::Pci => "pci",
+            DataClass::Credentials => "credentials",
+        }
+    }
+
+    /// Returns this class's bit in the record's data classes byte.
+    pub fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Decodes the data classes byte of a program record, in bit order.
+    pub fn from_byte(byte: u8) -> Vec<Self> {
+        DataClass::ALL
+            .iter()
+            .copied()
+            .filter(|class| byte & class.bit() != 0)
+            .collect()
+    }
+}
+
+impl fmt::Display for DataClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Context of a whole program, from its `#[security_program(...)]`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ProgramContext {
+    /// Path of the annotated entry point, such as `my_app::main`
+    pub entry_point: String,
+    /// Who can reach the program, when declared
+    pub exposure: Option<Exposure>,
+    /// Kinds of data the program handles
+    pub data_classes: Vec<DataClass>,
+    /// Where the entry point is defined
+    pub location: Option<SourceLocation>,
+}
+
+impl ProgramContext {
+    /// Decodes a raw program record.
+    ///
+    /// Returns `None` if the record fails
+    /// [`tlv::check_record`](crate::format::tlv::check_record), isn't a
+    /// program record or holds a malformed value. Function records, which
+    /// [`SecurityTestMetadata::from_record`] decodes, return `None` too.
+    pub fn from_record(record: &[u8]) -> Option<Self> {
+        let record = tlv::check_record(record).ok()?;
+        let byte = |entry: &tlv::Entry<'_>| u8::try_from(entry.as_uint()?).ok();
+
+        let mut program = ProgramContext::default();
+        let mut marked = false;
+        let mut location = SourceLocation::default();
+        for entry in tlv::entries(record) {
+            match entry.tag {
+                tags::PROGRAM => marked = byte(&entry)? == 1,
+                tags::PATH => program.entry_point = entry.as_str()?.to_string(),
+                tags::EXPOSURE => program.exposure = Some(Exposure::from_byte(byte(&entry)?)?),
+                tags::DATA_CLASSES => program.data_classes = DataClass::from_byte(byte(&entry)?),
+                tags::LINE => location.line = u32::try_from(entry.as_uint()?).ok()?,
+                tags::COLUMN => location.column = u32::try_from(entry.as_uint()?).ok()?,
+                tags::FILE => location.file = entry.as_str()?.to_string(),
+                _ => {}
+            }
+        }
+
+        if !marked {
+            return None;
+        }
+        if location.line != 0 {
+            program.location = Some(location);
+        }
+        Some(program)
+    }
+}
@@ -0,0 +1,73 @@
+//! Tests for the stress tests behind `race_condition` and `generate_tests`.
+//! The annotated functions below get their generated tests in this binary,
+//! as `__security_test_record_visit::race_condition` and so on.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use security_scanner::concurrency;
+use security_scanner::security_test;
+
+static VISITS: Mutex<Option<HashMap<String, u64>>> = Mutex::new(None);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[security_test(race_condition, generate_tests, high)]
+fn record_visit(page: &str) -> u64 {
+    let mut visits = VISITS.lock().unwrap();
+    let count = visits
+        .get_or_insert_with(HashMap::new)
+        .entry(page.to_string())
+        .or_default();
+    *count += 1;
+    *count
+}
+
+#[security_test(race_condition, generate_tests)]
+fn next_id() -> u64 {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Panics when two calls overlap, the way code assuming it runs alone
+/// breaks when it doesn't.
+fn exclusive(busy: &AtomicBool) {
+    assert!(!busy.swap(true, Ordering::SeqCst), "two calls overlapped");
+    std::thread::sleep(Duration::from_millis(1));
+    busy.store(false, Ordering::SeqCst);
+}
+
+#[test]
+fn every_thread_makes_every_call() {
+    let calls = AtomicUsize::new(0);
+    let threads = Mutex::new(Vec::new());
+    concurrency::stress(
+        |thread| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            threads.lock().unwrap().push(thread);
+        },
+        4,
+        50,
+    );
+
+    assert_eq!(calls.into_inner(), 200);
+    let mut threads = threads.into_inner().unwrap();
+    threads.sort_unstable();
+    threads.dedup();
+    assert_eq!(threads, [0, 1, 2, 3]);
+}
+
+#[test]
+#[should_panic(expected = "of 4 panicked: two calls overlapped")]
+fn overlapping_calls_fail() {
+    let busy = AtomicBool::new(false);
+    concurrency::stress(|_| exclusive(&busy), 4, 20);
+}
+
+#[test]
+fn generated_tests_call_the_function() {
+    let before = record_visit("home");
+    assert_eq!(record_visit("home"), before + 1);
+    assert!(next_id() < next_id());
+}
@@ -0,0 +1,142 @@
+//! Tests for reading core dumps, run with `--features scanner` on a core
+//! written here, of a process that had this test binary loaded.
+
+#![cfg(all(target_os = "linux", target_arch = "x86_64"))]
+
+use security_scanner::coredump::CoreDump;
+use security_scanner::extract;
+use security_scanner::FormatError;
+
+/// Where the core maps this test binary.
+const IMAGE_START: u64 = 0x5555_5555_0000;
+/// Where the core's stack memory starts, at the stack pointer.
+const STACK: u64 = 0x7ffd_1234_0000;
+
+fn note(out: &mut Vec<u8>, kind: u32, desc: &[u8]) {
+    out.extend_from_slice(&5u32.to_le_bytes());
+    out.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(b"CORE\0\0\0\0");
+    out.extend_from_slice(desc);
+    out.resize(out.len().next_multiple_of(4), 0);
+}
+
+fn program_header(out: &mut Vec<u8>, kind: u32, offset: u64, address: u64, size: u64) {
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(&4u32.to_le_bytes());
+    for field in [offset, address, 0, size, size, 4] {
+        out.extend_from_slice(&field.to_le_bytes());
+    }
+}
+
+/// Writes an x86-64 core dump of a process stopped by `signal` at `pc`,
+/// with `binary_name` mapped at [`IMAGE_START`] and `stack` at [`STACK`].
+fn core(signal: u16, pc: u64, binary_name: &str, binary_len: u64, stack: &[u64]) -> Vec<u8> {
+    let mut status = vec![0u8; 336];
+    status[12..14].copy_from_slice(&signal.to_le_bytes());
+    status[112 + 16 * 8..][..8].copy_from_slice(&pc.to_le_bytes());
+    status[112 + 19 * 8..][..8].copy_from_slice(&STACK.to_le_bytes());
+
+    let mut files = Vec::new();
+    for word in [1, 4096, IMAGE_START, IMAGE_START + binary_len, 0] {
+        files.extend_from_slice(&word.to_le_bytes());
+    }
+    files.extend_from_slice(format!("/srv/app/{}\0", binary_name).as_bytes());
+
+    let mut notes = Vec::new();
+    note(&mut notes, 1, &status);
+    note(&mut notes, 0x4649_4c45, &files);
+    let memory: Vec<u8> = stack.iter().flat_map(|word| word.to_le_bytes()).collect();
+
+    let notes_offset = 64 + 2 * 56;
+    let memory_offset = notes_offset + notes.len() as u64;
+    let mut out = Vec::new();
+    out.extend_from_slice(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0");
+    out.extend_from_slice(&4u16.to_le_bytes());
+    out.extend_from_slice(&62u16.to_le_bytes());
+    out.extend_from_slice(&1u32.to_le_bytes());
+    for field in [0u64, 64, 0] {
+        out.extend_from_slice(&field.to_le_bytes());
+    }
+    out.extend_from_slice(&0u32.to_le_bytes());
+    for field in [64u16, 56, 2, 64, 0, 0] {
+        out.extend_from_slice(&field.to_le_bytes());
+    }
+    program_header(&mut out, 4, notes_offset, 0, notes.len() as u64);
+    program_header(&mut out, 1, memory_offset, STACK, memory.len() as u64);
+    out.extend_from_slice(&notes);
+    out.extend_from_slice(&memory);
+    out
+}
+
+#[inline(never)]
+fn parse(input: &str) -> usize {
+    input.len()
+}
+
+#[inline(never)]
+fn handle(input: &str) -> usize {
+    parse(input) + 1
+}
+
+#[test]
+fn reads_the_crash_state_and_load_map() {
+    assert_eq!(std::hint::black_box(handle as fn(&str) -> usize)("x"), 2);
+
+    let path = std::env::current_exe().unwrap();
+    let binary = std::fs::read(&path).unwrap();
+    let name = path.file_name().unwrap().to_str().unwrap();
+    let symbols = extract::function_symbols(&binary).unwrap().unwrap();
+    let [parse] = &symbols["coredump::parse"][..] else {
+        panic!("parse has several copies");
+    };
+    let [handle] = &symbols["coredump::handle"][..] else {
+        panic!("handle has several copies");
+    };
+
+    // Crashed inside `parse`, called from `handle`
+    let runtime = |symbol: &extract::FunctionSymbol, offset: u64| {
+        IMAGE_START + symbol.file_offset.unwrap() + offset
+    };
+    let data = core(
+        11,
+        runtime(parse, 1),
+        name,
+        binary.len() as u64,
+        &[0, runtime(handle, 5), 42],
+    );
+    let core = CoreDump::parse(&data).unwrap();
+    assert_eq!(core.signal, 11);
+    assert_eq!(core.sp, STACK);
+    assert_eq!(core.mappings.len(), 1);
+    assert_eq!(core.mappings[0].path, format!("/srv/app/{}", name));
+    assert_eq!(core.stack_words(2), [0, runtime(handle, 5)]);
+    assert_eq!(core.stack_words(8).len(), 3);
+    assert_eq!(core.read(STACK + 16, 8), Some(&42u64.to_le_bytes()[..]));
+    assert_eq!(core.read(STACK + 20, 8), None);
+
+    let load_map = core.load_map(name, &binary).unwrap().unwrap();
+    assert_eq!(
+        load_map.link_address(core.pc),
+        Some(parse.range.address + 1)
+    );
+    assert_eq!(
+        load_map.link_address(core.stack_words(2)[1]),
+        Some(handle.range.address + 5)
+    );
+    assert_eq!(load_map.link_address(STACK), None);
+    assert!(core.load_map("other", &binary).unwrap().is_none());
+}
+
+#[test]
+fn rejects_files_that_are_not_cores() {
+    let binary = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    assert!(matches!(
+        CoreDump::parse(&binary),
+        Err(FormatError::Unsupported(_))
+    ));
+    assert!(matches!(
+        CoreDump::parse(b"not a core"),
+        Err(FormatError::Unsupported(_))
+    ));
+}
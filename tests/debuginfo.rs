@@ -0,0 +1,89 @@
+//! Tests for reading this test binary's debug info, run with
+//! `--features debuginfo`.
+
+#![cfg(target_os = "linux")]
+
+use security_scanner::debuginfo::DebugInfo;
+use security_scanner::security_test;
+
+mod auth {
+    use security_scanner::security_test;
+
+    #[security_test(sql_injection, critical)]
+    pub fn login(user: &str, password: &str) -> bool {
+        let known = user == "admin";
+        let valid = password.len() >= 12;
+        known && valid
+    }
+}
+
+#[security_test(xss)]
+fn render<T: std::fmt::Display>(value: T) -> String {
+    let rendered = value.to_string();
+    rendered.replace('<', "&lt;")
+}
+
+fn own_binary() -> Vec<u8> {
+    std::fs::read(std::env::current_exe().unwrap()).unwrap()
+}
+
+#[test]
+fn reads_the_lines_of_functions() {
+    assert!(auth::login("admin", "correct horse battery"));
+    let info = DebugInfo::read(&own_binary()).unwrap().unwrap();
+    assert!(!info.is_empty());
+
+    let login = info.function("debuginfo::auth::login").unwrap();
+    assert!(
+        login.unit.starts_with("tests/debuginfo.rs"),
+        "{}",
+        login.unit
+    );
+    assert_eq!(login.file, "tests/debuginfo.rs");
+    assert_eq!((login.first_line, login.last_line), (13, 17));
+}
+
+#[test]
+fn generic_functions_are_read_once() {
+    assert_eq!(render(1) + &render("<b>"), "1&lt;b>");
+    let info = DebugInfo::read(&own_binary()).unwrap().unwrap();
+
+    let render = info.function("debuginfo::render").unwrap();
+    assert_eq!(render.file, "tests/debuginfo.rs");
+    assert_eq!((render.first_line, render.last_line), (21, 24));
+}
+
+#[test]
+// `strip` leaves no records to read
+#[cfg(not(feature = "strip"))]
+fn applies_to_extracted_functions() {
+    let data = own_binary();
+    let info = DebugInfo::read(&data).unwrap().unwrap();
+    let mut functions = security_scanner::extract::extract(&data).unwrap().functions;
+    info.apply(&mut functions);
+
+    let login = functions
+        .iter()
+        .find(|function| function.function_name == "debuginfo::auth::login")
+        .unwrap();
+    let location = login.location.as_ref().unwrap();
+    assert_eq!(location.file, "tests/debuginfo.rs");
+    assert_eq!(location.end_line, 17);
+
+    let mut unlocated = vec![login.clone()];
+    unlocated[0].location = None;
+    info.apply(&mut unlocated);
+    let location = unlocated[0].location.as_ref().unwrap();
+    assert_eq!((location.line, location.end_line), (13, 17));
+}
+
+#[test]
+fn rejects_unreadable_binaries() {
+    assert!(DebugInfo::read(b"not an object file").is_err());
+}
+
+#[test]
+fn webassembly_modules_have_none() {
+    let module = b"\0asm\x01\0\0\0";
+    assert!(DebugInfo::read(module).unwrap().is_none());
+}
@@ -0,0 +1,585 @@
+//! Tests for reading metadata out of this test binary, run with
+//! `--features scanner`.
+
+// `strip` leaves no records to read
+#![cfg(all(
+    any(target_os = "linux", target_os = "macos", target_os = "windows"),
+    not(feature = "strip")
+))]
+
+use security_scanner::extract;
+use security_scanner::format::tlv;
+use security_scanner::{
+    security_program, security_test, security_test_module, DataClass, Exposure, FormatError,
+    InputParam, ProfileThreatLevels, ScannerError, SecurityTestMetadata, TestType, ThreatLevel,
+};
+
+mod auth {
+    use super::*;
+
+    #[security_test(
+        sql_injection,
+        timing_attack,
+        reads_env("DATABASE_URL", "DB_PASSWORD"),
+        attack("T1190"),
+        critical
+    )]
+    pub fn login(user: &str) -> bool {
+        !user.is_empty()
+    }
+}
+
+#[security_test(
+    race_condition,
+    fixtures("ledger"),
+    stride(tampering),
+    sla_days = 14,
+    high,
+    threat_level(release = critical)
+)]
+fn transfer(amount: u64) -> u64 {
+    amount
+}
+
+#[security_test(
+    buffer_overflow,
+    xss,
+    custom = "template_escape",
+    cwe = [79, 120],
+    owasp = "A03:2021",
+    cvss = "AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N"
+)]
+fn render<'a>(
+    mut template: &'a str,
+    (width, height): (u32, u32),
+    values: Option<&'static [u8]>,
+    sink: &mut (dyn std::fmt::Write + 'a),
+) -> usize {
+    template = template.trim();
+    let _ = (values, sink.write_str(template));
+    (width * height) as usize
+}
+
+struct Session;
+
+#[security_test]
+impl Session {
+    #[security_test(timing_attack, high)]
+    fn verify(&self, token: &str) -> bool {
+        !token.is_empty()
+    }
+
+    fn close(self) {}
+}
+
+#[security_test]
+impl std::fmt::Display for Session {
+    #[security_test(xss)]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("session")
+    }
+}
+
+#[security_test_module(path_traversal, fixtures("uploads"), medium)]
+mod files {
+    pub fn read(name: &str) -> usize {
+        name.len()
+    }
+
+    #[security_test(command_injection, critical)]
+    pub fn convert(name: &str) -> usize {
+        name.len()
+    }
+
+    #[test]
+    fn tests_are_not_recorded() {
+        assert_eq!(read("a") + convert("b"), 2);
+    }
+}
+
+#[security_test(deserialization, high)]
+async fn fetch(url: &str) -> usize {
+    url.len()
+}
+
+#[security_test(deserialization, inline_never)]
+fn decode<'de, T, const N: usize>(input: &'de [u8; N]) -> Option<T>
+where
+    T: serde::Deserialize<'de> + Default,
+{
+    let _ = input;
+    Some(T::default())
+}
+
+struct Cache<T>(Vec<T>);
+
+#[security_test]
+impl<T: Clone> Cache<T> {
+    #[security_test(race_condition)]
+    async fn get<'a>(&'a self, index: usize, fallback: &'a T) -> &'a T
+    where
+        T: Send,
+    {
+        self.0.get(index).unwrap_or(fallback)
+    }
+}
+
+#[security_program(exposure = internet, data_class(pii, credentials))]
+fn serve() {}
+
+// Never called, so the linker drops the code and only the record is left
+#[allow(dead_code)]
+#[security_test(ssrf)]
+fn unreachable_fetch(url: &str) -> usize {
+    url.len()
+}
+
+// Anchored, so the binary records where its code is
+#[security_test(path_traversal, anchor)]
+fn open_upload(name: &str) -> usize {
+    name.len()
+}
+
+fn own_binary() -> Vec<u8> {
+    std::fs::read(std::env::current_exe().unwrap()).unwrap()
+}
+
+fn find<'a>(functions: &'a [SecurityTestMetadata], path: &str) -> &'a SecurityTestMetadata {
+    functions
+        .iter()
+        .find(|function| function.function_name == path)
+        .unwrap_or_else(|| panic!("{} not found in {:?}", path, functions))
+}
+
+#[test]
+fn extracts_functions_from_own_binary() {
+    assert!(auth::login("admin"));
+    assert_eq!(transfer(5), 5);
+
+    let extraction = extract::extract_file(std::env::current_exe().unwrap()).unwrap();
+    assert_eq!(extraction.integrity.verified, 13);
+    assert_eq!(extraction.integrity.valid(), 13);
+
+    let login = find(&extraction.functions, "extract::auth::login");
+    assert!(login.config.sql_injection && login.config.timing_attack);
+    assert_eq!(login.config.threat_level, ThreatLevel::Critical);
+    assert_eq!(login.config.reads_env, ["DATABASE_URL", "DB_PASSWORD"]);
+    assert_eq!(login.config.attack_techniques, ["T1190"]);
+    assert!(login.config.fixtures.is_empty());
+    assert_eq!(login.config.input_params, [InputParam::parse("user: &str")]);
+    assert!(login.config.test_context);
+    let location = login.location.as_ref().unwrap();
+    assert_eq!(
+        (location.file.as_str(), location.line),
+        ("tests/extract.rs", 27)
+    );
+    assert_eq!(location.to_string(), "tests/extract.rs:27:12");
+
+    let transfer = find(&extraction.functions, "extract::transfer");
+    assert!(transfer.config.race_condition);
+    assert_eq!(transfer.config.fixtures, ["ledger"]);
+    assert_eq!(transfer.config.sla_days, Some(14));
+    assert_eq!(
+        transfer.config.input_params,
+        [InputParam::parse("amount: u64")]
+    );
+    let level = if cfg!(debug_assertions) {
+        ThreatLevel::High
+    } else {
+        ThreatLevel::Critical
+    };
+    assert_eq!(transfer.config.threat_level, level);
+    assert_eq!(
+        transfer.config.profile_threat_levels,
+        Some(ProfileThreatLevels {
+            debug: ThreatLevel::High,
+            release: ThreatLevel::Critical,
+        })
+    );
+    assert_eq!(login.config.profile_threat_levels, None);
+}
+
+#[test]
+fn simplifies_parameter_types() {
+    let mut sink = String::new();
+    assert_eq!(render(" x ", (2, 3), None, &mut sink), 6);
+
+    let extraction = extract::extract_file(std::env::current_exe().unwrap()).unwrap();
+    let render = find(&extraction.functions, "extract::render");
+    assert_eq!(
+        render.config.test_types(),
+        [TestType::BufferOverflow, TestType::Xss]
+    );
+    assert_eq!(render.config.custom_tests, ["template_escape"]);
+    assert_eq!(render.config.threat_level, ThreatLevel::Medium);
+    let cvss = render.config.cvss.as_ref().unwrap();
+    assert_eq!(cvss.score_tenths, 61);
+    assert_eq!(
+        cvss.vector.as_deref(),
+        Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N")
+    );
+    assert_eq!(
+        render.config.compliance_tags,
+        ["CWE-79", "CWE-120", "OWASP-A03:2021"]
+    );
+    let params: Vec<String> = render
+        .config
+        .input_params
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        params,
+        [
+            "template: &str",
+            "(width, height): (u32, u32)",
+            "values: Option<&[u8]>",
+            "sink: &mut (dyn std::fmt::Write)",
+        ]
+    );
+}
+
+#[test]
+fn records_methods_under_their_type() {
+    assert!(Session.verify("token"));
+    assert_eq!(Session.to_string(), "session");
+    Session.close();
+
+    let extraction = extract::extract_file(std::env::current_exe().unwrap()).unwrap();
+    let verify = find(&extraction.functions, "extract::Session::verify");
+    assert!(verify.config.timing_attack);
+    assert_eq!(verify.config.threat_level, ThreatLevel::High);
+    assert_eq!(
+        verify.config.input_params,
+        [
+            InputParam::parse("self: &Self"),
+            InputParam::parse("token: &str")
+        ]
+    );
+    assert_eq!(verify.location.as_ref().unwrap().line, 68);
+
+    let fmt = find(
+        &extraction.functions,
+        "extract::<Session as std::fmt::Display>::fmt",
+    );
+    assert_eq!(fmt.config.test_types(), [TestType::Xss]);
+    assert!(!extraction
+        .functions
+        .iter()
+        .any(|function| function.function_name.ends_with("::close")));
+}
+
+#[test]
+fn records_async_and_generic_functions() {
+    drop(fetch("https://example.com"));
+    assert_eq!(decode::<u8, 2>(&[1, 2]), Some(0));
+    drop(Cache(vec![1]).get(0, &0));
+
+    let extraction = extract::extract_file(std::env::current_exe().unwrap()).unwrap();
+    let fetch = find(&extraction.functions, "extract::fetch");
+    assert!(fetch
+        .config
+        .test_types()
+        .contains(&TestType::Deserialization));
+    assert_eq!(fetch.config.input_params, [InputParam::parse("url: &str")]);
+
+    // One record however many instances the generic function has
+    let decode = find(&extraction.functions, "extract::decode");
+    assert_eq!(
+        decode.config.input_params,
+        [InputParam::parse("input: &[u8; N]")]
+    );
+    assert_eq!(
+        extraction
+            .functions
+            .iter()
+            .filter(|function| function.function_name == "extract::decode")
+            .count(),
+        1
+    );
+
+    let get = find(&extraction.functions, "extract::Cache<T>::get");
+    assert!(get.config.race_condition);
+    assert_eq!(
+        get.config.input_params,
+        [
+            InputParam::parse("self: &Self"),
+            InputParam::parse("index: usize"),
+            InputParam::parse("fallback: &T")
+        ]
+    );
+}
+
+#[test]
+fn module_defaults_apply_to_every_function() {
+    let extraction = extract::extract_file(std::env::current_exe().unwrap()).unwrap();
+    let read = find(&extraction.functions, "extract::files::read");
+    assert_eq!(read.config.test_types(), [TestType::PathTraversal]);
+    assert_eq!(read.config.threat_level, ThreatLevel::Medium);
+    assert_eq!(read.config.fixtures, ["uploads"]);
+
+    let convert = find(&extraction.functions, "extract::files::convert");
+    assert_eq!(convert.config.test_types(), [TestType::CommandInjection]);
+    assert_eq!(convert.config.threat_level, ThreatLevel::Critical);
+    assert_eq!(convert.config.fixtures, ["uploads"]);
+    assert!(!extraction
+        .functions
+        .iter()
+        .any(|function| function.function_name.ends_with("tests_are_not_recorded")));
+}
+
+#[test]
+fn records_the_build_settings_of_this_crate() {
+    let extraction = extract::extract_file(std::env::current_exe().unwrap()).unwrap();
+    let build = find(&extraction.functions, "extract::transfer")
+        .build
+        .as_ref()
+        .unwrap();
+
+    let overflow_panics = std::panic::catch_unwind(|| std::hint::black_box(u8::MAX) + 1).is_err();
+    assert_eq!(build.overflow_checks, overflow_panics);
+    assert_eq!(build.debug_assertions, cfg!(debug_assertions));
+    assert_eq!(build.panic_unwind, cfg!(panic = "unwind"));
+    assert!(build
+        .rustc_version
+        .starts_with(|c: char| c.is_ascii_digit()));
+}
+
+#[test]
+fn reads_the_program_context() {
+    serve();
+
+    let extraction = extract::extract(&own_binary()).unwrap();
+    let program = extraction.program.unwrap();
+    assert_eq!(program.entry_point, "extract::serve");
+    assert_eq!(program.exposure, Some(Exposure::Internet));
+    assert_eq!(
+        program.data_classes,
+        [DataClass::Pii, DataClass::Credentials]
+    );
+    assert!(program.location.is_some());
+    assert!(extraction
+        .functions
+        .iter()
+        .all(|function| function.function_name != "extract::serve"));
+    // Every function of the binary inherits the exposure
+    assert!(extraction
+        .functions
+        .iter()
+        .all(|function| function.exposure == Some(Exposure::Internet)));
+}
+
+#[test]
+fn finds_functions_without_code() {
+    // Taking the address keeps the code even when calls would be inlined
+    let login = std::hint::black_box(auth::login as fn(&str) -> bool);
+    assert!(login("admin"));
+    let verify = std::hint::black_box(Session::verify as fn(&Session, &str) -> bool);
+    assert!(verify(&Session, "token"));
+
+    let binary = own_binary();
+    let extraction = extract::extract(&binary).unwrap();
+    find(&extraction.functions, "extract::unreachable_fetch");
+
+    let defined = extract::defined_functions(&binary).unwrap();
+    // PE images keep their symbols in a separate PDB
+    if cfg!(windows) && defined.is_none() {
+        return;
+    }
+    let defined = defined.unwrap();
+    assert!(defined.contains_key("extract::auth::login"));
+    assert!(defined.contains_key("extract::Session::verify"));
+    assert!(!defined.contains_key("extract::unreachable_fetch"));
+}
+
+#[test]
+fn reads_function_ranges() {
+    let login = std::hint::black_box(auth::login as fn(&str) -> bool);
+    let verify = std::hint::black_box(Session::verify as fn(&Session, &str) -> bool);
+
+    let defined = extract::defined_functions(&own_binary()).unwrap();
+    if cfg!(windows) && defined.is_none() {
+        return;
+    }
+    let defined = defined.unwrap();
+    let [login_range] = defined["extract::auth::login"][..] else {
+        panic!("login has several copies");
+    };
+    let [verify_range] = defined["extract::Session::verify"][..] else {
+        panic!("verify has several copies");
+    };
+    assert!(login_range.size > 0 && verify_range.size > 0);
+    // The binary is loaded at some offset, the same for both functions
+    let distance = (verify as usize as u64).wrapping_sub(login as usize as u64);
+    assert_eq!(
+        verify_range.address.wrapping_sub(login_range.address),
+        distance
+    );
+    assert!(!login_range.contains(verify_range.address));
+}
+
+#[test]
+fn reads_function_symbols() {
+    let login = std::hint::black_box(auth::login as fn(&str) -> bool);
+    assert!(login("admin"));
+
+    let binary = own_binary();
+    let symbols = extract::function_symbols(&binary).unwrap();
+    if cfg!(windows) && symbols.is_none() {
+        return;
+    }
+    let symbols = symbols.unwrap();
+    let [symbol] = &symbols["extract::auth::login"][..] else {
+        panic!("login has several copies");
+    };
+    assert!(symbol.symbol.contains("login"), "{}", symbol.symbol);
+    let defined = extract::defined_functions(&binary).unwrap().unwrap();
+    assert_eq!(defined["extract::auth::login"], [symbol.range]);
+
+    // The file holds the code that is mapped at the address; elsewhere
+    // function pointers may lead to a thunk, or have the Thumb bit set
+    let offset = symbol.file_offset.unwrap() as usize;
+    let size = symbol.range.size as usize;
+    if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        let code = unsafe { std::slice::from_raw_parts(login as usize as *const u8, size) };
+        assert_eq!(&binary[offset..offset + size], code);
+    }
+}
+
+#[test]
+fn reads_function_addresses() {
+    let login = std::hint::black_box(auth::login as fn(&str) -> bool);
+    let upload = open_upload as fn(&str) -> usize;
+
+    let extraction = extract::extract(&own_binary()).unwrap();
+    let upload_address = find(&extraction.functions, "extract::open_upload").function_address;
+    // The binary is loaded at some offset, the same for every function
+    let slide = (upload as usize).wrapping_sub(upload_address.unwrap());
+    assert_eq!(slide % 4096, 0);
+
+    // Other functions' addresses come from the symbol table
+    let login_address = find(&extraction.functions, "extract::auth::login").function_address;
+    if !cfg!(windows) || login_address.is_some() {
+        assert_eq!((login as usize).wrapping_sub(login_address.unwrap()), slide);
+    }
+    assert_eq!(
+        find(&extraction.functions, "extract::unreachable_fetch").function_address,
+        None
+    );
+}
+
+#[test]
+fn reads_the_hardening_of_own_binary() {
+    use security_scanner::hardening::{Hardening, Relro};
+
+    let hardening = Hardening::read(&own_binary()).unwrap().unwrap();
+    assert!(hardening.stack_protector.is_some());
+    if cfg!(target_os = "linux") {
+        // rustc links PIE executables with full RELRO there
+        assert_eq!(hardening.pie, Some(true));
+        assert_eq!(hardening.relro, Some(Relro::Full));
+        assert_eq!(hardening.nx, Some(true));
+        assert_eq!(hardening.cfg, None);
+    }
+
+    let unprotected = Hardening {
+        stack_protector: Some(false),
+        nx: Some(true),
+        ..hardening
+    };
+    assert!(unprotected
+        .missing_overflow_mitigations()
+        .contains(&"stack canaries"));
+    assert!(!unprotected.missing_overflow_mitigations().contains(&"NX"));
+}
+
+#[test]
+fn reports_corrupted_records() {
+    let mut binary = own_binary();
+    let magic = tlv::MAGIC.to_le_bytes();
+    // The magic value also appears in the scanner's own code
+    let start = (0..binary.len())
+        .find(|&start| {
+            binary[start..].starts_with(&magic) && tlv::check_record(&binary[start..]).is_ok()
+        })
+        .unwrap();
+    binary[start + tlv::HEADER_LEN + tlv::ENTRY_HEADER_LEN] ^= 1;
+
+    let extraction = extract::extract(&binary).unwrap();
+    assert_eq!(extraction.integrity.corrupted, 1);
+    // The first record may be the program's, depending on the link order
+    let records = extraction.functions.len() + usize::from(extraction.program.is_some());
+    assert_eq!(records, 12);
+
+    let err = ScannerError::from(extraction.verify().unwrap_err());
+    assert_eq!(err.code(), "format.corrupted");
+}
+
+#[test]
+fn reads_records_written_from_c() {
+    use std::path::Path;
+    use std::process::Command;
+
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("c-annotations");
+    std::fs::create_dir_all(&dir).unwrap();
+    let include = Path::new(env!("CARGO_MANIFEST_DIR")).join("format/include");
+    let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/annotated.c");
+
+    for (variable, compiler, language) in [("CC", "cc", "c"), ("CXX", "c++", "c++")] {
+        let compiler = std::env::var(variable).unwrap_or_else(|_| compiler.into());
+        let object = dir.join(format!("annotated-{}.o", language));
+        let status = Command::new(&compiler)
+            .args(["-x", language, "-O2", "-Wall", "-Werror", "-c", "-I"])
+            .arg(&include)
+            .arg(&source)
+            .arg("-o")
+            .arg(&object)
+            .status();
+        let Ok(status) = status else {
+            eprintln!("skipping {}: no compiler ({})", language, compiler);
+            continue;
+        };
+        assert!(
+            status.success(),
+            "annotated.c failed to compile as {}",
+            language
+        );
+
+        let data = std::fs::read(&object).unwrap();
+        let extraction = extract::extract(&data).unwrap();
+        extraction.verify().unwrap();
+        let records = if language == "c" { 2 } else { 3 };
+        assert_eq!(extraction.functions.len(), records);
+        assert_eq!(extraction.integrity.unchecked, records);
+
+        let parse = find(&extraction.functions, "parse_packet");
+        assert_eq!(parse.config.threat_level, ThreatLevel::Critical);
+        assert_eq!(
+            parse.config.test_types(),
+            [TestType::BufferOverflow, TestType::CommandInjection]
+        );
+        let location = parse.location.as_ref().unwrap();
+        assert!(location.file.ends_with("annotated.c"), "{}", location.file);
+        assert_eq!(location.line, 11);
+        let check = find(&extraction.functions, "check_password");
+        assert_eq!(check.config.test_types(), [TestType::TimingAttack]);
+
+        let defined = extract::defined_functions(&data).unwrap().unwrap();
+        assert!(defined.contains_key("parse_packet"));
+        assert!(defined.contains_key("check_password"));
+        if language == "c++" {
+            let resolve = find(&extraction.functions, "net::resolve");
+            assert_eq!(resolve.config.threat_level, ThreatLevel::Medium);
+            assert!(defined.contains_key("net::resolve"));
+        }
+    }
+}
+
+#[test]
+fn rejects_files_that_are_not_binaries() {
+    let err = extract::extract(b"not an executable").unwrap_err();
+    assert!(matches!(err, FormatError::Unsupported(_)), "{:?}", err);
+
+    let err = extract::extract_file("/nonexistent/binary").unwrap_err();
+    assert_eq!(err.code(), "io");
+}
@@ -0,0 +1,152 @@
+//! Refactoring scenarios that must not change a finding's fingerprint, and a
+//! few that must.
+
+use security_scanner::fingerprint::Fingerprint;
+use security_scanner::TestType;
+
+fn login(path: &str) -> Fingerprint {
+    Fingerprint::new("shop", path, TestType::SqlInjection, "union_select")
+}
+
+#[test]
+fn crate_prefix_spelling_does_not_matter() {
+    assert_eq!(login("auth::login"), login("crate::auth::login"));
+    assert_eq!(login("auth::login"), login("shop::auth::login"));
+}
+
+#[test]
+fn similarly_named_crate_prefix_is_kept() {
+    assert_ne!(login("auth::login"), login("shop_api::auth::login"));
+}
+
+#[test]
+fn hyphenated_crate_names_match_their_identifier_form() {
+    let hyphenated = Fingerprint::new(
+        "my-shop",
+        "my_shop::auth::login",
+        TestType::SqlInjection,
+        "x",
+    );
+    let underscored = Fingerprint::new("my_shop", "auth::login", TestType::SqlInjection, "x");
+    assert_eq!(hyphenated, underscored);
+}
+
+#[test]
+fn generic_arguments_and_whitespace_are_ignored() {
+    let plain = Fingerprint::new(
+        "shop",
+        "codec::decode",
+        TestType::BufferOverflow,
+        "overlong",
+    );
+    let turbofish = Fingerprint::new(
+        "shop",
+        "codec::decode::<u32>",
+        TestType::BufferOverflow,
+        "overlong",
+    );
+    let spaced = Fingerprint::new(
+        "shop",
+        " codec :: decode ",
+        TestType::BufferOverflow,
+        "overlong",
+    );
+    assert_eq!(plain, turbofish);
+    assert_eq!(plain, spaced);
+
+    let generic_type = Fingerprint::new("shop", "Cache<K, V>::get", TestType::RaceCondition, "x");
+    let bare_type = Fingerprint::new("shop", "Cache::get", TestType::RaceCondition, "x");
+    assert_eq!(generic_type, bare_type);
+}
+
+#[test]
+fn qualified_self_distinguishes_trait_impls() {
+    let session = Fingerprint::new(
+        "shop",
+        "<Session as Drop>::drop",
+        TestType::RaceCondition,
+        "x",
+    );
+    let pool = Fingerprint::new("shop", "<Pool as Drop>::drop", TestType::RaceCondition, "x");
+    assert_ne!(session, pool);
+    assert_eq!(
+        session,
+        Fingerprint::new(
+            "shop",
+            "<Session  as  Drop>::drop",
+            TestType::RaceCondition,
+            "x"
+        )
+    );
+}
+
+#[test]
+fn payload_class_spelling_is_normalized() {
+    assert_eq!(
+        login("auth::login"),
+        Fingerprint::new(
+            "shop",
+            "auth::login",
+            TestType::SqlInjection,
+            "Union Select"
+        )
+    );
+    assert_eq!(
+        login("auth::login"),
+        Fingerprint::new(
+            "shop",
+            "auth::login",
+            TestType::SqlInjection,
+            "union--select"
+        )
+    );
+}
+
+#[test]
+fn moving_between_modules_changes_the_fingerprint() {
+    assert_ne!(login("auth::login"), login("session::login"));
+}
+
+#[test]
+fn test_type_and_payload_class_are_significant() {
+    let sql = login("auth::login");
+    let timing = Fingerprint::new(
+        "shop",
+        "auth::login",
+        TestType::TimingAttack,
+        "union_select",
+    );
+    let stacked = Fingerprint::new(
+        "shop",
+        "auth::login",
+        TestType::SqlInjection,
+        "stacked_query",
+    );
+    assert_ne!(sql, timing);
+    assert_ne!(sql, stacked);
+}
+
+#[test]
+fn fields_cannot_bleed_into_each_other() {
+    let a = Fingerprint::new("shop", "a::b", TestType::SqlInjection, "c");
+    let b = Fingerprint::new("shop", "a", TestType::SqlInjection, "b_c");
+    assert_ne!(a, b);
+}
+
+#[test]
+fn display_round_trips_through_from_str() {
+    let fingerprint = login("auth::login");
+    let rendered = fingerprint.to_string();
+    assert_eq!(rendered.len(), 16);
+    assert_eq!(rendered.parse::<Fingerprint>().unwrap(), fingerprint);
+    assert!("not-a-fingerprint".parse::<Fingerprint>().is_err());
+}
+
+#[test]
+fn algorithm_output_is_pinned() {
+    // Changing this value means existing triage state is invalidated; bump
+    // the algorithm version instead.
+    assert_eq!(login("auth::login").to_string(), PINNED_LOGIN);
+}
+
+const PINNED_LOGIN: &str = "6ea898635e34e2d9";
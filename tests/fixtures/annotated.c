@@ -0,0 +1,32 @@
+/* C and C++ functions annotated with security_test.h, compiled by
+ * tests/extract.rs. */
+#include <stddef.h>
+
+#include "security_test.h"
+
+int parse_packet(const unsigned char *data, size_t len)
+{
+    return len > 1 && data[0] == 0x7f ? data[1] : -1;
+}
+SECURITY_TEST(parse_packet, SECSCAN_TEST_BUFFER_OVERFLOW | SECSCAN_TEST_COMMAND_INJECTION,
+              SECSCAN_CRITICAL);
+
+int check_password(const char *given, const char *expected)
+{
+    int diff = 0;
+    while (*given && *expected) {
+        diff |= *given++ ^ *expected++;
+    }
+    return diff == 0 && *given == *expected;
+}
+SECURITY_TEST(check_password, SECSCAN_TEST_TIMING_ATTACK, SECSCAN_HIGH);
+
+#ifdef __cplusplus
+namespace net {
+int resolve(const char *host)
+{
+    return host[0];
+}
+}
+SECURITY_TEST(net::resolve, SECSCAN_TEST_SSRF, SECSCAN_MEDIUM);
+#endif
@@ -0,0 +1,122 @@
+//! Regression tests for identifiers generated by `#[security_test]`.
+//!
+//! Most of these are compile tests: before the generated statics used hashed
+//! names, each of these modules failed to build.
+
+#![allow(non_snake_case)]
+
+use security_scanner::security_test;
+
+mod case_collision {
+    use super::*;
+
+    #[security_test(sql_injection)]
+    pub fn foo() -> u8 {
+        1
+    }
+
+    #[security_test(timing_attack)]
+    pub fn FOO() -> u8 {
+        2
+    }
+}
+
+mod user_defined_statics {
+    use super::*;
+
+    // The names the macro used to generate for `login`
+    #[allow(dead_code)]
+    static __SEC_TEST_LOGIN: u8 = 0;
+    #[allow(dead_code)]
+    static __SEC_NAME_LOGIN: u8 = 0;
+
+    #[security_test(sql_injection, critical)]
+    pub fn login(user: &str) -> bool {
+        !user.is_empty()
+    }
+}
+
+mod raw_identifiers {
+    use super::*;
+
+    #[security_test(buffer_overflow, anchor)]
+    pub fn r#match(input: &[u8]) -> bool {
+        input.is_empty()
+    }
+
+    #[security_test(race_condition)]
+    pub fn r#fn() -> u8 {
+        3
+    }
+}
+
+mod non_ascii {
+    use super::*;
+
+    #[security_test(sql_injection, high)]
+    pub fn grüße(name: &str) -> String {
+        format!("hallo {}", name)
+    }
+
+    #[security_test(timing_attack)]
+    pub fn проверить(token: &str) -> bool {
+        token.len() == 32
+    }
+}
+
+mod same_name_in_different_modules {
+    use security_scanner::security_test;
+
+    pub mod admin {
+        use super::*;
+
+        #[security_test(sql_injection, critical)]
+        pub fn login(user: &str) -> bool {
+            user == "root"
+        }
+    }
+
+    pub mod customer {
+        use super::*;
+
+        #[security_test(timing_attack, high)]
+        pub fn login(user: &str) -> bool {
+            !user.is_empty()
+        }
+    }
+
+    #[security_test(race_condition)]
+    pub fn login() -> u8 {
+        4
+    }
+}
+
+#[test]
+fn case_colliding_functions_both_compile_and_run() {
+    assert_eq!(case_collision::foo(), 1);
+    assert_eq!(case_collision::FOO(), 2);
+}
+
+#[test]
+fn user_statics_with_legacy_names_do_not_clash() {
+    assert!(user_defined_statics::login("admin"));
+}
+
+#[test]
+fn same_named_functions_in_different_modules_do_not_clash() {
+    assert!(same_name_in_different_modules::admin::login("root"));
+    assert!(same_name_in_different_modules::customer::login("alice"));
+    assert_eq!(same_name_in_different_modules::login(), 4);
+}
+
+#[test]
+fn raw_identifiers_are_supported() {
+    assert!(raw_identifiers::r#match(&[]));
+    assert_eq!(raw_identifiers::r#fn(), 3);
+}
+
+#[test]
+fn non_ascii_names_are_supported() {
+    assert_eq!(non_ascii::grüße("Welt"), "hallo Welt");
+    assert!(!non_ascii::проверить("short"));
+}
@@ -0,0 +1,127 @@
+//! Tests for merging the records of one definition site, run with
+//! `--features scanner`.
+//!
+//! `#[security_test]` writes one record per function, so the records of
+//! `parse`, one per generic instance, are written by hand here, as they
+//! would be by a strategy recording each instance.
+
+#![cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+
+use security_scanner::format::tlv::Record;
+use security_scanner::{extract, SecurityTestMetadata};
+
+const PARSE: Record<'static> = Record {
+    path: "instances::parse",
+    tests: 1,
+    threat_level: 2,
+    line: 20,
+    column: 4,
+    file: "tests/instances.rs",
+    ..Record::EMPTY
+};
+
+const HELPER: Record<'static> = Record {
+    path: "helper",
+    threat_level: 1,
+    line: 3,
+    column: 1,
+    file: "a.c",
+    ..Record::EMPTY
+};
+
+/// Embeds `$record` in the records section.
+macro_rules! embed {
+    ($record:expr) => {
+        const _: () = {
+            const RECORD: Record<'static> = $record;
+            #[cfg_attr(target_os = "linux", link_section = ".security_tests")]
+            #[cfg_attr(target_os = "macos", link_section = "__DATA,__sectests")]
+            #[cfg_attr(target_os = "windows", link_section = ".sectests")]
+            #[used]
+            static BYTES: [u8; RECORD.encoded_len()] = RECORD.encode();
+        };
+    };
+}
+
+embed!(Record {
+    instance: "T = u32",
+    ..PARSE
+});
+embed!(Record {
+    instance: "T = String",
+    ..PARSE
+});
+// The same instance, from another crate's copy of it
+embed!(Record {
+    instance: "T = u32",
+    ..PARSE
+});
+// A C annotation in a header writes a record into every file including it
+embed!(HELPER);
+embed!(HELPER);
+// A function of the same name defined in another file
+embed!(Record {
+    file: "b.c",
+    ..HELPER
+});
+
+// `strip` leaves it without a record
+#[cfg(not(feature = "strip"))]
+#[security_scanner::security_test(deserialization, high)]
+fn decode<T: Default>(input: &[u8]) -> T {
+    let _ = input;
+    T::default()
+}
+
+fn functions() -> Vec<SecurityTestMetadata> {
+    let binary = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    extract::extract(&binary).unwrap().functions
+}
+
+fn named<'a>(functions: &'a [SecurityTestMetadata], path: &str) -> Vec<&'a SecurityTestMetadata> {
+    functions
+        .iter()
+        .filter(|function| function.function_name == path)
+        .collect()
+}
+
+#[test]
+fn instances_of_a_function_are_merged() {
+    let functions = functions();
+    let [parse] = named(&functions, "instances::parse")[..] else {
+        panic!("parse isn't one function: {:?}", functions);
+    };
+    let mut instances = parse.instances.clone();
+    instances.sort();
+    // Link order decides which comes first
+    assert_eq!(instances, ["T = String", "T = u32"]);
+    assert!(parse.config.sql_injection);
+    assert_eq!(parse.location.as_ref().unwrap().line, 20);
+}
+
+#[test]
+fn repeated_records_are_one_function() {
+    let functions = functions();
+    let helpers = named(&functions, "helper");
+    let mut files: Vec<_> = helpers
+        .iter()
+        .map(|helper| helper.location.as_ref().unwrap().file.as_str())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["a.c", "b.c"]);
+    assert!(helpers.iter().all(|helper| helper.instances.is_empty()));
+}
+
+#[test]
+#[cfg(not(feature = "strip"))]
+fn generic_functions_have_one_record() {
+    assert_eq!(decode::<u32>(b"1") + decode::<String>(b"a").len() as u32, 0);
+
+    let functions = functions();
+    let [decode] = named(&functions, "instances::decode")[..] else {
+        panic!("decode isn't one function: {:?}", functions);
+    };
+    assert!(decode.instances.is_empty());
+    // One copy of the code per instance, neither of them the function's
+    assert_eq!(decode.function_address, None);
+}
@@ -0,0 +1,428 @@
+//! Record checksums and section scanning over damaged input.
+
+use security_scanner::format::tlv::{self, Program, Record};
+use security_scanner::format::{
+    check_record, crc32, empty_record, offsets, read_u32, scan_section, seal, write_u16, write_u32,
+    write_u64, RecordError, RecordStatus, RECORD_MAGIC, RECORD_SIZE,
+};
+use security_scanner::{
+    DataClass, Exposure, ProgramContext, SecurityTestConfig, SecurityTestMetadata, TestType,
+    ThreatLevel,
+};
+
+fn sealed(threat_level: u8) -> [u8; RECORD_SIZE] {
+    let mut record = empty_record();
+    record[offsets::NAME_LEN] = 5;
+    record[offsets::SQL_INJECTION] = 1;
+    record[offsets::THREAT_LEVEL] = threat_level;
+    seal(&mut record);
+    record
+}
+
+#[test]
+fn crc32_matches_the_ieee_check_value() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+}
+
+#[test]
+fn sealed_records_verify_and_decode() {
+    let record = sealed(3);
+    assert_eq!(check_record(&record), Ok(RecordStatus::Verified));
+
+    let config = SecurityTestConfig::from_record(&record).unwrap();
+    assert!(config.sql_injection);
+    assert_eq!(config.threat_level, ThreatLevel::Critical);
+}
+
+#[test]
+fn records_without_a_checksum_are_unchecked() {
+    let mut record = empty_record();
+    record[offsets::THREAT_LEVEL] = 1;
+    assert_eq!(check_record(&record), Ok(RecordStatus::Unchecked));
+    assert!(SecurityTestConfig::from_record(&record).is_some());
+}
+
+#[test]
+fn flipped_bytes_fail_the_checksum() {
+    let mut record = sealed(0);
+    record[offsets::THREAT_LEVEL] = 3;
+
+    assert!(matches!(
+        check_record(&record),
+        Err(RecordError::ChecksumMismatch { .. })
+    ));
+    assert!(SecurityTestConfig::from_record(&record).is_none());
+}
+
+#[test]
+fn short_input_is_truncated() {
+    let record = sealed(2);
+    assert_eq!(
+        check_record(&record[..40]),
+        Err(RecordError::Truncated {
+            len: 40,
+            expected: RECORD_SIZE
+        })
+    );
+}
+
+#[test]
+fn section_scan_counts_each_kind_of_damage() {
+    let mut corrupted = sealed(1);
+    corrupted[offsets::SQL_INJECTION] = 0;
+
+    let mut section = Vec::new();
+    section.extend_from_slice(&sealed(1));
+    section.extend_from_slice(&corrupted);
+    section.extend_from_slice(&[0xAA; 7]);
+    section.extend_from_slice(&empty_record());
+    section.extend_from_slice(&sealed(2)[..20]);
+
+    let scan = scan_section(&section);
+    assert_eq!(scan.records.len(), 2);
+    assert_eq!(scan.stats.verified, 1);
+    assert_eq!(scan.stats.unchecked, 1);
+    assert_eq!(scan.stats.corrupted, 1);
+    assert_eq!(scan.stats.unrecognized, 1);
+    assert_eq!(scan.stats.truncated, 1);
+    assert_eq!(scan.stats.valid(), 2);
+}
+
+#[test]
+fn trailing_zero_padding_is_not_damage() {
+    let mut section = sealed(0).to_vec();
+    section.extend_from_slice(&[0; 16]);
+
+    let scan = scan_section(&section);
+    assert_eq!(scan.records.len(), 1);
+    assert_eq!(scan.stats.unrecognized, 0);
+    assert_eq!(scan.stats.truncated, 0);
+}
+
+#[test]
+fn multi_byte_fields_are_little_endian() {
+    let record = empty_record();
+    assert_eq!(
+        record[..8],
+        [0xBE, 0xBA, 0xFE, 0xCA, 0xEF, 0xBE, 0xAD, 0xDE]
+    );
+
+    let mut bytes = [0u8; 6];
+    write_u32(&mut bytes, 1, 0x1234_5678);
+    assert_eq!(bytes, [0, 0x78, 0x56, 0x34, 0x12, 0]);
+    assert_eq!(read_u32(&bytes, 1), 0x1234_5678);
+}
+
+#[test]
+fn big_endian_records_are_rejected() {
+    let mut record = sealed(2);
+    write_u64(&mut record, offsets::MAGIC, RECORD_MAGIC.swap_bytes());
+
+    assert_eq!(check_record(&record), Err(RecordError::ForeignEndian));
+    assert!(SecurityTestConfig::from_record(&record).is_none());
+
+    let mut section = record.to_vec();
+    section.extend_from_slice(&sealed(1));
+    let scan = scan_section(&section);
+    assert_eq!(scan.stats.foreign_endian, 1);
+    assert_eq!(scan.stats.verified, 1);
+}
+
+const TAGGED: Record<'static> = Record {
+    path: "app::auth::login",
+    tests: 0b1_0001_0001,
+    threat_level: 3,
+    sla_days: 7,
+    line: 42,
+    column: 8,
+    file: "src/auth.rs",
+    reads_env: &["DATABASE_URL", "DB_PASSWORD"],
+    input_params: &["user: &str"],
+    build: "rustc=1.80.0; panic-unwind=true; overflow-checks=false; debug-assertions=false",
+    custom_tests: &["tenant_isolation"],
+    cwe: &[89],
+    owasp: &["A03:2021"],
+    banned_calls: &["std::mem::transmute"],
+    ..Record::EMPTY
+};
+
+fn tagged() -> Vec<u8> {
+    let record: [u8; TAGGED.encoded_len()] = TAGGED.encode();
+    record.to_vec()
+}
+
+/// Recomputes the length and checksum of a record edited in place.
+fn reseal(record: &mut [u8]) {
+    let len = record.len();
+    write_u32(record, tlv::header::RECORD_LEN, len as u32);
+    let crc = crc32(&record[..len - tlv::CHECKSUM_LEN]);
+    write_u32(record, len - tlv::CHECKSUM_LEN, crc);
+}
+
+#[test]
+fn tagged_records_decode_in_full() {
+    let metadata = SecurityTestMetadata::from_record(&tagged()).unwrap();
+    assert_eq!(metadata.function_name, "app::auth::login");
+    assert_eq!(
+        metadata.config.test_types(),
+        [
+            TestType::SqlInjection,
+            TestType::EnvInjection,
+            TestType::Ssrf
+        ]
+    );
+    assert_eq!(metadata.config.custom_tests, ["tenant_isolation"]);
+    assert_eq!(
+        metadata.config.compliance_tags,
+        ["CWE-89", "OWASP-A03:2021"]
+    );
+    assert_eq!(metadata.config.threat_level, ThreatLevel::Critical);
+    assert_eq!(metadata.config.sla_days, Some(7));
+    assert_eq!(metadata.config.reads_env, ["DATABASE_URL", "DB_PASSWORD"]);
+    assert_eq!(metadata.config.banned_calls, ["std::mem::transmute"]);
+    assert_eq!(metadata.config.input_params[0].ty, "&str");
+    assert_eq!(metadata.location.unwrap().to_string(), "src/auth.rs:42:8");
+    assert!(!metadata.build.unwrap().overflow_checks);
+}
+
+#[test]
+fn program_records_are_not_functions() {
+    const PROGRAM: Program<'static> = Program {
+        path: "app::main",
+        exposure: Some(3),
+        data_classes: 0x05,
+        line: 3,
+        file: "src/main.rs",
+        ..Program::EMPTY
+    };
+    let record: [u8; PROGRAM.encoded_len()] = PROGRAM.encode();
+
+    assert_eq!(tlv::check_record(&record), Ok(&record[..]));
+    assert!(SecurityTestMetadata::from_record(&record).is_none());
+    let program = ProgramContext::from_record(&record).unwrap();
+    assert_eq!(program.entry_point, "app::main");
+    assert_eq!(program.exposure, Some(Exposure::Internet));
+    assert_eq!(program.data_classes, [DataClass::Pii, DataClass::Pci]);
+    assert_eq!(program.location.unwrap().to_string(), "src/main.rs:3");
+    assert!(ProgramContext::from_record(&tagged()).is_none());
+}
+
+#[test]
+fn unknown_tags_are_skipped() {
+    let mut record = tagged();
+    let checksum = record.len() - tlv::CHECKSUM_LEN;
+    record.splice(checksum..checksum, [0xFF, 0x7F, 3, 0, 1, 2, 3]);
+    reseal(&mut record);
+
+    let metadata = SecurityTestMetadata::from_record(&record).unwrap();
+    assert_eq!(metadata.function_name, "app::auth::login");
+    assert!(tlv::entries(&record).any(|entry| entry.tag == 0x7FFF && entry.value == [1, 2, 3]));
+}
+
+#[test]
+fn instance_records_name_their_instance() {
+    const INSTANCE: Record<'static> = Record {
+        instance: "T = u32, N = 4",
+        ..TAGGED
+    };
+    let record: [u8; INSTANCE.encoded_len()] = INSTANCE.encode();
+
+    let metadata = SecurityTestMetadata::from_record(&record).unwrap();
+    assert_eq!(metadata.instances, ["T = u32, N = 4"]);
+    assert!(SecurityTestMetadata::from_record(&tagged())
+        .unwrap()
+        .instances
+        .is_empty());
+}
+
+#[test]
+fn tagged_records_fail_the_checksum_when_edited() {
+    let mut record = tagged();
+    record[tlv::HEADER_LEN + tlv::ENTRY_HEADER_LEN] ^= 0x20;
+
+    assert!(matches!(
+        tlv::check_record(&record),
+        Err(RecordError::ChecksumMismatch { .. })
+    ));
+    assert!(SecurityTestMetadata::from_record(&record).is_none());
+}
+
+#[test]
+fn records_written_from_c_are_unchecked() {
+    // security_test.h writes NUL-terminated strings and no checksum
+    const FROM_C: Record<'static> = Record {
+        path: "parse_packet\0",
+        threat_level: 3,
+        file: "src/parser.c\0",
+        line: 40,
+        ..Record::EMPTY
+    };
+    let mut record: [u8; FROM_C.encoded_len()] = FROM_C.encode();
+    write_u32(&mut record, FROM_C.encoded_len() - tlv::CHECKSUM_LEN, 0);
+
+    let metadata = SecurityTestMetadata::from_record(&record).unwrap();
+    assert_eq!(metadata.function_name, "parse_packet");
+    assert_eq!(metadata.location.unwrap().to_string(), "src/parser.c:40");
+    let scan = scan_section(&record);
+    assert_eq!(scan.stats.unchecked, 1);
+    assert_eq!(scan.records.len(), 1);
+
+    record[tlv::HEADER_LEN + tlv::ENTRY_HEADER_LEN] ^= 0x20;
+    assert_eq!(tlv::check_record(&record), Ok(&record[..]));
+}
+
+#[test]
+fn newer_versions_are_reported_not_decoded() {
+    let mut record = tagged();
+    write_u16(&mut record, tlv::header::VERSION, tlv::VERSION + 1);
+    reseal(&mut record);
+
+    assert_eq!(
+        tlv::check_record(&record),
+        Err(RecordError::UnsupportedVersion {
+            version: tlv::VERSION + 1
+        })
+    );
+
+    let mut section = record.clone();
+    section.extend_from_slice(&tagged());
+    let scan = scan_section(&section);
+    assert_eq!(scan.stats.unsupported, 1);
+    assert_eq!(scan.stats.verified, 1);
+    assert_eq!(scan.records.len(), 1);
+}
+
+#[test]
+fn sections_can_mix_record_versions() {
+    let mut section = sealed(1).to_vec();
+    section.extend_from_slice(&tagged());
+    section.extend_from_slice(&[0; 3]);
+    section.extend_from_slice(&sealed(2));
+    section.extend_from_slice(&tagged()[..30]);
+
+    let scan = scan_section(&section);
+    assert_eq!(scan.records.len(), 3);
+    assert_eq!(scan.records[0].len(), RECORD_SIZE);
+    assert_eq!(scan.records[1], &tagged()[..]);
+    assert_eq!(scan.stats.verified, 3);
+    assert_eq!(scan.stats.unrecognized, 0);
+    assert_eq!(scan.stats.truncated, 1);
+}
+
+#[test]
+fn records_after_a_damaged_length_are_kept() {
+    let mut damaged = tagged();
+    write_u32(&mut damaged, tlv::header::RECORD_LEN, 0xFFFF);
+
+    let mut section = tagged();
+    section.extend_from_slice(&damaged);
+    section.extend_from_slice(&sealed(2));
+    section.extend_from_slice(&tagged());
+
+    let scan = scan_section(&section);
+    assert_eq!(scan.stats.truncated, 1);
+    assert_eq!(scan.stats.verified, 3);
+    assert_eq!(scan.stats.unrecognized, 0);
+    assert_eq!(scan.records.len(), 3);
+    assert_eq!(scan.records[1].len(), RECORD_SIZE);
+    assert_eq!(scan.records[2], &tagged()[..]);
+}
+
+const KEY: [u8; tlv::KEY_LEN] = [0x42; tlv::KEY_LEN];
+
+fn encrypted() -> Vec<u8> {
+    let record: [u8; tlv::encrypted_len(TAGGED.encoded_len())] =
+        tlv::encrypt(&TAGGED.encode::<{ TAGGED.encoded_len() }>(), &KEY);
+    record.to_vec()
+}
+
+#[test]
+fn encrypted_records_hide_their_contents() {
+    let record = encrypted();
+    assert_eq!(tlv::check_record(&record), Ok(&record[..]));
+    assert!(tlv::is_encrypted(&record));
+    assert!(!tlv::is_encrypted(&tagged()));
+    assert!(!record.windows(4).any(|window| window == b"auth"));
+    assert!(SecurityTestMetadata::from_record(&record).is_none());
+    assert!(ProgramContext::from_record(&record).is_none());
+
+    let plain = tlv::decrypt(&record, &KEY).unwrap();
+    assert_eq!(plain, tagged());
+    assert_eq!(encrypted(), record, "encryption is reproducible");
+    assert_eq!(tlv::decrypt(&record, &[0x24; tlv::KEY_LEN]), None);
+    assert_eq!(tlv::decrypt(&tagged(), &KEY), None);
+}
+
+/// HMAC-SHA-256 with the `sha2` crate, to check the const one against.
+fn hmac(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+
+    let mut pad = [0u8; 64];
+    pad[..key.len()].copy_from_slice(key);
+    let mut inner = Sha256::new();
+    inner.update(pad.map(|byte| byte ^ 0x36));
+    for part in parts {
+        inner.update(part);
+    }
+    let mut outer = Sha256::new();
+    outer.update(pad.map(|byte| byte ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+#[test]
+fn encryption_is_siv_over_hmac_sha256() {
+    // RFC 4231, test case 2
+    assert_eq!(
+        hmac(b"Jefe", &[b"what do ya want ", b"for nothing?"]),
+        [
+            0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+            0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+            0x64, 0xec, 0x38, 0x43,
+        ]
+    );
+
+    // Long enough for several SHA-256 blocks and keystream blocks
+    const PLAIN: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you \
+                           only one tip for the future, sunscreen would be it.";
+    let key: [u8; tlv::KEY_LEN] = std::array::from_fn(|i| i as u8);
+    let record: [u8; tlv::encrypted_len(PLAIN.len())] = tlv::encrypt(PLAIN, &key);
+
+    let entry = tlv::entries(&record).next().unwrap();
+    assert_eq!(entry.tag, tlv::tags::ENCRYPTED);
+    let (siv, encrypted) = entry.value.split_at(tlv::SIV_LEN);
+
+    let mac_key = hmac(&key, &[b"security-scanner record mac"]);
+    assert_eq!(siv, &hmac(&mac_key, &[PLAIN])[..tlv::SIV_LEN]);
+    let stream_key = hmac(&key, &[b"security-scanner record stream"]);
+    let keystream = (0u32..)
+        .flat_map(|counter| hmac(&stream_key, &[siv, &counter.to_le_bytes()]))
+        .take(PLAIN.len());
+    let expected: Vec<u8> = PLAIN.iter().zip(keystream).map(|(a, b)| a ^ b).collect();
+    assert_eq!(encrypted, expected);
+}
+
+#[test]
+fn changed_encrypted_records_are_rejected() {
+    let record = encrypted();
+    assert!(tlv::decrypt(&record, &KEY).is_some());
+
+    // Every byte of the IV and of the encrypted record
+    let value = tlv::HEADER_LEN + tlv::ENTRY_HEADER_LEN;
+    for at in value..record.len() - tlv::CHECKSUM_LEN {
+        let mut changed = record.clone();
+        changed[at] ^= 0x01;
+        assert_eq!(tlv::decrypt(&changed, &KEY), None, "byte {} changed", at);
+    }
+}
+
+#[test]
+fn keys_are_64_hex_digits() {
+    let hex = "000102030405060708090a0b0c0d0e0f101112131415161718191A1B1C1D1E1F";
+    let key = tlv::parse_key(hex).unwrap();
+    assert_eq!(key, std::array::from_fn(|i| i as u8));
+    assert_eq!(tlv::parse_key(&format!(" {}\n", hex)), Some(key));
+    assert_eq!(tlv::parse_key(&hex[2..]), None);
+    assert_eq!(tlv::parse_key(&hex.replace("0a", "+a")), None);
+    assert_eq!(tlv::parse_key(&hex.replace("0a", "0g")), None);
+}
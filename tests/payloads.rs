@@ -0,0 +1,55 @@
+//! Tests for the payload tests of `generate_tests`. The annotated functions
+//! below get their generated tests in this binary, as
+//! `__security_test_find_user::sql_injection_name` and so on.
+
+use security_scanner::payloads::{self, SQL_INJECTION};
+use security_scanner::security_test;
+
+/// Stands in for a query with a bound parameter: the name is data, whatever
+/// it contains.
+#[security_test(sql_injection, generate_tests, high)]
+fn find_user(name: &str) -> Result<Option<u64>, String> {
+    if name.len() > 64 {
+        return Err("name too long".to_string());
+    }
+    Ok((name == "alice").then_some(1))
+}
+
+#[security_test(sql_injection, timing_attack, generate_tests, critical)]
+fn login(user: &str, password: String) -> bool {
+    find_user(user).ok().flatten().is_some() && password.len() >= 12
+}
+
+/// Splices the name into the query and trips over the first quote.
+fn splice(name: &str) -> String {
+    assert!(!name.contains('\''), "unbalanced quote in query");
+    format!("SELECT id FROM users WHERE name = '{}'", name)
+}
+
+#[test]
+fn payloads_reach_the_function() {
+    let mut seen = Vec::new();
+    payloads::run("find_user", "name", SQL_INJECTION, |payload| {
+        seen.push(payload.to_string());
+    });
+    assert_eq!(seen, SQL_INJECTION);
+    assert!(seen.iter().any(|payload| payload.contains("DROP TABLE")));
+    assert!(seen.iter().any(|payload| !payload.is_ascii()));
+}
+
+#[test]
+#[should_panic(
+    expected = "`splice` panicked with the payload \"' OR 1=1 --\" as `name`: unbalanced quote in \
+                query"
+)]
+fn panics_name_the_payload() {
+    payloads::run("splice", "name", SQL_INJECTION, |payload| {
+        splice(payload);
+    });
+}
+
+#[test]
+fn annotated_functions_still_work() {
+    assert_eq!(find_user("alice"), Ok(Some(1)));
+    assert!(login("alice", "correct horse battery".to_string()));
+}
@@ -0,0 +1,77 @@
+//! Tests for the link-time registry, run with `--features registry`.
+
+// `strip` leaves no records to read
+#![cfg(all(
+    any(target_os = "linux", target_os = "macos", target_os = "windows"),
+    not(feature = "strip")
+))]
+
+use security_scanner::{registry, security_test, SecurityTestMetadata, ThreatLevel};
+
+mod auth {
+    use super::*;
+
+    #[security_test(sql_injection, reads_env("DATABASE_URL"), attack("T1190"), critical)]
+    pub fn login(user: &str) -> bool {
+        !user.is_empty()
+    }
+}
+
+mod billing {
+    use super::*;
+
+    #[security_test(race_condition, fixtures("ledger"), high, anchor)]
+    pub fn login() -> u8 {
+        1
+    }
+}
+
+fn find(path: &str) -> &'static SecurityTestMetadata {
+    registry::iter()
+        .find(|metadata| metadata.function_name == path)
+        .unwrap_or_else(|| panic!("{} is not registered", path))
+}
+
+#[test]
+fn registers_every_annotated_function_by_path() {
+    assert!(auth::login("admin"));
+    assert_eq!(billing::login(), 1);
+
+    let auth = find("registry::auth::login");
+    assert!(auth.config.sql_injection);
+    assert_eq!(auth.config.threat_level, ThreatLevel::Critical);
+    assert_eq!(auth.config.reads_env, ["DATABASE_URL"]);
+    assert_eq!(auth.config.attack_techniques, ["T1190"]);
+    // Compiled as part of this test binary
+    assert!(auth.config.test_context);
+    let location = auth.location.as_ref().unwrap();
+    assert_eq!(
+        (location.file.as_str(), location.line),
+        ("tests/registry.rs", 15)
+    );
+
+    let billing = find("registry::billing::login");
+    assert!(billing.config.race_condition);
+    assert_eq!(billing.config.fixtures, ["ledger"]);
+}
+
+#[test]
+fn records_the_address_of_anchored_functions() {
+    assert_eq!(
+        find("registry::billing::login").function_address,
+        Some(billing::login as fn() -> u8 as usize)
+    );
+    assert_eq!(find("registry::auth::login").function_address, None);
+}
+
+#[test]
+fn iteration_is_stable() {
+    let first: Vec<_> = registry::iter()
+        .map(|metadata| &metadata.function_name)
+        .collect();
+    let second: Vec<_> = registry::iter()
+        .map(|metadata| &metadata.function_name)
+        .collect();
+    assert_eq!(first, second);
+    assert_eq!(first.len(), 2);
+}
@@ -0,0 +1,101 @@
+//! Registering and unregistering plugin descriptors from several threads.
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use security_scanner::runtime::{self, RegistryEvent};
+use security_scanner::SecurityTestMetadata;
+
+fn descriptor(name: &str) -> SecurityTestMetadata {
+    SecurityTestMetadata {
+        function_name: name.to_string(),
+        ..SecurityTestMetadata::default()
+    }
+}
+
+fn registered(name: &str) -> bool {
+    runtime::descriptors()
+        .iter()
+        .any(|descriptor| descriptor.function_name == name)
+}
+
+#[test]
+fn unregistering_removes_only_that_source() {
+    let first = runtime::register("plugin_a", vec![descriptor("a_login")]);
+    let second = runtime::register("plugin_b", vec![descriptor("b_login")]);
+
+    assert!(runtime::unregister(first));
+    assert!(!registered("a_login"));
+    assert!(registered("b_login"));
+
+    assert!(!runtime::unregister(first));
+    assert!(runtime::unregister(second));
+}
+
+#[test]
+fn subscribers_see_changes_and_can_read_the_registry() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&events);
+    let subscription = runtime::subscribe(move |event| {
+        let source = match event {
+            RegistryEvent::Registered { source, .. }
+            | RegistryEvent::Unregistered { source, .. } => source,
+        };
+        if source == "notifying_plugin" {
+            // Reading from inside a callback must not deadlock
+            let present = registered("notifying_fn");
+            seen.lock().unwrap().push((event.clone(), present));
+        }
+    });
+
+    let id = runtime::register("notifying_plugin", vec![descriptor("notifying_fn")]);
+    runtime::unregister(id);
+    assert!(runtime::unsubscribe(subscription));
+    runtime::register("notifying_plugin", vec![descriptor("notifying_fn")]);
+
+    let events = events.lock().unwrap();
+    assert_eq!(
+        *events,
+        vec![
+            (
+                RegistryEvent::Registered {
+                    id,
+                    source: "notifying_plugin".to_string(),
+                    count: 1,
+                },
+                true
+            ),
+            (
+                RegistryEvent::Unregistered {
+                    id,
+                    source: "notifying_plugin".to_string(),
+                    count: 1,
+                },
+                false
+            ),
+        ]
+    );
+}
+
+#[test]
+fn concurrent_registration() {
+    let handles: Vec<_> = (0..8)
+        .map(|thread| {
+            thread::spawn(move || {
+                let name = format!("concurrent_{}", thread);
+                for _ in 0..100 {
+                    let id = runtime::register(name.clone(), vec![descriptor(&name)]);
+                    assert!(registered(&name));
+                    assert!(runtime::unregister(id));
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert!(!runtime::descriptors()
+        .iter()
+        .any(|descriptor| descriptor.function_name.starts_with("concurrent_")));
+}
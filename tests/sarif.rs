@@ -0,0 +1,152 @@
+//! Shape of the SARIF logs built from extracted metadata.
+
+use security_scanner::fingerprint::Fingerprint;
+use security_scanner::names::DisplayNames;
+use security_scanner::report::sarif::{Log, FINGERPRINT_KEY};
+use security_scanner::{
+    Exposure, SecurityTestConfig, SecurityTestMetadata, SourceLocation, TestType, ThreatLevel,
+};
+
+fn function(path: &str, threat_level: ThreatLevel) -> SecurityTestMetadata {
+    SecurityTestMetadata {
+        function_name: path.to_string(),
+        config: SecurityTestConfig {
+            sql_injection: true,
+            timing_attack: true,
+            threat_level,
+            sla_days: Some(7),
+            compliance_tags: vec!["CWE-89".to_string()],
+            ..SecurityTestConfig::default()
+        },
+        location: Some(SourceLocation {
+            file: "src\\auth.rs".to_string(),
+            line: 12,
+            column: 4,
+            end_line: 20,
+        }),
+        ..SecurityTestMetadata::default()
+    }
+}
+
+#[test]
+fn log_has_one_rule_per_test_type_in_discriminant_order() {
+    let log = Log::from_metadata(&[]);
+    let json = serde_json::to_value(&log).unwrap();
+
+    assert_eq!(json["version"], "2.1.0");
+    let rules = json["runs"][0]["tool"]["driver"]["rules"]
+        .as_array()
+        .unwrap();
+    let ids: Vec<_> = rules
+        .iter()
+        .map(|rule| rule["id"].as_str().unwrap())
+        .collect();
+    let expected: Vec<_> = TestType::ALL
+        .iter()
+        .map(|test_type| test_type.as_str())
+        .collect();
+    assert_eq!(ids, expected);
+    assert!(json["runs"][0]["results"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn each_requested_test_type_is_one_result() {
+    let log = Log::from_metadata(&[
+        function("shop::auth::login", ThreatLevel::Critical),
+        function("shop::feed::render", ThreatLevel::Low),
+    ]);
+    let json = serde_json::to_value(&log).unwrap();
+    let results = json["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 4);
+
+    let login = &results[0];
+    assert_eq!(login["ruleId"], "sql_injection");
+    assert_eq!(login["ruleIndex"], 0);
+    assert_eq!(login["level"], "error");
+    assert_eq!(
+        login["locations"][0]["logicalLocations"][0]["fullyQualifiedName"],
+        "shop::auth::login"
+    );
+    let physical = &login["locations"][0]["physicalLocation"];
+    assert_eq!(physical["artifactLocation"]["uri"], "src/auth.rs");
+    assert_eq!(physical["artifactLocation"]["uriBaseId"], "%SRCROOT%");
+    assert_eq!(physical["region"]["startLine"], 12);
+    assert_eq!(physical["region"]["startColumn"], 4);
+    assert_eq!(physical["region"]["endLine"], 20);
+    assert_eq!(login["properties"]["threatLevel"], "critical");
+    assert_eq!(login["properties"]["slaDays"], 7);
+    assert!(login["properties"].get("attackTechniques").is_none());
+    assert_eq!(login["properties"]["tags"], serde_json::json!(["CWE-89"]));
+
+    assert_eq!(results[1]["ruleId"], "timing_attack");
+    assert_eq!(results[1]["ruleIndex"], 2);
+    assert_eq!(results[2]["level"], "note");
+}
+
+#[test]
+fn results_carry_the_finding_fingerprint() {
+    let log = Log::from_metadata(&[function("shop::auth::login", ThreatLevel::High)]);
+    let expected = Fingerprint::new("shop", "auth::login", TestType::SqlInjection, "");
+
+    assert_eq!(
+        log.runs[0].results[0].partial_fingerprints[FINGERPRINT_KEY],
+        expected.to_string()
+    );
+}
+
+#[test]
+fn levels_follow_the_program_exposure() {
+    let mut internal = function("tool::auth::login", ThreatLevel::Critical);
+    internal.exposure = Some(Exposure::Airgapped);
+    let public = function("shop::auth::login", ThreatLevel::Critical);
+    let json = serde_json::to_value(Log::from_metadata(&[internal, public])).unwrap();
+
+    let results = json["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results[0]["level"], "warning");
+    assert_eq!(results[0]["properties"]["threatLevel"], "critical");
+    assert_eq!(results[0]["properties"]["exposure"], "airgapped");
+    assert_eq!(results[2]["level"], "error");
+    assert!(results[2]["properties"].get("exposure").is_none());
+}
+
+#[test]
+fn functions_without_a_file_have_only_a_logical_location() {
+    let mut unlocated = function("shop::auth::login", ThreatLevel::Medium);
+    unlocated.location = None;
+    let json = serde_json::to_value(Log::from_metadata(&[unlocated])).unwrap();
+
+    let location = &json["runs"][0]["results"][0]["locations"][0];
+    assert!(location.get("physicalLocation").is_none());
+    assert_eq!(location["logicalLocations"][0]["kind"], "function");
+}
+
+#[test]
+fn display_names_change_only_messages() {
+    let login = function("shop::auth::login", ThreatLevel::Critical);
+    let mut names = DisplayNames::default();
+    names.rename_threat_level(ThreatLevel::Critical, "kritisch");
+    names.rename_test_type(TestType::SqlInjection, "SQL-Injektion");
+
+    let english = Log::from_metadata(std::slice::from_ref(&login));
+    let renamed = Log::from_metadata_with_names(&[login], &names);
+
+    let results = &english.runs[0].results;
+    assert_eq!(
+        results[0].message.text,
+        "`shop::auth::login` (critical) is annotated for SQL injection testing"
+    );
+    let results = &renamed.runs[0].results;
+    assert_eq!(
+        results[0].message.text,
+        "`shop::auth::login` (kritisch) is annotated for SQL-Injektion testing"
+    );
+    assert_eq!(
+        results[1].message.text,
+        "`shop::auth::login` (kritisch) is annotated for timing attack testing"
+    );
+
+    let json = serde_json::to_value(&renamed).unwrap();
+    let result = &json["runs"][0]["results"][0];
+    assert_eq!(result["ruleId"], "sql_injection");
+    assert_eq!(result["properties"]["threatLevel"], "critical");
+}
@@ -0,0 +1,57 @@
+//! Tests for the timing harness behind `timing_attack` and
+//! `generate_tests`. `verify_token` gets its generated test in this binary,
+//! as `__security_test_verify_token::timing_attack_token`. Like it, the
+//! test of a constant-time function depends on the machine's noise and only
+//! runs with `--ignored`.
+
+use std::hint::black_box;
+
+use security_scanner::security_test;
+use security_scanner::timing::{self, THRESHOLD};
+
+const TOKEN: &[u8; 32] = b"0123456789abcdef0123456789abcdef";
+
+/// Compares every byte, whatever the input.
+#[security_test(timing_attack, generate_tests, critical)]
+fn verify_token(token: &str) -> bool {
+    let difference = token
+        .bytes()
+        .zip(TOKEN)
+        .fold(0, |difference, (a, b)| difference | (a ^ b));
+    token.len() == TOKEN.len() && black_box(difference) == 0
+}
+
+/// Does extra work on input of the fixed class only.
+fn slow_on_repeats(input: &str) -> u64 {
+    let first = input.bytes().next().unwrap_or_default();
+    let rounds = if input.bytes().all(|byte| byte == first) {
+        2_000
+    } else {
+        1
+    };
+    (0..rounds).fold(0u64, |sum, round| sum.wrapping_add(black_box(round)))
+}
+
+#[test]
+fn data_dependent_timing_is_found() {
+    let measurement = timing::measure(&["aaaa"], &["abcd", "zyxw"], 2_000, slow_on_repeats);
+    assert!(!measurement.is_constant_time());
+    assert!(measurement.t > THRESHOLD, "{:?}", measurement);
+    assert!(measurement.means[0] > measurement.means[1]);
+    // The slowest 5% are dropped
+    assert!((1_900..2_000).contains(&(measurement.samples[0] + measurement.samples[1])));
+}
+
+#[test]
+#[should_panic(expected = "timing depends on the input")]
+fn assertions_fail_on_data_dependent_timing() {
+    timing::assert_constant_time(slow_on_repeats);
+}
+
+#[test]
+#[ignore = "timing test; run with --ignored"]
+fn constant_work_passes() {
+    timing::assert_constant_time(|input| input.len());
+    assert!(verify_token("0123456789abcdef0123456789abcdef"));
+    assert!(!verify_token("0123456789abcdef0123456789abcdeF"));
+}